@@ -25,6 +25,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         physical_dimension: "uV".to_string(),
         prefilter: "0.1-35Hz".to_string(),
         transducer: "AgAgCl".to_string(),
+        reserved: String::new(),
     };
     writer.add_signal(signal)?;
     
@@ -44,8 +45,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let long_description = "This is a very long annotation description that exceeds the 40 character limit and will be truncated";
     writer.add_annotation(25.0, None, long_description)?;
     
-    println!("   ❌ 添加超出范围的注释（会被丢失）");
-    // 这个注释将被丢失，因为超出了60秒的数据范围
+    println!("   ❌ 添加超出范围的注释（finalize时会报错）");
+    // 这个注释超出了60秒的数据范围，finalize会返回错误并且不会写入该注释
     writer.add_annotation(65.0, None, "Beyond range")?;
     
     println!("3. 然后写入数据以建立有效的时间范围");
@@ -64,7 +65,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
     
     println!("4. 完成文件");
-    writer.finalize()?;
+    // 即使返回错误，文件本身也已完整写入
+    match writer.finalize() {
+        Ok(summary) => println!("   写入了 {} 个数据记录", summary.datarecords),
+        Err(e) => println!("   ⚠️  {}", e),
+    }
     
     println!("\n=== 验证结果 ===");
     
@@ -79,14 +84,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("\n注释列表:");
     for (i, annotation) in annotations.iter().enumerate() {
         let onset_s = annotation.onset as f64 / 10_000_000.0;
-        let duration_s = if annotation.duration >= 0 {
-            Some(annotation.duration as f64 / 10_000_000.0)
-        } else {
-            None
-        };
+        let duration_s = annotation.duration.map(|d| d as f64 / 10_000_000.0);
         
         let truncated = if annotation.description.len() == 40 && 
-                          annotation.description.chars().last() != Some(' ') &&
+                          !annotation.description.ends_with(' ') &&
                           !annotation.description.ends_with('.') {
             " [截断]"
         } else {
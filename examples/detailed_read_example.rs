@@ -75,8 +75,7 @@ fn main() -> Result<()> {
             
             // 读取并显示前10个样本的数据
             println!("📊 样本数据预览 (前10个样本):");
-            for signal_idx in 0..signals.len() {
-                let signal = &signals[signal_idx];
+            for (signal_idx, signal) in signals.iter().enumerate() {
                 
                 // 注意：EDF文件中的注释信号不能用常规方法读取样本数据
                 // EDF规范中字符串字段可能包含null字节，所以使用contains()检查更可靠
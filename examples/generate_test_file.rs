@@ -21,6 +21,7 @@ fn main() -> Result<()> {
         physical_dimension: "uV".to_string(),
         prefilter: "HP:0.1Hz LP:70Hz".to_string(),
         transducer: "AgAgCl cup electrodes".to_string(),
+        reserved: String::new(),
     };
     
     let ecg_signal = SignalParam {
@@ -34,6 +35,7 @@ fn main() -> Result<()> {
         physical_dimension: "mV".to_string(),
         prefilter: "HP:0.05Hz LP:40Hz".to_string(),
         transducer: "Ag/AgCl electrodes".to_string(),
+        reserved: String::new(),
     };
     
     // 添加信号到写入器
@@ -58,6 +58,7 @@ fn main() -> Result<()> {
             physical_dimension: "uV".to_string(),
             prefilter: "HP:0.1Hz LP:70Hz".to_string(),
             transducer: "AgAgCl cup electrodes".to_string(),
+            reserved: String::new(),
         };
         writer.add_signal(signal)?;
         println!("  ✓ 添加通道: {} (范围: {:.1} 到 {:.1} μV)", label, phys_min, phys_max);
@@ -32,6 +32,7 @@ fn main() -> Result<()> {
                 physical_dimension: "uV".to_string(),
                 prefilter: "HP:0.1Hz LP:100Hz".to_string(),
                 transducer: "Test electrode".to_string(),
+                reserved: String::new(),
             };
             writer.add_signal(signal)?;
         }
@@ -97,8 +98,7 @@ fn main() -> Result<()> {
     
     // 读取所有数据
     let mut total_samples_read = 0;
-    for signal_idx in 0..signals.len() {
-        let signal = &signals[signal_idx];
+    for (signal_idx, signal) in signals.iter().enumerate() {
         let samples_to_read = signal.samples_in_file as usize;
         
         reader.rewind(signal_idx)?;
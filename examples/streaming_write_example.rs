@@ -24,6 +24,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         physical_dimension: "uV".to_string(),
         prefilter: "HP:0.1Hz LP:70Hz".to_string(),
         transducer: "AgAgCl cup electrode".to_string(),
+        reserved: String::new(),
     };
     
     let emg_signal = SignalParam {
@@ -37,6 +38,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         physical_dimension: "uV".to_string(),
         prefilter: "HP:10Hz LP:500Hz".to_string(),
         transducer: "Surface electrode".to_string(),
+        reserved: String::new(),
     };
     
     writer.add_signal(eeg_signal)?;
@@ -28,6 +28,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             physical_dimension: "uV".to_string(),
             prefilter: "HP:0.1Hz LP:70Hz".to_string(),
             transducer: "AgAgCl electrode".to_string(),
+            reserved: String::new(),
         };
         
         writer.add_signal(signal_param)?;
@@ -76,11 +77,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("\nFound {} annotations:", annotations.len());
     for (i, annotation) in annotations.iter().enumerate() {
         let onset_seconds = annotation.onset as f64 / 10000000.0;
-        let duration_seconds = if annotation.duration >= 0 {
-            annotation.duration as f64 / 10000000.0
-        } else {
-            -1.0
-        };
+        let duration_seconds = annotation.duration.map_or(-1.0, |d| d as f64 / 10000000.0);
         
         println!("  {}: onset={:.3}s, duration={:.3}s, text='{}'", 
                 i + 1, 
@@ -90,7 +87,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
     
     // 验证注释是否正确
-    let expected_annotations = vec![
+    let expected_annotations = [
         (1.5, Some(0.5), "Test annotation 1"),
         (3.0, None, "Test annotation 2"),
         (5.25, Some(1.0), "Test annotation 3 with longer duration"),
@@ -107,11 +104,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         for (i, (expected_onset, expected_duration, expected_text)) in expected_annotations.iter().enumerate() {
             let annotation = &annotations[i];
             let onset_seconds = annotation.onset as f64 / 10000000.0;
-            let duration_seconds = if annotation.duration >= 0 {
-                Some(annotation.duration as f64 / 10000000.0)
-            } else {
-                None
-            };
+            let duration_seconds = annotation.duration.map(|d| d as f64 / 10000000.0);
             
             let onset_ok = (onset_seconds - expected_onset).abs() < 0.001;
             let duration_ok = match (duration_seconds, expected_duration) {
@@ -0,0 +1,201 @@
+//! Plausibility checks of signal calibration
+//!
+//! A channel whose data never leaves a tiny fraction of its declared
+//! physical range, or that sits on its digital minimum/maximum most of the
+//! time, usually has a wrong calibration or a broken electrode.
+//! [`EdfReader::calibration_audit`](crate::EdfReader::calibration_audit)
+//! scans the samples of every signal once and reports such signals as
+//! [`CalibrationFinding`]s, together with the numbers behind each finding.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use edfplus::EdfReader;
+//! use edfplus::calibration::CalibrationOptions;
+//!
+//! # edfplus::doctest_utils::create_multi_channel_test_file("calibration.edf")?;
+//! let mut reader = EdfReader::open("calibration.edf")?;
+//!
+//! let options = CalibrationOptions::new().min_span_fraction(0.05);
+//! for finding in reader.calibration_audit_with(&options)? {
+//!     println!("{}", finding);
+//! }
+//! # drop(reader);
+//! # std::fs::remove_file("calibration.edf").ok();
+//! # Ok::<(), edfplus::EdfError>(())
+//! ```
+
+use std::fmt;
+
+use crate::types::SignalParam;
+
+/// Thresholds for [`EdfReader::calibration_audit_with`](crate::EdfReader::calibration_audit_with)
+#[derive(Debug, Clone)]
+pub struct CalibrationOptions {
+    min_span_fraction: f64,
+    max_rail_fraction: f64,
+}
+
+impl CalibrationOptions {
+    /// Creates options with the default thresholds
+    pub fn new() -> Self {
+        CalibrationOptions {
+            min_span_fraction: 0.01,
+            max_rail_fraction: 0.01,
+        }
+    }
+
+    /// Smallest acceptable ratio of observed to declared physical span (default: `0.01`)
+    pub fn min_span_fraction(mut self, fraction: f64) -> Self {
+        self.min_span_fraction = fraction;
+        self
+    }
+
+    /// Largest acceptable fraction of samples at the digital minimum or
+    /// maximum (default: `0.01`)
+    pub fn max_rail_fraction(mut self, fraction: f64) -> Self {
+        self.max_rail_fraction = fraction;
+        self
+    }
+}
+
+impl Default for CalibrationOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Suspicious calibration of one signal
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CalibrationFinding {
+    /// The data spans less than the configured fraction of the declared physical range
+    NarrowSpan {
+        signal: usize,
+        label: String,
+        observed_min: f64,
+        observed_max: f64,
+        physical_min: f64,
+        physical_max: f64,
+        /// Observed span divided by declared span
+        fraction: f64,
+    },
+    /// Too many samples sit at the digital minimum or maximum (clipping)
+    RailHugging {
+        signal: usize,
+        label: String,
+        samples_at_min: i64,
+        samples_at_max: i64,
+        total_samples: i64,
+    },
+    /// The physical dimension is empty, so the values have no unit
+    EmptyDimension {
+        signal: usize,
+        label: String,
+    },
+}
+
+impl CalibrationFinding {
+    /// Index of the affected signal
+    pub fn signal(&self) -> usize {
+        match self {
+            CalibrationFinding::NarrowSpan { signal, .. }
+            | CalibrationFinding::RailHugging { signal, .. }
+            | CalibrationFinding::EmptyDimension { signal, .. } => *signal,
+        }
+    }
+}
+
+impl fmt::Display for CalibrationFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CalibrationFinding::NarrowSpan { signal, label, observed_min, observed_max, physical_min, physical_max, fraction } => {
+                write!(f, "Signal {} ({}): data spans {}..{}, only {:.4}% of the declared range {}..{}",
+                       signal, label, observed_min, observed_max, fraction * 100.0, physical_min, physical_max)
+            }
+            CalibrationFinding::RailHugging { signal, label, samples_at_min, samples_at_max, total_samples } => {
+                write!(f, "Signal {} ({}): {} of {} samples at the digital minimum, {} at the maximum",
+                       signal, label, samples_at_min, total_samples, samples_at_max)
+            }
+            CalibrationFinding::EmptyDimension { signal, label } => {
+                write!(f, "Signal {} ({}): physical dimension is empty", signal, label)
+            }
+        }
+    }
+}
+
+/// 一次扫描得到的单个信号的数字值统计
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) struct DigitalExtremes {
+    /// 限制到数字范围后的最小值/最大值，没有样本时为 `None`
+    pub range: Option<(i32, i32)>,
+    /// 位于（或超出）数字最小值的样本数
+    pub at_min: i64,
+    /// 位于（或超出）数字最大值的样本数
+    pub at_max: i64,
+    /// 样本总数
+    pub count: i64,
+}
+
+impl DigitalExtremes {
+    pub(crate) fn add(&mut self, raw: i32, param: &SignalParam) {
+        let digital = raw.max(param.digital_min).min(param.digital_max);
+        self.range = Some(match self.range {
+            Some((min, max)) => (min.min(digital), max.max(digital)),
+            None => (digital, digital),
+        });
+        if raw <= param.digital_min {
+            self.at_min += 1;
+        }
+        if raw >= param.digital_max {
+            self.at_max += 1;
+        }
+        self.count += 1;
+    }
+}
+
+/// 根据扫描结果生成一个信号的审计结果
+pub(crate) fn audit_signal(
+    signal: usize,
+    param: &SignalParam,
+    extremes: &DigitalExtremes,
+    options: &CalibrationOptions,
+) -> Vec<CalibrationFinding> {
+    let mut findings = Vec::new();
+    let label = param.label.trim().to_string();
+
+    if let Some((min, max)) = extremes.range {
+        let (a, b) = (param.to_physical(min), param.to_physical(max));
+        let (observed_min, observed_max) = (a.min(b), a.max(b));
+        let declared_span = (param.physical_max - param.physical_min).abs();
+        let fraction = (observed_max - observed_min) / declared_span;
+        if fraction < options.min_span_fraction {
+            findings.push(CalibrationFinding::NarrowSpan {
+                signal,
+                label: label.clone(),
+                observed_min,
+                observed_max,
+                physical_min: param.physical_min,
+                physical_max: param.physical_max,
+                fraction,
+            });
+        }
+
+        let rail_fraction = (extremes.at_min + extremes.at_max) as f64 / extremes.count as f64;
+        if rail_fraction > options.max_rail_fraction {
+            findings.push(CalibrationFinding::RailHugging {
+                signal,
+                label: label.clone(),
+                samples_at_min: extremes.at_min,
+                samples_at_max: extremes.at_max,
+                total_samples: extremes.count,
+            });
+        }
+    }
+
+    if param.physical_dimension.trim_matches(|c: char| c == '\0' || c.is_whitespace()).is_empty() {
+        findings.push(CalibrationFinding::EmptyDimension { signal, label });
+    }
+
+    findings
+}
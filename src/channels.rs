@@ -0,0 +1,302 @@
+//! Writing from independent per-channel streams
+//!
+//! When every signal is acquired on its own thread, samples arrive in
+//! unrelated chunk sizes and at unrelated times. [`ChannelWriter`] turns an
+//! [`EdfWriter`] into one [`ChannelSender`] per signal; each sender queues its
+//! samples, and as soon as every channel has at least one data record worth
+//! of samples queued, the record is assembled and written. Queues are
+//! bounded, so a fast channel blocks in [`ChannelSender::send`] until the
+//! slower channels catch up.
+//!
+//! The file is finalized when the last sender is closed. Samples left over
+//! at that point, which do not make up a complete record on every channel,
+//! are handled according to the [`RaggedTail`] policy.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use edfplus::{EdfWriter, SignalParam};
+//!
+//! let mut writer = EdfWriter::create("channels.edf")?;
+//! for (label, rate) in [("EEG", 256), ("Resp", 25)] {
+//!     writer.add_signal(SignalParam {
+//!         label: label.to_string(),
+//!         samples_in_file: 0,
+//!         physical_max: 100.0, physical_min: -100.0,
+//!         digital_max: 32767, digital_min: -32768,
+//!         samples_per_record: rate,
+//!         physical_dimension: "uV".to_string(),
+//!         prefilter: "".to_string(),
+//!         transducer: "".to_string(),
+//!         reserved: String::new(),
+//!     })?;
+//! }
+//!
+//! let handles: Vec<_> = writer.into_channels()
+//!     .into_iter()
+//!     .enumerate()
+//!     .map(|(i, mut sender)| std::thread::spawn(move || {
+//!         let rate = if i == 0 { 256 } else { 25 };
+//!         // Each channel delivers 3 seconds of data in its own chunk size
+//!         for chunk in vec![1.0; rate * 3].chunks(7 + i) {
+//!             sender.send(chunk)?;
+//!         }
+//!         sender.close()
+//!     }))
+//!     .collect();
+//!
+//! let mut summaries = Vec::new();
+//! for handle in handles {
+//!     summaries.extend(handle.join().unwrap()?);
+//! }
+//! // Exactly one close() finalized the file
+//! assert_eq!(summaries.len(), 1);
+//! assert_eq!(summaries[0].datarecords, 3);
+//! # std::fs::remove_file("channels.edf").ok();
+//! # Ok::<(), edfplus::EdfError>(())
+//! ```
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+use crate::error::{EdfError, Result};
+use crate::writer::{EdfWriter, WriteSummary};
+
+/// What to do with samples that do not fill a final data record
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RaggedTail {
+    /// Discard them; the file ends with the last complete record
+    #[default]
+    Drop,
+
+    /// Pad every channel with physical zeros to complete the record(s)
+    PadWithZeros,
+}
+
+/// Splits an [`EdfWriter`] into one [`ChannelSender`] per signal
+///
+/// [`EdfWriter::into_channels`] is a shortcut for
+/// `ChannelWriter::new(writer).into_senders()` with the default options.
+pub struct ChannelWriter {
+    writer: EdfWriter,
+    queue_records: usize,
+    ragged_tail: RaggedTail,
+}
+
+impl ChannelWriter {
+    /// Wraps a configured writer whose signals have all been added
+    pub fn new(writer: EdfWriter) -> Self {
+        ChannelWriter {
+            writer,
+            queue_records: 4,
+            ragged_tail: RaggedTail::default(),
+        }
+    }
+
+    /// Maximum number of data records each channel may queue (default: 4)
+    ///
+    /// A sender whose queue is full blocks until a record has been written.
+    /// Values below 1 are treated as 1.
+    pub fn queue_records(mut self, records: usize) -> Self {
+        self.queue_records = records.max(1);
+        self
+    }
+
+    /// Policy for leftover samples when the last sender closes (default: [`RaggedTail::Drop`])
+    pub fn ragged_tail(mut self, policy: RaggedTail) -> Self {
+        self.ragged_tail = policy;
+        self
+    }
+
+    /// Creates the senders, in the order the signals were added
+    pub fn into_senders(self) -> Vec<ChannelSender> {
+        let samples_per_record: Vec<usize> = self.writer.signals().iter()
+            .map(|signal| signal.samples_per_record as usize)
+            .collect();
+        let channel_count = samples_per_record.len();
+
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                writer: Some(self.writer),
+                queues: vec![VecDeque::new(); channel_count],
+                closed: vec![false; channel_count],
+                failed: false,
+            }),
+            progress: Condvar::new(),
+            samples_per_record,
+            queue_records: self.queue_records,
+            ragged_tail: self.ragged_tail,
+        });
+
+        (0..channel_count)
+            .map(|channel| ChannelSender {
+                shared: Arc::clone(&shared),
+                channel,
+                closed: false,
+            })
+            .collect()
+    }
+}
+
+/// Sending half for one signal, see the [module documentation](self)
+///
+/// Senders can be moved to other threads. Dropping a sender without
+/// calling [`close`](Self::close) closes its channel as well.
+pub struct ChannelSender {
+    shared: Arc<Shared>,
+    channel: usize,
+    closed: bool,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    progress: Condvar,
+    samples_per_record: Vec<usize>,
+    queue_records: usize,
+    ragged_tail: RaggedTail,
+}
+
+struct State {
+    writer: Option<EdfWriter>,
+    queues: Vec<VecDeque<f64>>,
+    closed: Vec<bool>,
+    failed: bool,
+}
+
+impl ChannelSender {
+    /// Index of the signal this sender feeds
+    pub fn channel(&self) -> usize {
+        self.channel
+    }
+
+    /// Queues physical samples for this channel
+    ///
+    /// Writes every data record that becomes complete. Blocks while this
+    /// channel's queue is full.
+    ///
+    /// # Errors
+    ///
+    /// * `EdfError::InvalidFormat` - Another channel was closed while this
+    ///   channel's queue is full, or an earlier write failed
+    /// * Any error from [`EdfWriter::write_samples`]
+    pub fn send(&mut self, samples: &[f64]) -> Result<()> {
+        let shared = &*self.shared;
+        let capacity = shared.queue_records * shared.samples_per_record[self.channel];
+        let mut remaining = samples;
+        let mut state = shared.lock()?;
+
+        while !remaining.is_empty() {
+            // 队列已满时等待其他通道跟上（背压）
+            while state.queues[self.channel].len() >= capacity {
+                // 已关闭且样本不足一个记录的通道无法再组成记录，避免死锁
+                let starved = (0..state.closed.len())
+                    .find(|&ch| state.closed[ch] && state.queues[ch].len() < shared.samples_per_record[ch]);
+                if let Some(closed) = starved {
+                    return Err(EdfError::InvalidFormat(
+                        format!("Channel {} is closed, channel {} cannot complete a data record", closed, self.channel)
+                    ));
+                }
+                state = shared.progress.wait(state)
+                    .map_err(|_| EdfError::InvalidFormat("Channel writer lock poisoned".to_string()))?;
+                if state.failed {
+                    return Err(EdfError::InvalidFormat("Channel writer failed earlier".to_string()));
+                }
+            }
+
+            let free = capacity - state.queues[self.channel].len();
+            let (now, later) = remaining.split_at(free.min(remaining.len()));
+            state.queues[self.channel].extend(now);
+            remaining = later;
+
+            shared.write_complete_records(&mut state)?;
+        }
+
+        Ok(())
+    }
+
+    /// Closes this channel
+    ///
+    /// The sender that closes last writes the ragged tail, finalizes the
+    /// file and returns `Some` with the [`WriteSummary`]; all others return
+    /// `None`.
+    ///
+    /// # Errors
+    ///
+    /// * Any error from [`EdfWriter::write_samples`] or [`EdfWriter::finalize`]
+    pub fn close(mut self) -> Result<Option<WriteSummary>> {
+        self.closed = true;
+        self.shared.close_channel(self.channel)
+    }
+}
+
+impl Drop for ChannelSender {
+    fn drop(&mut self) {
+        if !self.closed {
+            self.shared.close_channel(self.channel).ok();
+        }
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> Result<MutexGuard<'_, State>> {
+        let state = self.state.lock()
+            .map_err(|_| EdfError::InvalidFormat("Channel writer lock poisoned".to_string()))?;
+        if state.failed {
+            return Err(EdfError::InvalidFormat("Channel writer failed earlier".to_string()));
+        }
+        Ok(state)
+    }
+
+    /// 当所有通道都有足够的样本时，按顺序写入完整的数据记录
+    fn write_complete_records(&self, state: &mut State) -> Result<()> {
+        while state.queues.iter().zip(&self.samples_per_record).all(|(queue, &spr)| queue.len() >= spr) {
+            let record: Vec<Vec<f64>> = state.queues.iter_mut().zip(&self.samples_per_record)
+                .map(|(queue, &spr)| queue.drain(..spr).collect())
+                .collect();
+            self.write_record(state, &record)?;
+        }
+        Ok(())
+    }
+
+    fn write_record(&self, state: &mut State, record: &[Vec<f64>]) -> Result<()> {
+        let result = match state.writer.as_mut() {
+            Some(writer) => writer.write_samples(record),
+            None => Err(EdfError::InvalidFormat("Channel writer is already finalized".to_string())),
+        };
+        if result.is_err() {
+            state.failed = true;
+        }
+        self.progress.notify_all();
+        result
+    }
+
+    fn close_channel(&self, channel: usize) -> Result<Option<WriteSummary>> {
+        let mut state = self.lock()?;
+        state.closed[channel] = true;
+        self.progress.notify_all();
+
+        if !state.closed.iter().all(|&closed| closed) {
+            return Ok(None);
+        }
+
+        // 处理剩余的不完整记录
+        if self.ragged_tail == RaggedTail::PadWithZeros {
+            while state.queues.iter().any(|queue| !queue.is_empty()) {
+                let record: Vec<Vec<f64>> = state.queues.iter_mut().zip(&self.samples_per_record)
+                    .map(|(queue, &spr)| {
+                        let mut samples: Vec<f64> = queue.drain(..spr.min(queue.len())).collect();
+                        samples.resize(spr, 0.0);
+                        samples
+                    })
+                    .collect();
+                self.write_record(&mut state, &record)?;
+            }
+        }
+        state.queues.iter_mut().for_each(VecDeque::clear);
+
+        match state.writer.take() {
+            Some(writer) => writer.finalize().map(Some),
+            None => Ok(None),
+        }
+    }
+}
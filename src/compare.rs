@@ -0,0 +1,368 @@
+//! Data-level comparison of two EDF+ files
+//!
+//! [`compare`] answers "do these two files contain the same recording?",
+//! which is what matters when validating converters: signal data and
+//! annotations are compared, while identification fields can optionally be
+//! ignored. Samples are streamed in chunks, so files of any size can be
+//! compared in constant memory.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use edfplus::EdfReader;
+//! use edfplus::compare::{compare, CompareOptions};
+//!
+//! # edfplus::doctest_utils::create_simple_test_file("compare_a.edf")?;
+//! # edfplus::doctest_utils::create_simple_test_file("compare_b.edf")?;
+//! let mut a = EdfReader::open("compare_a.edf")?;
+//! let mut b = EdfReader::open("compare_b.edf")?;
+//!
+//! let report = compare(&mut a, &mut b, &CompareOptions::new().ignore_metadata(true))?;
+//! if !report.is_identical() {
+//!     for mismatch in &report.mismatches {
+//!         println!("{:?}", mismatch);
+//!     }
+//! }
+//! # assert!(report.is_identical());
+//! # drop(a);
+//! # drop(b);
+//! # std::fs::remove_file("compare_a.edf").ok();
+//! # std::fs::remove_file("compare_b.edf").ok();
+//! # Ok::<(), edfplus::EdfError>(())
+//! ```
+
+use std::fmt;
+
+use crate::error::{EdfError, Result};
+use crate::reader::EdfReader;
+use crate::types::Annotation;
+
+/// 每次从文件读取的样本数
+const CHUNK_SIZE: usize = 4096;
+
+/// Options for [`compare`]
+#[derive(Debug, Clone)]
+pub struct CompareOptions {
+    ignore_metadata: bool,
+    tolerance: Option<f64>,
+    signals: Option<Vec<usize>>,
+    annotations: bool,
+    max_mismatches: usize,
+}
+
+impl CompareOptions {
+    /// Creates options for a strict comparison
+    ///
+    /// By default metadata and annotations are compared, samples must be
+    /// digitally equal, all signals are compared and up to 100 mismatches
+    /// are recorded.
+    pub fn new() -> Self {
+        CompareOptions {
+            ignore_metadata: false,
+            tolerance: None,
+            signals: None,
+            annotations: true,
+            max_mismatches: 100,
+        }
+    }
+
+    /// Skips descriptive fields (default: `false`)
+    ///
+    /// When enabled, patient and recording identification, the reserved
+    /// fields, the start date/time and the textual signal parameters (label,
+    /// transducer, physical dimension, prefilter) are not compared.
+    pub fn ignore_metadata(mut self, ignore: bool) -> Self {
+        self.ignore_metadata = ignore;
+        self
+    }
+
+    /// Compares physical values within `tolerance` instead of requiring
+    /// equal digital values
+    ///
+    /// Use this when the files may have been written with a different
+    /// calibration; the physical/digital ranges are then not compared.
+    pub fn physical_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = Some(tolerance.abs());
+        self
+    }
+
+    /// Restricts the comparison to the given signal indices
+    pub fn signals(mut self, signals: &[usize]) -> Self {
+        self.signals = Some(signals.to_vec());
+        self
+    }
+
+    /// Compares the annotation lists (default: `true`)
+    pub fn annotations(mut self, compare: bool) -> Self {
+        self.annotations = compare;
+        self
+    }
+
+    /// Maximum number of mismatches kept in the report (default: 100)
+    ///
+    /// Mismatches beyond this limit are still counted in
+    /// [`CompareReport::total_mismatches`].
+    pub fn max_mismatches(mut self, max: usize) -> Self {
+        self.max_mismatches = max;
+        self
+    }
+}
+
+impl Default for CompareOptions {
+    fn default() -> Self {
+        CompareOptions::new()
+    }
+}
+
+/// A single difference found by [`compare`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mismatch {
+    /// The files have a different number of signals
+    SignalCount { a: usize, b: usize },
+    /// A patient/recording header field differs
+    Metadata { field: &'static str, a: String, b: String },
+    /// A signal parameter differs
+    SignalParameter { signal: usize, field: &'static str, a: String, b: String },
+    /// A signal has a different number of samples; the common prefix is compared
+    SampleCount { signal: usize, a: i64, b: i64 },
+    /// A sample value differs
+    ///
+    /// The values are digital values, or physical values when
+    /// [`CompareOptions::physical_tolerance`] is used.
+    Sample { signal: usize, sample: i64, a: f64, b: f64 },
+    /// The annotation at `index` (in onset order) differs or exists in one file only
+    Annotation { index: usize, a: Option<Annotation>, b: Option<Annotation> },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mismatch::SignalCount { a, b } => write!(f, "signal count: {} vs {}", a, b),
+            Mismatch::Metadata { field, a, b } => write!(f, "header {}: {:?} vs {:?}", field, a, b),
+            Mismatch::SignalParameter { signal, field, a, b } => {
+                write!(f, "signal {} {}: {:?} vs {:?}", signal, field, a, b)
+            }
+            Mismatch::SampleCount { signal, a, b } => {
+                write!(f, "signal {} sample count: {} vs {}", signal, a, b)
+            }
+            Mismatch::Sample { signal, sample, a, b } => {
+                write!(f, "signal {} sample {}: {} vs {}", signal, sample, a, b)
+            }
+            Mismatch::Annotation { index, a, b } => {
+                let describe = |annotation: &Option<Annotation>| match annotation {
+                    Some(annotation) => format!("{:?} at {}s", annotation.description,
+                        crate::time::format_seconds(annotation.onset)),
+                    None => "missing".to_string(),
+                };
+                write!(f, "annotation {}: {} vs {}", index, describe(a), describe(b))
+            }
+        }
+    }
+}
+
+/// Result of [`compare`]
+#[derive(Debug, Clone, Default)]
+pub struct CompareReport {
+    /// The first mismatches found, up to [`CompareOptions::max_mismatches`]
+    pub mismatches: Vec<Mismatch>,
+    /// Total number of mismatches, including those not kept in `mismatches`
+    pub total_mismatches: usize,
+}
+
+impl CompareReport {
+    /// Returns `true` if no differences were found
+    pub fn is_identical(&self) -> bool {
+        self.total_mismatches == 0
+    }
+
+    fn push(&mut self, mismatch: Mismatch, max: usize) {
+        if self.mismatches.len() < max {
+            self.mismatches.push(mismatch);
+        }
+        self.total_mismatches += 1;
+    }
+}
+
+/// Lists one mismatch per line, followed by the number of omitted ones
+impl fmt::Display for CompareReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_identical() {
+            return write!(f, "no differences");
+        }
+        writeln!(f, "{} difference(s):", self.total_mismatches)?;
+        for mismatch in &self.mismatches {
+            writeln!(f, "  {}", mismatch)?;
+        }
+        let omitted = self.total_mismatches - self.mismatches.len();
+        if omitted > 0 {
+            writeln!(f, "  ... and {} more", omitted)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares the signal data and annotations of two files
+///
+/// Samples are read in chunks from both readers; the read positions of the
+/// compared signals are restored afterwards.
+///
+/// # Arguments
+///
+/// * `a` - First file
+/// * `b` - Second file
+/// * `options` - What to compare and how strictly
+///
+/// # Errors
+///
+/// * `EdfError::InvalidSignalIndex` - A requested signal does not exist in either file
+/// * `EdfError::Io` - Reading sample data failed
+pub fn compare(a: &mut EdfReader, b: &mut EdfReader, options: &CompareOptions) -> Result<CompareReport> {
+    let mut report = CompareReport::default();
+    let max = options.max_mismatches;
+
+    if !options.ignore_metadata {
+        compare_metadata(a, b, &mut report, max);
+    }
+
+    let count_a = a.header().signals.len();
+    let count_b = b.header().signals.len();
+    if count_a != count_b {
+        report.push(Mismatch::SignalCount { a: count_a, b: count_b }, max);
+    }
+
+    let signals: Vec<usize> = match &options.signals {
+        Some(signals) => {
+            for &signal in signals {
+                if signal >= count_a.max(count_b) {
+                    return Err(EdfError::InvalidSignalIndex(signal));
+                }
+            }
+            signals.iter().copied().filter(|&s| s < count_a.min(count_b)).collect()
+        }
+        None => (0..count_a.min(count_b)).collect(),
+    };
+
+    for signal in signals {
+        compare_parameters(a, b, signal, options, &mut report);
+        compare_samples(a, b, signal, options, &mut report)?;
+    }
+
+    if options.annotations {
+        compare_annotations(a.annotations(), b.annotations(), &mut report, max);
+    }
+
+    Ok(report)
+}
+
+fn compare_metadata(a: &EdfReader, b: &EdfReader, report: &mut CompareReport, max: usize) {
+    let (ha, hb) = (a.header(), b.header());
+    let fields: [(&'static str, &String, &String); 10] = [
+        ("patient_code", &ha.patient_code, &hb.patient_code),
+        ("sex", &ha.sex, &hb.sex),
+        ("birthdate", &ha.birthdate, &hb.birthdate),
+        ("patient_name", &ha.patient_name, &hb.patient_name),
+        ("patient_additional", &ha.patient_additional, &hb.patient_additional),
+        ("admin_code", &ha.admin_code, &hb.admin_code),
+        ("technician", &ha.technician, &hb.technician),
+        ("equipment", &ha.equipment, &hb.equipment),
+        ("recording_additional", &ha.recording_additional, &hb.recording_additional),
+        ("reserved_main", &ha.reserved_main, &hb.reserved_main),
+    ];
+    for (field, va, vb) in fields {
+        // 字段末尾的填充字符（空格或NUL）不算差异
+        let padding: &[char] = &[' ', '\0'];
+        let (va, vb) = (va.trim_end_matches(padding), vb.trim_end_matches(padding));
+        if va != vb {
+            report.push(Mismatch::Metadata { field, a: va.to_string(), b: vb.to_string() }, max);
+        }
+    }
+
+    if ha.start_datetime() != hb.start_datetime() {
+        report.push(Mismatch::Metadata {
+            field: "start_datetime",
+            a: ha.start_datetime().to_string(),
+            b: hb.start_datetime().to_string(),
+        }, max);
+    }
+}
+
+fn compare_parameters(a: &EdfReader, b: &EdfReader, signal: usize, options: &CompareOptions, report: &mut CompareReport) {
+    let (sa, sb) = (&a.header().signals[signal], &b.header().signals[signal]);
+    let mut fields: Vec<(&'static str, String, String)> = vec![
+        ("samples_per_record", sa.samples_per_record.to_string(), sb.samples_per_record.to_string()),
+    ];
+    // 比较物理值时校准参数可以不同
+    if options.tolerance.is_none() {
+        fields.push(("physical_min", sa.physical_min.to_string(), sb.physical_min.to_string()));
+        fields.push(("physical_max", sa.physical_max.to_string(), sb.physical_max.to_string()));
+        fields.push(("digital_min", sa.digital_min.to_string(), sb.digital_min.to_string()));
+        fields.push(("digital_max", sa.digital_max.to_string(), sb.digital_max.to_string()));
+    }
+    if !options.ignore_metadata {
+        fields.push(("label", sa.label.clone(), sb.label.clone()));
+        fields.push(("transducer", sa.transducer.clone(), sb.transducer.clone()));
+        fields.push(("physical_dimension", sa.physical_dimension.clone(), sb.physical_dimension.clone()));
+        fields.push(("prefilter", sa.prefilter.clone(), sb.prefilter.clone()));
+        fields.push(("reserved", sa.reserved.clone(), sb.reserved.clone()));
+    }
+
+    for (field, va, vb) in fields {
+        if va != vb {
+            report.push(Mismatch::SignalParameter { signal, field, a: va, b: vb }, options.max_mismatches);
+        }
+    }
+}
+
+fn compare_samples(a: &mut EdfReader, b: &mut EdfReader, signal: usize, options: &CompareOptions, report: &mut CompareReport) -> Result<()> {
+    let samples_a = a.header().signals[signal].samples_in_file;
+    let samples_b = b.header().signals[signal].samples_in_file;
+    if samples_a != samples_b {
+        report.push(Mismatch::SampleCount { signal, a: samples_a, b: samples_b }, options.max_mismatches);
+    }
+
+    // 保存读取位置，比较结束后恢复
+    let position_a = a.tell(signal)?;
+    let position_b = b.tell(signal)?;
+    a.rewind(signal)?;
+    b.rewind(signal)?;
+
+    let total = samples_a.min(samples_b);
+    let mut index = 0i64;
+    while index < total {
+        let count = (CHUNK_SIZE as i64).min(total - index) as usize;
+        match options.tolerance {
+            Some(tolerance) => {
+                let chunk_a = a.read_physical_samples(signal, count)?;
+                let chunk_b = b.read_physical_samples(signal, count)?;
+                for (offset, (&va, &vb)) in chunk_a.iter().zip(&chunk_b).enumerate() {
+                    if (va - vb).abs() > tolerance {
+                        report.push(Mismatch::Sample { signal, sample: index + offset as i64, a: va, b: vb }, options.max_mismatches);
+                    }
+                }
+            }
+            None => {
+                let chunk_a = a.read_digital_samples(signal, count)?;
+                let chunk_b = b.read_digital_samples(signal, count)?;
+                for (offset, (&va, &vb)) in chunk_a.iter().zip(&chunk_b).enumerate() {
+                    if va != vb {
+                        report.push(Mismatch::Sample { signal, sample: index + offset as i64, a: va as f64, b: vb as f64 }, options.max_mismatches);
+                    }
+                }
+            }
+        }
+        index += count as i64;
+    }
+
+    a.seek(signal, position_a)?;
+    b.seek(signal, position_b)?;
+    Ok(())
+}
+
+fn compare_annotations(a: &[Annotation], b: &[Annotation], report: &mut CompareReport, max: usize) {
+    for index in 0..a.len().max(b.len()) {
+        let (va, vb) = (a.get(index), b.get(index));
+        if va != vb {
+            report.push(Mismatch::Annotation { index, a: va.cloned(), b: vb.cloned() }, max);
+        }
+    }
+}
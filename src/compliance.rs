@@ -0,0 +1,103 @@
+//! Compliance profiles for written files
+//!
+//! By default the writer accepts some input that review software may flag
+//! in a finished file: header texts are truncated to their field width,
+//! annotation descriptions to 40 bytes, patient subfields are written as
+//! given, and nonstandard labels only show up in the
+//! [`WriteSummary`](crate::WriteSummary). [`Profile::Strict`] turns all of
+//! these into violations, see [`EdfWriter::compliance_profile`](crate::EdfWriter::compliance_profile).
+//!
+//! A strict writer keeps writing when it finds a violation and collects
+//! them; [`EdfWriter::finalize`](crate::EdfWriter::finalize) then fails with
+//! `EdfError::ComplianceViolations` listing every violation at once, so all
+//! problems of an export can be fixed in one go.
+//!
+//! # Rules
+//!
+//! | [`Rule`] | Checked |
+//! |---|---|
+//! | [`Length`](Rule::Length) | Header texts and numbers fit their field; annotation descriptions fit the 40 bytes written |
+//! | [`Charset`](Rule::Charset) | Header texts are printable ASCII (unless [`sanitize`](crate::EdfWriter::sanitize)d) |
+//! | [`PatientFormat`](Rule::PatientFormat) | Sex is `M`, `F` or `X`, the birthdate is `dd-MMM-yyyy` or `X`, subfields contain no spaces |
+//! | [`Label`](Rule::Label) | Signal labels follow the EDF+ standard texts, see [`labels`](crate::labels) |
+//! | [`RecordSize`](Rule::RecordSize) | Data records are at most 61440 bytes, even if oversized records are allowed |
+//! | [`AnnotationPlacement`](Rule::AnnotationPlacement) | Every annotation is stored in the data record it starts in and ends within the recording |
+//!
+//! The start date of the header and of the recording identification and
+//! the timekeeping annotations are always written consistently and need no
+//! check.
+
+use std::fmt;
+
+/// Set of validations applied by [`EdfWriter`](crate::EdfWriter), see the
+/// [module documentation](self)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Profile {
+    /// The checks selected with the individual writer settings
+    #[default]
+    Relaxed,
+
+    /// All checks, violations fail [`finalize`](crate::EdfWriter::finalize)
+    Strict,
+}
+
+/// Rule broken by a [`Violation`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Rule {
+    /// A value does not fit its field and would be truncated
+    Length,
+    /// A header text is not printable ASCII
+    Charset,
+    /// A patient subfield is malformed
+    PatientFormat,
+    /// A signal label does not follow the standard texts
+    Label,
+    /// A data record is larger than the EDF limit
+    RecordSize,
+    /// An annotation is not stored where it belongs
+    AnnotationPlacement,
+}
+
+impl Rule {
+    /// Short name of the rule, e.g. `"charset"`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Rule::Length => "length",
+            Rule::Charset => "charset",
+            Rule::PatientFormat => "patient format",
+            Rule::Label => "label",
+            Rule::RecordSize => "record size",
+            Rule::AnnotationPlacement => "annotation placement",
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A single compliance problem found by a strict writer
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Violation {
+    /// Rule that is broken
+    pub rule: Rule,
+    /// Description of the problem, naming the field or annotation
+    pub message: String,
+}
+
+impl Violation {
+    pub(crate) fn new(rule: Rule, message: impl Into<String>) -> Self {
+        Violation { rule, message: message.into() }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.rule, self.message)
+    }
+}
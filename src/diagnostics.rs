@@ -0,0 +1,35 @@
+//! Internal diagnostics macros
+//!
+//! With the `tracing` feature enabled these forward to the corresponding
+//! `tracing` macros under the `edfplus` target. Without it they expand to
+//! nothing, so parsing pays no cost and behaves identically either way.
+
+#[cfg(feature = "tracing")]
+macro_rules! edf_warn {
+    ($($arg:tt)*) => { tracing::warn!(target: "edfplus", $($arg)*) };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! edf_warn {
+    ($($arg:tt)*) => { () };
+}
+
+#[cfg(feature = "tracing")]
+macro_rules! edf_info {
+    ($($arg:tt)*) => { tracing::info!(target: "edfplus", $($arg)*) };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! edf_info {
+    ($($arg:tt)*) => { () };
+}
+
+#[cfg(feature = "tracing")]
+macro_rules! edf_debug {
+    ($($arg:tt)*) => { tracing::debug!(target: "edfplus", $($arg)*) };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! edf_debug {
+    ($($arg:tt)*) => { () };
+}
@@ -0,0 +1,197 @@
+//! Header-independent fingerprints of the stored signal data
+//!
+//! [`EdfReader::signal_digest`](crate::EdfReader::signal_digest) and
+//! [`EdfReader::data_digest`](crate::EdfReader::data_digest) hash the raw
+//! stored sample bytes, streamed record by record. The header is never
+//! hashed, so two copies of a recording with edited patient fields, labels or
+//! start times produce the same digest, which makes the digests suitable for
+//! finding duplicates in an archive.
+//!
+//! SHA-256 is built in. Any other hash function can be plugged in by
+//! implementing [`DigestHasher`] and calling the `*_with` variants.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use edfplus::EdfReader;
+//! use edfplus::digest::{DigestHasher, Sha256};
+//!
+//! # edfplus::doctest_utils::create_simple_test_file("digest.edf")?;
+//! let mut reader = EdfReader::open("digest.edf")?;
+//!
+//! let digest = reader.data_digest()?;
+//! let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+//! println!("{}", hex);
+//!
+//! // A custom hasher: count the hashed bytes
+//! struct ByteCount(usize);
+//! impl DigestHasher for ByteCount {
+//!     type Output = usize;
+//!     fn update(&mut self, data: &[u8]) { self.0 += data.len(); }
+//!     fn finish(self) -> usize { self.0 }
+//! }
+//! let bytes = reader.signal_digest_with(0, ByteCount(0))?;
+//! assert_eq!(bytes, reader.header().signals[0].samples_in_file as usize * 2);
+//! # assert_eq!(reader.signal_digest(0)?, reader.signal_digest_with(0, Sha256::new())?);
+//! # drop(reader);
+//! # std::fs::remove_file("digest.edf").ok();
+//! # Ok::<(), edfplus::EdfError>(())
+//! ```
+
+/// Incremental hash function used for data digests
+pub trait DigestHasher {
+    /// Final digest type
+    type Output;
+
+    /// Feeds the next chunk of bytes
+    fn update(&mut self, data: &[u8]);
+
+    /// Consumes the hasher and returns the digest
+    fn finish(self) -> Self::Output;
+}
+
+/// SHA-256 hasher (FIPS 180-4)
+#[derive(Debug, Clone)]
+pub struct Sha256 {
+    state: [u32; 8],
+    block: [u8; 64],
+    block_len: usize,
+    total_len: u64,
+}
+
+const INITIAL_STATE: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a,
+    0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const ROUND_CONSTANTS: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+impl Sha256 {
+    /// Creates a hasher with the standard initial state
+    pub fn new() -> Self {
+        Sha256 {
+            state: INITIAL_STATE,
+            block: [0; 64],
+            block_len: 0,
+            total_len: 0,
+        }
+    }
+
+    /// 处理一个完整的64字节块
+    fn compress(&mut self) {
+        let mut w = [0u32; 64];
+        for (i, word) in self.block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = self.state;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = h.wrapping_add(s1).wrapping_add(ch).wrapping_add(ROUND_CONSTANTS[i]).wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+
+        for (state, value) in self.state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+}
+
+impl Default for Sha256 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DigestHasher for Sha256 {
+    type Output = [u8; 32];
+
+    fn update(&mut self, mut data: &[u8]) {
+        self.total_len += data.len() as u64;
+        while !data.is_empty() {
+            let take = (64 - self.block_len).min(data.len());
+            self.block[self.block_len..self.block_len + take].copy_from_slice(&data[..take]);
+            self.block_len += take;
+            data = &data[take..];
+            if self.block_len == 64 {
+                self.compress();
+                self.block_len = 0;
+            }
+        }
+    }
+
+    fn finish(mut self) -> [u8; 32] {
+        // 填充：0x80，补零，最后8字节为消息的比特长度
+        let bit_len = self.total_len.wrapping_mul(8);
+        self.block[self.block_len] = 0x80;
+        self.block[self.block_len + 1..].fill(0);
+        if self.block_len >= 56 {
+            self.compress();
+            self.block.fill(0);
+        }
+        self.block[56..].copy_from_slice(&bit_len.to_be_bytes());
+        self.compress();
+
+        let mut digest = [0u8; 32];
+        for (bytes, word) in digest.chunks_exact_mut(4).zip(self.state) {
+            bytes.copy_from_slice(&word.to_be_bytes());
+        }
+        digest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sha256_hex(chunks: &[&[u8]]) -> String {
+        let mut hasher = Sha256::new();
+        for chunk in chunks {
+            hasher.update(chunk);
+        }
+        hasher.finish().iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn test_sha256_known_vectors() {
+        assert_eq!(sha256_hex(&[]),
+                   "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        assert_eq!(sha256_hex(&[b"abc"]),
+                   "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert_eq!(sha256_hex(&[b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"]),
+                   "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1");
+
+        // 分块输入与一次性输入结果相同
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 7) as u8).collect();
+        let (a, b) = data.split_at(333);
+        assert_eq!(sha256_hex(&[a, b]), sha256_hex(&[&data]));
+        assert_eq!(sha256_hex(&[&[b'a'; 64]]),
+                   "ffe054fe7ae0cb6dc65c3af9b61d5209f439851db43d0ba5997337df154668eb");
+    }
+}
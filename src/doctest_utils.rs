@@ -23,6 +23,7 @@ pub fn create_simple_test_file<P: AsRef<Path>>(path: P) -> Result<()> {
         physical_dimension: "uV".to_string(),
         prefilter: "HP:0.1Hz LP:70Hz".to_string(),
         transducer: "AgAgCl cup electrodes".to_string(),
+        reserved: String::new(),
     })?;
     
     // Generate one second of 10Hz sine wave data
@@ -56,6 +57,7 @@ pub fn create_multi_channel_test_file<P: AsRef<Path>>(path: P) -> Result<()> {
         physical_dimension: "uV".to_string(),
         prefilter: "HP:0.1Hz LP:70Hz".to_string(),
         transducer: "AgAgCl electrodes".to_string(),
+        reserved: String::new(),
     })?;
     
     // Add ECG signal
@@ -70,6 +72,7 @@ pub fn create_multi_channel_test_file<P: AsRef<Path>>(path: P) -> Result<()> {
         physical_dimension: "mV".to_string(),
         prefilter: "HP:0.1Hz LP:100Hz".to_string(),
         transducer: "Chest electrodes".to_string(),
+        reserved: String::new(),
     })?;
     
     // Generate sample data
@@ -112,6 +115,7 @@ pub fn create_validation_test_file<P: AsRef<Path>>(path: P) -> Result<()> {
         physical_dimension: "uV".to_string(),
         prefilter: "None".to_string(),
         transducer: "Test Sensor".to_string(),
+        reserved: String::new(),
     })?;
     
     // Generate predictable test data
@@ -121,6 +125,48 @@ pub fn create_validation_test_file<P: AsRef<Path>>(path: P) -> Result<()> {
     Ok(())
 }
 
+/// Creates an EDF+D test file with two gaps of different lengths
+/// 
+/// Ten one-second data records of a single 100 Hz "Counter" signal whose
+/// samples count up from 0. Records 0-3 start at 0-3 s, records 4-6 at
+/// 6-8 s and records 7-9 at 1208-1210 s, leaving a 2 s gap after record 3
+/// and a 1199 s gap after record 6.
+pub fn create_discontinuous_test_file<P: AsRef<Path>>(path: P) -> Result<()> {
+    use std::io::{Seek, SeekFrom, Write};
+    
+    let mut writer = EdfWriter::create(&path)?;
+    writer.add_signal(SignalParam {
+        label: "Counter".to_string(),
+        samples_in_file: 0,
+        physical_max: 32767.0,
+        physical_min: -32768.0,
+        digital_max: 32767,
+        digital_min: -32768,
+        samples_per_record: 100,
+        physical_dimension: "".to_string(),
+        prefilter: "".to_string(),
+        transducer: "".to_string(),
+        reserved: String::new(),
+    })?;
+    for record in 0..10 {
+        writer.write_samples(&[(0..100).map(|i| (record * 100 + i) as f64).collect()])?;
+    }
+    writer.finalize()?;
+    
+    // Mark the file as EDF+D and move the records after each gap
+    let mut file = std::fs::OpenOptions::new().write(true).open(&path)?;
+    file.seek(SeekFrom::Start(192))?;
+    file.write_all(b"EDF+D")?;
+    let onsets = [0, 1, 2, 3, 6, 7, 8, 1208, 1209, 1210];
+    for (record, onset) in onsets.iter().enumerate() {
+        let mut tal = format!("+{}\x14\x14\0", onset).into_bytes();
+        tal.resize(120, 0);
+        file.seek(SeekFrom::Start(3 * 256 + record as u64 * 320 + 200))?;
+        file.write_all(&tal)?;
+    }
+    Ok(())
+}
+
 /// Cleanup function to remove test files after doctests
 pub fn cleanup_doctest_files() {
     let test_files = [
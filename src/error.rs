@@ -1,43 +1,238 @@
+//! Error type and edflib-compatible error codes
+//!
+//! Every [`EdfError`] maps to a negative integer code via
+//! [`EdfError::code`], using the values of the `EDFLIB_*` error macros of
+//! edflib where a variant has an equivalent. The code is also shown in
+//! brackets at the start of the `Display` output, e.g.
+//! `[-2] File not found: missing.edf`.
+//!
+//! | Variant | Code | edflib |
+//! |---|---|---|
+//! | `MemoryError` | -1 | `EDFLIB_MALLOC_ERROR` |
+//! | `FileNotFound` | -2 | `EDFLIB_NO_SUCH_FILE_OR_DIRECTORY` |
+//! | `InvalidFormat`, `FormatError`, `MissingField`, `InvalidHeader`, `MalformedTal` | -3 | `EDFLIB_FILE_CONTAINS_FORMAT_ERRORS` |
+//! | `Io` | -5 | `EDFLIB_FILE_READ_ERROR`, also for write errors |
+//! | `UnsupportedFileType` | -7 | `EDFLIB_FILETYPE_ERROR` |
+//! | `InvalidSignalCount` | -9 | `EDFLIB_NUMBER_OF_SIGNALS_INVALID` |
+//! | `DiscontinuousFile` | -10 | `EDFLIB_FILE_IS_DISCONTINUOUS` |
+//! | `InvalidSignalIndex`, `InvalidRecordIndex`, `InvalidSampleIndex` | -12 | `EDFLIB_INVALID_ARGUMENT` |
+//! | `DigitalMinEqualsMax` | -23 | `EDFLIB_DIGMIN_IS_DIGMAX` |
+//! | `PhysicalMinEqualsMax` | -25 | `EDFLIB_PHYSMIN_IS_PHYSMAX` |
+//! | `RecordTooLarge` | -26 | `EDFLIB_DATARECORD_SIZE_TOO_BIG` |
+//! | `FileExists`, `DigitalValueOutOfRange`, `AnnotationsOutOfRange`, `LimitExceeded`, `IncompatibleDimension`, `WindowCrossesGap`, `ComplianceViolations` | -100 | none ([`EDFPLUS_OTHER_ERROR`]) |
+//!
+//! `Io` carries the underlying [`io::Error`] for reading and writing
+//! alike, so a failed write (e.g. in `EdfWriter::write_samples` or
+//! `finalize`) is also reported as -5; edflib's `EDFLIB_FILE_WRITE_ERROR`
+//! (-8) is not used. Use [`io::Error::kind`] to tell the causes apart.
+//!
+//! The codes are part of the public API and do not change between releases.
+
 use std::io;
 use thiserror::Error;
 
+use crate::compliance::Violation;
+use crate::types::Annotation;
+use crate::units::Dimension;
+
+/// `EDFLIB_MALLOC_ERROR`
+pub const EDFLIB_MALLOC_ERROR: i32 = -1;
+/// `EDFLIB_NO_SUCH_FILE_OR_DIRECTORY`
+pub const EDFLIB_NO_SUCH_FILE_OR_DIRECTORY: i32 = -2;
+/// `EDFLIB_FILE_CONTAINS_FORMAT_ERRORS`
+pub const EDFLIB_FILE_CONTAINS_FORMAT_ERRORS: i32 = -3;
+/// `EDFLIB_FILE_READ_ERROR`
+pub const EDFLIB_FILE_READ_ERROR: i32 = -5;
+/// `EDFLIB_FILETYPE_ERROR`
+pub const EDFLIB_FILETYPE_ERROR: i32 = -7;
+/// `EDFLIB_NUMBER_OF_SIGNALS_INVALID`
+pub const EDFLIB_NUMBER_OF_SIGNALS_INVALID: i32 = -9;
+/// `EDFLIB_FILE_IS_DISCONTINUOUS`
+pub const EDFLIB_FILE_IS_DISCONTINUOUS: i32 = -10;
+/// `EDFLIB_INVALID_ARGUMENT`
+pub const EDFLIB_INVALID_ARGUMENT: i32 = -12;
+/// `EDFLIB_DIGMIN_IS_DIGMAX`
+pub const EDFLIB_DIGMIN_IS_DIGMAX: i32 = -23;
+/// `EDFLIB_PHYSMIN_IS_PHYSMAX`
+pub const EDFLIB_PHYSMIN_IS_PHYSMAX: i32 = -25;
+/// `EDFLIB_DATARECORD_SIZE_TOO_BIG`
+pub const EDFLIB_DATARECORD_SIZE_TOO_BIG: i32 = -26;
+/// Errors without an edflib equivalent
+pub const EDFPLUS_OTHER_ERROR: i32 = -100;
+
 #[derive(Debug, Error)]
 pub enum EdfError {
-    #[error("File not found: {0}")]
+    #[error("[-2] File not found: {0}")]
     FileNotFound(String),
     
-    #[error("IO error: {0}")]
+    #[error("[-100] File already exists: {0}")]
+    FileExists(String),
+    
+    #[error("[-5] IO error: {0}")]
     Io(#[from] io::Error),
     
-    #[error("Invalid file format: {0}")]
+    #[error("[-3] Invalid file format: {0}")]
     InvalidFormat(String),
     
-    #[error("File contains format errors")]
+    #[error("[-3] File contains format errors")]
     FormatError,
     
-    #[error("Signal index {0} out of range")]
+    #[error("[-3] Missing value in header field: {0}")]
+    MissingField(String),
+    
+    #[error("[-12] Signal index {0} out of range")]
     InvalidSignalIndex(usize),
     
-    #[error("Unsupported file type: {0}")]
+    #[error("[-12] Data record index {index} out of range, valid range is 0..{count}")]
+    InvalidRecordIndex { index: i64, count: i64 },
+    
+    #[error("[-12] Sample index {index} out of range, valid range is 0..{count}")]
+    InvalidSampleIndex { index: i64, count: i64 },
+    
+    #[error("[-100] Digital value {value} of signal {signal} at index {index} is outside {min}..={max}")]
+    DigitalValueOutOfRange { signal: usize, index: usize, value: i32, min: i32, max: i32 },
+    
+    #[error("[-7] Unsupported file type: {0}")]
     UnsupportedFileType(String),
     
-    #[error("File is discontinuous")]
+    #[error("[-10] File is discontinuous")]
     DiscontinuousFile,
     
-    #[error("Memory allocation error")]
+    #[error("[-1] Memory allocation error")]
     MemoryError,
     
-    #[error("Invalid header size")]
+    #[error("[-3] Invalid header size")]
     InvalidHeader,
     
-    #[error("Invalid number of signals: {0}")]
+    #[error("[-9] Invalid number of signals: {0}")]
     InvalidSignalCount(i32),
     
-    #[error("Physical min equals physical max")]
+    #[error("[-25] Physical min equals physical max")]
     PhysicalMinEqualsMax,
     
-    #[error("Digital min equals digital max")]
+    #[error("[-23] Digital min equals digital max")]
     DigitalMinEqualsMax,
+    
+    #[error("[-100] {} annotation(s) outside the recorded duration of {}s: {}",
+        annotations.len(), crate::time::format_seconds(*duration), describe_annotations(annotations))]
+    AnnotationsOutOfRange { annotations: Vec<Annotation>, duration: i64 },
+    
+    #[error("[-100] Limit {which} exceeded: file requires {requested}, limit is {limit}")]
+    LimitExceeded { which: &'static str, limit: u64, requested: u64 },
+    
+    #[error("[-100] Signal {signal} has unit '{unit}', which is not a {requested} unit")]
+    IncompatibleDimension { signal: usize, unit: String, requested: Dimension },
+    
+    #[error("[-26] Data record is {size} bytes, the EDF specification allows at most {limit}")]
+    RecordTooLarge { size: usize, limit: usize },
+    
+    #[error("[-100] Window crosses a gap of {}s at {}s",
+        crate::time::format_seconds(*duration), crate::time::format_seconds(*start))]
+    WindowCrossesGap { start: i64, duration: i64 },
+    
+    #[error("[-100] {} compliance violation(s): {}", .0.len(), describe_violations(.0))]
+    ComplianceViolations(Vec<Violation>),
+    
+    #[error("[-3] Malformed TAL in data record {record} at byte {offset} of the annotation signal: {reason}")]
+    MalformedTal { record: usize, offset: usize, reason: &'static str },
+}
+
+impl EdfError {
+    /// edflib-compatible error code, see the [module documentation](self)
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::EdfReader;
+    /// use edfplus::error::EDFLIB_NO_SUCH_FILE_OR_DIRECTORY;
+    /// 
+    /// let error = EdfReader::open("does_not_exist.edf").err().unwrap();
+    /// assert_eq!(error.code(), EDFLIB_NO_SUCH_FILE_OR_DIRECTORY);
+    /// assert!(error.to_string().starts_with("[-2] "));
+    /// ```
+    pub fn code(&self) -> i32 {
+        match self {
+            EdfError::MemoryError => EDFLIB_MALLOC_ERROR,
+            EdfError::InvalidSignalIndex(_)
+            | EdfError::InvalidRecordIndex { .. }
+            | EdfError::InvalidSampleIndex { .. } => EDFLIB_INVALID_ARGUMENT,
+            EdfError::FileNotFound(_) => EDFLIB_NO_SUCH_FILE_OR_DIRECTORY,
+            EdfError::InvalidFormat(_)
+            | EdfError::FormatError
+            | EdfError::MissingField(_)
+            | EdfError::InvalidHeader
+            | EdfError::MalformedTal { .. } => EDFLIB_FILE_CONTAINS_FORMAT_ERRORS,
+            // 读写错误都使用 -5，见模块文档
+            EdfError::Io(_) => EDFLIB_FILE_READ_ERROR,
+            EdfError::UnsupportedFileType(_) => EDFLIB_FILETYPE_ERROR,
+            EdfError::InvalidSignalCount(_) => EDFLIB_NUMBER_OF_SIGNALS_INVALID,
+            EdfError::DiscontinuousFile => EDFLIB_FILE_IS_DISCONTINUOUS,
+            EdfError::DigitalMinEqualsMax => EDFLIB_DIGMIN_IS_DIGMAX,
+            EdfError::PhysicalMinEqualsMax => EDFLIB_PHYSMIN_IS_PHYSMAX,
+            EdfError::RecordTooLarge { .. } => EDFLIB_DATARECORD_SIZE_TOO_BIG,
+            EdfError::FileExists(_)
+            | EdfError::DigitalValueOutOfRange { .. }
+            | EdfError::AnnotationsOutOfRange { .. }
+            | EdfError::LimitExceeded { .. }
+            | EdfError::IncompatibleDimension { .. }
+            | EdfError::WindowCrossesGap { .. }
+            | EdfError::ComplianceViolations(_) => EDFPLUS_OTHER_ERROR,
+        }
+    }
+}
+
+/// 列出注释的描述和开始时间
+fn describe_annotations(annotations: &[Annotation]) -> String {
+    annotations.iter()
+        .map(|a| format!("'{}' at {}s", a.description, crate::time::format_seconds(a.onset)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// 列出所有违规项
+fn describe_violations(violations: &[Violation]) -> String {
+    violations.iter().map(Violation::to_string).collect::<Vec<_>>().join("; ")
 }
 
 pub type Result<T> = std::result::Result<T, EdfError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_codes_are_stable() {
+        // 错误码是公开接口的一部分，不能改变
+        let table: Vec<(EdfError, i32)> = vec![
+            (EdfError::FileNotFound("x".to_string()), -2),
+            (EdfError::FileExists("x".to_string()), -100),
+            (EdfError::Io(io::Error::other("x")), -5),
+            (EdfError::InvalidFormat("x".to_string()), -3),
+            (EdfError::FormatError, -3),
+            (EdfError::MissingField("x".to_string()), -3),
+            (EdfError::InvalidSignalIndex(3), -12),
+            (EdfError::InvalidRecordIndex { index: 5, count: 2 }, -12),
+            (EdfError::InvalidSampleIndex { index: 5, count: 2 }, -12),
+            (EdfError::DigitalValueOutOfRange { signal: 0, index: 0, value: 9, min: 0, max: 1 }, -100),
+            (EdfError::UnsupportedFileType("x".to_string()), -7),
+            (EdfError::DiscontinuousFile, -10),
+            (EdfError::MemoryError, -1),
+            (EdfError::InvalidHeader, -3),
+            (EdfError::InvalidSignalCount(0), -9),
+            (EdfError::PhysicalMinEqualsMax, -25),
+            (EdfError::DigitalMinEqualsMax, -23),
+            (EdfError::AnnotationsOutOfRange { annotations: Vec::new(), duration: 0 }, -100),
+            (EdfError::LimitExceeded { which: "max_signals", limit: 1, requested: 2 }, -100),
+            (EdfError::IncompatibleDimension { signal: 0, unit: "mmHg".to_string(), requested: Dimension::ElectricPotential }, -100),
+            (EdfError::RecordTooLarge { size: 102400, limit: 61440 }, -26),
+            (EdfError::WindowCrossesGap { start: 30_000_000, duration: 20_000_000 }, -100),
+            (EdfError::ComplianceViolations(Vec::new()), -100),
+            (EdfError::MalformedTal { record: 2, offset: 17, reason: "invalid onset" }, -3),
+        ];
+
+        for (error, code) in table {
+            assert_eq!(error.code(), code, "{:?}", error);
+            assert!(error.to_string().starts_with(&format!("[{}] ", code)), "{}", error);
+        }
+    }
+}
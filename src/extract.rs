@@ -0,0 +1,97 @@
+//! Events-only copies of EDF+ files
+//!
+//! [`extract_annotations`] writes an EDF+ file that contains the annotation
+//! signals of a recording but none of its ordinary signals, for sharing
+//! event markers without sharing the biosignals.
+
+use std::path::Path;
+
+use crate::error::Result;
+use crate::reader::EdfReader;
+use crate::time;
+use crate::writer::{EdfWriter, WriteSummary};
+
+/// Copies the annotations of `input` into a new events-only EDF+ file
+///
+/// The output has no ordinary signals, only as many annotation signals as
+/// the input. It keeps the start date and time (including the subsecond
+/// offset), the data record duration and the number of data records, so
+/// every annotation is placed in the data record covering its onset and
+/// opening the output gives the same
+/// [`annotations()`](EdfReader::annotations) as the input. Annotations
+/// starting after the last data record are stored in the last one.
+///
+/// Patient and recording identification are not copied.
+///
+/// # Arguments
+///
+/// * `input` - EDF+ file to read the annotations from
+/// * `output` - Path of the new file, replaced if it exists
+///
+/// # Returns
+///
+/// The [`WriteSummary`] of the output file. Annotations that did not fit
+/// into the annotation signals are listed in `dropped_annotations`.
+///
+/// # Errors
+///
+/// * Any error from [`EdfReader::open`] for the input
+/// * Any error from [`EdfWriter`] for the output
+///
+/// # Examples
+///
+/// ```rust
+/// use edfplus::EdfReader;
+///
+/// # let mut writer = edfplus::EdfWriter::create("recording_with_events.edf")?;
+/// # writer.add_signal(edfplus::SignalParam {
+/// #     label: "EEG Fpz-Cz".to_string(), samples_in_file: 0,
+/// #     physical_max: 100.0, physical_min: -100.0,
+/// #     digital_max: 32767, digital_min: -32768,
+/// #     samples_per_record: 10, physical_dimension: "uV".to_string(),
+/// #     prefilter: "".to_string(), transducer: "".to_string(),
+/// #     reserved: String::new(),
+/// # })?;
+/// # writer.add_annotation(0.5, Some(1.5), "Lights off")?;
+/// # writer.add_annotation(2.25, None, "Arousal")?;
+/// # for _ in 0..3 {
+/// #     writer.write_samples(&[vec![0.0; 10]])?;
+/// # }
+/// # writer.finalize()?;
+/// let summary = edfplus::extract_annotations("recording_with_events.edf", "events_only.edf")?;
+///
+/// let source = EdfReader::open("recording_with_events.edf")?;
+/// let events = EdfReader::open("events_only.edf")?;
+/// assert!(events.header().signals.is_empty());
+/// assert_eq!(events.annotations(), source.annotations());
+/// assert_eq!(summary.annotations, source.annotations().len());
+/// # drop(source);
+/// # drop(events);
+/// # std::fs::remove_file("recording_with_events.edf").ok();
+/// # std::fs::remove_file("events_only.edf").ok();
+/// # Ok::<(), edfplus::EdfError>(())
+/// ```
+pub fn extract_annotations<P: AsRef<Path>, Q: AsRef<Path>>(input: P, output: Q) -> Result<WriteSummary> {
+    let reader = EdfReader::open(input)?;
+    let header = reader.header();
+
+    let mut writer = EdfWriter::create(output)?;
+    writer.set_number_of_annotation_signals(reader.header().annotation_signal_count().max(1))?;
+    writer.set_start_datetime(header.start_datetime())?;
+    writer.set_datarecord_duration(time::ticks_to_seconds(header.datarecord_duration))?;
+    writer.allow_out_of_range_annotations(true);
+
+    for annotation in reader.annotations() {
+        writer.add_annotation(
+            time::ticks_to_seconds(annotation.onset),
+            annotation.duration.map(time::ticks_to_seconds),
+            &annotation.description,
+        )?;
+    }
+
+    // 没有普通信号，每个数据记录只包含注释信号
+    for _ in 0..header.datarecords_in_file {
+        writer.write_samples(&[])?;
+    }
+    writer.finalize()
+}
@@ -0,0 +1,241 @@
+//! Exact fixed-point conversion of digital values
+//!
+//! [`SignalParam::to_physical`](crate::SignalParam::to_physical) computes
+//! `scale * digital + offset` in `f64`, which is fast but rounds twice and
+//! can differ in the last bit between platforms or refactorings. Pipelines
+//! that hash, diff or archive physical values need a result that depends
+//! only on the header.
+//!
+//! The physical limits in an EDF header are decimal texts of at most eight
+//! characters, so the calibration is a ratio of integers:
+//!
+//! ```text
+//! physical = physical_min + (digital - digital_min) * (physical_max - physical_min) / (digital_max - digital_min)
+//! ```
+//!
+//! [`SignalParam::to_physical_exact`](crate::SignalParam::to_physical_exact)
+//! and [`EdfReader::read_physical_samples_exact`](crate::EdfReader::read_physical_samples_exact)
+//! evaluate this formula with 128-bit integers and return the value as a
+//! [`FixedPhysical`]: an integer count of `10^-decimals` physical units,
+//! e.g. nanovolts for a signal in `uV` with `decimals = 3`.
+//!
+//! # Exactness
+//!
+//! The result is exact when the calibration terminates in decimal, i.e. when
+//! `digital_max - digital_min`, after cancelling common factors, only has
+//! the prime factors 2 and 5 (`-3276.8..3276.7` over `-32768..32767` has a
+//! step of exactly `0.1`). Most real calibrations, such as `-200..200` over
+//! `-32768..32767`, have a non-terminating step; their values are rounded
+//! to the requested number of decimals, half away from zero, and
+//! [`FixedPhysical::exact`] is `false`. The rounding is done on integers,
+//! so the same header and digital value always give the same result.
+//!
+//! If the physical limits are not finite, the digital range is empty or the
+//! intermediate values do not fit into 128 bits, the `f64` result is
+//! rounded instead, also with `exact == false`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use edfplus::SignalParam;
+//!
+//! let signal = SignalParam {
+//!     label: "EEG Fpz-Cz".to_string(),
+//!     samples_in_file: 0,
+//!     physical_max: 3276.7,
+//!     physical_min: -3276.8,
+//!     digital_max: 32767,
+//!     digital_min: -32768,
+//!     samples_per_record: 256,
+//!     physical_dimension: "uV".to_string(),
+//!     prefilter: "".to_string(),
+//!     transducer: "".to_string(),
+//!     reserved: String::new(),
+//! };
+//!
+//! let value = signal.to_physical_exact(-123);
+//! assert_eq!((value.value, value.decimals, value.exact), (-123, 1, true));
+//! assert_eq!(value.to_string(), "-12.3");
+//!
+//! // Nanovolts
+//! assert_eq!(signal.to_physical_fixed(-123, 3).value, -12_300);
+//! ```
+
+use std::fmt;
+
+use crate::types::SignalParam;
+
+/// Largest number of decimals [`SignalParam::to_physical_exact`] picks
+/// on its own
+pub const MAX_AUTO_DECIMALS: u32 = 9;
+
+/// Largest number of decimals accepted by
+/// [`SignalParam::to_physical_fixed`]; larger values are clamped
+pub const MAX_DECIMALS: u32 = 18;
+
+/// A physical value as an integer number of `10^-decimals` units
+///
+/// See the [module documentation](self).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FixedPhysical {
+    /// Value in units of `10^-decimals` of the physical dimension
+    pub value: i64,
+    /// Number of decimal places of `value`
+    pub decimals: u32,
+    /// Whether `value` is the exact physical value, without rounding
+    pub exact: bool,
+}
+
+impl FixedPhysical {
+    /// Converts the value to `f64`
+    ///
+    /// Performs a single, correctly rounded division.
+    pub fn to_f64(&self) -> f64 {
+        self.value as f64 / 10f64.powi(self.decimals as i32)
+    }
+}
+
+impl fmt::Display for FixedPhysical {
+    /// Formats the value as a decimal number with exactly `decimals` places
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.decimals == 0 {
+            return write!(f, "{}", self.value);
+        }
+        let unit = 10u64.pow(self.decimals);
+        let magnitude = self.value.unsigned_abs();
+        let sign = if self.value < 0 { "-" } else { "" };
+        write!(f, "{}{}.{:0width$}", sign, magnitude / unit, magnitude % unit, width = self.decimals as usize)
+    }
+}
+
+/// Calibration of a signal as a ratio of integers
+///
+/// `physical = (base + slope * (digital - digital_min)) / denominator`,
+/// with `denominator > 0`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ExactCalibration {
+    base: i128,
+    slope: i128,
+    digital_min: i128,
+    denominator: i128,
+}
+
+impl ExactCalibration {
+    /// `None` if the calibration has no exact integer representation
+    pub(crate) fn new(param: &SignalParam) -> Option<Self> {
+        let (min, min_exponent) = decimal_parts(param.physical_min)?;
+        let (max, max_exponent) = decimal_parts(param.physical_max)?;
+        let exponent = min_exponent.max(max_exponent);
+        let min = min.checked_mul(10i128.checked_pow(exponent - min_exponent)?)?;
+        let max = max.checked_mul(10i128.checked_pow(exponent - max_exponent)?)?;
+
+        let digital_span = param.digital_max as i128 - param.digital_min as i128;
+        if digital_span == 0 {
+            return None;
+        }
+        // 分母保持为正
+        let sign = digital_span.signum();
+        Some(ExactCalibration {
+            base: min.checked_mul(digital_span.abs())?,
+            slope: max.checked_sub(min)?.checked_mul(sign)?,
+            digital_min: param.digital_min as i128,
+            denominator: 10i128.checked_pow(exponent)?.checked_mul(digital_span.abs())?,
+        })
+    }
+
+    /// Smallest number of decimals that represents every digital value
+    /// exactly, `None` if the step does not terminate in decimal
+    pub(crate) fn exact_decimals(&self) -> Option<u32> {
+        let mut denominator = self.denominator / gcd(gcd(self.base, self.slope), self.denominator);
+        let (mut twos, mut fives) = (0, 0);
+        while denominator % 2 == 0 {
+            denominator /= 2;
+            twos += 1;
+        }
+        while denominator % 5 == 0 {
+            denominator /= 5;
+            fives += 1;
+        }
+        (denominator == 1).then_some(u32::max(twos, fives))
+    }
+
+    /// Value in units of `10^-decimals`, rounded half away from zero;
+    /// `None` on overflow
+    pub(crate) fn scaled(&self, digital: i32, decimals: u32) -> Option<(i64, bool)> {
+        let steps = digital as i128 - self.digital_min;
+        let numerator = self.slope.checked_mul(steps)?
+            .checked_add(self.base)?
+            .checked_mul(10i128.checked_pow(decimals)?)?;
+        let quotient = numerator / self.denominator;
+        let remainder = numerator % self.denominator;
+        let rounded = if remainder.abs() * 2 >= self.denominator {
+            quotient + numerator.signum()
+        } else {
+            quotient
+        };
+        Some((i64::try_from(rounded).ok()?, remainder == 0))
+    }
+}
+
+/// Converts with `calibration` if possible, otherwise rounds the `f64` result
+pub(crate) fn to_fixed(param: &SignalParam, calibration: Option<&ExactCalibration>, digital: i32, decimals: u32) -> FixedPhysical {
+    let decimals = decimals.min(MAX_DECIMALS);
+    match calibration.and_then(|c| c.scaled(digital, decimals)) {
+        Some((value, exact)) => FixedPhysical { value, decimals, exact },
+        None => FixedPhysical {
+            // f64 到 i64 的转换是饱和的，NaN 为 0
+            value: (param.to_physical(digital) * 10f64.powi(decimals as i32)).round() as i64,
+            decimals,
+            exact: false,
+        },
+    }
+}
+
+/// Splits a finite `f64` into `(mantissa, exponent)` with
+/// `x == mantissa / 10^exponent`, using its shortest decimal representation
+///
+/// Header values are parsed from short decimal texts, so the shortest
+/// representation gives back the text of the header.
+fn decimal_parts(x: f64) -> Option<(i128, u32)> {
+    if !x.is_finite() {
+        return None;
+    }
+    // Display 从不使用指数形式
+    let text = x.to_string();
+    let (integer, fraction) = text.split_once('.').unwrap_or((&text, ""));
+    let digits = format!("{}{}", integer, fraction);
+    let mantissa = digits.parse::<i128>().ok()?;
+    Some((mantissa, fraction.len() as u32))
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decimal_parts() {
+        assert_eq!(decimal_parts(-3276.8), Some((-32768, 1)));
+        assert_eq!(decimal_parts(200.0), Some((200, 0)));
+        assert_eq!(decimal_parts(0.0001), Some((1, 4)));
+        assert_eq!(decimal_parts(-0.5), Some((-5, 1)));
+        assert_eq!(decimal_parts(f64::NAN), None);
+    }
+
+    #[test]
+    fn test_display() {
+        let fixed = |value, decimals| FixedPhysical { value, decimals, exact: true }.to_string();
+        assert_eq!(fixed(-123, 1), "-12.3");
+        assert_eq!(fixed(-5, 3), "-0.005");
+        assert_eq!(fixed(42, 0), "42");
+        assert_eq!(fixed(i64::MIN, 2), "-92233720368547758.08");
+    }
+}
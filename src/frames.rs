@@ -0,0 +1,172 @@
+//! Time-aligned frames across several signals
+//!
+//! [`EdfReader::iter_frames`](crate::EdfReader::iter_frames) walks the
+//! recording forward in time and yields one [`Frame`] per sample period of
+//! the fastest selected signal. Each frame holds one value per selected
+//! signal and the frame's time since the recording start. The samples are
+//! read one data record at a time.
+//!
+//! # Slower signals
+//!
+//! A signal with fewer samples per record than the fastest one does not
+//! have a sample at every frame. [`SlowerSignals`] selects what the frame
+//! contains for it:
+//!
+//! * [`SlowerSignals::Hold`] repeats the latest sample at or before the
+//!   frame time (sample-and-hold).
+//! * [`SlowerSignals::Missing`] yields each sample exactly once, in the
+//!   first frame at or after its sample time, and `None` in all other
+//!   frames.
+//!
+//! When the rates are integer multiples (e.g. 256 Hz and 128 Hz), every
+//! sample of the slower signal falls exactly on a frame. When they are not
+//! (e.g. 256 Hz and 100 Hz), a sample usually lies between two frames: it
+//! is then held from, or reported in, the next frame, so it appears up to
+//! one frame period late. Both rules restart at every data record, where
+//! all signals have a sample.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use edfplus::EdfReader;
+//! use edfplus::frames::SlowerSignals;
+//!
+//! # edfplus::doctest_utils::create_multi_channel_test_file("frames.edf")?;
+//! let mut reader = EdfReader::open("frames.edf")?;
+//!
+//! for frame in reader.iter_frames(&[0, 1])?.take(3) {
+//!     let frame = frame?;
+//!     println!("{} {:?}", frame.time_100ns, frame.values);
+//! }
+//!
+//! // Report slower signals only at their own sample times
+//! let frames = reader.iter_frames_with(&[0, 1], SlowerSignals::Missing)?;
+//! for frame in frames.take(3) {
+//!     let _ = frame?.values;
+//! }
+//! # drop(reader);
+//! # std::fs::remove_file("frames.edf").ok();
+//! # Ok::<(), edfplus::EdfError>(())
+//! ```
+
+use crate::error::{EdfError, Result};
+use crate::reader::EdfReader;
+
+/// Values of slower signals at frames without a sample of their own,
+/// see the [module documentation](self)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SlowerSignals {
+    /// Repeat the latest sample (sample-and-hold)
+    #[default]
+    Hold,
+
+    /// `None` except in the frame a sample is reported in
+    Missing,
+}
+
+/// One point in time across the selected signals
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    /// Time of the frame in 100-nanosecond units since recording start
+    /// (like [`Annotation::onset`](crate::Annotation::onset)), rounded to
+    /// the nearest unit
+    pub time_100ns: i64,
+
+    /// Physical value of each selected signal, in the order of selection
+    ///
+    /// Always `Some` with [`SlowerSignals::Hold`].
+    pub values: Vec<Option<f64>>,
+}
+
+/// Iterator returned by [`EdfReader::iter_frames`](crate::EdfReader::iter_frames)
+///
+/// Stops after the first error.
+pub struct Frames<'a> {
+    reader: &'a mut EdfReader,
+    signals: Vec<usize>,
+    samples_per_record: Vec<i64>,
+    frames_per_record: i64,
+    slower: SlowerSignals,
+    // 当前记录中所选信号的物理值
+    samples: Vec<Vec<f64>>,
+    next_record: i64,
+    frame: i64,
+}
+
+impl<'a> Frames<'a> {
+    pub(crate) fn new(reader: &'a mut EdfReader, signals: &[usize], slower: SlowerSignals) -> Result<Self> {
+        let header = reader.header();
+        let samples_per_record = signals.iter()
+            .map(|&signal| header.signals.get(signal)
+                .map(|param| param.samples_per_record as i64)
+                .ok_or(EdfError::InvalidSignalIndex(signal)))
+            .collect::<Result<Vec<i64>>>()?;
+        let frames_per_record = samples_per_record.iter().copied().max().unwrap_or(0);
+
+        Ok(Frames {
+            reader,
+            signals: signals.to_vec(),
+            samples_per_record,
+            frames_per_record,
+            slower,
+            samples: Vec::new(),
+            next_record: 0,
+            frame: frames_per_record,
+        })
+    }
+
+    /// 读取下一个记录，没有更多记录时返回 false
+    fn load_next_record(&mut self) -> Result<bool> {
+        if self.frames_per_record == 0 || self.next_record >= self.reader.header().datarecords_in_file {
+            return Ok(false);
+        }
+
+        let record = self.reader.read_record(self.next_record)?;
+        self.samples = self.signals.iter()
+            .map(|&signal| record.signals[signal].clone())
+            .collect();
+        self.next_record += 1;
+        self.frame = 0;
+        Ok(true)
+    }
+}
+
+impl Iterator for Frames<'_> {
+    type Item = Result<Frame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.frame >= self.frames_per_record {
+            match self.load_next_record() {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(e) => {
+                    // 出错后结束迭代
+                    self.frames_per_record = 0;
+                    return Some(Err(e));
+                }
+            }
+        }
+
+        let frame = self.frame;
+        let frames = self.frames_per_record;
+        let values = self.samples.iter().zip(&self.samples_per_record)
+            .map(|(samples, &spr)| {
+                // 帧时间之前（含）的最后一个样本
+                let index = frame * spr / frames;
+                let is_new = frame == 0 || (frame - 1) * spr / frames != index;
+                match self.slower {
+                    SlowerSignals::Hold => Some(samples[index as usize]),
+                    SlowerSignals::Missing => is_new.then_some(samples[index as usize]),
+                }
+            })
+            .collect();
+
+        // 记录起始时间加上记录内偏移，四舍五入到100纳秒
+        let duration = self.reader.header().datarecord_duration;
+        let offset = (2 * frame as i128 * duration as i128 + frames as i128) / (2 * frames as i128);
+        let time_100ns = (self.next_record - 1) * duration + offset as i64;
+
+        self.frame += 1;
+        Some(Ok(Frame { time_100ns, values }))
+    }
+}
@@ -0,0 +1,528 @@
+//! EDF/EDF+ header layout
+//!
+//! An EDF file starts with a fixed 256-byte main header followed by
+//! `ns × 256` bytes of signal headers, where `ns` is the number of signals
+//! (including annotation signals). All header fields are fixed-width,
+//! space-padded ASCII.
+//!
+//! The constants in this module name the byte positions of every field so
+//! that tooling working on raw bytes (see `EdfReader::raw_main_header` and
+//! `EdfReader::raw_signal_header`) does not need to hard-code magic numbers.
+//! [`parse_main_header`] and [`parse_signal_headers`] are the parsers the
+//! reader uses, as pure functions over byte slices.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use edfplus::EdfReader;
+//! use edfplus::header::{self, SignalField};
+//!
+//! # edfplus::doctest_utils::create_simple_test_file("header_layout.edf")?;
+//! let reader = EdfReader::open("header_layout.edf")?;
+//!
+//! let main = reader.raw_main_header();
+//! assert_eq!(&main[header::VERSION], b"0       ");
+//! assert_eq!(&main[header::RESERVED][..5], b"EDF+C");
+//!
+//! // Signal header fields are stored column by column
+//! let signal_count = reader.raw_signal_header().len() / header::SIGNAL_HEADER_SIZE;
+//! let label = &reader.raw_signal_header()[SignalField::Label.range(signal_count, 0)];
+//! assert_eq!(label, b"EEG Fp1         ");
+//! # drop(reader);
+//! # std::fs::remove_file("header_layout.edf").ok();
+//! # Ok::<(), edfplus::EdfError>(())
+//! ```
+
+use std::ops::Range;
+
+use chrono::{NaiveDate, NaiveTime};
+
+use crate::error::{EdfError, Result};
+use crate::time::seconds_to_ticks;
+use crate::types::{FileType, SignalParam};
+use crate::utils::{atoi_nonlocalized, parse_edf_time, parse_header_float, parse_header_int};
+use crate::EDFLIB_TIME_DIMENSION;
+
+/// Size of the main header in bytes
+pub const MAIN_HEADER_SIZE: usize = 256;
+
+/// Size of the header block per signal in bytes
+pub const SIGNAL_HEADER_SIZE: usize = 256;
+
+/// Version of the data format, `"0"` for EDF/EDF+
+pub const VERSION: Range<usize> = 0..8;
+
+/// Local patient identification (EDF+ subfields)
+pub const PATIENT: Range<usize> = 8..88;
+
+/// Local recording identification (EDF+ subfields)
+pub const RECORDING: Range<usize> = 88..168;
+
+/// Start date of recording, `dd.mm.yy`
+pub const START_DATE: Range<usize> = 168..176;
+
+/// Start time of recording, `hh.mm.ss`
+pub const START_TIME: Range<usize> = 176..184;
+
+/// Number of bytes in the header record
+pub const HEADER_BYTES: Range<usize> = 184..192;
+
+/// Reserved field, starts with `EDF+C` or `EDF+D` for EDF+ files
+pub const RESERVED: Range<usize> = 192..236;
+
+/// Number of data records (`-1` if unknown)
+pub const DATARECORDS: Range<usize> = 236..244;
+
+/// Duration of a data record in seconds
+pub const DATARECORD_DURATION: Range<usize> = 244..252;
+
+/// Number of signals in the data record
+pub const SIGNAL_COUNT: Range<usize> = 252..256;
+
+/// Fields of the per-signal header, in file order
+///
+/// Unlike the main header, the signal header is laid out column by column:
+/// first the labels of all signals, then all transducer types, and so on.
+/// [`range`](Self::range) computes where a given signal's field lives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalField {
+    /// Label, e.g. `"EEG Fpz-Cz"` (16 bytes)
+    Label,
+    /// Transducer type (80 bytes)
+    Transducer,
+    /// Physical dimension (8 bytes)
+    PhysicalDimension,
+    /// Physical minimum (8 bytes)
+    PhysicalMin,
+    /// Physical maximum (8 bytes)
+    PhysicalMax,
+    /// Digital minimum (8 bytes)
+    DigitalMin,
+    /// Digital maximum (8 bytes)
+    DigitalMax,
+    /// Prefiltering (80 bytes)
+    Prefilter,
+    /// Number of samples in each data record (8 bytes)
+    SamplesPerRecord,
+    /// Reserved (32 bytes)
+    Reserved,
+}
+
+impl SignalField {
+    /// All fields in file order
+    pub const ALL: [SignalField; 10] = [
+        SignalField::Label,
+        SignalField::Transducer,
+        SignalField::PhysicalDimension,
+        SignalField::PhysicalMin,
+        SignalField::PhysicalMax,
+        SignalField::DigitalMin,
+        SignalField::DigitalMax,
+        SignalField::Prefilter,
+        SignalField::SamplesPerRecord,
+        SignalField::Reserved,
+    ];
+
+    /// Width of the field in bytes
+    pub const fn width(self) -> usize {
+        match self {
+            SignalField::Label => 16,
+            SignalField::Transducer => 80,
+            SignalField::PhysicalDimension => 8,
+            SignalField::PhysicalMin => 8,
+            SignalField::PhysicalMax => 8,
+            SignalField::DigitalMin => 8,
+            SignalField::DigitalMax => 8,
+            SignalField::Prefilter => 80,
+            SignalField::SamplesPerRecord => 8,
+            SignalField::Reserved => 32,
+        }
+    }
+
+    /// Sum of the widths of all preceding fields
+    ///
+    /// The field's column starts at `signal_count * column_offset()`.
+    pub const fn column_offset(self) -> usize {
+        match self {
+            SignalField::Label => 0,
+            SignalField::Transducer => 16,
+            SignalField::PhysicalDimension => 96,
+            SignalField::PhysicalMin => 104,
+            SignalField::PhysicalMax => 112,
+            SignalField::DigitalMin => 120,
+            SignalField::DigitalMax => 128,
+            SignalField::Prefilter => 136,
+            SignalField::SamplesPerRecord => 216,
+            SignalField::Reserved => 224,
+        }
+    }
+
+    /// Byte range of this field for `signal`, relative to the start of the
+    /// signal header block (i.e. file offset minus [`MAIN_HEADER_SIZE`])
+    pub const fn range(self, signal_count: usize, signal: usize) -> Range<usize> {
+        let start = signal_count * self.column_offset() + signal * self.width();
+        start..start + self.width()
+    }
+}
+
+/// Main header fields, as parsed by [`parse_main_header`]
+///
+/// Text fields are trimmed. The EDF+ subfields of the patient and
+/// recording identification are not split.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MainHeaderFields {
+    /// Format variant, from the version and reserved fields
+    pub file_type: FileType,
+    /// Local patient identification
+    pub patient: String,
+    /// Local recording identification
+    pub recording: String,
+    /// Start date of the recording
+    pub start_date: NaiveDate,
+    /// Start time of the recording
+    pub start_time: NaiveTime,
+    /// Declared size of the whole header in bytes
+    ///
+    /// Not checked against the signal count; the expected value is
+    /// `MAIN_HEADER_SIZE + signal_count * SIGNAL_HEADER_SIZE`.
+    pub header_bytes: i32,
+    /// Reserved field after the `EDF+C`/`EDF+D` marker
+    pub reserved: String,
+    /// Number of data records, `-1` if unknown
+    pub datarecords: i64,
+    /// Duration of a data record in 100 ns units
+    pub datarecord_duration: i64,
+    /// Number of signals, including annotation signals
+    pub signal_count: usize,
+}
+
+/// Parses the 256-byte main header
+///
+/// A pure function over the bytes: nothing else of the file is needed or
+/// checked, so it also works on fragments of damaged files. Any EDF or BDF
+/// variant is accepted.
+///
+/// # Errors
+///
+/// * `EdfError::UnsupportedFileType` - The version field is neither EDF nor BDF
+/// * `EdfError::InvalidSignalCount` - The number of signals is out of range
+/// * `EdfError::MissingField` / `EdfError::InvalidFormat` - A numeric
+///   field is empty or not a number; the message names the field
+/// * `EdfError::FormatError` - The start date or time is invalid
+///
+/// # Examples
+///
+/// ```rust
+/// use edfplus::{EdfReader, FileType};
+/// use edfplus::header;
+///
+/// # edfplus::doctest_utils::create_simple_test_file("parse_main_header.edf")?;
+/// let bytes = std::fs::read("parse_main_header.edf")?;
+/// let main: &[u8; header::MAIN_HEADER_SIZE] = bytes[..header::MAIN_HEADER_SIZE].try_into().unwrap();
+///
+/// let fields = header::parse_main_header(main)?;
+/// assert_eq!(fields.file_type, FileType::EdfPlusContinuous);
+/// assert_eq!(fields.header_bytes as usize, header::MAIN_HEADER_SIZE * (fields.signal_count + 1));
+///
+/// let signals = header::parse_signal_headers(&bytes[header::MAIN_HEADER_SIZE..], fields.signal_count)?;
+/// assert_eq!(signals.len(), fields.signal_count);
+/// assert_eq!(signals.last().unwrap().label, "EDF Annotations");
+/// # std::fs::remove_file("parse_main_header.edf").ok();
+/// # Ok::<(), edfplus::EdfError>(())
+/// ```
+pub fn parse_main_header(raw: &[u8; MAIN_HEADER_SIZE]) -> Result<MainHeaderFields> {
+    parse_main_header_with(raw, false)
+}
+
+/// [`parse_main_header`]; with `lenient`, unusable fields other than the
+/// version and the signal count get defaults
+pub(crate) fn parse_main_header_with(raw: &[u8; MAIN_HEADER_SIZE], lenient: bool) -> Result<MainHeaderFields> {
+    // 根据版本和保留字段识别文件类型
+    let file_type = FileType::detect(&raw[VERSION], &raw[RESERVED])
+        .ok_or_else(|| EdfError::UnsupportedFileType(format!(
+            "Not an EDF file: {}", String::from_utf8_lossy(&raw[VERSION])
+        )))?;
+
+    // 保留字段中文件类型标识之后的内容
+    let marker_len = if file_type.is_plus() { file_type.to_string().len() } else { 0 };
+    let reserved = String::from_utf8_lossy(&raw[RESERVED][marker_len..])
+        .trim_end_matches([' ', '\0'])
+        .to_string();
+
+    // 解析信号数量
+    let signal_count = parse_header_int(&raw[SIGNAL_COUNT], "number of signals", false, 0)?;
+    if signal_count < 1 || signal_count > crate::EDFLIB_MAXSIGNALS as i32 {
+        return Err(EdfError::InvalidSignalCount(signal_count));
+    }
+
+    let expected_header_bytes = MAIN_HEADER_SIZE as i32 + signal_count * SIGNAL_HEADER_SIZE as i32;
+    let header_bytes = parse_header_int(&raw[HEADER_BYTES], "number of bytes in header", lenient, expected_header_bytes)?;
+
+    // 解析基本信息
+    let patient = String::from_utf8_lossy(&raw[PATIENT]).trim().to_string();
+    let recording = String::from_utf8_lossy(&raw[RECORDING]).trim().to_string();
+
+    // 解析日期和时间
+    let date_str = String::from_utf8_lossy(&raw[START_DATE]);
+    let time_str = String::from_utf8_lossy(&raw[START_TIME]);
+    let (start_date, start_time) = match parse_datetime(&date_str, &time_str) {
+        Ok(datetime) => datetime,
+        // 宽松模式下使用EDF的起始日期 01-JAN-1985 00:00:00
+        Err(_) if lenient => {
+            edf_debug!(date = %date_str, time = %time_str, "unparseable start date/time, using 01-JAN-1985 00:00:00");
+            (
+                NaiveDate::from_ymd_opt(1985, 1, 1).unwrap(),
+                NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
+            )
+        }
+        Err(e) => return Err(e),
+    };
+
+    // 解析数据记录信息
+    let datarecords = parse_header_int(&raw[DATARECORDS], "number of data records", lenient, 0)? as i64;
+
+    // 先按数字字段校验，再用精确的十进制解析得到100纳秒单位（避免浮点误差）
+    let duration_field = &raw[DATARECORD_DURATION];
+    let duration_seconds = parse_header_float(duration_field, "duration of a data record", lenient, 1.0)?;
+    let datarecord_duration = if duration_seconds == 1.0 {
+        EDFLIB_TIME_DIMENSION
+    } else {
+        let duration_str = String::from_utf8_lossy(duration_field).replace('\0', " ");
+        parse_edf_time(&duration_str)
+            .unwrap_or_else(|_| seconds_to_ticks(duration_seconds))
+    };
+
+    Ok(MainHeaderFields {
+        file_type,
+        patient,
+        recording,
+        start_date,
+        start_time,
+        header_bytes,
+        reserved,
+        datarecords,
+        datarecord_duration,
+        signal_count: signal_count as usize,
+    })
+}
+
+/// 解析日期时间
+fn parse_datetime(date_str: &str, time_str: &str) -> Result<(NaiveDate, NaiveTime)> {
+    // 解析日期 "dd.mm.yy"
+    let date_parts: Vec<&str> = date_str.split('.').collect();
+    if date_parts.len() != 3 {
+        return Err(EdfError::FormatError);
+    }
+
+    let day = atoi_nonlocalized(date_parts[0]);
+    let month = atoi_nonlocalized(date_parts[1]);
+    let year = {
+        let yy = atoi_nonlocalized(date_parts[2]);
+        if yy > 84 { 1900 + yy } else { 2000 + yy }
+    };
+
+    let start_date = NaiveDate::from_ymd_opt(year, month as u32, day as u32)
+        .ok_or(EdfError::FormatError)?;
+
+    // 解析时间 "hh.mm.ss"
+    let time_parts: Vec<&str> = time_str.split('.').collect();
+    if time_parts.len() != 3 {
+        return Err(EdfError::FormatError);
+    }
+
+    let hour = atoi_nonlocalized(time_parts[0]);
+    let minute = atoi_nonlocalized(time_parts[1]);
+    let second = atoi_nonlocalized(time_parts[2]);
+
+    let start_time = NaiveTime::from_hms_opt(hour as u32, minute as u32, second as u32)
+        .ok_or(EdfError::FormatError)?;
+
+    Ok((start_date, start_time))
+}
+
+/// Whether the raw label field marks an annotation signal
+///
+/// The label must be exactly `"EDF Annotations "`, including the trailing
+/// space.
+pub fn is_annotation_label(raw: &[u8]) -> bool {
+    raw == b"EDF Annotations "
+}
+
+/// Parses the signal header block of `signal_count` signals
+///
+/// `raw` starts right after the main header and must hold at least
+/// `signal_count * SIGNAL_HEADER_SIZE` bytes. All signals are returned in
+/// file order, annotation signals included (see [`is_annotation_label`]).
+/// `samples_in_file` is 0, as it depends on the number of data records.
+///
+/// # Errors
+///
+/// * `EdfError::InvalidHeader` - `raw` is too short
+/// * `EdfError::MissingField` / `EdfError::InvalidFormat` - A numeric
+///   field is empty or not a number; the message names the field
+/// * `EdfError::PhysicalMinEqualsMax` / `EdfError::DigitalMinEqualsMax` -
+///   An ordinary signal has an empty range
+pub fn parse_signal_headers(raw: &[u8], signal_count: usize) -> Result<Vec<SignalParam>> {
+    parse_signal_headers_with(raw, signal_count, false)
+}
+
+/// [`parse_signal_headers`]; with `lenient`, unusable ranges get defaults
+pub(crate) fn parse_signal_headers_with(raw: &[u8], signal_count: usize, lenient: bool) -> Result<Vec<SignalParam>> {
+    if raw.len() < signal_count * SIGNAL_HEADER_SIZE {
+        return Err(EdfError::InvalidHeader);
+    }
+    let field = |field: SignalField, signal: usize| &raw[field.range(signal_count, signal)];
+    let text = |field: SignalField, signal: usize| String::from_utf8_lossy(&raw[field.range(signal_count, signal)]).trim().to_string();
+
+    let mut signals = Vec::with_capacity(signal_count);
+    for i in 0..signal_count {
+        // 数字最小值/最大值 (各8字节)，缺失时使用16位的完整范围
+        let digital_min = parse_header_int(field(SignalField::DigitalMin, i), "digital minimum", lenient, -32768)?;
+        let digital_max = parse_header_int(field(SignalField::DigitalMax, i), "digital maximum", lenient, 32767)?;
+
+        // 物理最小值/最大值 (各8字节)，缺失时与数字值相同
+        let physical_min = parse_header_float(field(SignalField::PhysicalMin, i), "physical minimum", lenient, digital_min as f64)?;
+        let physical_max = parse_header_float(field(SignalField::PhysicalMax, i), "physical maximum", lenient, digital_max as f64)?;
+
+        // 样本数决定了数据记录布局，没有可用的默认值
+        let samples_per_record = parse_header_int(
+            field(SignalField::SamplesPerRecord, i), "number of samples in each data record", false, 0
+        )?;
+        if samples_per_record < 0 {
+            return Err(EdfError::InvalidFormat(format!(
+                "Invalid value '{}' in header field: number of samples in each data record", samples_per_record
+            )));
+        }
+
+        // 注释信号不携带有意义的定标参数
+        if !is_annotation_label(field(SignalField::Label, i)) {
+            if physical_min == physical_max {
+                return Err(EdfError::PhysicalMinEqualsMax);
+            }
+            if digital_min == digital_max {
+                return Err(EdfError::DigitalMinEqualsMax);
+            }
+        }
+
+        signals.push(SignalParam {
+            label: text(SignalField::Label, i),
+            samples_in_file: 0,
+            physical_max,
+            physical_min,
+            digital_max,
+            digital_min,
+            samples_per_record,
+            physical_dimension: text(SignalField::PhysicalDimension, i),
+            prefilter: text(SignalField::Prefilter, i),
+            transducer: text(SignalField::Transducer, i),
+            // 保留字段保持前导空格以便原样写回
+            reserved: String::from_utf8_lossy(field(SignalField::Reserved, i))
+                .trim_end_matches([' ', '\0']).to_string(),
+        });
+    }
+
+    Ok(signals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout_is_contiguous() {
+        let main = [VERSION, PATIENT, RECORDING, START_DATE, START_TIME, HEADER_BYTES,
+                    RESERVED, DATARECORDS, DATARECORD_DURATION, SIGNAL_COUNT];
+        let mut expected_start = 0;
+        for range in main {
+            assert_eq!(range.start, expected_start);
+            expected_start = range.end;
+        }
+        assert_eq!(expected_start, MAIN_HEADER_SIZE);
+
+        let mut column = 0;
+        for field in SignalField::ALL {
+            assert_eq!(field.column_offset(), column);
+            column += field.width();
+        }
+        assert_eq!(column, SIGNAL_HEADER_SIZE);
+
+        // 最后一个信号的保留字段正好结束于信号头部末尾
+        assert_eq!(SignalField::Reserved.range(3, 2).end, 3 * SIGNAL_HEADER_SIZE);
+        assert_eq!(SignalField::Transducer.range(3, 1), 48 + 80..48 + 160);
+    }
+
+    fn main_header(signal_count: &str) -> [u8; MAIN_HEADER_SIZE] {
+        let mut raw = [b' '; MAIN_HEADER_SIZE];
+        let mut set = |range: Range<usize>, text: &str| raw[range.start..range.start + text.len()].copy_from_slice(text.as_bytes());
+        set(VERSION, "0");
+        set(PATIENT, "X X X X");
+        set(RECORDING, "Startdate X X X X");
+        set(START_DATE, "15.10.26");
+        set(START_TIME, "08.30.00");
+        set(HEADER_BYTES, "768");
+        set(RESERVED, "EDF+C");
+        set(DATARECORDS, "-1");
+        set(DATARECORD_DURATION, "0.5");
+        set(SIGNAL_COUNT, signal_count);
+        raw
+    }
+
+    #[test]
+    fn test_parse_main_header() {
+        let fields = parse_main_header(&main_header("2")).unwrap();
+        assert_eq!(fields.file_type, FileType::EdfPlusContinuous);
+        assert_eq!(fields.patient, "X X X X");
+        assert_eq!(fields.start_date, NaiveDate::from_ymd_opt(2026, 10, 15).unwrap());
+        assert_eq!(fields.start_time, NaiveTime::from_hms_opt(8, 30, 0).unwrap());
+        assert_eq!(fields.header_bytes, 768);
+        assert_eq!(fields.reserved, "");
+        assert_eq!(fields.datarecords, -1);
+        assert_eq!(fields.datarecord_duration, EDFLIB_TIME_DIMENSION / 2);
+        assert_eq!(fields.signal_count, 2);
+
+        assert!(matches!(parse_main_header(&main_header("0")), Err(EdfError::InvalidSignalCount(0))));
+        match parse_main_header(&main_header("x")) {
+            Err(EdfError::InvalidFormat(message)) => assert!(message.contains("number of signals")),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let mut raw = main_header("2");
+        raw[DATARECORDS].fill(b' ');
+        assert!(matches!(parse_main_header(&raw), Err(EdfError::MissingField(field)) if field == "number of data records"));
+        assert_eq!(parse_main_header_with(&raw, true).unwrap().datarecords, 0);
+    }
+
+    #[test]
+    fn test_parse_signal_headers() {
+        let mut raw = vec![b' '; 2 * SIGNAL_HEADER_SIZE];
+        let mut set = |field: SignalField, signal: usize, text: &str| {
+            let start = field.range(2, signal).start;
+            raw[start..start + text.len()].copy_from_slice(text.as_bytes());
+        };
+        for (signal, (label, physical, samples)) in [("EEG Fpz-Cz", "200", "100"), ("EDF Annotations", "1", "60")].into_iter().enumerate() {
+            set(SignalField::Label, signal, label);
+            set(SignalField::PhysicalMin, signal, &format!("-{}", physical));
+            set(SignalField::PhysicalMax, signal, physical);
+            set(SignalField::DigitalMin, signal, "-32768");
+            set(SignalField::DigitalMax, signal, "32767");
+            set(SignalField::SamplesPerRecord, signal, samples);
+        }
+        set(SignalField::PhysicalDimension, 0, "uV");
+
+        let signals = parse_signal_headers(&raw, 2).unwrap();
+        assert_eq!(signals.len(), 2);
+        assert_eq!(signals[0].label, "EEG Fpz-Cz");
+        assert_eq!(signals[0].physical_dimension, "uV");
+        assert_eq!((signals[0].physical_min, signals[0].physical_max), (-200.0, 200.0));
+        assert_eq!(signals[1].samples_per_record, 60);
+        assert!(is_annotation_label(&raw[SignalField::Label.range(2, 1)]));
+
+        assert!(matches!(parse_signal_headers(&raw[..300], 2), Err(EdfError::InvalidHeader)));
+
+        raw[SignalField::SamplesPerRecord.range(2, 0)].copy_from_slice(b"abc     ");
+        match parse_signal_headers(&raw, 2) {
+            Err(EdfError::InvalidFormat(message)) => assert!(message.contains("number of samples in each data record")),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
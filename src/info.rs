@@ -0,0 +1,184 @@
+//! Quick file summaries for cataloging
+//!
+//! [`file_info`] gathers everything needed for a catalog row in a single call
+//! without parsing the annotation signals, so it stays fast on long recordings.
+
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDateTime;
+
+use crate::error::Result;
+use crate::time;
+use crate::types::FileType;
+use crate::reader::EdfReaderOptions;
+
+/// Summary of one (non-annotation) signal
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SignalSummary {
+    /// Signal label, e.g. `"EEG Fp1"`
+    pub label: String,
+    /// Sampling rate in Hz
+    pub sample_rate: f64,
+    /// Physical dimension, e.g. `"uV"`
+    pub physical_dimension: String,
+}
+
+/// Problem found while checking a file's integrity
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IntegrityFinding {
+    /// The file is shorter than the header declares
+    ///
+    /// `complete_records` is the number of data records that are fully present.
+    Truncated {
+        expected_bytes: u64,
+        actual_bytes: u64,
+        complete_records: i64,
+    },
+    /// The file has bytes after the last declared data record
+    TrailingBytes {
+        expected_bytes: u64,
+        actual_bytes: u64,
+    },
+}
+
+/// Catalog summary of an EDF+ file, see [`file_info`]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FileInfo {
+    /// Path the file was opened from
+    pub path: PathBuf,
+    /// File size in bytes
+    pub file_size: u64,
+    /// Format variant of the file
+    pub format: FileType,
+    /// Start of the recording, including the subsecond offset
+    pub start: NaiveDateTime,
+    /// End of the recording (start + duration)
+    pub end: NaiveDateTime,
+    /// Declared duration in units of 100 nanoseconds
+    pub duration: i64,
+    /// Duration of one data record in units of 100 nanoseconds
+    pub datarecord_duration: i64,
+    /// Declared number of data records
+    pub datarecords: i64,
+    /// Ordinary signals, annotation signals excluded
+    pub signals: Vec<SignalSummary>,
+    /// Number of annotations, `None` when unknown
+    ///
+    /// [`file_info`] does not parse annotations, so this is currently
+    /// always `None`; open the file with [`EdfReader`](crate::EdfReader)
+    /// for the exact list.
+    pub annotation_count: Option<i64>,
+    /// Integrity problems; empty for a consistent file
+    pub findings: Vec<IntegrityFinding>,
+}
+
+impl FileInfo {
+    /// Returns `true` if no integrity problems were found
+    pub fn is_consistent(&self) -> bool {
+        self.findings.is_empty()
+    }
+}
+
+/// Summarizes an EDF+ file without reading its annotations
+///
+/// Only the header and the timekeeping annotation of the first data record
+/// (for the subsecond start time) are read. The file size is compared with
+/// the size implied by the header, and mismatches are reported in
+/// [`FileInfo::findings`] instead of failing.
+///
+/// # Arguments
+///
+/// * `path` - Path to the EDF+ file
+///
+/// # Errors
+///
+/// Same as [`EdfReader::open`](crate::EdfReader::open) for files whose
+/// header cannot be parsed.
+///
+/// # Examples
+///
+/// Building a catalog of every EDF file in a directory:
+///
+/// ```rust
+/// use edfplus::file_info;
+///
+/// # std::fs::create_dir_all("catalog_dir").unwrap();
+/// # edfplus::doctest_utils::create_simple_test_file("catalog_dir/night1.edf")?;
+/// for entry in std::fs::read_dir("catalog_dir")? {
+///     let path = entry?.path();
+///     if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("edf")) {
+///         let info = file_info(&path)?;
+///         println!("{}\t{}\t{}\t{:.1}s\t{} signals\t{}",
+///             info.path.display(),
+///             info.format,
+///             info.start,
+///             edfplus::time::ticks_to_seconds(info.duration),
+///             info.signals.len(),
+///             if info.is_consistent() { "ok" } else { "CHECK" });
+///         # assert!(info.is_consistent());
+///     }
+/// }
+/// # std::fs::remove_dir_all("catalog_dir").ok();
+/// # Ok::<(), edfplus::EdfError>(())
+/// ```
+pub fn file_info<P: AsRef<Path>>(path: P) -> Result<FileInfo> {
+    let path = path.as_ref();
+    let reader = EdfReaderOptions::new()
+        .parse_annotations(false)
+        .open(path)?;
+    let file_size = std::fs::metadata(path)?.len();
+    
+    let header = reader.header();
+    
+    // 根据头部计算文件应有的大小
+    let record_size = reader.record_size() as u64;
+    let declared_records = header.datarecords_in_file.max(0) as u64;
+    let expected_bytes = reader.header_size() as u64 + declared_records * record_size;
+    let mut findings = Vec::new();
+    if file_size < expected_bytes {
+        let available = file_size.saturating_sub(reader.header_size() as u64);
+        findings.push(IntegrityFinding::Truncated {
+            expected_bytes,
+            actual_bytes: file_size,
+            complete_records: available.checked_div(record_size).unwrap_or(0) as i64,
+        });
+    } else if file_size > expected_bytes {
+        findings.push(IntegrityFinding::TrailingBytes {
+            expected_bytes,
+            actual_bytes: file_size,
+        });
+    }
+    
+    let record_seconds = time::ticks_to_seconds(header.datarecord_duration);
+    let signals = header.signals.iter()
+        .map(|signal| SignalSummary {
+            label: signal.label.clone(),
+            sample_rate: if record_seconds > 0.0 {
+                signal.samples_per_record as f64 / record_seconds
+            } else {
+                0.0
+            },
+            physical_dimension: signal.physical_dimension.clone(),
+        })
+        .collect();
+    
+    let start = header.start_datetime();
+    let end = start + time::ticks_to_chrono_duration(header.file_duration);
+    
+    Ok(FileInfo {
+        path: path.to_path_buf(),
+        file_size,
+        format: header.file_type,
+        start,
+        end,
+        duration: header.file_duration,
+        datarecord_duration: header.datarecord_duration,
+        datarecords: header.datarecords_in_file,
+        signals,
+        annotation_count: None,
+        findings,
+    })
+}
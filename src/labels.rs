@@ -0,0 +1,564 @@
+//! Structured EDF+ signal labels
+//!
+//! EDF+ labels consist of a signal type and a specification separated by a
+//! space, for example `"EEG Fpz-Cz"`, `"ECG V1"` or `"Temp rectal"`.
+//! [`ParsedLabel`] splits a label into these parts, and
+//! [`ParsedLabel::to_edf_label`] builds a conformant label from them.
+//!
+//! Labels that do not start with one of the standard [`SignalType`]s are
+//! not an error: they parse with `signal_type: None` and the whole label as
+//! specification.
+//!
+//! # Standard texts
+//!
+//! [`check`] compares a label with the standard texts of the EDF+
+//! specification: the signal type is written exactly as in the
+//! specification, and EEG derivations use electrode names of the 10-20 and
+//! 10-10 systems (`"EEG Fpz-Cz"`, `"EEG C3-M2"`). Near misses get a
+//! suggestion. [`EdfWriter::validate_labels`](crate::EdfWriter::validate_labels)
+//! applies the check to every signal of a new file.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use edfplus::labels::{ParsedLabel, SignalType};
+//!
+//! let parsed = ParsedLabel::parse("EEG Fpz-Cz");
+//! assert_eq!(parsed.signal_type, Some(SignalType::Eeg));
+//! assert_eq!(parsed.specification, "Fpz-Cz");
+//!
+//! // Nonconforming labels keep their text as specification
+//! let parsed = ParsedLabel::parse("Fp1-A1");
+//! assert_eq!(parsed.signal_type, None);
+//! assert_eq!(parsed.specification, "Fp1-A1");
+//!
+//! let label = ParsedLabel::new(SignalType::Resp, "nasal").to_edf_label()?;
+//! assert_eq!(label, "Resp nasal");
+//!
+//! let check = edfplus::labels::check("EEG FPZ-CZ");
+//! assert!(!check.conformant);
+//! assert_eq!(check.suggestion.as_deref(), Some("EEG Fpz-Cz"));
+//! # Ok::<(), edfplus::EdfError>(())
+//! ```
+
+use std::fmt;
+
+use crate::error::Result;
+
+/// Maximum length of a label in the signal header, in bytes
+pub const LABEL_BYTES: usize = 16;
+
+/// Electrode names of the 10-20 and 10-10 systems, including the older
+/// names T3/T4/T5/T6 and the reference sites A1/A2 (earlobes) and M1/M2
+/// (mastoids)
+pub const STANDARD_ELECTRODES: &[&str] = &[
+    "Nz", "Fpz", "Fp1", "Fp2",
+    "AF7", "AF3", "AFz", "AF4", "AF8",
+    "F9", "F7", "F5", "F3", "F1", "Fz", "F2", "F4", "F6", "F8", "F10",
+    "FT9", "FT7", "FC5", "FC3", "FC1", "FCz", "FC2", "FC4", "FC6", "FT8", "FT10",
+    "T9", "T7", "C5", "C3", "C1", "Cz", "C2", "C4", "C6", "T8", "T10",
+    "TP9", "TP7", "CP5", "CP3", "CP1", "CPz", "CP2", "CP4", "CP6", "TP8", "TP10",
+    "P9", "P7", "P5", "P3", "P1", "Pz", "P2", "P4", "P6", "P8", "P10",
+    "PO7", "PO3", "POz", "PO4", "PO8",
+    "O1", "Oz", "O2", "Iz",
+    "T3", "T4", "T5", "T6",
+    "A1", "A2", "M1", "M2",
+];
+
+/// How [`EdfWriter`](crate::EdfWriter) treats signal labels that do not
+/// follow the standard texts, see [`check`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LabelPolicy {
+    /// Labels are not checked
+    #[default]
+    Off,
+
+    /// Nonconformant labels are listed in
+    /// [`WriteSummary::nonconformant_labels`](crate::WriteSummary::nonconformant_labels)
+    Standard,
+
+    /// Nonconformant labels are an error when the header is written
+    Strict,
+}
+
+/// Result of [`check`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelCheck {
+    /// Whether the label follows the standard texts
+    pub conformant: bool,
+
+    /// Closest standard label for a near miss, e.g. `"EEG Fpz-Cz"` for
+    /// `"EEG FPZ-CZ"`
+    ///
+    /// `None` for conformant labels and labels without a close match.
+    pub suggestion: Option<String>,
+}
+
+/// Checks a label against the standard texts of the EDF+ specification
+///
+/// A label is conformant if it starts with a [`SignalType`] written exactly
+/// as in the specification, followed by a single space and the
+/// specification. EEG specifications must be one electrode or a derivation
+/// of two electrodes from [`STANDARD_ELECTRODES`] (`"Fpz-Cz"`); other types
+/// accept any specification. Padding spaces and NUL bytes are ignored.
+///
+/// Suggestions fix the case of type and electrode names, add a missing
+/// `"EEG "` prefix to a bare derivation and correct electrode names that
+/// are one edit away from exactly one standard name.
+///
+/// # Examples
+///
+/// ```rust
+/// use edfplus::labels::check;
+///
+/// assert!(check("EEG Fpz-Cz").conformant);
+/// assert!(check("ECG V1").conformant);
+///
+/// assert_eq!(check("eeg fp1-m2").suggestion.as_deref(), Some("EEG Fp1-M2"));
+/// assert_eq!(check("Fp1-A1").suggestion.as_deref(), Some("EEG Fp1-A1"));
+/// assert_eq!(check("Pleth").suggestion, None);
+/// ```
+pub fn check(label: &str) -> LabelCheck {
+    let label = label.trim_matches(|c: char| c == ' ' || c == '\0');
+    let parsed = ParsedLabel::parse(label);
+
+    let canonical = match parsed.signal_type {
+        // 没有类型前缀：整个标签是电极导联时建议加上 EEG
+        None => normalize_derivation(&parsed.specification)
+            .map(|derivation| ParsedLabel::new(SignalType::Eeg, &derivation)),
+        Some(SignalType::Eeg) => normalize_derivation(&parsed.specification)
+            .map(|derivation| ParsedLabel::new(SignalType::Eeg, &derivation)),
+        Some(signal_type) => Some(ParsedLabel::new(signal_type, &parsed.specification)),
+    };
+    let canonical = canonical.and_then(|canonical| canonical.to_edf_label().ok());
+
+    match canonical {
+        Some(canonical) if canonical == label => LabelCheck { conformant: true, suggestion: None },
+        suggestion => LabelCheck { conformant: false, suggestion },
+    }
+}
+
+/// 规范化 "电极" 或 "电极-电极" 形式的导联
+fn normalize_derivation(specification: &str) -> Option<String> {
+    let electrodes = specification.split('-')
+        .map(standard_electrode)
+        .collect::<Option<Vec<&str>>>()?;
+    (1..=2).contains(&electrodes.len()).then(|| electrodes.join("-"))
+}
+
+/// 查找标准电极名：先忽略大小写精确匹配，再接受唯一的编辑距离为1的匹配
+fn standard_electrode(name: &str) -> Option<&'static str> {
+    if let Some(&electrode) = STANDARD_ELECTRODES.iter().find(|e| e.eq_ignore_ascii_case(name)) {
+        return Some(electrode);
+    }
+
+    let name = name.to_ascii_lowercase();
+    let mut close = STANDARD_ELECTRODES.iter()
+        .filter(|e| edit_distance(&e.to_ascii_lowercase(), &name) == 1);
+    match (close.next(), close.next()) {
+        (Some(&electrode), None) => Some(electrode),
+        _ => None,
+    }
+}
+
+/// Levenshtein 距离（ASCII 短字符串）
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.as_bytes();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, &ca) in a.as_bytes().iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = (above + 1).min(row[j] + 1).min(diagonal + usize::from(ca != cb));
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// Signal types of the EDF+ specification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SignalType {
+    /// Electroencephalogram, `EEG`
+    Eeg,
+    /// Electrocardiogram, `ECG`
+    Ecg,
+    /// Electrooculogram, `EOG`
+    Eog,
+    /// Electroretinogram, `ERG`
+    Erg,
+    /// Electromyogram, `EMG`
+    Emg,
+    /// Magnetoencephalogram, `MEG`
+    Meg,
+    /// Magnetocardiogram, `MCG`
+    Mcg,
+    /// Evoked potential, `EP`
+    Ep,
+    /// Body temperature, `Temp`
+    Temp,
+    /// Respiration, `Resp`
+    Resp,
+    /// Oxygen saturation, `SaO2`
+    SaO2,
+    /// Light, `Light`
+    Light,
+    /// Sound, `Sound`
+    Sound,
+    /// Event button, `Event`
+    Event,
+}
+
+impl SignalType {
+    /// All signal types, in the order of the EDF+ specification
+    pub const ALL: [SignalType; 14] = [
+        SignalType::Eeg,
+        SignalType::Ecg,
+        SignalType::Eog,
+        SignalType::Erg,
+        SignalType::Emg,
+        SignalType::Meg,
+        SignalType::Mcg,
+        SignalType::Ep,
+        SignalType::Temp,
+        SignalType::Resp,
+        SignalType::SaO2,
+        SignalType::Light,
+        SignalType::Sound,
+        SignalType::Event,
+    ];
+
+    /// The label prefix as written in the EDF+ specification, e.g. `"SaO2"`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignalType::Eeg => "EEG",
+            SignalType::Ecg => "ECG",
+            SignalType::Eog => "EOG",
+            SignalType::Erg => "ERG",
+            SignalType::Emg => "EMG",
+            SignalType::Meg => "MEG",
+            SignalType::Mcg => "MCG",
+            SignalType::Ep => "EP",
+            SignalType::Temp => "Temp",
+            SignalType::Resp => "Resp",
+            SignalType::SaO2 => "SaO2",
+            SignalType::Light => "Light",
+            SignalType::Sound => "Sound",
+            SignalType::Event => "Event",
+        }
+    }
+
+    /// Looks up a label prefix, ignoring ASCII case
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use edfplus::labels::SignalType;
+    ///
+    /// assert_eq!(SignalType::from_prefix("Temp"), Some(SignalType::Temp));
+    /// assert_eq!(SignalType::from_prefix("sao2"), Some(SignalType::SaO2));
+    /// assert_eq!(SignalType::from_prefix("Fp1"), None);
+    /// ```
+    pub fn from_prefix(prefix: &str) -> Option<SignalType> {
+        SignalType::ALL.into_iter()
+            .find(|signal_type| signal_type.as_str().eq_ignore_ascii_case(prefix))
+    }
+}
+
+impl fmt::Display for SignalType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A label split into signal type and specification, see the
+/// [module documentation](self)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParsedLabel {
+    /// Signal type, `None` if the label does not start with a standard type
+    pub signal_type: Option<SignalType>,
+
+    /// Everything after the type, e.g. the electrode derivation `"Fpz-Cz"`
+    ///
+    /// The whole (trimmed) label if `signal_type` is `None`.
+    pub specification: String,
+}
+
+impl ParsedLabel {
+    /// Creates a label of the given type
+    pub fn new(signal_type: SignalType, specification: &str) -> Self {
+        ParsedLabel {
+            signal_type: Some(signal_type),
+            specification: specification.to_string(),
+        }
+    }
+
+    /// Splits a label at the first space
+    ///
+    /// The type prefix is matched ignoring ASCII case, a label consisting of
+    /// the type alone (e.g. `"ECG"`) has an empty specification. Padding
+    /// spaces and NUL bytes around the label are ignored.
+    pub fn parse(label: &str) -> Self {
+        let label = label.trim_matches(|c: char| c == ' ' || c == '\0');
+        let (prefix, rest) = label.split_once(' ').unwrap_or((label, ""));
+
+        match SignalType::from_prefix(prefix) {
+            Some(signal_type) => ParsedLabel {
+                signal_type: Some(signal_type),
+                specification: rest.trim_start().to_string(),
+            },
+            None => ParsedLabel {
+                signal_type: None,
+                specification: label.to_string(),
+            },
+        }
+    }
+
+    /// Builds the label text, `"TYPE SPEC"`
+    ///
+    /// The type is written as in the specification (`"SaO2"`, not
+    /// `"SAO2"`). Without a type, the specification alone is the label.
+    ///
+    /// # Errors
+    ///
+    /// * `EdfError::InvalidFormat` - The label is longer than
+    ///   [`LABEL_BYTES`] or contains characters other than printable ASCII
+    pub fn to_edf_label(&self) -> Result<String> {
+        let label = match (self.signal_type, self.specification.is_empty()) {
+            (Some(signal_type), true) => signal_type.as_str().to_string(),
+            (Some(signal_type), false) => format!("{} {}", signal_type, self.specification),
+            (None, _) => self.specification.clone(),
+        };
+        crate::writer::validate_header_text(&label, LABEL_BYTES, "Signal label")?;
+        Ok(label)
+    }
+}
+
+/// Signal type of a label, see [`TypeHeuristic::classify`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LabelType {
+    /// Signal type
+    pub signal_type: SignalType,
+
+    /// `true` if the type was guessed by the heuristic, `false` if the
+    /// label starts with the type
+    pub guessed: bool,
+}
+
+/// Guesses the signal type of labels that do not follow the `"TYPE SPEC"`
+/// convention
+///
+/// Many recorders write labels like `"EKG"`, `"Chin EMG"` or `"Fp1-A1"`.
+/// The heuristic looks for keywords anywhere in the label, ignoring ASCII
+/// case; the first matching keyword wins. Optionally a label that is a
+/// derivation of [`STANDARD_ELECTRODES`] is taken for EEG.
+///
+/// The default heuristic knows `"EEG"`, `"EKG"`, `"ECG"`, `"EOG"` and
+/// `"EMG"` and recognizes electrode derivations. Labels with a standard
+/// type prefix never reach the heuristic.
+///
+/// # Examples
+///
+/// ```rust
+/// use edfplus::labels::{LabelType, SignalType, TypeHeuristic};
+///
+/// let heuristic = TypeHeuristic::default();
+/// assert_eq!(heuristic.classify("ECG V1"), Some(LabelType { signal_type: SignalType::Ecg, guessed: false }));
+/// assert_eq!(heuristic.classify("EKG"), Some(LabelType { signal_type: SignalType::Ecg, guessed: true }));
+/// assert_eq!(heuristic.classify("C3-M2"), Some(LabelType { signal_type: SignalType::Eeg, guessed: true }));
+/// assert_eq!(heuristic.classify("Pleth"), None);
+///
+/// // Site specific keywords
+/// let heuristic = TypeHeuristic::default().keyword("SpO2", SignalType::SaO2);
+/// assert_eq!(heuristic.classify("SpO2").map(|t| t.signal_type), Some(SignalType::SaO2));
+///
+/// // Only labels with a type prefix
+/// assert_eq!(TypeHeuristic::none().classify("EKG"), None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeHeuristic {
+    keywords: Vec<(String, SignalType)>,
+    electrodes: bool,
+}
+
+impl TypeHeuristic {
+    /// A heuristic that guesses nothing
+    pub fn none() -> Self {
+        TypeHeuristic { keywords: Vec::new(), electrodes: false }
+    }
+
+    /// Adds a keyword, checked after the keywords added before
+    pub fn keyword(mut self, keyword: &str, signal_type: SignalType) -> Self {
+        self.keywords.push((keyword.to_ascii_uppercase(), signal_type));
+        self
+    }
+
+    /// Takes labels that are a derivation of standard electrodes, e.g.
+    /// `"Fp1-A1"`, for EEG
+    pub fn electrodes(mut self, electrodes: bool) -> Self {
+        self.electrodes = electrodes;
+        self
+    }
+
+    /// Signal type of a label: the type prefix if there is one, the guess
+    /// of the heuristic otherwise
+    pub fn classify(&self, label: &str) -> Option<LabelType> {
+        let parsed = ParsedLabel::parse(label);
+        if let Some(signal_type) = parsed.signal_type {
+            return Some(LabelType { signal_type, guessed: false });
+        }
+
+        let upper = parsed.specification.to_ascii_uppercase();
+        let keyword = self.keywords.iter()
+            .find(|(keyword, _)| upper.contains(keyword.as_str()))
+            .map(|&(_, signal_type)| signal_type);
+        // 只接受与标准电极名大小写无关地完全一致的导联，不做模糊匹配
+        let electrodes = || {
+            let standard = parsed.specification.split('-')
+                .all(|name| STANDARD_ELECTRODES.iter().any(|e| e.eq_ignore_ascii_case(name)));
+            (self.electrodes && standard).then_some(SignalType::Eeg)
+        };
+
+        keyword.or_else(electrodes)
+            .map(|signal_type| LabelType { signal_type, guessed: true })
+    }
+}
+
+impl Default for TypeHeuristic {
+    fn default() -> Self {
+        TypeHeuristic::none()
+            .keyword("EEG", SignalType::Eeg)
+            .keyword("EKG", SignalType::Ecg)
+            .keyword("ECG", SignalType::Ecg)
+            .keyword("EOG", SignalType::Eog)
+            .keyword("EMG", SignalType::Emg)
+            .electrodes(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_real_world_labels() {
+        let table: [(&str, Option<SignalType>, &str); 16] = [
+            ("EEG Fpz-Cz", Some(SignalType::Eeg), "Fpz-Cz"),
+            ("EEG Fp1-REF     ", Some(SignalType::Eeg), "Fp1-REF"),
+            ("eeg C3-M2", Some(SignalType::Eeg), "C3-M2"),
+            ("ECG V1", Some(SignalType::Ecg), "V1"),
+            ("ECG", Some(SignalType::Ecg), ""),
+            ("EOG horizontal", Some(SignalType::Eog), "horizontal"),
+            ("EMG submental", Some(SignalType::Emg), "submental"),
+            ("Temp rectal", Some(SignalType::Temp), "rectal"),
+            ("Resp oro-nasal", Some(SignalType::Resp), "oro-nasal"),
+            ("SaO2 finger\0\0", Some(SignalType::SaO2), "finger"),
+            ("Event marker", Some(SignalType::Event), "marker"),
+            ("Fp1-A1", None, "Fp1-A1"),
+            ("EKG", None, "EKG"),
+            ("EEG-Fpz", None, "EEG-Fpz"),
+            ("Pleth", None, "Pleth"),
+            ("", None, ""),
+        ];
+
+        for (label, signal_type, specification) in table {
+            let parsed = ParsedLabel::parse(label);
+            assert_eq!(parsed.signal_type, signal_type, "{:?}", label);
+            assert_eq!(parsed.specification, specification, "{:?}", label);
+        }
+    }
+
+    #[test]
+    fn test_to_edf_label() {
+        assert_eq!(ParsedLabel::parse("sao2 finger").to_edf_label().unwrap(), "SaO2 finger");
+        assert_eq!(ParsedLabel::parse("Fp1-A1").to_edf_label().unwrap(), "Fp1-A1");
+        assert_eq!(ParsedLabel::new(SignalType::Ecg, "").to_edf_label().unwrap(), "ECG");
+
+        // 16字节是上限
+        assert_eq!(ParsedLabel::new(SignalType::Eeg, "Fp1-F7-T3-T5").to_edf_label().unwrap().len(), 16);
+        assert!(ParsedLabel::new(SignalType::Eeg, "Fp1-F7-T3-T5-O1").to_edf_label().is_err());
+        assert!(ParsedLabel::new(SignalType::Temp, "Körper").to_edf_label().is_err());
+
+        for signal_type in SignalType::ALL {
+            let label = ParsedLabel::new(signal_type, "x").to_edf_label().unwrap();
+            assert_eq!(ParsedLabel::parse(&label), ParsedLabel::new(signal_type, "x"));
+        }
+    }
+
+    #[test]
+    fn test_classify_real_world_labels() {
+        use SignalType::*;
+        let heuristic = TypeHeuristic::default();
+        let table: [(&str, Option<(SignalType, bool)>); 24] = [
+            ("EEG Fpz-Cz", Some((Eeg, false))),
+            ("EEG Fp1-REF     ", Some((Eeg, false))),
+            ("EOG horizontal", Some((Eog, false))),
+            ("EMG submental", Some((Emg, false))),
+            ("ECG", Some((Ecg, false))),
+            ("Resp oro-nasal", Some((Resp, false))),
+            ("Event marker", Some((Event, false))),
+            ("EKG", Some((Ecg, true))),
+            ("ECG1", Some((Ecg, true))),
+            ("ECG-II", Some((Ecg, true))),
+            ("POL EKG", Some((Ecg, true))),
+            ("EEG-Fpz", Some((Eeg, true))),
+            ("EEG(sec)", Some((Eeg, true))),
+            ("Chin EMG", Some((Emg, true))),
+            ("Leg/L-EMG", Some((Emg, true))),
+            ("EOG(L)", Some((Eog, true))),
+            ("L-EOG", Some((Eog, true))),
+            ("Fp1-A1", Some((Eeg, true))),
+            ("C3-M2", Some((Eeg, true))),
+            ("O2", Some((Eeg, true))),
+            ("Pleth", None),
+            ("SpO2", None),
+            ("LOC-A2", None),
+            ("", None),
+        ];
+
+        for (label, expected) in table {
+            let classified = heuristic.classify(label).map(|t| (t.signal_type, t.guessed));
+            assert_eq!(classified, expected, "{:?}", label);
+        }
+
+        // 没有启发式时只识别类型前缀
+        assert_eq!(TypeHeuristic::none().classify("Fp1-A1"), None);
+        assert!(!TypeHeuristic::none().classify("eeg C3").unwrap().guessed);
+    }
+
+    #[test]
+    fn test_check_standard_texts() {
+        let conformant = [
+            "EEG Fpz-Cz", "EEG Fp1-F7", "EEG C3-M2", "EEG O2", "EEG T3-T5  ", "EEG POz-Oz",
+            "ECG V1", "ECG", "EOG horizontal", "Resp oro-nasal", "SaO2 finger", "Temp rectal",
+        ];
+        for label in conformant {
+            assert_eq!(check(label), LabelCheck { conformant: true, suggestion: None }, "{:?}", label);
+        }
+
+        let nonconformant: [(&str, Option<&str>); 12] = [
+            ("EEG FPZ-CZ", Some("EEG Fpz-Cz")),
+            ("eeg c3-a2", Some("EEG C3-A2")),
+            ("EEG  Fpz-Cz", Some("EEG Fpz-Cz")),
+            ("Fp1-A1", Some("EEG Fp1-A1")),
+            ("EEG Fpzz-Cz", Some("EEG Fpz-Cz")),
+            ("SAO2 finger", Some("SaO2 finger")),
+            ("temp rectal", Some("Temp rectal")),
+            // 与多个电极名距离为1，无法确定
+            ("EEG Cx-Cz", None),
+            ("EEG Fp1-F7-T3", None),
+            ("EEG Fp1-REF", None),
+            ("EKG", None),
+            ("Pleth", None),
+        ];
+        for (label, suggestion) in nonconformant {
+            let result = check(label);
+            assert!(!result.conformant, "{:?}", label);
+            assert_eq!(result.suggestion.as_deref(), suggestion, "{:?}", label);
+        }
+    }
+}
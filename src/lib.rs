@@ -26,6 +26,7 @@
 //!     #     physical_dimension: "uV".to_string(),
 //!     #     prefilter: "HP:0.1Hz".to_string(),
 //!     #     transducer: "AgAgCl".to_string(),
+//!     #     reserved: String::new(),
 //!     # };
 //!     # writer.add_signal(signal)?;
 //!     # let samples = vec![10.0; 256];
@@ -39,7 +40,7 @@
 //!     let header = reader.header();
 //!     println!("Number of signals: {}", header.signals.len());
 //!     println!("File duration: {:.2} seconds", 
-//!         header.file_duration as f64 / 10_000_000.0);
+//!         edfplus::time::ticks_to_seconds(header.file_duration));
 //!     
 //!     // Read first 1000 samples from signal 0
 //!     if !header.signals.is_empty() {
@@ -79,6 +80,7 @@
 //!         physical_dimension: "uV".to_string(),
 //!         prefilter: "HP:0.1Hz LP:70Hz".to_string(),
 //!         transducer: "AgAgCl cup electrodes".to_string(),
+//!         reserved: String::new(),
 //!     };
 //!     
 //!     // Add the signal
@@ -143,6 +145,7 @@
 //! #     physical_dimension: "uV".to_string(),
 //! #     prefilter: "".to_string(),
 //! #     transducer: "".to_string(),
+//! #     reserved: String::new(),
 //! # };
 //! # writer.add_signal(signal)?;
 //! 
@@ -150,14 +153,15 @@
 //! writer.add_annotation(2.5, None, "Valid event")?;
 //! writer.add_annotation(4.999, None, "Last moment")?;
 //! 
-//! // ❌ Lost - outside file duration
-//! writer.add_annotation(5.0, None, "Will be discarded")?;
-//! writer.add_annotation(6.0, None, "Also discarded")?;
+//! // ❌ Rejected by finalize() - outside file duration
+//! writer.add_annotation(5.0, None, "Out of range")?;
+//! writer.add_annotation(6.0, None, "Also out of range")?;
 //! 
 //! for i in 0..5 {
 //!     let samples = vec![0.0; 256];
 //!     writer.write_samples(&[samples])?;
 //! }
+//! assert!(writer.finalize().is_err());
 //! # std::fs::remove_file("annotations.edf").ok();
 //! # Ok(())
 //! # }
@@ -184,6 +188,7 @@
 //!     physical_dimension: "uV".to_string(),
 //!     prefilter: "".to_string(),
 //!     transducer: "".to_string(),
+//!     reserved: String::new(),
 //! };
 //!
 //! // Convert digital to physical
@@ -198,21 +203,56 @@
 //! ```
 
 
+#[macro_use]
+mod diagnostics;
 
 pub mod error;
 pub mod types;
+pub mod header;
+pub mod labels;
 pub mod utils;
 pub mod reader;
 pub mod writer; // 新增
+pub mod channels;
+pub mod live;
+pub mod prefetch;
+pub mod frames;
+pub mod signal;
+pub mod sleep;
+pub mod info;
+pub mod compare;
+pub mod compliance;
+pub mod extract;
+pub mod fixed;
+pub mod units;
+pub mod vocabulary;
+#[cfg(feature = "plotters")]
+pub mod plot;
+pub mod digest;
+pub mod calibration;
+pub mod time;
+pub mod timing;
+pub mod testing;
 
 #[doc(hidden)]
 pub mod doctest_utils; // For internal doctest support
 
 // Re-export main types for convenience
 pub use error::{EdfError, Result};
-pub use types::{EdfHeader, SignalParam, Annotation};
-pub use reader::EdfReader;
-pub use writer::EdfWriter; // 新增
+pub use types::{DatarecordsSource, EdfHeader, FileType, SignalParam, Annotation, RecordView, SampleLocation, PatientInfo, Sex, TalWarning};
+pub use reader::{AnnotationChannels, EdfReader, EdfReaderOptions};
+pub use writer::{AnnotationCapacity, EdfWriter, EdfWriterOptions, NarrowingPolicy, Quantization, WriteSummary, DEFAULT_START_DATETIME};
+pub use channels::{ChannelSender, ChannelWriter, RaggedTail}; // 新增
+pub use live::EdfLiveView;
+pub use prefetch::PrefetchingReader;
+pub use signal::{SignalHandle, SignalStats, Samples};
+pub use labels::{LabelPolicy, LabelType, ParsedLabel, SignalType, TypeHeuristic};
+pub use info::{file_info, FileInfo};
+pub use compare::{compare, CompareOptions, CompareReport};
+pub use compliance::Profile;
+pub use extract::extract_annotations;
+pub use fixed::FixedPhysical;
+pub use timing::{Gap, TimingReport, TimingSegment, WindowPart, WindowWithGaps};
 
 // Important constants
 pub const EDFLIB_TIME_DIMENSION: i64 = 10_000_000; // 100 nanoseconds unit
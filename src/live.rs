@@ -0,0 +1,266 @@
+//! Reading a file while it is being written
+//!
+//! Reopening a file that an [`EdfWriter`](crate::EdfWriter) is still
+//! writing races with the writer: the header only states the final number
+//! of data records once the file is finalized, and the last record may be
+//! half written. [`EdfWriter::reader_view`](crate::EdfWriter::reader_view)
+//! returns an [`EdfLiveView`] instead, a read-only handle that shares the
+//! writer's count of complete data records.
+//!
+//! While a view exists the writer flushes every data record before
+//! publishing it, so the view only sees records whose samples are fully in
+//! the file. Reads never block the writer and the writer never waits for a
+//! view; a read that reaches past the published records fails with
+//! `EdfError::InvalidSampleIndex` instead of returning partial data.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use edfplus::{EdfWriter, SignalParam};
+//!
+//! let mut writer = EdfWriter::create("live_view.edf")?;
+//! writer.add_signal(SignalParam {
+//!     label: "EEG Fpz-Cz".to_string(),
+//!     samples_in_file: 0,
+//!     physical_max: 100.0,
+//!     physical_min: -100.0,
+//!     digital_max: 32767,
+//!     digital_min: -32768,
+//!     samples_per_record: 100,
+//!     physical_dimension: "uV".to_string(),
+//!     prefilter: "".to_string(),
+//!     transducer: "".to_string(),
+//!     reserved: String::new(),
+//! })?;
+//! writer.write_samples(&[vec![0.0; 100]])?;
+//!
+//! let mut view = writer.reader_view()?;
+//! assert_eq!(view.records(), 1);
+//!
+//! writer.write_samples(&[vec![10.0; 100]])?;
+//! assert_eq!(view.records(), 2);
+//!
+//! // The last half second
+//! let seconds = edfplus::time::ticks_to_seconds(view.duration());
+//! let trailing = view.read_window(0, seconds - 0.5, 0.5)?;
+//! assert_eq!(trailing.len(), 50);
+//! assert!(trailing.iter().all(|&v| (v - 10.0).abs() < 0.01));
+//!
+//! // Nothing beyond the written records
+//! assert!(view.read_window(0, seconds - 0.5, 1.0).is_err());
+//!
+//! writer.finalize()?;
+//! # drop(view);
+//! # std::fs::remove_file("live_view.edf").ok();
+//! # Ok::<(), edfplus::EdfError>(())
+//! ```
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::error::{EdfError, Result};
+use crate::time;
+use crate::types::SignalParam;
+
+/// Read-only handle on a file that is still being written, see the
+/// [module documentation](self)
+///
+/// Like [`EdfReader`](crate::EdfReader) the view keeps a read position per
+/// signal for [`read_physical_samples`](Self::read_physical_samples) and
+/// [`read_digital_samples`](Self::read_digital_samples);
+/// [`read_window`](Self::read_window) reads by time without moving it.
+pub struct EdfLiveView {
+    file: File,
+    signals: Vec<SignalParam>,
+    datarecord_duration: i64,
+    header_size: u64,
+    record_size: u64,
+    buffer_offsets: Vec<u64>,
+    sample_positions: Vec<i64>,
+    // 写入器发布的完整记录数
+    records: Arc<AtomicUsize>,
+}
+
+impl EdfLiveView {
+    /// `signals` 只包含普通信号，注释信号位于每个记录末尾
+    pub(crate) fn new(
+        path: &Path,
+        signals: Vec<SignalParam>,
+        datarecord_duration: i64,
+        header_size: usize,
+        record_size: usize,
+        records: Arc<AtomicUsize>,
+    ) -> Result<Self> {
+        let file = File::open(path)
+            .map_err(|e| EdfError::FileNotFound(format!("{}: {}", path.display(), e)))?;
+        let buffer_offsets = signals.iter()
+            .scan(0u64, |offset, signal| {
+                let current = *offset;
+                *offset += signal.samples_per_record as u64 * 2;
+                Some(current)
+            })
+            .collect();
+
+        Ok(EdfLiveView {
+            file,
+            sample_positions: vec![0; signals.len()],
+            signals,
+            datarecord_duration,
+            header_size: header_size as u64,
+            record_size: record_size as u64,
+            buffer_offsets,
+            records,
+        })
+    }
+
+    /// Parameters of the data signals, annotation signals excluded
+    ///
+    /// The digital ranges are the ones written to the header.
+    pub fn signals(&self) -> &[SignalParam] {
+        &self.signals
+    }
+
+    /// Number of complete data records that can be read
+    ///
+    /// Grows while the writer writes; the value may be outdated as soon as
+    /// it is returned, but never counts a record that is not fully written.
+    pub fn records(&self) -> i64 {
+        self.records.load(Ordering::Acquire) as i64
+    }
+
+    /// Duration of the readable data records in 100-nanosecond units
+    pub fn duration(&self) -> i64 {
+        self.records() * self.datarecord_duration
+    }
+
+    /// Number of samples of `signal` that can be read
+    ///
+    /// # Errors
+    ///
+    /// * `EdfError::InvalidSignalIndex` - Signal index out of bounds
+    pub fn samples_available(&self, signal: usize) -> Result<i64> {
+        let param = self.signals.get(signal).ok_or(EdfError::InvalidSignalIndex(signal))?;
+        Ok(param.samples_per_record as i64 * self.records())
+    }
+
+    /// Moves the read position of `signal` and returns the new position
+    ///
+    /// Unlike [`EdfReader::seek`](crate::EdfReader::seek) the position is
+    /// not clamped, since the readable region grows; reading fails while it
+    /// lies beyond [`samples_available`](Self::samples_available).
+    ///
+    /// # Errors
+    ///
+    /// * `EdfError::InvalidSignalIndex` - Signal index out of bounds
+    pub fn seek(&mut self, signal: usize, position: i64) -> Result<i64> {
+        let slot = self.sample_positions.get_mut(signal).ok_or(EdfError::InvalidSignalIndex(signal))?;
+        *slot = position.max(0);
+        Ok(*slot)
+    }
+
+    /// Current read position of `signal`
+    ///
+    /// # Errors
+    ///
+    /// * `EdfError::InvalidSignalIndex` - Signal index out of bounds
+    pub fn tell(&self, signal: usize) -> Result<i64> {
+        self.sample_positions.get(signal).copied().ok_or(EdfError::InvalidSignalIndex(signal))
+    }
+
+    /// Reads `count` digital values from the current position of `signal`
+    ///
+    /// # Errors
+    ///
+    /// * `EdfError::InvalidSignalIndex` - Signal index out of bounds
+    /// * `EdfError::InvalidSampleIndex` - The samples are not all written yet
+    /// * `EdfError::Io` - I/O error reading from the file
+    pub fn read_digital_samples(&mut self, signal: usize, count: usize) -> Result<Vec<i32>> {
+        let position = self.tell(signal)?;
+        let samples = self.read_digital_at(signal, position, count)?;
+        self.sample_positions[signal] += count as i64;
+        Ok(samples)
+    }
+
+    /// Reads `count` physical values from the current position of `signal`
+    ///
+    /// # Errors
+    ///
+    /// See [`read_digital_samples`](Self::read_digital_samples).
+    pub fn read_physical_samples(&mut self, signal: usize, count: usize) -> Result<Vec<f64>> {
+        let digital_samples = self.read_digital_samples(signal, count)?;
+        let param = &self.signals[signal];
+        Ok(digital_samples.into_iter().map(|d| param.to_physical(d)).collect())
+    }
+
+    /// Reads the physical values of `signal` between two points in time
+    ///
+    /// The window starts at the first sample at or after `start_seconds`
+    /// and ends before the first sample at or after
+    /// `start_seconds + duration_seconds`, relative to the start of the
+    /// recording. The read position is not changed.
+    ///
+    /// # Errors
+    ///
+    /// * `EdfError::InvalidSignalIndex` - Signal index out of bounds
+    /// * `EdfError::InvalidFormat` - Negative start or duration
+    /// * `EdfError::InvalidSampleIndex` - The window is not fully written yet
+    /// * `EdfError::Io` - I/O error reading from the file
+    pub fn read_window(&mut self, signal: usize, start_seconds: f64, duration_seconds: f64) -> Result<Vec<f64>> {
+        let param = self.signals.get(signal).ok_or(EdfError::InvalidSignalIndex(signal))?;
+        if !(start_seconds >= 0.0 && duration_seconds >= 0.0) {
+            return Err(EdfError::InvalidFormat(format!(
+                "Window must have a non-negative start and duration, got {}s + {}s", start_seconds, duration_seconds
+            )));
+        }
+
+        let start_ticks = time::seconds_to_ticks(start_seconds);
+        let end_ticks = time::seconds_to_ticks(start_seconds + duration_seconds);
+        let start = self.first_sample_at(param, start_ticks);
+        let end = self.first_sample_at(param, end_ticks);
+
+        let digital_samples = self.read_digital_at(signal, start, (end - start) as usize)?;
+        let param = &self.signals[signal];
+        Ok(digital_samples.into_iter().map(|d| param.to_physical(d)).collect())
+    }
+
+    /// 时间点（100纳秒）处或之后的第一个样本
+    fn first_sample_at(&self, param: &SignalParam, ticks: i64) -> i64 {
+        let samples_per_record = param.samples_per_record as i128;
+        let duration = self.datarecord_duration.max(1) as i128;
+        ((ticks as i128 * samples_per_record + duration - 1) / duration) as i64
+    }
+
+    fn read_digital_at(&mut self, signal: usize, position: i64, count: usize) -> Result<Vec<i32>> {
+        let available = self.samples_available(signal)?;
+        let end = position + count as i64;
+        if end > available {
+            return Err(EdfError::InvalidSampleIndex { index: end, count: available });
+        }
+
+        let param = &self.signals[signal];
+        let samples_per_record = param.samples_per_record as i64;
+        let mut samples = Vec::with_capacity(count);
+        let mut current = position;
+        // 每次读取一个记录内的连续样本
+        while current < end {
+            let (record, sample_in_record) = (current / samples_per_record, current % samples_per_record);
+            let chunk = (samples_per_record - sample_in_record).min(end - current) as usize;
+            let offset = self.header_size
+                + record as u64 * self.record_size
+                + self.buffer_offsets[signal]
+                + sample_in_record as u64 * 2;
+
+            let mut buffer = vec![0u8; chunk * 2];
+            self.file.seek(SeekFrom::Start(offset))?;
+            self.file.read_exact(&mut buffer)?;
+            samples.extend(buffer.chunks_exact(2).map(|bytes| {
+                (i16::from_le_bytes([bytes[0], bytes[1]]) as i32).clamp(param.digital_min, param.digital_max)
+            }));
+            current += chunk as i64;
+        }
+        Ok(samples)
+    }
+}
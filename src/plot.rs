@@ -0,0 +1,178 @@
+//! Quick-look PNG plots of a signal (feature `plotters`)
+//!
+//! [`EdfReader::plot_signal`](crate::EdfReader::plot_signal) renders the
+//! physical values of one signal over time, for triage rather than
+//! publication. Long ranges are decimated to one (min, max) pair per pixel
+//! column with [`EdfReader::read_envelope`](crate::EdfReader::read_envelope),
+//! so plotting a whole night reads the file once without holding it in
+//! memory. Annotations inside the range are drawn as vertical markers.
+//!
+//! The output only depends on the file, the options and the installed
+//! fonts, which makes it suitable for snapshot tests.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use edfplus::EdfReader;
+//! use edfplus::plot::PlotOptions;
+//!
+//! # edfplus::doctest_utils::create_simple_test_file("plot_example.edf")?;
+//! let mut reader = EdfReader::open("plot_example.edf")?;
+//!
+//! // First half second of the first signal, 800 × 300 pixels
+//! let options = PlotOptions::new().size(800, 300).range(0.0..0.5);
+//! reader.plot_signal(0, "plot_example.png", &options)?;
+//! # drop(reader);
+//! # std::fs::remove_file("plot_example.edf").ok();
+//! # std::fs::remove_file("plot_example.png").ok();
+//! # Ok::<(), edfplus::EdfError>(())
+//! ```
+
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+
+use plotters::prelude::*;
+
+use crate::error::{EdfError, Result};
+use crate::reader::EdfReader;
+use crate::time;
+
+/// Options for [`EdfReader::plot_signal`](crate::EdfReader::plot_signal)
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlotOptions {
+    width: u32,
+    height: u32,
+    range: Option<Range<f64>>,
+    show_annotations: bool,
+}
+
+impl PlotOptions {
+    /// Creates options for a 1200 × 400 pixel plot of the whole signal with
+    /// annotations
+    pub fn new() -> Self {
+        PlotOptions {
+            width: 1200,
+            height: 400,
+            range: None,
+            show_annotations: true,
+        }
+    }
+
+    /// Image size in pixels (default: 1200 × 400)
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Time range to plot, in seconds since the recording start (default:
+    /// the whole signal)
+    ///
+    /// The range is clipped to the recording.
+    pub fn range(mut self, seconds: Range<f64>) -> Self {
+        self.range = Some(seconds);
+        self
+    }
+
+    /// Draws annotations inside the range as vertical markers (default: `true`)
+    pub fn show_annotations(mut self, show: bool) -> Self {
+        self.show_annotations = show;
+        self
+    }
+}
+
+impl Default for PlotOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 绘图错误统一转换为I/O错误
+fn plot_error<E: std::error::Error + Send + Sync>(e: DrawingAreaErrorKind<E>) -> EdfError {
+    EdfError::Io(io::Error::other(e.to_string()))
+}
+
+pub(crate) fn plot_signal(reader: &mut EdfReader, signal: usize, path: &Path, options: &PlotOptions) -> Result<()> {
+    let header = reader.header();
+    let param = header.signals.get(signal).ok_or(EdfError::InvalidSignalIndex(signal))?.clone();
+    let record_duration = header.datarecord_duration;
+    let samples_per_record = param.samples_per_record as i64;
+    let total = param.samples_in_file;
+
+    // 秒 -> 样本位置（截断到信号范围内）
+    let to_sample = |seconds: f64| {
+        let ticks = time::seconds_to_ticks(seconds.max(0.0)) as i128;
+        let sample = ticks * samples_per_record as i128 / record_duration.max(1) as i128;
+        sample.min(total as i128) as i64
+    };
+    let (start, end) = match &options.range {
+        Some(range) => (to_sample(range.start), to_sample(range.end).max(to_sample(range.start))),
+        None => (0, total),
+    };
+    let to_seconds = |sample: f64| {
+        sample * time::ticks_to_seconds(record_duration) / samples_per_record.max(1) as f64
+    };
+
+    // 每个像素列一个桶
+    let envelope = reader.read_envelope(signal, start, end, options.width.max(1) as usize)?;
+    let buckets = envelope.len() as i64;
+    let bucket_time = |b: i64| {
+        let first = start + ((end - start) as i128 * b as i128 / buckets.max(1) as i128) as i64;
+        let next = start + ((end - start) as i128 * (b + 1) as i128 / buckets.max(1) as i128) as i64;
+        to_seconds((first + next - 1) as f64 / 2.0)
+    };
+
+    let (mut low, mut high) = envelope.iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), &(min, max)| (low.min(min), high.max(max)));
+    if envelope.is_empty() {
+        (low, high) = (-1.0, 1.0);
+    } else if low == high {
+        (low, high) = (low - 1.0, high + 1.0);
+    }
+    let margin = (high - low) * 0.05;
+    let (low, high) = (low - margin, high + margin);
+
+    let (t0, mut t1) = (to_seconds(start as f64), to_seconds(end as f64));
+    if t1 <= t0 {
+        t1 = t0 + 1.0;
+    }
+
+    let root = BitMapBackend::new(path, (options.width, options.height)).into_drawing_area();
+    root.fill(&WHITE).map_err(plot_error)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption(param.label.trim(), ("sans-serif", 18))
+        .margin(10)
+        .x_label_area_size(35)
+        .y_label_area_size(60)
+        .build_cartesian_2d(t0..t1, low..high)
+        .map_err(plot_error)?;
+
+    chart.configure_mesh()
+        .x_desc("Time (s)")
+        .y_desc(param.physical_dimension.trim())
+        .draw()
+        .map_err(plot_error)?;
+
+    // 每个桶画一条从最小值到最大值的竖线，并与相邻桶相连
+    let points = envelope.iter().enumerate()
+        .flat_map(|(b, &(min, max))| {
+            let t = bucket_time(b as i64);
+            [(t, min), (t, max)]
+        });
+    chart.draw_series(LineSeries::new(points, &BLUE)).map_err(plot_error)?;
+
+    if options.show_annotations {
+        let markers: Vec<f64> = reader.annotations().iter()
+            .map(|annotation| time::ticks_to_seconds(annotation.onset))
+            .filter(|&onset| onset >= t0 && onset <= t1)
+            .collect();
+        chart.draw_series(markers.into_iter()
+            .map(|onset| PathElement::new(vec![(onset, low), (onset, high)], RED)))
+            .map_err(plot_error)?;
+    }
+
+    root.present().map_err(plot_error)?;
+    Ok(())
+}
@@ -0,0 +1,206 @@
+//! Reading data records ahead on a background thread
+//!
+//! Processing pipelines that alternate CPU-heavy work with reads leave the
+//! disk idle while they compute. [`PrefetchingReader`] moves an
+//! [`EdfReader`] to a worker thread that decodes data records sequentially
+//! into a bounded queue, so the next records are usually ready by the time
+//! the consumer asks for them. The queue holds at most
+//! [`lookahead`](PrefetchingReader::lookahead) records; the worker blocks
+//! when it is full.
+//!
+//! [`seek`](PrefetchingReader::seek) stops the worker, discards the queued
+//! records and restarts prefetching at the new position.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use edfplus::EdfReader;
+//!
+//! # edfplus::doctest_utils::create_multi_channel_test_file("prefetch.edf")?;
+//! let reader = EdfReader::open("prefetch.edf")?;
+//! let mut records = reader.into_prefetching().lookahead(8);
+//!
+//! while let Some(record) = records.next_record() {
+//!     let record = record?;
+//!     // CPU-heavy work here, while the next records are being read
+//!     println!("record {}: {} signals", record.index, record.signals.len());
+//! }
+//!
+//! // Start over from the first record
+//! records.seek(0)?;
+//! assert_eq!(records.next_record().unwrap()?.index, 0);
+//! # drop(records);
+//! # std::fs::remove_file("prefetch.edf").ok();
+//! # Ok::<(), edfplus::EdfError>(())
+//! ```
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use crate::error::{EdfError, Result};
+use crate::reader::EdfReader;
+use crate::types::{EdfHeader, RecordView};
+
+/// Wraps an [`EdfReader`] and decodes data records on a background thread,
+/// see the [module documentation](self)
+///
+/// [`EdfReader::into_prefetching`] is a shortcut for
+/// `PrefetchingReader::new(reader)`. The worker is started by the first
+/// call to [`next_record`](Self::next_record).
+pub struct PrefetchingReader {
+    header: EdfHeader,
+    lookahead: usize,
+    next_index: i64,
+    // 空闲时持有阅读器，预读时阅读器属于工作线程
+    reader: Option<EdfReader>,
+    worker: Option<Worker>,
+}
+
+struct Worker {
+    receiver: Receiver<Result<RecordView>>,
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<EdfReader>,
+}
+
+impl PrefetchingReader {
+    /// Wraps a reader, prefetching starts at the first data record
+    pub fn new(reader: EdfReader) -> Self {
+        PrefetchingReader {
+            header: reader.header().clone(),
+            lookahead: 4,
+            next_index: 0,
+            reader: Some(reader),
+            worker: None,
+        }
+    }
+
+    /// Maximum number of decoded records queued ahead of the consumer (default: 4)
+    ///
+    /// Values below 1 are treated as 1. Takes effect the next time the
+    /// worker is started.
+    pub fn lookahead(mut self, records: usize) -> Self {
+        self.lookahead = records.max(1);
+        self
+    }
+
+    /// Header of the wrapped reader
+    pub fn header(&self) -> &EdfHeader {
+        &self.header
+    }
+
+    /// Index of the data record the next call to
+    /// [`next_record`](Self::next_record) returns
+    pub fn position(&self) -> i64 {
+        self.next_index
+    }
+
+    /// Returns the next data record
+    ///
+    /// Records are delivered in file order, without gaps or duplicates.
+    /// Returns `None` after the last record. After an error the worker
+    /// stops, and `None` is returned until the next [`seek`](Self::seek).
+    ///
+    /// # Errors
+    ///
+    /// * Any error from [`EdfReader::read_record`]
+    pub fn next_record(&mut self) -> Option<Result<RecordView>> {
+        if self.worker.is_none() {
+            let reader = self.reader.take()?;
+            self.worker = Some(Worker::spawn(reader, self.next_index, self.lookahead));
+        }
+
+        match self.worker.as_ref()?.receiver.recv() {
+            Ok(Ok(record)) => {
+                self.next_index = record.index + 1;
+                Some(Ok(record))
+            }
+            Ok(Err(e)) => Some(Err(e)),
+            // 工作线程已结束：读到文件末尾或出错后
+            Err(_) => None,
+        }
+    }
+
+    /// Moves to a data record and restarts prefetching there
+    ///
+    /// Records that were already prefetched are discarded. Seeking to
+    /// `datarecords_in_file` positions after the last record.
+    ///
+    /// # Errors
+    ///
+    /// * `EdfError::InvalidRecordIndex` - Index is not in `0..=datarecords_in_file`
+    pub fn seek(&mut self, record_index: i64) -> Result<()> {
+        let count = self.header.datarecords_in_file;
+        if record_index < 0 || record_index > count {
+            return Err(EdfError::InvalidRecordIndex { index: record_index, count });
+        }
+
+        self.stop_worker();
+        self.next_index = record_index;
+        Ok(())
+    }
+
+    /// Stops prefetching and returns the wrapped reader
+    pub fn into_inner(mut self) -> EdfReader {
+        self.stop_worker();
+        self.reader.take().expect("reader is returned by the stopped worker")
+    }
+
+    /// 停止工作线程并取回阅读器，丢弃已预读的记录
+    fn stop_worker(&mut self) {
+        if let Some(worker) = self.worker.take() {
+            self.reader = Some(worker.stop());
+        }
+    }
+}
+
+impl Iterator for PrefetchingReader {
+    type Item = Result<RecordView>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record()
+    }
+}
+
+impl Drop for PrefetchingReader {
+    fn drop(&mut self) {
+        self.stop_worker();
+    }
+}
+
+impl Worker {
+    fn spawn(mut reader: EdfReader, start: i64, lookahead: usize) -> Self {
+        let (sender, receiver) = mpsc::sync_channel(lookahead);
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::clone(&stop);
+
+        let handle = thread::spawn(move || {
+            let count = reader.header().datarecords_in_file;
+            for index in start..count {
+                if stopped.load(Ordering::Relaxed) {
+                    break;
+                }
+                let result = reader.read_record(index);
+                let failed = result.is_err();
+                // 接收端已丢弃时发送失败
+                if sender.send(result).is_err() || failed {
+                    break;
+                }
+            }
+            reader
+        });
+
+        Worker { receiver, stop, handle }
+    }
+
+    fn stop(self) -> EdfReader {
+        self.stop.store(true, Ordering::Relaxed);
+        // 丢弃接收端以唤醒阻塞在发送上的工作线程
+        drop(self.receiver);
+        match self.handle.join() {
+            Ok(reader) => reader,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    }
+}
@@ -1,11 +1,23 @@
 use std::fs::File;
 use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::ops::ControlFlow;
 use std::path::Path;
-use chrono::{NaiveDate, NaiveTime};
+use chrono::{NaiveDateTime, TimeDelta};
 
-use crate::types::{EdfHeader, SignalParam, Annotation};
+use crate::types::{DatarecordsSource, EdfHeader, FileType, SignalParam, Annotation, RecordView, SampleLocation, TalWarning};
 use crate::error::{EdfError, Result};
-use crate::utils::{atoi_nonlocalized, atof_nonlocalized, parse_edf_time};
+use crate::header::{self, SignalField};
+use crate::digest::{DigestHasher, Sha256};
+use crate::calibration::{self, CalibrationFinding, CalibrationOptions, DigitalExtremes};
+use crate::prefetch::PrefetchingReader;
+use crate::frames::{Frames, SlowerSignals};
+use crate::timing::{Gap, TimingAccumulator, TimingReport, TimingSegment, WindowPart, WindowWithGaps};
+use crate::fixed::{self, ExactCalibration};
+use crate::signal::{Samples, SignalHandle};
+use crate::vocabulary::{self, VocabularyOptions};
+use crate::labels::{LabelType, SignalType, TypeHeuristic};
+use crate::units::{self, Dimension};
+use crate::time::seconds_to_ticks;
 use crate::EDFLIB_TIME_DIMENSION;
 
 /// TAL parsing state machine states
@@ -17,6 +29,282 @@ enum TalState {
     CollectingDescription,  // Collecting description text
 }
 
+/// Open-time options for [`EdfReader`]
+/// 
+/// All open-time behavior is configured through this builder instead of a
+/// growing list of constructors. The defaults reproduce
+/// [`EdfReader::open`] exactly, which is a thin wrapper over
+/// `EdfReaderOptions::new().open(path)`.
+/// 
+/// # Examples
+/// 
+/// ```rust
+/// use edfplus::EdfReaderOptions;
+/// 
+/// # edfplus::doctest_utils::create_simple_test_file("options.edf")?;
+/// // Open a large file quickly, without scanning its annotations
+/// let reader = EdfReaderOptions::new()
+///     .lenient(true)
+///     .parse_annotations(false)
+///     .allow_truncated(true)
+///     .open("options.edf")?;
+/// 
+/// assert!(reader.annotations().is_empty());
+/// # drop(reader);
+/// # std::fs::remove_file("options.edf").ok();
+/// # Ok::<(), edfplus::EdfError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct EdfReaderOptions {
+    lenient: bool,
+    parse_annotations: bool,
+    annotation_channels: AnnotationChannels,
+    type_heuristic: TypeHeuristic,
+    strict_tal: bool,
+    allow_truncated: bool,
+    max_signals: usize,
+    max_header_bytes: usize,
+    max_record_bytes: usize,
+    max_annotations: usize,
+    max_annotation_bytes: u64,
+}
+
+impl EdfReaderOptions {
+    /// Creates options matching the behavior of [`EdfReader::open`]
+    pub fn new() -> Self {
+        EdfReaderOptions {
+            lenient: false,
+            parse_annotations: true,
+            annotation_channels: AnnotationChannels::All,
+            type_heuristic: TypeHeuristic::default(),
+            strict_tal: false,
+            allow_truncated: false,
+            max_signals: crate::EDFLIB_MAXSIGNALS,
+            max_header_bytes: header::MAIN_HEADER_SIZE + crate::EDFLIB_MAXSIGNALS * header::SIGNAL_HEADER_SIZE,
+            max_record_bytes: 64 * 1024 * 1024,
+            max_annotations: 1_000_000,
+            max_annotation_bytes: 256 * 1024 * 1024,
+        }
+    }
+    
+    /// Tolerates recoverable header irregularities (default: `false`)
+    /// 
+    /// In lenient mode the reader accepts:
+    /// - a "number of bytes in header" field that disagrees with the signal count
+    /// - an unparseable start date or time, which falls back to 01-JAN-1985 00:00:00
+    /// - empty or unparseable numeric fields, which fall back to a default
+    ///   (the computed header size, 0 data records, 1 second per record,
+    ///   the full 16-bit digital range, or a physical range equal to the
+    ///   digital range); the signal count and samples per record are always
+    ///   required
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+    
+    /// Parses the annotation signals at open time (default: `true`)
+    /// 
+    /// When disabled, only the timekeeping TAL of the first data record is read
+    /// (to obtain the subsecond start time); [`EdfReader::annotations`] is empty
+    /// and `annotations_in_file` is 0. This makes opening very large files cheap.
+    pub fn parse_annotations(mut self, parse: bool) -> Self {
+        self.parse_annotations = parse;
+        self
+    }
+    
+    /// Selects the annotation signals whose events are parsed at open time
+    /// (default: [`AnnotationChannels::All`])
+    /// 
+    /// Files may carry several `EDF Annotations` signals, see
+    /// [`EdfHeader::annotation_signal_count`](crate::EdfHeader::annotation_signal_count);
+    /// the excluded ones are skipped when [`EdfReader::annotations`] is
+    /// built and counted in `annotations_in_file`. The timekeeping TALs are
+    /// always read from the first annotation signal, even when its events
+    /// are excluded. Has no effect with
+    /// [`parse_annotations(false)`](Self::parse_annotations), and the
+    /// per-record methods such as [`EdfReader::annotations_in_record`] still
+    /// read all annotation signals.
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::{AnnotationChannels, EdfReaderOptions};
+    /// 
+    /// # edfplus::doctest_utils::create_simple_test_file("annotation_channels.edf")?;
+    /// // Only the events of the second annotation signal
+    /// let result = EdfReaderOptions::new()
+    ///     .parse_annotation_channels(AnnotationChannels::Only(vec![1]))
+    ///     .open("annotation_channels.edf");
+    /// 
+    /// // The test file has a single annotation signal
+    /// assert!(result.is_err());
+    /// # std::fs::remove_file("annotation_channels.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn parse_annotation_channels(mut self, channels: AnnotationChannels) -> Self {
+        self.annotation_channels = channels;
+        self
+    }
+    
+    /// Fails on malformed annotation lists instead of skipping them
+    /// (default: `false`)
+    /// 
+    /// The annotation signals hold time-stamped annotation lists (TALs)
+    /// separated by NUL bytes. By default a malformed TAL, e.g. one with
+    /// an invalid onset, is skipped up to the next NUL byte and parsing
+    /// continues with the following TAL of the same data record. Each
+    /// skipped TAL is recorded with its data record and byte offset in
+    /// [`EdfReader::tal_warnings`], and logged as a warning with the
+    /// `tracing` feature. In strict mode opening the file fails
+    /// with `EdfError::MalformedTal` at the first malformed TAL instead, so
+    /// no annotation can go missing unnoticed. The setting also applies to
+    /// annotations read after opening, e.g. by
+    /// [`EdfReader::read_record`].
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::EdfReaderOptions;
+    /// 
+    /// # edfplus::doctest_utils::create_simple_test_file("strict_tal.edf")?;
+    /// // The file is well formed, so both modes read the same annotations
+    /// let strict = EdfReaderOptions::new().strict_tal(true).open("strict_tal.edf")?;
+    /// let lenient = EdfReaderOptions::new().open("strict_tal.edf")?;
+    /// assert_eq!(strict.annotations(), lenient.annotations());
+    /// # drop((strict, lenient));
+    /// # std::fs::remove_file("strict_tal.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn strict_tal(mut self, strict: bool) -> Self {
+        self.strict_tal = strict;
+        self
+    }
+    
+    /// Heuristic for the signal type of labels without a type prefix
+    /// (default: [`TypeHeuristic::default`])
+    /// 
+    /// Used by [`EdfReader::signals_of_type`] and the channel helpers
+    /// built on it. [`TypeHeuristic::none`] selects by label prefix only.
+    pub fn type_heuristic(mut self, heuristic: TypeHeuristic) -> Self {
+        self.type_heuristic = heuristic;
+        self
+    }
+    
+    /// Accepts files that are shorter than their header declares (default: `false`)
+    /// 
+    /// When enabled, the number of data records is reduced to the number of
+    /// complete records actually present in the file, so every sample that
+    /// was written can be read and a partial trailing record is ignored.
+    /// When disabled, the declared record count is used as-is and reads
+    /// beyond the end of the file fail with an I/O error.
+    pub fn allow_truncated(mut self, allow: bool) -> Self {
+        self.allow_truncated = allow;
+        self
+    }
+    
+    /// Largest accepted number of signals, annotation signals included
+    /// (default: 4096, the maximum of edflib)
+    /// 
+    /// Checked right after the main header, before the signal headers are
+    /// read. This and the other `max_*` limits protect services that open
+    /// untrusted files: a crafted header fails with
+    /// `EdfError::LimitExceeded` before the reader allocates memory or
+    /// scans data for it. The defaults accept every realistic recording.
+    pub fn max_signals(mut self, max: usize) -> Self {
+        self.max_signals = max;
+        self
+    }
+    
+    /// Largest accepted header size in bytes (default: the header size of
+    /// 4096 signals, about 1 MiB)
+    pub fn max_header_bytes(mut self, max: usize) -> Self {
+        self.max_header_bytes = max;
+        self
+    }
+    
+    /// Largest accepted data record size in bytes (default: 64 MiB)
+    /// 
+    /// Checked after the signal headers are parsed, before the first data
+    /// record is read. Reading data allocates buffers of this size.
+    pub fn max_record_bytes(mut self, max: usize) -> Self {
+        self.max_record_bytes = max;
+        self
+    }
+    
+    /// Largest accepted number of annotations (default: 1 000 000)
+    /// 
+    /// Parsing stops with an error as soon as the annotations read so far
+    /// exceed the limit.
+    pub fn max_annotations(mut self, max: usize) -> Self {
+        self.max_annotations = max;
+        self
+    }
+    
+    /// Largest accepted total size of the annotation signals in bytes,
+    /// over all data records (default: 256 MiB)
+    /// 
+    /// Checked before the annotations are parsed, from the header alone.
+    /// With [`parse_annotations(false)`](Self::parse_annotations) only the
+    /// first data record counts.
+    pub fn max_annotation_bytes(mut self, max: u64) -> Self {
+        self.max_annotation_bytes = max;
+        self
+    }
+    
+    /// `EdfError::LimitExceeded` 如果 `requested` 超过 `limit`
+    fn check_limit(which: &'static str, limit: u64, requested: u64) -> Result<()> {
+        if requested > limit {
+            edf_warn!(which, limit, requested, "resource limit exceeded");
+            return Err(EdfError::LimitExceeded { which, limit, requested });
+        }
+        Ok(())
+    }
+    
+    /// Opens an EDF+ file with these options
+    /// 
+    /// # Errors
+    /// 
+    /// Same as [`EdfReader::open`].
+    pub fn open<P: AsRef<Path>>(&self, path: P) -> Result<EdfReader> {
+        EdfReader::open_with_options(path.as_ref(), self)
+    }
+}
+
+impl Default for EdfReaderOptions {
+    fn default() -> Self {
+        EdfReaderOptions::new()
+    }
+}
+
+/// Annotation signals parsed at open time, see
+/// [`EdfReaderOptions::parse_annotation_channels`]
+/// 
+/// Indices count the `EDF Annotations` signals in file order, starting at 0
+/// for the first one.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum AnnotationChannels {
+    /// Events of every annotation signal
+    #[default]
+    All,
+    
+    /// Events of the given annotation signals only
+    Only(Vec<usize>),
+    
+    /// No events, only the timekeeping TALs are read
+    None,
+}
+
+impl AnnotationChannels {
+    /// 是否解析第 `channel` 个注释信号的事件
+    fn includes(&self, channel: usize) -> bool {
+        match self {
+            AnnotationChannels::All => true,
+            AnnotationChannels::Only(channels) => channels.contains(&channel),
+            AnnotationChannels::None => false,
+        }
+    }
+}
+
 /// EDF+ file reader for reading European Data Format Plus files
 /// 
 /// The `EdfReader` provides methods to open and read EDF+ files, which are
@@ -37,7 +325,7 @@ enum TalState {
 /// 
 /// // Get header information
 /// let header = reader.header();
-/// println!("Duration: {:.1} seconds", header.file_duration as f64 / 10_000_000.0);
+/// println!("Duration: {:.1} seconds", edfplus::time::ticks_to_seconds(header.file_duration));
 /// println!("Signals: {}", header.signals.len());
 /// 
 /// // Read physical samples from first signal
@@ -96,6 +384,19 @@ pub struct EdfReader {
     record_size: usize,
     /// 注释列表
     annotations: Vec<Annotation>,
+    /// 打开时读取的原始主头部
+    raw_main_header: [u8; header::MAIN_HEADER_SIZE],
+    /// 打开时读取的原始信号头部 (ns × 256 字节)
+    raw_signal_header: Vec<u8>,
+    /// for_each_digital_chunk 复用的缓冲区
+    chunk_bytes: Vec<u8>,
+    chunk_samples: Vec<i16>,
+    /// 没有类型前缀的标签的类型猜测
+    type_heuristic: TypeHeuristic,
+    /// 格式错误的TAL是否报错
+    strict_tal: bool,
+    /// 宽松模式下跳过的TAL
+    tal_warnings: Vec<TalWarning>,
 }
 
 #[derive(Debug, Clone)]
@@ -130,6 +431,8 @@ impl EdfReader {
     /// * `EdfError::UnsupportedFileType` - File is not EDF+ format
     /// * `EdfError::InvalidHeader` - File header is corrupted or invalid
     /// * `EdfError::InvalidSignalCount` - Invalid number of signals
+    /// * `EdfError::LimitExceeded` - The file exceeds a resource limit of
+    ///   [`EdfReaderOptions`], e.g. [`max_signals`](EdfReaderOptions::max_signals)
     /// 
     /// # Examples
     /// 
@@ -144,7 +447,7 @@ impl EdfReader {
     ///     Ok(reader) => {
     ///         println!("File opened successfully!");
     ///         println!("Duration: {:.1} seconds", 
-    ///             reader.header().file_duration as f64 / 10_000_000.0);
+    ///             edfplus::time::ticks_to_seconds(reader.header().file_duration));
     ///     }
     ///     Err(e) => eprintln!("Failed to open file: {}", e),
     /// }
@@ -163,33 +466,103 @@ impl EdfReader {
     /// # Ok::<(), edfplus::EdfError>(())
     /// ```
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let file = File::open(&path)
-            .map_err(|e| EdfError::FileNotFound(format!("{}: {}", path.as_ref().display(), e)))?;
+        EdfReaderOptions::new().open(path)
+    }
+    
+    /// 按照给定选项打开文件
+    fn open_with_options(path: &Path, options: &EdfReaderOptions) -> Result<Self> {
+        #[cfg(feature = "tracing")]
+        let started = std::time::Instant::now();
+        
+        let file = File::open(path)
+            .map_err(|e| EdfError::FileNotFound(format!("{}: {}", path.display(), e)))?;
         
         let mut reader = BufReader::new(file);
         
         // 读取并解析头部
-        let (mut header, signal_info, record_size) = Self::parse_header(&mut reader)?;
+        let (mut header, signal_info, record_size, raw_main_header, raw_signal_header) =
+            Self::parse_header(&mut reader, options)?;
         
         // 计算头部大小
         let total_signals = signal_info.len();
-        let header_size = (total_signals + 1) * 256;
+        let header_size = header::MAIN_HEADER_SIZE + total_signals * header::SIGNAL_HEADER_SIZE;
+        
+        // 数据记录数为 -1（记录未正常结束）时，根据文件大小计算
+        if header.datarecords_in_file == -1 && record_size > 0 {
+            let file_size = reader.get_ref().metadata()?.len();
+            let available = file_size.saturating_sub(header_size as u64) / record_size as u64;
+            edf_debug!(available, "number of data records is -1, computed from file size");
+            Self::set_datarecords(&mut header, available as i64);
+            header.datarecords_source = DatarecordsSource::ComputedFromSize;
+        }
+        
+        // 文件被截断时，只保留完整的数据记录
+        if options.allow_truncated && record_size > 0 {
+            let file_size = reader.get_ref().metadata()?.len();
+            let available = file_size.saturating_sub(header_size as u64) / record_size as u64;
+            if (available as i64) < header.datarecords_in_file {
+                edf_debug!(declared = header.datarecords_in_file, available, "file truncated, using complete data records only");
+                Self::set_datarecords(&mut header, available as i64);
+            }
+        }
+        
+        // 扫描注释之前检查注释信号的总大小
+        let annotation_bytes_per_record: u64 = signal_info.iter()
+            .filter(|info| info.is_annotation)
+            .map(|info| info.samples_per_record as u64 * 2)
+            .sum();
+        let scanned_records = if options.parse_annotations {
+            header.datarecords_in_file
+        } else {
+            header.datarecords_in_file.min(1)
+        };
+        EdfReaderOptions::check_limit(
+            "max_annotation_bytes",
+            options.max_annotation_bytes,
+            annotation_bytes_per_record.saturating_mul(scanned_records.max(0) as u64),
+        )?;
+        
+        if let AnnotationChannels::Only(channels) = &options.annotation_channels {
+            if let Some(&channel) = channels.iter().find(|&&channel| channel >= header.annotation_signal_count) {
+                return Err(EdfError::InvalidFormat(format!(
+                    "Annotation signal {} selected, the file has {}", channel, header.annotation_signal_count
+                )));
+            }
+        }
         
         // 初始化样本位置指针
         let sample_positions = vec![0i64; header.signals.len()];
         
-        // 解析注释以获取准确的注释数量和可能的subsecond时间
-        let (annotations_count, starttime_subsecond) = Self::count_annotations_and_parse_subsecond(
-            &mut reader, 
-            &signal_info, 
-            header.datarecords_in_file,
-            record_size,
-            header_size
-        ).unwrap_or((0, 0));
-        
-        // 更新头部信息
-        header.annotations_in_file = annotations_count;
-        header.starttime_subsecond = starttime_subsecond;
+        if options.parse_annotations {
+            // 解析注释以获取准确的注释数量和可能的subsecond时间
+            let (annotations_count, starttime_subsecond) = Self::count_annotations_and_parse_subsecond(
+                &mut reader, 
+                &signal_info, 
+                header.datarecords_in_file,
+                record_size,
+                header_size,
+                &options.annotation_channels,
+            )
+            .inspect_err(|_e| edf_warn!(error = %_e, "failed to count annotations, assuming none"))
+            .unwrap_or_default();
+            
+            // 更新头部信息
+            header.annotations_in_file = annotations_count;
+            header.starttime_subsecond = starttime_subsecond;
+        } else {
+            // 跳过注释时仍然只读取第一个记录以获取subsecond时间
+            let (_, starttime_subsecond) = Self::count_annotations_and_parse_subsecond(
+                &mut reader, 
+                &signal_info, 
+                header.datarecords_in_file.min(1),
+                record_size,
+                header_size,
+                &AnnotationChannels::None,
+            )
+            .inspect_err(|_e| edf_warn!(error = %_e, "failed to read the first timekeeping TAL"))
+            .unwrap_or_default();
+            header.starttime_subsecond = starttime_subsecond;
+        }
         
         // 创建读取器实例
         let mut temp_reader = EdfReader {
@@ -200,11 +573,37 @@ impl EdfReader {
             header_size,
             record_size,
             annotations: Vec::new(),
+            raw_main_header,
+            raw_signal_header,
+            chunk_bytes: Vec::new(),
+            chunk_samples: Vec::new(),
+            type_heuristic: options.type_heuristic.clone(),
+            strict_tal: options.strict_tal,
+            tal_warnings: Vec::new(),
         };
         
         // 解析注释数据
-        let annotations = temp_reader.parse_annotations().unwrap_or_else(|_| Vec::new());
-        temp_reader.annotations = annotations;
+        if options.parse_annotations {
+            let annotations = match temp_reader.parse_annotations(options.max_annotations, &options.annotation_channels) {
+                Ok(annotations) => annotations,
+                // 资源限制和严格模式下的TAL错误不能被忽略
+                Err(e @ (EdfError::LimitExceeded { .. } | EdfError::MalformedTal { .. })) => return Err(e),
+                Err(_e) => {
+                    edf_warn!(error = %_e, "failed to parse annotations, discarding them");
+                    Vec::new()
+                }
+            };
+            temp_reader.annotations = annotations;
+        }
+        
+        edf_info!(
+            path = %path.display(),
+            signals = temp_reader.header.signals.len(),
+            records = temp_reader.header.datarecords_in_file,
+            annotations = temp_reader.annotations.len(),
+            elapsed_us = started.elapsed().as_micros() as u64,
+            "opened EDF+ file"
+        );
         
         Ok(temp_reader)
     }
@@ -231,7 +630,7 @@ impl EdfReader {
     /// // Display basic file information
     /// println!("Patient: {}", header.patient_name);
     /// println!("Recording duration: {:.2} seconds", 
-    ///     header.file_duration as f64 / 10_000_000.0);
+    ///     edfplus::time::ticks_to_seconds(header.file_duration));
     /// println!("Number of signals: {}", header.signals.len());
     /// 
     /// // Display signal information
@@ -277,6 +676,7 @@ impl EdfReader {
     /// #     physical_dimension: "uV".to_string(),
     /// #     prefilter: "HP:0.1Hz".to_string(),
     /// #     transducer: "AgAgCl".to_string(),
+    /// #     reserved: String::new(),
     /// # };
     /// # writer.add_signal(signal).unwrap();
     /// # let samples = vec![10.0; 256];
@@ -289,12 +689,9 @@ impl EdfReader {
     /// println!("Found {} annotations", annotations.len());
     /// 
     /// for (i, annotation) in annotations.iter().enumerate() {
-    ///     let onset_seconds = annotation.onset as f64 / 10_000_000.0;
-    ///     let duration_seconds = if annotation.duration >= 0 {
-    ///         annotation.duration as f64 / 10_000_000.0
-    ///     } else {
-    ///         0.0  // Instantaneous event
-    ///     };
+    ///     let onset_seconds = edfplus::time::ticks_to_seconds(annotation.onset);
+    ///     // No duration: instantaneous event
+    ///     let duration_seconds = annotation.duration.map_or(0.0, edfplus::time::ticks_to_seconds);
     ///     
     ///     println!("Annotation {}: {} at {:.2}s (duration: {:.2}s)",
     ///         i, annotation.description, onset_seconds, duration_seconds);
@@ -308,6 +705,260 @@ impl EdfReader {
         &self.annotations
     }
     
+    /// Malformed TALs that were skipped while reading annotations
+    /// 
+    /// In lenient mode (the default, see
+    /// [`EdfReaderOptions::strict_tal`]) every skipped part of an
+    /// annotation signal is listed here in the order it was found: first
+    /// those found while opening the file, then new ones found by later
+    /// reads such as [`read_record`](Self::read_record). The same warnings
+    /// are also logged with the `tracing` feature. In strict mode the list
+    /// stays empty because a malformed TAL is an error.
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::EdfReader;
+    /// 
+    /// # edfplus::doctest_utils::create_simple_test_file("tal_warnings.edf")?;
+    /// let reader = EdfReader::open("tal_warnings.edf")?;
+    /// for warning in reader.tal_warnings() {
+    ///     println!("record {}, byte {}: {}", warning.record, warning.offset, warning.reason);
+    /// }
+    /// assert!(reader.tal_warnings().is_empty());
+    /// # drop(reader);
+    /// # std::fs::remove_file("tal_warnings.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn tal_warnings(&self) -> &[TalWarning] {
+        &self.tal_warnings
+    }
+    
+    /// Distinct annotation descriptions with their count and total duration
+    /// 
+    /// Returns `(description, count, total duration in seconds)` for every
+    /// distinct description of [`annotations`](Self::annotations), most
+    /// frequent first. Descriptions are compared exactly; use
+    /// [`annotation_vocabulary_with`](Self::annotation_vocabulary_with) to
+    /// ignore case or whitespace. See the [`vocabulary`](crate::vocabulary)
+    /// module.
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::EdfReader;
+    /// 
+    /// # let mut writer = edfplus::EdfWriter::create("vocabulary.edf")?;
+    /// # writer.add_signal(edfplus::SignalParam {
+    /// #     label: "EEG".to_string(), samples_in_file: 0,
+    /// #     physical_max: 100.0, physical_min: -100.0,
+    /// #     digital_max: 32767, digital_min: -32768,
+    /// #     samples_per_record: 10, physical_dimension: "uV".to_string(),
+    /// #     prefilter: "".to_string(), transducer: "".to_string(),
+    /// #     reserved: String::new(),
+    /// # })?;
+    /// # writer.add_annotation(0.5, Some(1.0), "Spike")?;
+    /// # writer.add_annotation(1.5, Some(0.5), "Spike")?;
+    /// # writer.add_annotation(2.5, None, "Eyes closed")?;
+    /// # for _ in 0..3 { writer.write_samples(&[vec![0.0; 10]])?; }
+    /// # writer.finalize()?;
+    /// let reader = EdfReader::open("vocabulary.edf")?;
+    /// 
+    /// for (description, count, seconds) in reader.annotation_vocabulary() {
+    ///     println!("{}: {} times, {:.1}s", description, count, seconds);
+    /// }
+    /// assert_eq!(reader.annotation_vocabulary()[0], ("Spike".to_string(), 2, 1.5));
+    /// # drop(reader);
+    /// # std::fs::remove_file("vocabulary.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn annotation_vocabulary(&self) -> Vec<(String, usize, f64)> {
+        vocabulary::vocabulary(&self.annotations, &VocabularyOptions::new())
+    }
+    
+    /// Like [`annotation_vocabulary`](Self::annotation_vocabulary), with
+    /// options for comparing the descriptions
+    pub fn annotation_vocabulary_with(&self, options: &VocabularyOptions) -> Vec<(String, usize, f64)> {
+        vocabulary::vocabulary(&self.annotations, options)
+    }
+    
+    /// Indices of the signals whose label has the given type
+    /// 
+    /// The type is taken from the label prefix, see
+    /// [`SignalParam::parsed_label`]. Signals with nonconforming labels
+    /// are never selected.
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::EdfReader;
+    /// use edfplus::labels::SignalType;
+    /// 
+    /// # edfplus::doctest_utils::create_multi_channel_test_file("select_by_type.edf")?;
+    /// let reader = EdfReader::open("select_by_type.edf")?;
+    /// 
+    /// for signal in reader.select_by_type(SignalType::Eeg) {
+    ///     println!("EEG channel: {}", reader.header().signals[signal].label);
+    /// }
+    /// assert_eq!(reader.select_by_type(SignalType::Ecg), vec![1]);
+    /// assert!(reader.select_by_type(SignalType::Resp).is_empty());
+    /// # drop(reader);
+    /// # std::fs::remove_file("select_by_type.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn select_by_type(&self, signal_type: SignalType) -> Vec<usize> {
+        self.header.signals.iter()
+            .enumerate()
+            .filter(|(_, signal)| signal.parsed_label().signal_type == Some(signal_type))
+            .map(|(i, _)| i)
+            .collect()
+    }
+    
+    /// Signal type of a signal, from its label prefix or guessed
+    /// 
+    /// Labels without a type prefix are classified by the
+    /// [`TypeHeuristic`] set with
+    /// [`EdfReaderOptions::type_heuristic`]; such guesses have
+    /// `guessed: true`. Returns `None` if the type is unknown or the index
+    /// is out of bounds.
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::EdfReader;
+    /// use edfplus::labels::SignalType;
+    /// 
+    /// # edfplus::doctest_utils::create_multi_channel_test_file("signal_type.edf")?;
+    /// let reader = EdfReader::open("signal_type.edf")?;
+    /// 
+    /// let ecg = reader.signal_type(1).unwrap();
+    /// assert_eq!(ecg.signal_type, SignalType::Ecg);
+    /// assert!(!ecg.guessed);
+    /// # drop(reader);
+    /// # std::fs::remove_file("signal_type.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn signal_type(&self, signal: usize) -> Option<LabelType> {
+        let param = self.header.signals.get(signal)?;
+        self.type_heuristic.classify(&param.label)
+    }
+    
+    /// Indices of the signals of the given type, including signals whose
+    /// type is guessed from a nonconforming label
+    /// 
+    /// Unlike [`select_by_type`](Self::select_by_type), which only trusts
+    /// the label prefix, this also selects labels like `"EKG"` or
+    /// `"Fp1-A1"`; see [`signal_type`](Self::signal_type) to tell the
+    /// guesses apart.
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::{EdfReader, EdfReaderOptions, TypeHeuristic};
+    /// use edfplus::labels::SignalType;
+    /// 
+    /// # edfplus::doctest_utils::create_multi_channel_test_file("signals_of_type.edf")?;
+    /// let reader = EdfReader::open("signals_of_type.edf")?;
+    /// assert_eq!(reader.signals_of_type(SignalType::Ecg), vec![1]);
+    /// assert_eq!(reader.ecg_channels(), vec![1]);
+    /// 
+    /// // Trust the label prefix only
+    /// let reader = EdfReaderOptions::new()
+    ///     .type_heuristic(TypeHeuristic::none())
+    ///     .open("signals_of_type.edf")?;
+    /// assert_eq!(reader.signals_of_type(SignalType::Ecg), vec![1]);
+    /// # drop(reader);
+    /// # std::fs::remove_file("signals_of_type.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn signals_of_type(&self, signal_type: SignalType) -> Vec<usize> {
+        (0..self.header.signals.len())
+            .filter(|&signal| self.signal_type(signal).is_some_and(|t| t.signal_type == signal_type))
+            .collect()
+    }
+    
+    /// EEG signals, see [`signals_of_type`](Self::signals_of_type)
+    pub fn eeg_channels(&self) -> Vec<usize> {
+        self.signals_of_type(SignalType::Eeg)
+    }
+    
+    /// ECG signals, see [`signals_of_type`](Self::signals_of_type)
+    pub fn ecg_channels(&self) -> Vec<usize> {
+        self.signals_of_type(SignalType::Ecg)
+    }
+    
+    /// EOG signals, see [`signals_of_type`](Self::signals_of_type)
+    pub fn eog_channels(&self) -> Vec<usize> {
+        self.signals_of_type(SignalType::Eog)
+    }
+    
+    /// EMG signals, see [`signals_of_type`](Self::signals_of_type)
+    pub fn emg_channels(&self) -> Vec<usize> {
+        self.signals_of_type(SignalType::Emg)
+    }
+    
+    /// Gets the exact 256 bytes of the main header as read at open time
+    /// 
+    /// Useful for forensic comparison and fix-up tools that need to show the
+    /// raw field contents next to the parsed values. Field positions are
+    /// available as constants in the [`header`](crate::header) module.
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::{header, EdfReader};
+    /// 
+    /// # edfplus::doctest_utils::create_simple_test_file("raw_main.edf")?;
+    /// let reader = EdfReader::open("raw_main.edf")?;
+    /// let raw = reader.raw_main_header();
+    /// 
+    /// let signal_count = String::from_utf8_lossy(&raw[header::SIGNAL_COUNT]);
+    /// assert_eq!(signal_count.trim(), "2"); // EEG + annotation signal
+    /// # drop(reader);
+    /// # std::fs::remove_file("raw_main.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn raw_main_header(&self) -> &[u8; header::MAIN_HEADER_SIZE] {
+        &self.raw_main_header
+    }
+    
+    /// Gets the exact signal header bytes (`ns × 256`) as read at open time
+    /// 
+    /// The block includes the headers of annotation signals. Use
+    /// [`SignalField::range`](crate::header::SignalField::range) to locate
+    /// individual fields.
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::EdfReader;
+    /// use edfplus::header::SignalField;
+    /// 
+    /// # edfplus::doctest_utils::create_simple_test_file("raw_signal.edf")?;
+    /// let reader = EdfReader::open("raw_signal.edf")?;
+    /// let raw = reader.raw_signal_header();
+    /// assert_eq!(raw.len(), 2 * 256);
+    /// 
+    /// let dimension = &raw[SignalField::PhysicalDimension.range(2, 0)];
+    /// assert_eq!(dimension, b"uV      ");
+    /// # drop(reader);
+    /// # std::fs::remove_file("raw_signal.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn raw_signal_header(&self) -> &[u8] {
+        &self.raw_signal_header
+    }
+    
+    /// 头部总字节数（主头部 + 所有信号头部）
+    pub(crate) fn header_size(&self) -> usize {
+        self.header_size
+    }
+    
+    /// 每个数据记录的字节数（包括注释信号）
+    pub(crate) fn record_size(&self) -> usize {
+        self.record_size
+    }
+    
     /// Reads physical value samples from the specified signal
     /// 
     /// Physical values are the real-world measurements (e.g., microvolts for EEG,
@@ -408,7 +1059,156 @@ impl EdfReader {
         
         Ok(physical_samples)
     }
-    
+
+    /// Reads physical values as integers in a decimal sub-unit
+    ///
+    /// Like [`read_physical_samples`](Self::read_physical_samples), but
+    /// every value is an integer count of `10^-decimals` physical units,
+    /// computed with integer arithmetic from the header calibration: with
+    /// `decimals = 3` a signal in `uV` is read in nanovolts. The result
+    /// is the same on every platform. Values that are not representable
+    /// with `decimals` places are rounded half away from zero, see the
+    /// [`fixed`](crate::fixed) module for when that happens.
+    ///
+    /// # Arguments
+    ///
+    /// * `signal` - Zero-based index of the signal to read from
+    /// * `count` - Number of samples to read
+    /// * `decimals` - Decimal places of the result, clamped to
+    ///   [`MAX_DECIMALS`](crate::fixed::MAX_DECIMALS)
+    ///
+    /// # Errors
+    ///
+    /// * `EdfError::InvalidSignalIndex` - Signal index out of bounds
+    /// * `EdfError::FileReadError` - I/O error reading from file
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use edfplus::EdfReader;
+    ///
+    /// # edfplus::doctest_utils::create_simple_test_file("exact_samples.edf")?;
+    /// let mut reader = EdfReader::open("exact_samples.edf")?;
+    ///
+    /// let nanovolts = reader.read_physical_samples_exact(0, 10, 3)?;
+    /// reader.seek(0, 0)?;
+    /// let microvolts = reader.read_physical_samples(0, 10)?;
+    ///
+    /// for (exact, approximate) in nanovolts.iter().zip(&microvolts) {
+    ///     assert!((*exact as f64 / 1000.0 - approximate).abs() <= 0.0005);
+    /// }
+    /// # drop(reader);
+    /// # std::fs::remove_file("exact_samples.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn read_physical_samples_exact(&mut self, signal: usize, count: usize, decimals: u32) -> Result<Vec<i64>> {
+        let digital_samples = self.read_digital_samples(signal, count)?;
+
+        let signal_param = self.header.signals.get(signal).ok_or(EdfError::InvalidSignalIndex(signal))?;
+        let calibration = ExactCalibration::new(signal_param);
+        let exact_samples = digital_samples
+            .into_iter()
+            .map(|d| fixed::to_fixed(signal_param, calibration.as_ref(), d, decimals).value)
+            .collect();
+
+        Ok(exact_samples)
+    }
+
+    /// Reads physical values converted to the SI unit of `dimension`
+    ///
+    /// The signal's `physical_dimension` is looked up with
+    /// [`units::normalize`](crate::units::normalize): a `uV` channel read as
+    /// [`Dimension::ElectricPotential`] returns volts, a `mmHg` channel read
+    /// as [`Dimension::Pressure`] returns pascals. Signals with a blank or
+    /// unknown unit can only be read as [`Dimension::Ratio`] and return
+    /// their physical values unchanged, see the [`units`](crate::units)
+    /// module.
+    ///
+    /// # Arguments
+    ///
+    /// * `signal` - Zero-based index of the signal to read from
+    /// * `count` - Number of samples to read
+    /// * `dimension` - Quantity the caller expects the signal to measure
+    ///
+    /// # Errors
+    ///
+    /// * `EdfError::InvalidSignalIndex` - Signal index out of bounds
+    /// * `EdfError::IncompatibleDimension` - The signal's unit measures a
+    ///   different quantity; the read position does not move
+    /// * `EdfError::FileReadError` - I/O error reading from file
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use edfplus::EdfReader;
+    /// use edfplus::units::Dimension;
+    ///
+    /// # edfplus::doctest_utils::create_simple_test_file("si_samples.edf")?;
+    /// let mut reader = EdfReader::open("si_samples.edf")?;
+    /// assert_eq!(reader.header().signals[0].physical_dimension, "uV");
+    ///
+    /// let volts = reader.read_si_samples(0, 10, Dimension::ElectricPotential)?;
+    /// assert_eq!(volts.len(), 10);
+    ///
+    /// assert!(reader.read_si_samples(0, 10, Dimension::Pressure).is_err());
+    /// # drop(reader);
+    /// # std::fs::remove_file("si_samples.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn read_si_samples(&mut self, signal: usize, count: usize, dimension: Dimension) -> Result<Vec<f64>> {
+        let signal_param = self.header.signals.get(signal).ok_or(EdfError::InvalidSignalIndex(signal))?;
+        let unit = units::si_unit(&signal_param.physical_dimension, dimension)
+            .ok_or_else(|| EdfError::IncompatibleDimension {
+                signal,
+                unit: signal_param.physical_dimension.trim().to_string(),
+                requested: dimension,
+            })?;
+
+        let physical_samples = self.read_physical_samples(signal, count)?;
+        Ok(physical_samples.into_iter().map(|v| unit.to_si(v)).collect())
+    }
+
+    /// Reads physical values as `uom` quantities
+    ///
+    /// Like [`read_si_samples`](Self::read_si_samples) with the dimension
+    /// of `Q`, see [`units::Quantity`] for the supported quantity types.
+    /// Signals with a blank or unknown unit can only be read as
+    /// `uom::si::f64::Ratio`, with their physical values unchanged (a value
+    /// of 3.0 becomes a ratio of 3); `%` signals are divided by 100. Requires
+    /// the `uom` feature.
+    ///
+    /// # Errors
+    ///
+    /// * `EdfError::InvalidSignalIndex` - Signal index out of bounds
+    /// * `EdfError::IncompatibleDimension` - The signal's unit does not
+    ///   measure the quantity `Q`; the read position does not move
+    /// * `EdfError::FileReadError` - I/O error reading from file
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use edfplus::EdfReader;
+    /// use uom::si::electric_potential::microvolt;
+    /// use uom::si::f64::{ElectricPotential, Pressure};
+    ///
+    /// # edfplus::doctest_utils::create_simple_test_file("read_quantity.edf")?;
+    /// let mut reader = EdfReader::open("read_quantity.edf")?;
+    ///
+    /// let eeg = reader.read_quantity::<ElectricPotential>(0, 10)?;
+    /// println!("{:.1} uV", eeg[0].get::<microvolt>());
+    ///
+    /// // The channel is in uV, not a pressure
+    /// assert!(reader.read_quantity::<Pressure>(0, 10).is_err());
+    /// # drop(reader);
+    /// # std::fs::remove_file("read_quantity.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    #[cfg(feature = "uom")]
+    pub fn read_quantity<Q: units::Quantity>(&mut self, signal: usize, count: usize) -> Result<Vec<Q>> {
+        let si_samples = self.read_si_samples(signal, count, Q::DIMENSION)?;
+        Ok(si_samples.into_iter().map(Q::from_si).collect())
+    }
+
     /// Reads digital value samples from the specified signal
     /// 
     /// Digital values are the raw integer values stored in the EDF+ file,
@@ -540,8 +1340,7 @@ impl EdfReader {
         // ✅ 性能优化：使用类似 edflib 的直接计算方式
         while samples_read < actual_count {
             let pos = current_pos + samples_read as i64;
-            let record_index = pos / signal_param.samples_per_record as i64;
-            let sample_in_record = pos % signal_param.samples_per_record as i64;
+            let (record_index, sample_in_record) = split_position(signal_param.samples_per_record, pos);
             
             // 计算连续可读取的样本数（避免跨记录）
             let samples_remaining_in_record = 
@@ -586,32 +1385,125 @@ impl EdfReader {
         Ok(samples)
     }
     
-    /// Sets the sample position for the specified signal
+    /// Visits raw digital samples in contiguous chunks without allocating
     /// 
-    /// This method allows you to jump to any position within the signal's data
-    /// for non-sequential reading. Position is automatically clamped to valid
-    /// range [0, total_samples_in_signal].
+    /// Seeks `signal` to `start` (clamped like [`seek`](Self::seek)) and passes
+    /// up to `count` samples to `f`, one call per contiguous run within a data
+    /// record, so no chunk is longer than `samples_per_record`. The slices
+    /// borrow a buffer inside the reader that is reused across calls. Unlike
+    /// [`read_digital_samples`](Self::read_digital_samples), the values are
+    /// not clamped to the digital range.
+    /// 
+    /// Returning `ControlFlow::Break` from `f` stops the iteration. The signal
+    /// position is left after the last visited sample in either case.
     /// 
     /// # Arguments
     /// 
     /// * `signal` - Zero-based index of the signal
-    /// * `position` - Sample position to seek to (0-based)
+    /// * `start` - Sample position of the first visited sample
+    /// * `count` - Maximum number of samples to visit
+    /// * `f` - Callback receiving each chunk
     /// 
     /// # Returns
     /// 
-    /// Returns the actual position after clamping to valid range.
+    /// Number of samples passed to `f`, including the chunk that broke off.
     /// 
     /// # Errors
     /// 
     /// * `EdfError::InvalidSignalIndex` - Signal index is out of bounds
+    /// * `EdfError::FileReadError` - I/O error reading from file
     /// 
     /// # Examples
     /// 
     /// ```rust
+    /// use std::ops::ControlFlow;
     /// use edfplus::EdfReader;
     /// 
-    /// # // Generate test file (hidden from docs)
-    /// # edfplus::doctest_utils::create_simple_test_file("positioning.edf")?;
+    /// # edfplus::doctest_utils::create_simple_test_file("digital_chunks.edf")?;
+    /// let mut reader = EdfReader::open("digital_chunks.edf")?;
+    /// 
+    /// // Peak absolute value over the whole signal
+    /// let mut peak = 0i32;
+    /// let visited = reader.for_each_digital_chunk(0, 0, usize::MAX, |chunk| {
+    ///     peak = chunk.iter().fold(peak, |peak, &v| peak.max((v as i32).abs()));
+    ///     ControlFlow::Continue(())
+    /// })?;
+    /// assert_eq!(visited as i64, reader.header().signals[0].samples_in_file);
+    /// 
+    /// // Stop after the first chunk
+    /// reader.for_each_digital_chunk(0, 0, usize::MAX, |_| ControlFlow::Break(()))?;
+    /// assert_eq!(reader.tell(0)?, reader.header().signals[0].samples_per_record as i64);
+    /// # drop(reader);
+    /// # std::fs::remove_file("digital_chunks.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn for_each_digital_chunk<F>(&mut self, signal: usize, start: i64, count: usize, mut f: F) -> Result<u64>
+    where
+        F: FnMut(&[i16]) -> ControlFlow<()>,
+    {
+        let signal_info = self.data_signal_info(signal)?;
+        let samples_per_record = signal_info.samples_per_record;
+        let start = self.seek(signal, start)?;
+        
+        let samples_in_file = samples_per_record as i64 * self.header.datarecords_in_file;
+        let total = (count as u64).min((samples_in_file - start) as u64);
+        let mut visited = 0u64;
+        
+        while visited < total {
+            let (record_index, sample_in_record) = split_position(samples_per_record, start + visited as i64);
+            let chunk_len = (total - visited).min((samples_per_record as i64 - sample_in_record) as u64) as usize;
+            
+            let file_offset = self.header_size as u64
+                + record_index as u64 * self.record_size as u64
+                + signal_info.buffer_offset as u64
+                + sample_in_record as u64 * 2;
+            self.file.seek(SeekFrom::Start(file_offset))?;
+            
+            self.chunk_bytes.resize(chunk_len * 2, 0);
+            if let Err(e) = self.file.read_exact(&mut self.chunk_bytes) {
+                self.sample_positions[signal] = start + visited as i64;
+                return Err(e.into());
+            }
+            self.chunk_samples.clear();
+            self.chunk_samples.extend(self.chunk_bytes.chunks_exact(2)
+                .map(|bytes| i16::from_le_bytes([bytes[0], bytes[1]])));
+            
+            visited += chunk_len as u64;
+            if f(&self.chunk_samples).is_break() {
+                break;
+            }
+        }
+        
+        self.sample_positions[signal] = start + visited as i64;
+        Ok(visited)
+    }
+    
+    /// Sets the sample position for the specified signal
+    /// 
+    /// This method allows you to jump to any position within the signal's data
+    /// for non-sequential reading. Position is automatically clamped to valid
+    /// range [0, total_samples_in_signal].
+    /// 
+    /// # Arguments
+    /// 
+    /// * `signal` - Zero-based index of the signal
+    /// * `position` - Sample position to seek to (0-based)
+    /// 
+    /// # Returns
+    /// 
+    /// Returns the actual position after clamping to valid range.
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidSignalIndex` - Signal index is out of bounds
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::EdfReader;
+    /// 
+    /// # // Generate test file (hidden from docs)
+    /// # edfplus::doctest_utils::create_simple_test_file("positioning.edf")?;
     /// # 
     /// let mut reader = EdfReader::open("positioning.edf")?;
     /// 
@@ -665,263 +1557,1465 @@ impl EdfReader {
             return Err(EdfError::InvalidSignalIndex(signal));
         }
         
-        let signal_param = &self.header.signals[signal];
-        let max_position = signal_param.samples_per_record as i64 * self.header.datarecords_in_file;
+        let signal_param = &self.header.signals[signal];
+        let max_position = signal_param.samples_per_record as i64 * self.header.datarecords_in_file;
+        
+        let new_position = position.max(0).min(max_position);
+        self.sample_positions[signal] = new_position;
+        
+        Ok(new_position)
+    }
+    
+    /// Gets the current sample position for the specified signal
+    /// 
+    /// This method returns the current reading position within the signal's data.
+    /// The position indicates which sample will be read next by `read_physical_samples()`
+    /// or `read_digital_samples()`.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `signal` - Zero-based index of the signal
+    /// 
+    /// # Returns
+    /// 
+    /// Current sample position (0-based) within the signal.
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidSignalIndex` - Signal index is out of bounds
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::EdfReader;
+    /// 
+    /// # // Generate test file (hidden from docs)
+    /// # edfplus::doctest_utils::create_simple_test_file("position_test.edf")?;
+    /// # 
+    /// let mut reader = EdfReader::open("position_test.edf")?;
+    /// 
+    /// // Initially at position 0
+    /// assert_eq!(reader.tell(0)?, 0);
+    /// 
+    /// // Read some samples
+    /// reader.read_physical_samples(0, 10)?;
+    /// assert_eq!(reader.tell(0)?, 10);
+    /// 
+    /// // Seek to different position
+    /// reader.seek(0, 100)?;
+    /// assert_eq!(reader.tell(0)?, 100);
+    /// 
+    /// // Read more samples
+    /// reader.read_physical_samples(0, 5)?;
+    /// assert_eq!(reader.tell(0)?, 105);
+    /// 
+    /// # // Cleanup (hidden from docs)
+    /// # std::fs::remove_file("position_test.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    /// 
+    /// ## Working with multiple signals
+    /// 
+    /// ```rust
+    /// use edfplus::EdfReader;
+    /// 
+    /// # // Generate test file (hidden from docs)
+    /// # edfplus::doctest_utils::create_multi_channel_test_file("multi_pos.edf")?;
+    /// # 
+    /// let mut reader = EdfReader::open("multi_pos.edf")?;
+    /// let signal_count = reader.header().signals.len();
+    /// 
+    /// // Each signal has independent position tracking
+    /// for i in 0..signal_count {
+    ///     assert_eq!(reader.tell(i)?, 0);
+    ///     
+    ///     // Read different amounts from each signal
+    ///     reader.read_physical_samples(i, (i + 1) * 10)?;
+    ///     
+    ///     // Each signal should be at different position
+    ///     assert_eq!(reader.tell(i)?, ((i + 1) * 10) as i64);
+    /// }
+    /// 
+    /// # // Cleanup (hidden from docs)
+    /// # std::fs::remove_file("multi_pos.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn tell(&self, signal: usize) -> Result<i64> {
+        if signal >= self.header.signals.len() {
+            return Err(EdfError::InvalidSignalIndex(signal));
+        }
+        
+        Ok(self.sample_positions[signal])
+    }
+    
+    /// Resets the position of the specified signal to the beginning
+    /// 
+    /// This is equivalent to calling `seek(signal, 0)` but provides a more
+    /// convenient and semantic interface for returning to the start of the signal.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `signal` - Zero-based index of the signal to rewind
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidSignalIndex` - Signal index is out of bounds
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::EdfReader;
+    /// 
+    /// # // Generate test file (hidden from docs)
+    /// # edfplus::doctest_utils::create_simple_test_file("rewind_test.edf")?;
+    /// # 
+    /// let mut reader = EdfReader::open("rewind_test.edf")?;
+    /// 
+    /// // Read some data to advance position
+    /// reader.read_physical_samples(0, 50)?;
+    /// assert_eq!(reader.tell(0)?, 50);
+    /// 
+    /// // Rewind to beginning
+    /// reader.rewind(0)?;
+    /// assert_eq!(reader.tell(0)?, 0);
+    /// 
+    /// // We can read from beginning again
+    /// let samples_after_rewind = reader.read_physical_samples(0, 5)?;
+    /// assert_eq!(samples_after_rewind.len(), 5);
+    /// 
+    /// // Position should advance from 0 to 5
+    /// assert_eq!(reader.tell(0)?, 5);
+    /// 
+    /// # // Cleanup (hidden from docs)
+    /// # std::fs::remove_file("rewind_test.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    /// 
+    /// ## Complete positioning workflow
+    /// 
+    /// ```rust
+    /// use edfplus::EdfReader;
+    /// 
+    /// # // Generate test file (hidden from docs)
+    /// # edfplus::doctest_utils::create_simple_test_file("workflow.edf")?;
+    /// # 
+    /// let mut reader = EdfReader::open("workflow.edf")?;
+    /// 
+    /// // Start from beginning
+    /// assert_eq!(reader.tell(0)?, 0);
+    /// let start_data = reader.read_physical_samples(0, 3)?;
+    /// 
+    /// // Jump to middle
+    /// let signal_length = reader.header().signals[0].samples_in_file;
+    /// reader.seek(0, signal_length / 2)?;
+    /// let mid_data = reader.read_physical_samples(0, 3)?;
+    /// 
+    /// // Jump near end
+    /// reader.seek(0, signal_length - 10)?;
+    /// let end_data = reader.read_physical_samples(0, 3)?;
+    /// 
+    /// // Go back to beginning
+    /// reader.rewind(0)?;
+    /// let start_again = reader.read_physical_samples(0, 3)?;
+    /// 
+    /// // First and last reads from start should be identical
+    /// assert_eq!(start_data, start_again);
+    /// 
+    /// # // Cleanup (hidden from docs)
+    /// # std::fs::remove_file("workflow.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn rewind(&mut self, signal: usize) -> Result<()> {
+        self.seek(signal, 0)?;
+        Ok(())
+    }
+    
+    /// Maps a sample position to its absolute wall-clock time
+    /// 
+    /// The time is computed from the recording start (including the
+    /// subsecond offset) plus `sample_index * datarecord_duration / samples_per_record`,
+    /// evaluated with exact integer arithmetic and rounded to the nearest
+    /// nanosecond. Positions are clamped to `[0, samples_in_file]` exactly like
+    /// [`seek`](Self::seek).
+    /// 
+    /// The data records are assumed to be contiguous, so for EDF+D files the
+    /// time of a sample after a gap is off by the length of the gaps before
+    /// it; use [`read_window_with_gaps`](Self::read_window_with_gaps) to get
+    /// the timing of such samples.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `signal` - Zero-based index of the signal
+    /// * `sample_index` - Sample position within the signal
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidSignalIndex` - Signal index is out of bounds
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::EdfReader;
+    /// use chrono::TimeDelta;
+    /// 
+    /// # edfplus::doctest_utils::create_simple_test_file("sample_datetime.edf")?;
+    /// let reader = EdfReader::open("sample_datetime.edf")?;
+    /// let start = reader.header().start_datetime();
+    /// 
+    /// // 256 Hz signal: sample 128 is half a second into the recording
+    /// let t = reader.sample_datetime(0, 128)?;
+    /// assert_eq!(t - start, TimeDelta::milliseconds(500));
+    /// 
+    /// # drop(reader);
+    /// # std::fs::remove_file("sample_datetime.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn sample_datetime(&self, signal: usize, sample_index: i64) -> Result<NaiveDateTime> {
+        if signal >= self.header.signals.len() {
+            return Err(EdfError::InvalidSignalIndex(signal));
+        }
+        
+        let samples_per_record = self.header.signals[signal].samples_per_record as i128;
+        let max_position = samples_per_record as i64 * self.header.datarecords_in_file;
+        let position = sample_index.max(0).min(max_position) as i128;
+        
+        // 纳秒级精确计算并四舍五入，避免浮点误差
+        let record_ns = self.header.datarecord_duration as i128 * 100;
+        let offset_ns = (2 * position * record_ns + samples_per_record) / (2 * samples_per_record);
+        
+        Ok(self.header.start_datetime() + TimeDelta::nanoseconds(offset_ns as i64))
+    }
+    
+    /// Maps an absolute wall-clock time to a sample position
+    /// 
+    /// Returns the index of the last sample whose timestamp (as reported by
+    /// [`sample_datetime`](Self::sample_datetime)) is not after `datetime`, so
+    /// the two methods round-trip exactly. Times before the recording start or
+    /// after its end are clamped to `[0, samples_in_file]`, consistent with
+    /// [`seek`](Self::seek); the result can be passed straight to `seek`.
+    /// Like `sample_datetime` this assumes contiguous data records and
+    /// ignores the [`gaps`](Self::gaps) of EDF+D files.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `signal` - Zero-based index of the signal
+    /// * `datetime` - Wall-clock time to locate
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidSignalIndex` - Signal index is out of bounds
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::EdfReader;
+    /// use chrono::TimeDelta;
+    /// 
+    /// # edfplus::doctest_utils::create_simple_test_file("datetime_seek.edf")?;
+    /// let mut reader = EdfReader::open("datetime_seek.edf")?;
+    /// let start = reader.header().start_datetime();
+    /// 
+    /// // Seek to a clock time taken from an external log
+    /// let position = reader.datetime_to_sample(0, start + TimeDelta::milliseconds(250))?;
+    /// assert_eq!(position, 64);
+    /// reader.seek(0, position)?;
+    /// 
+    /// // Times before the start clamp to the first sample
+    /// assert_eq!(reader.datetime_to_sample(0, start - TimeDelta::hours(1))?, 0);
+    /// 
+    /// # drop(reader);
+    /// # std::fs::remove_file("datetime_seek.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn datetime_to_sample(&self, signal: usize, datetime: NaiveDateTime) -> Result<i64> {
+        if signal >= self.header.signals.len() {
+            return Err(EdfError::InvalidSignalIndex(signal));
+        }
+        
+        let samples_per_record = self.header.signals[signal].samples_per_record as i128;
+        let max_position = samples_per_record as i64 * self.header.datarecords_in_file;
+        
+        let elapsed_ns = match (datetime - self.header.start_datetime()).num_nanoseconds() {
+            Some(ns) => ns as i128,
+            None if datetime < self.header.start_datetime() => return Ok(0),
+            None => return Ok(max_position),
+        };
+        
+        // sample_datetime 使用四舍五入，因此取满足 round(i * D / s) <= t 的最大 i
+        let record_ns = self.header.datarecord_duration as i128 * 100;
+        let position = ((2 * elapsed_ns + 1) * samples_per_record - 1).div_euclid(2 * record_ns);
+        
+        Ok(position.max(0).min(max_position as i128) as i64)
+    }
+    
+    /// Maps a sample position to its data record and time
+    /// 
+    /// Every signal has its own `samples_per_record`, so the same sample index
+    /// lands in different records for signals with different rates. The time
+    /// is `sample_index * datarecord_duration / samples_per_record`, computed
+    /// with exact integer arithmetic, relative to the recording start.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `signal` - Zero-based index of the signal
+    /// * `sample_index` - Sample position within the signal
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidSignalIndex` - Signal index is out of bounds
+    /// * `EdfError::InvalidSampleIndex` - Position is not in `0..samples_in_file`
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::{EdfReader, EdfWriter, SampleLocation, SignalParam};
+    /// 
+    /// # let mut writer = EdfWriter::create("locate_sample.edf")?;
+    /// # writer.add_signal(SignalParam {
+    /// #     label: "EEG".to_string(), samples_in_file: 0,
+    /// #     physical_max: 100.0, physical_min: -100.0,
+    /// #     digital_max: 32767, digital_min: -32768,
+    /// #     samples_per_record: 256, physical_dimension: "uV".to_string(),
+    /// #     prefilter: "".to_string(), transducer: "".to_string(),
+    /// #     reserved: String::new(),
+    /// # })?;
+    /// # for _ in 0..3 {
+    /// #     writer.write_samples(&[vec![0.0; 256]])?;
+    /// # }
+    /// # writer.finalize()?;
+    /// let reader = EdfReader::open("locate_sample.edf")?;
+    /// 
+    /// // 256 samples per 1-second record
+    /// let location = reader.locate_sample(0, 300)?;
+    /// assert_eq!(location, SampleLocation { record: 1, offset_in_record: 44, time_100ns: 11_718_750 });
+    /// 
+    /// // The inverse mapping
+    /// assert_eq!(reader.first_sample_of_record(0, 1)?, 256);
+    /// # drop(reader);
+    /// # std::fs::remove_file("locate_sample.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn locate_sample(&self, signal: usize, sample_index: i64) -> Result<SampleLocation> {
+        if signal >= self.header.signals.len() {
+            return Err(EdfError::InvalidSignalIndex(signal));
+        }
+        
+        let samples_per_record = self.header.signals[signal].samples_per_record;
+        let count = samples_per_record as i64 * self.header.datarecords_in_file;
+        if sample_index < 0 || sample_index >= count {
+            return Err(EdfError::InvalidSampleIndex { index: sample_index, count });
+        }
+        
+        let (record, offset_in_record) = split_position(samples_per_record, sample_index);
+        
+        // 记录起始时间加上记录内偏移，四舍五入到100纳秒
+        let duration = self.header.datarecord_duration as i128;
+        let spr = samples_per_record as i128;
+        let offset_time = (2 * offset_in_record as i128 * duration + spr) / (2 * spr);
+        
+        Ok(SampleLocation {
+            record,
+            offset_in_record,
+            time_100ns: record * self.header.datarecord_duration + offset_time as i64,
+        })
+    }
+    
+    /// Returns the position of the first sample of `signal` in a data record
+    /// 
+    /// This is the inverse of [`locate_sample`](Self::locate_sample) for
+    /// `offset_in_record == 0`.
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidSignalIndex` - Signal index is out of bounds
+    /// * `EdfError::InvalidRecordIndex` - Index is not in `0..datarecords_in_file`
+    pub fn first_sample_of_record(&self, signal: usize, record: i64) -> Result<i64> {
+        if signal >= self.header.signals.len() {
+            return Err(EdfError::InvalidSignalIndex(signal));
+        }
+        
+        let count = self.header.datarecords_in_file;
+        if record < 0 || record >= count {
+            return Err(EdfError::InvalidRecordIndex { index: record, count });
+        }
+        
+        Ok(record * self.header.signals[signal].samples_per_record as i64)
+    }
+    
+    /// Reads all signals of one data record
+    /// 
+    /// Returns the physical samples of every signal in the record together
+    /// with the record's timekeeping timestamp and the annotations stored in
+    /// it. Unlike the sample-based methods, this does not use or change the
+    /// per-signal read positions.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `record_index` - Zero-based index of the data record
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidRecordIndex` - Index is not in `0..datarecords_in_file`
+    /// * `EdfError::Io` - I/O error reading from file
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::EdfReader;
+    /// 
+    /// # edfplus::doctest_utils::create_multi_channel_test_file("record_view.edf")?;
+    /// let mut reader = EdfReader::open("record_view.edf")?;
+    /// 
+    /// let record = reader.read_record(0)?;
+    /// for (i, samples) in record.signals.iter().enumerate() {
+    ///     println!("{}: {} samples", reader.header().signals[i].label, samples.len());
+    /// }
+    /// assert_eq!(record.timestamp, Some(0));
+    /// 
+    /// // Out-of-range indices report the valid range
+    /// let records = reader.header().datarecords_in_file;
+    /// assert!(reader.read_record(records).is_err());
+    /// # drop(reader);
+    /// # std::fs::remove_file("record_view.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn read_record(&mut self, record_index: i64) -> Result<RecordView> {
+        let count = self.header.datarecords_in_file;
+        if record_index < 0 || record_index >= count {
+            return Err(EdfError::InvalidRecordIndex { index: record_index, count });
+        }
+        
+        let record_offset = self.header_size as u64 + record_index as u64 * self.record_size as u64;
+        self.file.seek(SeekFrom::Start(record_offset))?;
+        let mut record_data = vec![0u8; self.record_size];
+        self.file.read_exact(&mut record_data)?;
+        
+        let mut signals = Vec::with_capacity(self.header.signals.len());
+        let mut timestamp = None;
+        let mut annotations = Vec::new();
+        let mut annotation_signal_idx = 0;
+        let mut tal_warnings = Vec::new();
+        
+        for info in &self.signal_info {
+            let bytes = &record_data[info.buffer_offset..info.buffer_offset + info.samples_per_record as usize * 2];
+            
+            if info.is_annotation {
+                // 第一个注释信号包含记录的时间戳
+                if annotation_signal_idx == 0 {
+                    timestamp = self.extract_timestamp(bytes, record_index)?
+                        .map(|t| t - self.header.starttime_subsecond);
+                }
+                annotations.extend(self.parse_tal_data(bytes, record_index as usize, annotation_signal_idx == 0, &mut tal_warnings)?);
+                annotation_signal_idx += 1;
+            } else {
+                let param = &self.header.signals[signals.len()];
+                let samples = bytes.chunks_exact(2)
+                    .map(|chunk| {
+                        let digital = (i16::from_le_bytes([chunk[0], chunk[1]]) as i32)
+                            .max(param.digital_min)
+                            .min(param.digital_max);
+                        param.to_physical(digital)
+                    })
+                    .collect();
+                signals.push(samples);
+            }
+        }
+        self.record_tal_warnings(tal_warnings);
+        
+        Ok(RecordView {
+            index: record_index,
+            timestamp,
+            signals,
+            annotations,
+        })
+    }
+    
+    /// Returns a handle bundling the metadata and reads of one signal
+    /// 
+    /// See the [`signal`](crate::signal) module. The handle borrows the
+    /// reader mutably until it is dropped.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `signal` - Zero-based index of the signal
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidSignalIndex` - Signal index is out of bounds
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::EdfReader;
+    /// 
+    /// # edfplus::doctest_utils::create_simple_test_file("signal_mut.edf")?;
+    /// let mut reader = EdfReader::open("signal_mut.edf")?;
+    /// let mut signal = reader.signal_mut(0)?;
+    /// 
+    /// let head = signal.read(10)?;
+    /// assert_eq!(signal.tell(), 10);
+    /// 
+    /// // Positional reads leave the position unchanged
+    /// assert_eq!(signal.read_at(0, 10)?, head);
+    /// assert_eq!(signal.tell(), 10);
+    /// # drop(reader);
+    /// # std::fs::remove_file("signal_mut.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn signal_mut(&mut self, signal: usize) -> Result<SignalHandle<'_>> {
+        if signal >= self.header.signals.len() {
+            return Err(EdfError::InvalidSignalIndex(signal));
+        }
+        Ok(SignalHandle::new(self, signal))
+    }
+    
+    /// Iterates over the physical values of one signal
+    /// 
+    /// The iterator reads one data record at a time and knows its exact
+    /// length, which is [`SignalParam::len`](crate::SignalParam::len). It can
+    /// also be consumed from the back: `rev()` reads the data records
+    /// backwards, so the last samples of a long recording are reached
+    /// without reading the rest. The read position of the signal is restored
+    /// when the iterator is dropped.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `signal` - Zero-based index of the signal
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidSignalIndex` - Signal index is out of bounds
+    /// 
+    /// Read errors are returned as items, after which the iteration ends.
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::EdfReader;
+    /// 
+    /// # edfplus::doctest_utils::create_simple_test_file("samples_iter.edf")?;
+    /// let mut reader = EdfReader::open("samples_iter.edf")?;
+    /// let total = reader.header().signals[0].len();
+    /// 
+    /// assert_eq!(reader.samples(0)?.len(), total);
+    /// 
+    /// // The last ten samples, newest first
+    /// let tail = reader.samples(0)?.rev().take(10).collect::<Result<Vec<f64>, _>>()?;
+    /// assert_eq!(tail.len(), 10);
+    /// assert_eq!(reader.tell(0)?, 0);
+    /// # drop(reader);
+    /// # std::fs::remove_file("samples_iter.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn samples(&mut self, signal: usize) -> Result<Samples<'_>> {
+        Samples::new(self, signal)
+    }
+    
+    /// Iterates over time-aligned frames of several signals
+    /// 
+    /// Yields one [`Frame`](crate::frames::Frame) per sample period of the fastest selected signal,
+    /// with the frame time and one value per selected signal. Slower signals
+    /// repeat their latest sample; use [`iter_frames_with`](Self::iter_frames_with)
+    /// to get `None` instead. See the [`frames`](crate::frames) module for
+    /// how rates that are not integer multiples are aligned.
+    /// 
+    /// The samples are read one data record at a time, independently of the
+    /// sample position used by [`read_physical_samples`](Self::read_physical_samples).
+    /// 
+    /// # Arguments
+    /// 
+    /// * `signals` - Zero-based signal indices, in the order of the frame values
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidSignalIndex` - A signal index is out of range
+    /// 
+    /// The iterator yields `EdfError::Io` for read errors and stops.
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::{EdfReader, EdfWriter, SignalParam};
+    /// 
+    /// # let mut writer = EdfWriter::create("iter_frames.edf")?;
+    /// # for (label, rate) in [("EEG Fp1", 4), ("Resp", 2)] {
+    /// #     writer.add_signal(SignalParam {
+    /// #         label: label.to_string(), samples_in_file: 0,
+    /// #         physical_max: 100.0, physical_min: -100.0,
+    /// #         digital_max: 32767, digital_min: -32768,
+    /// #         samples_per_record: rate, physical_dimension: "uV".to_string(),
+    /// #         prefilter: "".to_string(), transducer: "".to_string(),
+    /// #         reserved: String::new(),
+    /// #     })?;
+    /// # }
+    /// # writer.write_samples(&[vec![1.0, 2.0, 3.0, 4.0], vec![10.0, 20.0]])?;
+    /// # writer.finalize()?;
+    /// let mut reader = EdfReader::open("iter_frames.edf")?;
+    /// 
+    /// // 4 Hz EEG and 2 Hz respiration
+    /// let frames = reader.iter_frames(&[0, 1])?.collect::<Result<Vec<_>, _>>()?;
+    /// assert_eq!(frames.len(), 4);
+    /// assert_eq!(frames[1].time_100ns, 2_500_000);
+    /// 
+    /// let resp: Vec<f64> = frames.iter().map(|f| f.values[1].unwrap().round()).collect();
+    /// assert_eq!(resp, vec![10.0, 10.0, 20.0, 20.0]);
+    /// # drop(reader);
+    /// # std::fs::remove_file("iter_frames.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn iter_frames(&mut self, signals: &[usize]) -> Result<Frames<'_>> {
+        Frames::new(self, signals, SlowerSignals::Hold)
+    }
+    
+    /// Iterates over time-aligned frames with the given policy for slower signals
+    /// 
+    /// Same as [`iter_frames`](Self::iter_frames), see [`SlowerSignals`].
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidSignalIndex` - A signal index is out of range
+    pub fn iter_frames_with(&mut self, signals: &[usize], slower: SlowerSignals) -> Result<Frames<'_>> {
+        Frames::new(self, signals, slower)
+    }
+    
+    /// Renders the physical values of a signal to a PNG image
+    /// 
+    /// A quick look for triage, see the [`plot`](crate::plot) module: time
+    /// in seconds on the x axis, the signal's physical dimension on the y
+    /// axis and annotations as vertical markers. Requires the `plotters`
+    /// feature.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `signal` - Zero-based signal index
+    /// * `path` - Output file, replaced if it exists
+    /// * `options` - Size, time range and annotation markers
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidSignalIndex` - Signal index is out of range
+    /// * `EdfError::Io` - Reading the samples or rendering the image failed
+    #[cfg(feature = "plotters")]
+    pub fn plot_signal<P: AsRef<Path>>(&mut self, signal: usize, path: P, options: &crate::plot::PlotOptions) -> Result<()> {
+        crate::plot::plot_signal(self, signal, path.as_ref(), options)
+    }
+    
+    /// Moves the reader to a background thread that reads data records ahead
+    /// 
+    /// Shortcut for [`PrefetchingReader::new(self)`](PrefetchingReader::new),
+    /// see the [`prefetch`](crate::prefetch) module.
+    pub fn into_prefetching(self) -> PrefetchingReader {
+        PrefetchingReader::new(self)
+    }
+    
+    /// Computes a min/max envelope of a sample range for plotting
+    /// 
+    /// The samples `start_sample..end_sample` are split into `buckets`
+    /// consecutive buckets of (nearly) equal size and the minimum and maximum
+    /// physical value of each bucket is returned. The range is read in one
+    /// streaming pass with one read per data record, so drawing an overview
+    /// of a long recording does not require loading its samples.
+    /// 
+    /// If the range contains fewer samples than `buckets`, fewer buckets are
+    /// returned: one per sample, with equal minimum and maximum. An empty
+    /// range or `buckets == 0` returns an empty vector.
+    /// 
+    /// The sample position used by [`read_physical_samples`](Self::read_physical_samples)
+    /// is not changed.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `signal` - Zero-based signal index
+    /// * `start_sample` - First sample of the range
+    /// * `end_sample` - End of the range (exclusive)
+    /// * `buckets` - Number of buckets, e.g. the width of the plot in pixels
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidSignalIndex` - Signal index is out of range
+    /// * `EdfError::InvalidSampleIndex` - Range is not within `0..=samples_in_file`
+    ///   or `start_sample > end_sample`
+    /// * `EdfError::Io` - I/O error reading from file
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::EdfReader;
+    /// 
+    /// # edfplus::doctest_utils::create_simple_test_file("envelope.edf")?;
+    /// let mut reader = EdfReader::open("envelope.edf")?;
+    /// let samples = reader.header().signals[0].samples_in_file;
+    /// 
+    /// // One (min, max) pair per pixel column
+    /// let envelope = reader.read_envelope(0, 0, samples, 64)?;
+    /// assert_eq!(envelope.len(), 64);
+    /// for (min, max) in &envelope {
+    ///     assert!(min <= max);
+    /// }
+    /// 
+    /// // Zoomed in past one sample per pixel: one bucket per sample
+    /// assert_eq!(reader.read_envelope(0, 0, 10, 64)?.len(), 10);
+    /// # drop(reader);
+    /// # std::fs::remove_file("envelope.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn read_envelope(&mut self, signal: usize, start_sample: i64, end_sample: i64, buckets: usize) -> Result<Vec<(f64, f64)>> {
+        let info = self.data_signal_info(signal)?;
+        let param = &self.header.signals[signal];
+        let count = param.samples_per_record as i64 * self.header.datarecords_in_file;
+        if start_sample < 0 || start_sample > count {
+            return Err(EdfError::InvalidSampleIndex { index: start_sample, count });
+        }
+        if end_sample < start_sample || end_sample > count {
+            return Err(EdfError::InvalidSampleIndex { index: end_sample, count });
+        }
+        
+        let total = end_sample - start_sample;
+        let buckets = (buckets as i64).min(total);
+        if buckets == 0 {
+            return Ok(Vec::new());
+        }
+        
+        // 第b个桶的起始样本（用i128避免溢出）
+        let bucket_start = |b: i64| start_sample + (total as i128 * b as i128 / buckets as i128) as i64;
+        let to_envelope = |(min, max): (i32, i32)| {
+            let (a, b) = (param.to_physical(min), param.to_physical(max));
+            // 反相信号的数字最小值对应物理最大值
+            (a.min(b), a.max(b))
+        };
+        
+        let mut envelope = Vec::with_capacity(buckets as usize);
+        let mut next_bucket_start = bucket_start(1);
+        let mut current: Option<(i32, i32)> = None;
+        let mut buffer = vec![0u8; info.samples_per_record as usize * 2];
+        let mut position = start_sample;
+        
+        while position < end_sample {
+            let (record, offset) = split_position(param.samples_per_record, position);
+            let samples = (param.samples_per_record as i64 - offset).min(end_sample - position) as usize;
+            let file_offset = self.header_size as u64
+                + record as u64 * self.record_size as u64
+                + info.buffer_offset as u64
+                + offset as u64 * 2;
+            self.file.seek(SeekFrom::Start(file_offset))?;
+            self.file.read_exact(&mut buffer[..samples * 2])?;
+            
+            for chunk in buffer[..samples * 2].chunks_exact(2) {
+                if position == next_bucket_start {
+                    envelope.extend(current.take().map(to_envelope));
+                    next_bucket_start = bucket_start(envelope.len() as i64 + 1);
+                }
+                
+                let digital = (i16::from_le_bytes([chunk[0], chunk[1]]) as i32)
+                    .max(param.digital_min)
+                    .min(param.digital_max);
+                current = Some(match current {
+                    Some((min, max)) => (min.min(digital), max.max(digital)),
+                    None => (digital, digital),
+                });
+                position += 1;
+            }
+        }
+        envelope.extend(current.map(to_envelope));
+        
+        Ok(envelope)
+    }
+    
+    /// Reads the annotations stored in one data record
+    /// 
+    /// Only the annotation signal bytes of the record are read, so this works
+    /// with files opened with
+    /// [`parse_annotations(false)`](EdfReaderOptions::parse_annotations) and
+    /// allows processing a file record by record without parsing all
+    /// annotations up front. Onsets are adjusted exactly like in
+    /// [`annotations`](Self::annotations), so concatenating the results for
+    /// all records yields the same annotations. The timekeeping TAL is
+    /// excluded; see
+    /// [`annotations_in_record_with_timekeeping`](Self::annotations_in_record_with_timekeeping).
+    /// 
+    /// # Arguments
+    /// 
+    /// * `record_index` - Zero-based index of the data record
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidRecordIndex` - Index is not in `0..datarecords_in_file`
+    /// * `EdfError::Io` - I/O error reading from file
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::{EdfReaderOptions, EdfWriter, SignalParam};
+    /// 
+    /// # let mut writer = EdfWriter::create("record_annotations.edf")?;
+    /// # writer.add_signal(SignalParam {
+    /// #     label: "EEG".to_string(), samples_in_file: 0,
+    /// #     physical_max: 100.0, physical_min: -100.0,
+    /// #     digital_max: 32767, digital_min: -32768,
+    /// #     samples_per_record: 10, physical_dimension: "uV".to_string(),
+    /// #     prefilter: "".to_string(), transducer: "".to_string(),
+    /// #     reserved: String::new(),
+    /// # })?;
+    /// # writer.add_annotation(1.5, None, "Arousal")?;
+    /// # for _ in 0..3 {
+    /// #     writer.write_samples(&[vec![0.0; 10]])?;
+    /// # }
+    /// # writer.finalize()?;
+    /// let mut reader = EdfReaderOptions::new()
+    ///     .parse_annotations(false)
+    ///     .open("record_annotations.edf")?;
+    /// 
+    /// for record in 0..reader.header().datarecords_in_file {
+    ///     for annotation in reader.annotations_in_record(record)? {
+    ///         println!("record {}: {}", record, annotation.description);
+    ///     }
+    /// }
+    /// assert_eq!(reader.annotations_in_record(1)?[0].description, "Arousal");
+    /// # drop(reader);
+    /// # std::fs::remove_file("record_annotations.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn annotations_in_record(&mut self, record_index: i64) -> Result<Vec<Annotation>> {
+        self.read_record_annotations(record_index, false)
+    }
+    
+    /// Reads the annotations of one data record, including the timekeeping TAL
+    /// 
+    /// Same as [`annotations_in_record`](Self::annotations_in_record), but the
+    /// record's timekeeping TAL is returned as an annotation with an empty
+    /// description. Its onset is the record's start time.
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidRecordIndex` - Index is not in `0..datarecords_in_file`
+    /// * `EdfError::Io` - I/O error reading from file
+    pub fn annotations_in_record_with_timekeeping(&mut self, record_index: i64) -> Result<Vec<Annotation>> {
+        self.read_record_annotations(record_index, true)
+    }
+    
+    /// Reads the annotations of every data record, grouped by record
+    /// 
+    /// Element `i` of the result holds the annotations stored in data record
+    /// `i`, in the order of the TALs in the file (annotation signals in
+    /// header order). Unlike [`annotations`](Self::annotations), which is
+    /// sorted by onset, this keeps the placement of each annotation, e.g. to
+    /// reproduce the record-to-annotation mapping of a file when copying it.
+    /// Timekeeping TALs are not included.
+    /// 
+    /// The annotations are read from the file, so this also works when the
+    /// reader was opened with
+    /// [`parse_annotations(false)`](EdfReaderOptions::parse_annotations).
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::Io` - I/O error reading from file
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::{EdfReader, EdfWriter, SignalParam};
+    /// 
+    /// # let mut writer = EdfWriter::create("annotations_by_record.edf")?;
+    /// # writer.add_signal(SignalParam {
+    /// #     label: "EEG".to_string(), samples_in_file: 0,
+    /// #     physical_max: 100.0, physical_min: -100.0,
+    /// #     digital_max: 32767, digital_min: -32768,
+    /// #     samples_per_record: 10, physical_dimension: "uV".to_string(),
+    /// #     prefilter: "".to_string(), transducer: "".to_string(),
+    /// #     reserved: String::new(),
+    /// # })?;
+    /// # writer.add_annotation(0.2, None, "Eyes closed")?;
+    /// # writer.add_annotation(2.5, None, "Eyes open")?;
+    /// # for _ in 0..3 {
+    /// #     writer.write_samples(&[vec![0.0; 10]])?;
+    /// # }
+    /// # writer.finalize()?;
+    /// let mut reader = EdfReader::open("annotations_by_record.edf")?;
+    /// 
+    /// let records = reader.annotations_by_record()?;
+    /// assert_eq!(records.len(), 3);
+    /// assert_eq!(records[0][0].description, "Eyes closed");
+    /// assert!(records[1].is_empty());
+    /// assert_eq!(records[2][0].description, "Eyes open");
+    /// # drop(reader);
+    /// # std::fs::remove_file("annotations_by_record.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn annotations_by_record(&mut self) -> Result<Vec<Vec<Annotation>>> {
+        (0..self.header.datarecords_in_file)
+            .map(|record_index| self.read_record_tals(record_index, false))
+            .collect()
+    }
+    
+    /// Compares each data record's timekeeping TAL with its nominal position
+    /// 
+    /// Uses a threshold of 0.1 ms, the tolerance the reader itself accepts
+    /// between consecutive records when parsing annotations. See
+    /// [`timing_report_with`](Self::timing_report_with) and the
+    /// [`timing`](crate::timing) module.
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::Io` - I/O error reading from file
+    pub fn timing_report(&mut self) -> Result<TimingReport> {
+        self.timing_report_with(EDFLIB_TIME_DIMENSION / 10_000)
+    }
+    
+    /// Compares each data record's timekeeping TAL with its nominal position,
+    /// with a custom threshold
+    /// 
+    /// Reads the same timestamps as [`RecordView::timestamp`], but only the
+    /// bytes of the first annotation signal of every record.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `threshold` - Absolute drift in 100 ns units above which a record is
+    ///   reported in [`TimingReport::first_exceeding`]
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::Io` - I/O error reading from file
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::EdfReader;
+    /// 
+    /// # edfplus::doctest_utils::create_simple_test_file("timing_threshold.edf")?;
+    /// let mut reader = EdfReader::open("timing_threshold.edf")?;
+    /// 
+    /// // Tolerate one millisecond of clock skew
+    /// let report = reader.timing_report_with(edfplus::time::seconds_to_ticks(0.001))?;
+    /// if let Some(record) = report.first_exceeding {
+    ///     println!("record {} drifts by more than 1 ms", record);
+    /// }
+    /// assert_eq!(report.records, reader.header().datarecords_in_file);
+    /// # drop(reader);
+    /// # std::fs::remove_file("timing_threshold.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn timing_report_with(&mut self, threshold: i64) -> Result<TimingReport> {
+        let mut accumulator = TimingAccumulator::new(
+            self.header.file_type.is_continuous(), self.header.datarecord_duration, threshold
+        );
+        for record_index in 0..self.header.datarecords_in_file {
+            let timestamp = self.read_record_timestamp(record_index)?;
+            accumulator.push(record_index, timestamp);
+        }
+        Ok(accumulator.finish())
+    }
+    
+    /// Lists the gaps between the segments of a discontinuous file
+    /// 
+    /// A gap is found wherever the timekeeping TAL of a data record is at
+    /// least one data record duration later than the end of the previous
+    /// record, see [`TimingReport::gaps`]. EDF+C files have no gaps by
+    /// definition and are not scanned.
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::FileReadError` - I/O error reading the annotation signal
+    pub fn gaps(&mut self) -> Result<Vec<Gap>> {
+        if self.header.file_type.is_continuous() {
+            return Ok(Vec::new());
+        }
+        Ok(self.timing_report()?.gaps)
+    }
+    
+    /// Whether no data was recorded at `seconds` after the start because it
+    /// falls into a gap, see [`gaps`](Self::gaps)
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::FileReadError` - I/O error reading the annotation signal
+    pub fn is_gap_at(&mut self, seconds: f64) -> Result<bool> {
+        let time = seconds_to_ticks(seconds);
+        Ok(self.gaps()?.iter().any(|gap| gap.start <= time && time < gap.end()))
+    }
+    
+    /// Reads the physical values of `signal` between two points in time
+    /// 
+    /// The window starts at the first sample at or after `start_seconds`
+    /// and ends before the first sample at or after
+    /// `start_seconds + duration_seconds`, relative to the start of the
+    /// recording; it is cut off at the end of the recording. The read
+    /// position of the signal is not changed.
+    /// 
+    /// A window that crosses a gap of a discontinuous file is an error,
+    /// since its samples would not be evenly spaced in time. Use
+    /// [`read_window_with_gaps`](Self::read_window_with_gaps) to read such
+    /// windows.
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidSignalIndex` - Signal index out of bounds
+    /// * `EdfError::InvalidFormat` - Negative start or duration
+    /// * `EdfError::WindowCrossesGap` - The window contains a gap
+    /// * `EdfError::FileReadError` - I/O error reading from file
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::EdfReader;
+    /// 
+    /// # edfplus::doctest_utils::create_simple_test_file("read_window.edf")?;
+    /// let mut reader = EdfReader::open("read_window.edf")?;
+    /// let frequency = reader.signal_mut(0)?.sample_frequency();
+    /// 
+    /// let window = reader.read_window(0, 0.5, 0.25)?;
+    /// assert_eq!(window.len(), (0.25 * frequency) as usize);
+    /// # drop(reader);
+    /// # std::fs::remove_file("read_window.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn read_window(&mut self, signal: usize, start_seconds: f64, duration_seconds: f64) -> Result<Vec<f64>> {
+        let window = self.read_window_with_gaps(signal, start_seconds, duration_seconds)?;
+        if let Some(gap) = window.gaps.first() {
+            return Err(EdfError::WindowCrossesGap { start: gap.start, duration: gap.duration });
+        }
+        Ok(window.parts.into_iter().flat_map(|part| part.samples).collect())
+    }
+    
+    /// Reads the same time window of every signal of a type
+    /// 
+    /// Returns `(signal index, physical samples)` for each signal selected
+    /// by [`signals_of_type`](Self::signals_of_type), in signal order. The
+    /// window is read as by [`read_window`](Self::read_window), so signals
+    /// with different sample rates return different numbers of samples.
+    /// 
+    /// # Errors
+    /// 
+    /// Same as [`read_window`](Self::read_window).
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::EdfReader;
+    /// use edfplus::labels::SignalType;
+    /// 
+    /// # edfplus::doctest_utils::create_multi_channel_test_file("read_window_by_type.edf")?;
+    /// let mut reader = EdfReader::open("read_window_by_type.edf")?;
+    /// 
+    /// for (signal, samples) in reader.read_window_by_type(SignalType::Eeg, 0.0, 0.5)? {
+    ///     println!("{}: {} samples", reader.header().signals[signal].label, samples.len());
+    /// }
+    /// # drop(reader);
+    /// # std::fs::remove_file("read_window_by_type.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn read_window_by_type(&mut self, signal_type: SignalType, start_seconds: f64, duration_seconds: f64) -> Result<Vec<(usize, Vec<f64>)>> {
+        self.signals_of_type(signal_type).into_iter()
+            .map(|signal| Ok((signal, self.read_window(signal, start_seconds, duration_seconds)?)))
+            .collect()
+    }
+    
+    /// Reads a time window like [`read_window`](Self::read_window), keeping
+    /// the recorded parts apart and listing the gaps between them
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidSignalIndex` - Signal index out of bounds
+    /// * `EdfError::InvalidFormat` - Negative start or duration
+    /// * `EdfError::FileReadError` - I/O error reading from file
+    pub fn read_window_with_gaps(&mut self, signal: usize, start_seconds: f64, duration_seconds: f64) -> Result<WindowWithGaps> {
+        let samples_per_record = self.header.signals.get(signal)
+            .ok_or(EdfError::InvalidSignalIndex(signal))?
+            .samples_per_record as i64;
+        if !(start_seconds >= 0.0 && duration_seconds >= 0.0) {
+            return Err(EdfError::InvalidFormat(format!(
+                "Window must have a non-negative start and duration, got {}s + {}s", start_seconds, duration_seconds
+            )));
+        }
+        let window_start = seconds_to_ticks(start_seconds);
+        let window_end = seconds_to_ticks(start_seconds + duration_seconds);
+        let duration = self.header.datarecord_duration.max(1);
+        
+        let (segments, gaps) = if self.header.file_type.is_continuous() {
+            (vec![TimingSegment { first_record: 0, records: self.header.datarecords_in_file, onset: 0 }], Vec::new())
+        } else {
+            let report = self.timing_report()?;
+            (report.segments, report.gaps)
+        };
+        let gaps = gaps.into_iter()
+            .filter(|gap| gap.start < window_end && gap.end() > window_start)
+            .map(|gap| {
+                let start = gap.start.max(window_start);
+                Gap { start, duration: gap.end().min(window_end) - start, ..gap }
+            })
+            .collect();
+        
+        // 段内时间点处或之后的第一个样本
+        let first_sample_at = |segment: &TimingSegment, time: i64| {
+            let offset = (time - segment.onset) as i128 * samples_per_record as i128;
+            segment.first_record * samples_per_record + ((offset + duration as i128 - 1) / duration as i128) as i64
+        };
+        
+        let saved_position = self.sample_positions[signal];
+        let mut parts = Vec::new();
+        for segment in &segments {
+            let segment_end = segment.onset + segment.records * duration;
+            let (start, end) = (window_start.max(segment.onset), window_end.min(segment_end));
+            if start >= end {
+                continue;
+            }
+            let first_sample = first_sample_at(segment, start);
+            let count = first_sample_at(segment, end) - first_sample;
+            if count <= 0 {
+                continue;
+            }
+            self.sample_positions[signal] = first_sample;
+            let samples = self.read_physical_samples(signal, count as usize);
+            self.sample_positions[signal] = saved_position;
+            let segment_start = segment.onset - segment.first_record * duration;
+            parts.push(WindowPart {
+                start: segment_start + (first_sample as i128 * duration as i128 / samples_per_record as i128) as i64,
+                first_sample,
+                samples: samples?,
+            });
+        }
+        
+        Ok(WindowWithGaps { parts, gaps })
+    }
+    
+    /// 记录之后读取时发现的TAL警告，已经记录过的不重复添加
+    fn record_tal_warnings(&mut self, warnings: Vec<TalWarning>) {
+        for warning in warnings {
+            if !self.tal_warnings.contains(&warning) {
+                self.tal_warnings.push(warning);
+            }
+        }
+    }
+    
+    /// 只读取第一个注释信号，返回记录的时间戳（与 RecordView::timestamp 相同）
+    fn read_record_timestamp(&mut self, record_index: i64) -> Result<Option<i64>> {
+        let Some(info) = self.signal_info.iter().find(|info| info.is_annotation) else {
+            return Ok(None);
+        };
+        let record_offset = self.header_size as u64 + record_index as u64 * self.record_size as u64;
+        let mut tal_data = vec![0u8; info.samples_per_record as usize * 2];
+        self.file.seek(SeekFrom::Start(record_offset + info.buffer_offset as u64))?;
+        self.file.read_exact(&mut tal_data)?;
+        
+        Ok(self.extract_timestamp(&tal_data, record_index)?
+            .map(|t| t - self.header.starttime_subsecond))
+    }
+    
+    /// 读取指定记录的注释，按开始时间排序
+    fn read_record_annotations(&mut self, record_index: i64, include_timekeeping: bool) -> Result<Vec<Annotation>> {
+        let mut annotations = self.read_record_tals(record_index, include_timekeeping)?;
         
-        let new_position = position.max(0).min(max_position);
-        self.sample_positions[signal] = new_position;
+        // 与全局注释列表保持相同的排序
+        annotations.sort_by_key(|a| a.onset);
+        Ok(annotations)
+    }
+    
+    /// 只读取指定记录中注释信号的字节并解析，保持TAL在文件中的顺序
+    fn read_record_tals(&mut self, record_index: i64, include_timekeeping: bool) -> Result<Vec<Annotation>> {
+        let count = self.header.datarecords_in_file;
+        if record_index < 0 || record_index >= count {
+            return Err(EdfError::InvalidRecordIndex { index: record_index, count });
+        }
         
-        Ok(new_position)
+        let record_offset = self.header_size as u64 + record_index as u64 * self.record_size as u64;
+        let mut annotations = Vec::new();
+        let mut annotation_signal_idx = 0;
+        
+        for i in 0..self.signal_info.len() {
+            let info = &self.signal_info[i];
+            if !info.is_annotation {
+                continue;
+            }
+            
+            let mut tal_data = vec![0u8; info.samples_per_record as usize * 2];
+            self.file.seek(SeekFrom::Start(record_offset + info.buffer_offset as u64))?;
+            self.file.read_exact(&mut tal_data)?;
+            
+            let skip_timekeeping = annotation_signal_idx == 0 && !include_timekeeping;
+            let mut tal_warnings = Vec::new();
+            annotations.extend(self.parse_tal_data(&tal_data, record_index as usize, skip_timekeeping, &mut tal_warnings)?);
+            self.record_tal_warnings(tal_warnings);
+            annotation_signal_idx += 1;
+        }
+        
+        Ok(annotations)
     }
     
-    /// Gets the current sample position for the specified signal
+    /// Computes the SHA-256 digest of one signal's stored samples
     /// 
-    /// This method returns the current reading position within the signal's data.
-    /// The position indicates which sample will be read next by `read_physical_samples()`
-    /// or `read_digital_samples()`.
+    /// The raw little-endian sample bytes of the signal are hashed in record
+    /// order, one record at a time, so the file is never loaded into memory.
+    /// The header is not part of the digest: files whose signal data is
+    /// bit-identical hash equal even if labels, patient fields or calibration
+    /// differ. See the [`digest`](crate::digest) module for details.
     /// 
     /// # Arguments
     /// 
-    /// * `signal` - Zero-based index of the signal
-    /// 
-    /// # Returns
-    /// 
-    /// Current sample position (0-based) within the signal.
+    /// * `signal` - Zero-based signal index (annotation signals are not counted)
     /// 
     /// # Errors
     /// 
-    /// * `EdfError::InvalidSignalIndex` - Signal index is out of bounds
+    /// * `EdfError::InvalidSignalIndex` - Signal index is out of range
+    /// * `EdfError::Io` - I/O error reading from file
     /// 
     /// # Examples
     /// 
     /// ```rust
     /// use edfplus::EdfReader;
     /// 
-    /// # // Generate test file (hidden from docs)
-    /// # edfplus::doctest_utils::create_simple_test_file("position_test.edf")?;
-    /// # 
-    /// let mut reader = EdfReader::open("position_test.edf")?;
-    /// 
-    /// // Initially at position 0
-    /// assert_eq!(reader.tell(0)?, 0);
+    /// # edfplus::doctest_utils::create_multi_channel_test_file("signal_digest.edf")?;
+    /// let mut reader = EdfReader::open("signal_digest.edf")?;
     /// 
-    /// // Read some samples
-    /// reader.read_physical_samples(0, 10)?;
-    /// assert_eq!(reader.tell(0)?, 10);
+    /// let first = reader.signal_digest(0)?;
+    /// let second = reader.signal_digest(1)?;
+    /// assert_ne!(first, second);
+    /// # drop(reader);
+    /// # std::fs::remove_file("signal_digest.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn signal_digest(&mut self, signal: usize) -> Result<[u8; 32]> {
+        self.signal_digest_with(signal, Sha256::new())
+    }
+    
+    /// Like [`signal_digest`](Self::signal_digest), with a custom hasher
+    pub fn signal_digest_with<H: DigestHasher>(&mut self, signal: usize, mut hasher: H) -> Result<H::Output> {
+        let info = self.data_signal_info(signal)?;
+        
+        let mut buffer = vec![0u8; info.samples_per_record as usize * 2];
+        for record in 0..self.header.datarecords_in_file as u64 {
+            let offset = self.header_size as u64 + record * self.record_size as u64 + info.buffer_offset as u64;
+            self.file.seek(SeekFrom::Start(offset))?;
+            self.file.read_exact(&mut buffer)?;
+            hasher.update(&buffer);
+        }
+        
+        Ok(hasher.finish())
+    }
+    
+    /// Computes the SHA-256 digest of all stored signal data
     /// 
-    /// // Seek to different position
-    /// reader.seek(0, 100)?;
-    /// assert_eq!(reader.tell(0)?, 100);
+    /// The sample bytes of every non-annotation signal are hashed in file
+    /// order, record by record. Annotation signals are excluded, so two files
+    /// with the same samples but different annotations hash equal; use
+    /// [`data_digest_with`](Self::data_digest_with) to include them.
     /// 
-    /// // Read more samples
-    /// reader.read_physical_samples(0, 5)?;
-    /// assert_eq!(reader.tell(0)?, 105);
+    /// # Errors
     /// 
-    /// # // Cleanup (hidden from docs)
-    /// # std::fs::remove_file("position_test.edf").ok();
-    /// # Ok::<(), edfplus::EdfError>(())
-    /// ```
+    /// * `EdfError::Io` - I/O error reading from file
     /// 
-    /// ## Working with multiple signals
+    /// # Examples
     /// 
     /// ```rust
     /// use edfplus::EdfReader;
     /// 
-    /// # // Generate test file (hidden from docs)
-    /// # edfplus::doctest_utils::create_multi_channel_test_file("multi_pos.edf")?;
-    /// # 
-    /// let mut reader = EdfReader::open("multi_pos.edf")?;
-    /// let signal_count = reader.header().signals.len();
+    /// # edfplus::doctest_utils::create_simple_test_file("data_digest_a.edf")?;
+    /// # edfplus::doctest_utils::create_simple_test_file("data_digest_b.edf")?;
+    /// let mut a = EdfReader::open("data_digest_a.edf")?;
+    /// let mut b = EdfReader::open("data_digest_b.edf")?;
     /// 
-    /// // Each signal has independent position tracking
-    /// for i in 0..signal_count {
-    ///     assert_eq!(reader.tell(i)?, 0);
-    ///     
-    ///     // Read different amounts from each signal
-    ///     reader.read_physical_samples(i, (i + 1) * 10)?;
-    ///     
-    ///     // Each signal should be at different position
-    ///     assert_eq!(reader.tell(i)?, ((i + 1) * 10) as i64);
+    /// if a.data_digest()? == b.data_digest()? {
+    ///     println!("Same recording");
     /// }
-    /// 
-    /// # // Cleanup (hidden from docs)
-    /// # std::fs::remove_file("multi_pos.edf").ok();
+    /// # assert_eq!(a.data_digest()?, b.data_digest()?);
+    /// # drop(a);
+    /// # drop(b);
+    /// # std::fs::remove_file("data_digest_a.edf").ok();
+    /// # std::fs::remove_file("data_digest_b.edf").ok();
     /// # Ok::<(), edfplus::EdfError>(())
     /// ```
-    pub fn tell(&self, signal: usize) -> Result<i64> {
-        if signal >= self.header.signals.len() {
-            return Err(EdfError::InvalidSignalIndex(signal));
-        }
-        
-        Ok(self.sample_positions[signal])
+    pub fn data_digest(&mut self) -> Result<[u8; 32]> {
+        self.data_digest_with(Sha256::new(), false)
     }
     
-    /// Resets the position of the specified signal to the beginning
-    /// 
-    /// This is equivalent to calling `seek(signal, 0)` but provides a more
-    /// convenient and semantic interface for returning to the start of the signal.
+    /// Like [`data_digest`](Self::data_digest), with a custom hasher
     /// 
     /// # Arguments
     /// 
-    /// * `signal` - Zero-based index of the signal to rewind
+    /// * `hasher` - Hash function to feed the data to
+    /// * `include_annotations` - Also hash the raw bytes of the annotation
+    ///   signals, at their position within each record
+    pub fn data_digest_with<H: DigestHasher>(&mut self, mut hasher: H, include_annotations: bool) -> Result<H::Output> {
+        let mut record_data = vec![0u8; self.record_size];
+        for record in 0..self.header.datarecords_in_file as u64 {
+            self.file.seek(SeekFrom::Start(self.header_size as u64 + record * self.record_size as u64))?;
+            self.file.read_exact(&mut record_data)?;
+            
+            for info in &self.signal_info {
+                if info.is_annotation && !include_annotations {
+                    continue;
+                }
+                hasher.update(&record_data[info.buffer_offset..info.buffer_offset + info.samples_per_record as usize * 2]);
+            }
+        }
+        
+        Ok(hasher.finish())
+    }
+    
+    /// Checks whether the data of each signal fits its declared calibration
+    /// 
+    /// Equivalent to [`calibration_audit_with`](Self::calibration_audit_with)
+    /// with the default [`CalibrationOptions`].
     /// 
     /// # Errors
     /// 
-    /// * `EdfError::InvalidSignalIndex` - Signal index is out of bounds
+    /// * `EdfError::Io` - I/O error reading from file
     /// 
     /// # Examples
     /// 
     /// ```rust
-    /// use edfplus::EdfReader;
-    /// 
-    /// # // Generate test file (hidden from docs)
-    /// # edfplus::doctest_utils::create_simple_test_file("rewind_test.edf")?;
-    /// # 
-    /// let mut reader = EdfReader::open("rewind_test.edf")?;
-    /// 
-    /// // Read some data to advance position
-    /// reader.read_physical_samples(0, 50)?;
-    /// assert_eq!(reader.tell(0)?, 50);
-    /// 
-    /// // Rewind to beginning
-    /// reader.rewind(0)?;
-    /// assert_eq!(reader.tell(0)?, 0);
-    /// 
-    /// // We can read from beginning again
-    /// let samples_after_rewind = reader.read_physical_samples(0, 5)?;
-    /// assert_eq!(samples_after_rewind.len(), 5);
-    /// 
-    /// // Position should advance from 0 to 5
-    /// assert_eq!(reader.tell(0)?, 5);
-    /// 
-    /// # // Cleanup (hidden from docs)
-    /// # std::fs::remove_file("rewind_test.edf").ok();
+    /// use edfplus::{EdfReader, EdfWriter, SignalParam};
+    /// use edfplus::calibration::CalibrationFinding;
+    /// 
+    /// # let mut writer = EdfWriter::create("calibration_audit.edf")?;
+    /// # writer.add_signal(SignalParam {
+    /// #     label: "EEG".to_string(), samples_in_file: 0,
+    /// #     physical_max: 1000.0, physical_min: -1000.0,
+    /// #     digital_max: 32767, digital_min: -32768,
+    /// #     samples_per_record: 100, physical_dimension: "mV".to_string(),
+    /// #     prefilter: "".to_string(), transducer: "".to_string(),
+    /// #     reserved: String::new(),
+    /// # })?;
+    /// # let samples: Vec<f64> = (0..100).map(|i| (i as f64 * 0.1).sin() * 0.3).collect();
+    /// # writer.write_samples(&[samples])?;
+    /// # writer.finalize()?;
+    /// // Declared ±1000 mV, but the data never leaves ±0.3 mV
+    /// let mut reader = EdfReader::open("calibration_audit.edf")?;
+    /// 
+    /// let findings = reader.calibration_audit()?;
+    /// assert!(matches!(findings[0], CalibrationFinding::NarrowSpan { signal: 0, .. }));
+    /// # drop(reader);
+    /// # std::fs::remove_file("calibration_audit.edf").ok();
     /// # Ok::<(), edfplus::EdfError>(())
     /// ```
+    pub fn calibration_audit(&mut self) -> Result<Vec<CalibrationFinding>> {
+        self.calibration_audit_with(&CalibrationOptions::new())
+    }
+    
+    /// Checks the calibration of each signal with custom thresholds
     /// 
-    /// ## Complete positioning workflow
-    /// 
-    /// ```rust
-    /// use edfplus::EdfReader;
-    /// 
-    /// # // Generate test file (hidden from docs)
-    /// # edfplus::doctest_utils::create_simple_test_file("workflow.edf")?;
-    /// # 
-    /// let mut reader = EdfReader::open("workflow.edf")?;
-    /// 
-    /// // Start from beginning
-    /// assert_eq!(reader.tell(0)?, 0);
-    /// let start_data = reader.read_physical_samples(0, 3)?;
-    /// 
-    /// // Jump to middle
-    /// let signal_length = reader.header().signals[0].samples_in_file;
-    /// reader.seek(0, signal_length / 2)?;
-    /// let mid_data = reader.read_physical_samples(0, 3)?;
+    /// All samples are read once, record by record, to find the observed
+    /// range of every signal. A signal is reported when
     /// 
-    /// // Jump near end
-    /// reader.seek(0, signal_length - 10)?;
-    /// let end_data = reader.read_physical_samples(0, 3)?;
+    /// * its observed physical span is below
+    ///   [`min_span_fraction`](CalibrationOptions::min_span_fraction) of the
+    ///   declared span ([`CalibrationFinding::NarrowSpan`]),
+    /// * more than [`max_rail_fraction`](CalibrationOptions::max_rail_fraction)
+    ///   of its samples are at the digital minimum or maximum
+    ///   ([`CalibrationFinding::RailHugging`]), or
+    /// * its physical dimension is empty ([`CalibrationFinding::EmptyDimension`]).
     /// 
-    /// // Go back to beginning
-    /// reader.rewind(0)?;
-    /// let start_again = reader.read_physical_samples(0, 3)?;
+    /// Findings are ordered by signal index. Sample positions are not changed.
     /// 
-    /// // First and last reads from start should be identical
-    /// assert_eq!(start_data, start_again);
+    /// # Errors
     /// 
-    /// # // Cleanup (hidden from docs)
-    /// # std::fs::remove_file("workflow.edf").ok();
-    /// # Ok::<(), edfplus::EdfError>(())
-    /// ```
-    pub fn rewind(&mut self, signal: usize) -> Result<()> {
-        self.seek(signal, 0)?;
-        Ok(())
+    /// * `EdfError::Io` - I/O error reading from file
+    pub fn calibration_audit_with(&mut self, options: &CalibrationOptions) -> Result<Vec<CalibrationFinding>> {
+        let extremes = self.scan_digital_extremes()?;
+        Ok(self.header.signals.iter()
+            .zip(&extremes)
+            .enumerate()
+            .flat_map(|(signal, (param, extremes))| calibration::audit_signal(signal, param, extremes, options))
+            .collect())
+    }
+    
+    /// 逐记录扫描所有非注释信号的数字值
+    fn scan_digital_extremes(&mut self) -> Result<Vec<DigitalExtremes>> {
+        let mut extremes = vec![DigitalExtremes::default(); self.header.signals.len()];
+        let mut record_data = vec![0u8; self.record_size];
+        
+        for record in 0..self.header.datarecords_in_file as u64 {
+            self.file.seek(SeekFrom::Start(self.header_size as u64 + record * self.record_size as u64))?;
+            self.file.read_exact(&mut record_data)?;
+            
+            let data_signals = self.signal_info.iter().filter(|info| !info.is_annotation);
+            for ((info, param), extremes) in data_signals.zip(&self.header.signals).zip(&mut extremes) {
+                let bytes = &record_data[info.buffer_offset..info.buffer_offset + info.samples_per_record as usize * 2];
+                for chunk in bytes.chunks_exact(2) {
+                    extremes.add(i16::from_le_bytes([chunk[0], chunk[1]]) as i32, param);
+                }
+            }
+        }
+        
+        Ok(extremes)
+    }
+    
+    /// 更新数据记录数以及由它决定的时长和样本数
+    fn set_datarecords(header: &mut EdfHeader, datarecords: i64) {
+        header.datarecords_in_file = datarecords;
+        header.file_duration = header.datarecord_duration * datarecords;
+        for signal in &mut header.signals {
+            signal.samples_in_file = signal.samples_per_record as i64 * datarecords;
+        }
+    }
+    
+    /// 查找第 `signal` 个非注释信号的位置信息
+    fn data_signal_info(&self, signal: usize) -> Result<SignalInfo> {
+        self.signal_info.iter()
+            .filter(|info| !info.is_annotation)
+            .nth(signal)
+            .cloned()
+            .ok_or(EdfError::InvalidSignalIndex(signal))
     }
     
     /// 解析EDF+文件头部
-    fn parse_header(reader: &mut BufReader<File>) -> Result<(EdfHeader, Vec<SignalInfo>, usize)> {
+    #[allow(clippy::type_complexity)]
+    fn parse_header(
+        reader: &mut BufReader<File>,
+        options: &EdfReaderOptions,
+    ) -> Result<(EdfHeader, Vec<SignalInfo>, usize, [u8; header::MAIN_HEADER_SIZE], Vec<u8>)> {
         // 读取主头部（256字节）
         reader.seek(SeekFrom::Start(0))?;
-        let mut main_header = vec![0u8; 256];
+        let mut main_header = [0u8; header::MAIN_HEADER_SIZE];
         reader.read_exact(&mut main_header)?;
         
-        // 验证EDF+标识
-        let version = String::from_utf8_lossy(&main_header[0..8]);
-        if !version.trim().starts_with('0') {
-            return Err(EdfError::UnsupportedFileType(format!("Not an EDF file: {}", version)));
+        let fields = header::parse_main_header_with(&main_header, options.lenient)?;
+        let file_type = fields.file_type;
+        if !matches!(file_type, FileType::EdfPlusContinuous | FileType::EdfPlusDiscontinuous) {
+            return Err(EdfError::UnsupportedFileType(format!("Only EDF+ files are supported, found {}", file_type)));
         }
         
-        // 解析信号数量
-        let signals_str = String::from_utf8_lossy(&main_header[252..256]);
-        let total_signal_count = atoi_nonlocalized(&signals_str);
-        if total_signal_count < 1 || total_signal_count > crate::EDFLIB_MAXSIGNALS as i32 {
-            return Err(EdfError::InvalidSignalCount(total_signal_count));
-        }
+        // 分配信号头部之前检查资源限制
+        let total_signal_count = fields.signal_count;
+        let signal_header_size = total_signal_count * header::SIGNAL_HEADER_SIZE;
+        EdfReaderOptions::check_limit("max_signals", options.max_signals as u64, total_signal_count as u64)?;
+        EdfReaderOptions::check_limit(
+            "max_header_bytes", options.max_header_bytes as u64, (header::MAIN_HEADER_SIZE + signal_header_size) as u64
+        )?;
         
         // 验证头部大小
-        let header_size_str = String::from_utf8_lossy(&main_header[184..192]);
-        let expected_header_size = (total_signal_count + 1) * 256;
-        let actual_header_size = atoi_nonlocalized(&header_size_str);
-        if actual_header_size != expected_header_size {
-            return Err(EdfError::InvalidHeader);
-        }
-        
-        // 检查EDF+标识
-        let reserved = String::from_utf8_lossy(&main_header[192..236]);
-        let is_edfplus = reserved.starts_with("EDF+C");
-        if !is_edfplus {
-            return Err(EdfError::UnsupportedFileType("Only EDF+ files are supported".to_string()));
+        let expected_header_size = (header::MAIN_HEADER_SIZE + signal_header_size) as i32;
+        if fields.header_bytes != expected_header_size {
+            if !options.lenient {
+                return Err(EdfError::InvalidHeader);
+            }
+            edf_debug!(declared = fields.header_bytes, expected = expected_header_size, "header size field mismatch, ignored");
         }
         
-        // 解析基本信息
-        let patient_field = String::from_utf8_lossy(&main_header[8..88]).trim().to_string();
-        let recording_field = String::from_utf8_lossy(&main_header[88..168]).trim().to_string();
-        
-        // 解析日期和时间
-        let date_str = String::from_utf8_lossy(&main_header[168..176]);
-        let time_str = String::from_utf8_lossy(&main_header[176..184]);
-        
-        let (start_date, start_time) = Self::parse_datetime(&date_str, &time_str)?;
-        
-        // 解析数据记录信息
-        let datarecords_str = String::from_utf8_lossy(&main_header[236..244]);
-        let datarecords = atoi_nonlocalized(&datarecords_str) as i64;
-        
-        let duration_str = String::from_utf8_lossy(&main_header[244..252]);
-        let datarecord_duration = if duration_str.trim() == "1" {
-            EDFLIB_TIME_DIMENSION
-        } else {
-            parse_edf_time(&duration_str)?
-        };
-        
         // 读取信号头部信息
-        let signal_header_size = total_signal_count as usize * 256;
         let mut signal_header = vec![0u8; signal_header_size];
         reader.read_exact(&mut signal_header)?;
         
         // 解析信号参数
-        let (signals, signal_info, total_record_size) = Self::parse_signals(
-            &signal_header, 
-            total_signal_count as usize,
-            datarecords
-        )?;
+        let all_signals = header::parse_signal_headers_with(&signal_header, total_signal_count, options.lenient)?;
+        let (signals, signal_info, total_record_size) = Self::split_signals(&signal_header, all_signals);
+        EdfReaderOptions::check_limit("max_record_bytes", options.max_record_bytes as u64, total_record_size as u64)?;
         
         // 解析EDF+字段
         let (patient_code, sex, birthdate, patient_name, patient_additional) = 
-            Self::parse_edfplus_patient(&patient_field)?;
+            Self::parse_edfplus_patient(&fields.patient)?;
         
         let (admin_code, technician, equipment, recording_additional) = 
-            Self::parse_edfplus_recording(&recording_field)?;
+            Self::parse_edfplus_recording(&fields.recording)?;
         
-        // 创建临时头部用于注释解析
         let mut temp_header = EdfHeader {
+            file_type,
             signals,
-            file_duration: datarecord_duration * datarecords,
-            start_date,
-            start_time,
+            file_duration: 0,
+            start_date: fields.start_date,
+            start_time: fields.start_time,
             starttime_subsecond: 0,
-            datarecords_in_file: datarecords,
-            datarecord_duration,
+            datarecords_in_file: 0,
+            datarecords_source: DatarecordsSource::Declared,
+            datarecord_duration: fields.datarecord_duration,
             annotations_in_file: 0,
             patient_code,
             sex,
@@ -932,173 +3026,43 @@ impl EdfReader {
             technician,
             equipment,
             recording_additional,
+            reserved_main: fields.reserved,
+            annotation_signal_count: signal_info.iter().filter(|info| info.is_annotation).count(),
         };
+        Self::set_datarecords(&mut temp_header, fields.datarecords);
         
-        // 解析注释以获取准确的注释数量和可能的subsecond时间
-        let (annotations_count, starttime_subsecond) = Self::count_annotations_and_parse_subsecond(
-            reader, 
-            &signal_info, 
-            datarecords,
-            total_record_size,
-            (total_signal_count as usize + 1) * 256
-        ).unwrap_or((0, 0));
-        
-        // 更新头部信息
-        temp_header.annotations_in_file = annotations_count;
-        temp_header.starttime_subsecond = starttime_subsecond;
-        
-        Ok((temp_header, signal_info, total_record_size))
-    }
-    
-    /// 解析日期时间
-    fn parse_datetime(date_str: &str, time_str: &str) -> Result<(NaiveDate, NaiveTime)> {
-        // 解析日期 "dd.mm.yy"
-        let date_parts: Vec<&str> = date_str.split('.').collect();
-        if date_parts.len() != 3 {
-            return Err(EdfError::FormatError);
-        }
-        
-        let day = atoi_nonlocalized(date_parts[0]);
-        let month = atoi_nonlocalized(date_parts[1]);
-        let year = {
-            let yy = atoi_nonlocalized(date_parts[2]);
-            if yy > 84 { 1900 + yy } else { 2000 + yy }
-        };
-        
-        let start_date = NaiveDate::from_ymd_opt(year, month as u32, day as u32)
-            .ok_or(EdfError::FormatError)?;
-        
-        // 解析时间 "hh.mm.ss"
-        let time_parts: Vec<&str> = time_str.split('.').collect();
-        if time_parts.len() != 3 {
-            return Err(EdfError::FormatError);
-        }
-        
-        let hour = atoi_nonlocalized(time_parts[0]);
-        let minute = atoi_nonlocalized(time_parts[1]);
-        let second = atoi_nonlocalized(time_parts[2]);
-        
-        let start_time = NaiveTime::from_hms_opt(hour as u32, minute as u32, second as u32)
-            .ok_or(EdfError::FormatError)?;
-        
-        Ok((start_date, start_time))
+        Ok((temp_header, signal_info, total_record_size, main_header, signal_header))
     }
     
-    /// 解析信号参数
-    fn parse_signals(
-        signal_header: &[u8], 
-        total_signal_count: usize,
-        datarecords: i64
-    ) -> Result<(Vec<SignalParam>, Vec<SignalInfo>, usize)> {
+    /// 拆分出普通信号，并计算所有信号（包括注释信号）在数据记录中的位置
+    fn split_signals(signal_header: &[u8], all_signals: Vec<SignalParam>) -> (Vec<SignalParam>, Vec<SignalInfo>, usize) {
+        let total_signal_count = all_signals.len();
         let mut signals = Vec::new();
         let mut signal_info = Vec::new();
         let mut buffer_offset = 0;
         
-        // 解析每个信号的各个字段
-        for i in 0..total_signal_count {
-            // 标签 (16字节)
-            let label_start = i * 16;
-            let label_bytes = &signal_header[label_start..label_start + 16];
-            let label = String::from_utf8_lossy(label_bytes).trim().to_string();
-            
+        for (i, signal_param) in all_signals.into_iter().enumerate() {
             // 检查是否是注释信号 - 必须完全匹配 "EDF Annotations " (注意末尾的空格)
-            let is_annotation = label_bytes == b"EDF Annotations ";
-            
-            // 传感器类型 (80字节，从偏移16*signal_count开始)
-            let transducer_start = total_signal_count * 16 + i * 80;
-            let transducer = String::from_utf8_lossy(
-                &signal_header[transducer_start..transducer_start + 80]
-            ).trim().to_string();
-            
-            // 物理单位 (8字节)
-            let unit_start = total_signal_count * 96 + i * 8;
-            let physical_dimension = String::from_utf8_lossy(
-                &signal_header[unit_start..unit_start + 8]
-            ).trim().to_string();
-            
-            // 物理最小值 (8字节)
-            let phys_min_start = total_signal_count * 104 + i * 8;
-            let phys_min_str = String::from_utf8_lossy(
-                &signal_header[phys_min_start..phys_min_start + 8]
-            );
-            let physical_min = atof_nonlocalized(&phys_min_str);
+            let is_annotation = header::is_annotation_label(&signal_header[SignalField::Label.range(total_signal_count, i)]);
+            let samples_per_record = signal_param.samples_per_record;
             
-            // 物理最大值 (8字节)
-            let phys_max_start = total_signal_count * 112 + i * 8;
-            let phys_max_str = String::from_utf8_lossy(
-                &signal_header[phys_max_start..phys_max_start + 8]
-            );
-            let physical_max = atof_nonlocalized(&phys_max_str);
-            
-            // 数字最小值 (8字节)
-            let dig_min_start = total_signal_count * 120 + i * 8;
-            let dig_min_str = String::from_utf8_lossy(
-                &signal_header[dig_min_start..dig_min_start + 8]
-            );
-            let digital_min = atoi_nonlocalized(&dig_min_str);
-            
-            // 数字最大值 (8字节)  
-            let dig_max_start = total_signal_count * 128 + i * 8;
-            let dig_max_str = String::from_utf8_lossy(
-                &signal_header[dig_max_start..dig_max_start + 8]
-            );
-            let digital_max = atoi_nonlocalized(&dig_max_str);
-            
-            // 预滤波 (80字节)
-            let prefilter_start = total_signal_count * 136 + i * 80;
-            let prefilter = String::from_utf8_lossy(
-                &signal_header[prefilter_start..prefilter_start + 80]
-            ).trim().to_string();
-            
-            // 每个数据记录中的样本数 (8字节)
-            let samples_start = total_signal_count * 216 + i * 8;
-            let samples_str = String::from_utf8_lossy(
-                &signal_header[samples_start..samples_start + 8]
-            );
-            let samples_per_record = atoi_nonlocalized(&samples_str);
-            
-            // 创建 SignalInfo - 所有信号都要设置正确的 buffer_offset
-            let info = SignalInfo {
+            // 所有信号都要设置正确的 buffer_offset
+            signal_info.push(SignalInfo {
                 buffer_offset,  // 当前累计的字节偏移
                 samples_per_record,
                 is_annotation,
-            };
+            });
             
             // 只有非注释信号才添加到用户可见的信号列表中
             if !is_annotation {
-                // 验证参数
-                if physical_min == physical_max {
-                    return Err(EdfError::PhysicalMinEqualsMax);
-                }
-                if digital_min == digital_max {
-                    return Err(EdfError::DigitalMinEqualsMax);
-                }
-                
-                let signal_param = SignalParam {
-                    label,
-                    samples_in_file: samples_per_record as i64 * datarecords,
-                    physical_max,
-                    physical_min,
-                    digital_max,
-                    digital_min,
-                    samples_per_record,
-                    physical_dimension,
-                    prefilter,
-                    transducer,
-                };
-                
                 signals.push(signal_param);
             }
             
-            // 将信号信息添加到列表（包括注释信号）
-            signal_info.push(info);
-            
-            // ✅ 关键修复：为所有信号（包括注释信号）更新 buffer_offset
             // 每个样本占用 2 字节（EDF 格式固定）
             buffer_offset += samples_per_record as usize * 2;
         }
         
-        Ok((signals, signal_info, buffer_offset))
+        (signals, signal_info, buffer_offset)
     }
     
     /// 解析EDF+患者字段
@@ -1106,7 +3070,7 @@ impl EdfReader {
         // EDF+ 患者字段格式: "patientcode sex birthdate patientname additional_info"
         let parts: Vec<&str> = patient_field.split_whitespace().collect();
         
-        let patient_code = parts.get(0).unwrap_or(&"").to_string();
+        let patient_code = parts.first().unwrap_or(&"").to_string();
         let sex = parts.get(1).unwrap_or(&"").to_string();
         let birthdate = parts.get(2).unwrap_or(&"").to_string();
         let patient_name = parts.get(3).unwrap_or(&"").to_string();
@@ -1132,7 +3096,7 @@ impl EdfReader {
     /// 
     /// This reads the annotation signal data and extracts annotations according 
     /// to the EDF+ TAL format specification, following the edflib implementation.
-    fn parse_annotations(&mut self) -> Result<Vec<Annotation>> {
+    fn parse_annotations(&mut self, max_annotations: usize, channels: &AnnotationChannels) -> Result<Vec<Annotation>> {
         let mut annotations = Vec::new();
         let mut elapsed_time = 0i64;
         
@@ -1176,11 +3140,16 @@ impl EdfReader {
                     if ann_idx == 0 {
                         if let Some(timestamp) = self.extract_timestamp(tal_data, record_idx)? {
                             if record_idx > 0 {
-                                // 验证时间连续性
+                                // 验证时间连续性：EDF+C 必须紧接上一记录，EDF+D 只允许向后跳（间隙）
                                 let expected_time = elapsed_time + self.header.datarecord_duration;
-                                let time_diff = (timestamp - expected_time).abs();
-                                if time_diff > EDFLIB_TIME_DIMENSION / 1000 {
-                                    // 时间不连续，可能是discontinuous文件
+                                let time_diff = timestamp - expected_time;
+                                let tolerance = EDFLIB_TIME_DIMENSION / 1000;
+                                let out_of_order = if self.header.file_type.is_continuous() {
+                                    time_diff.abs() > tolerance
+                                } else {
+                                    time_diff < -tolerance
+                                };
+                                if out_of_order {
                                     return Err(EdfError::InvalidHeader);
                                 }
                             } else if !first_record_processed {
@@ -1194,19 +3163,28 @@ impl EdfReader {
                         }
                     }
                     
+                    // 未选择的注释信号只用于上面的时间戳验证
+                    if !channels.includes(ann_idx) {
+                        continue;
+                    }
+                    
                     // 解析注释
+                    let mut tal_warnings = Vec::new();
                     let record_annotations = self.parse_tal_data(
                         tal_data, 
                         record_idx as usize, 
-                        ann_idx == 0
+                        ann_idx == 0,
+                        &mut tal_warnings,
                     )?;
+                    self.tal_warnings.extend(tal_warnings);
                     annotations.extend(record_annotations);
+                    EdfReaderOptions::check_limit("max_annotations", max_annotations as u64, annotations.len() as u64)?;
                 }
             }
         }
         
         // 按时间排序
-        annotations.sort_by(|a, b| a.onset.cmp(&b.onset));
+        annotations.sort_by_key(|a| a.onset);
         
         Ok(annotations)
     }
@@ -1215,7 +3193,7 @@ impl EdfReader {
         // 提取第一个时间戳用于验证
         let mut k = 0;
         let mut n = 0;
-        let mut scratchpad = vec![0u8; 64];
+        let mut scratchpad = [0u8; 64];
         
         while k < data.len() - 1 {
             let byte = data[k];
@@ -1230,7 +3208,7 @@ impl EdfReader {
                 // 移除前导'+'号
                 let time_str = time_str.trim_start_matches('+');
                 if let Ok(timestamp) = time_str.parse::<f64>() {
-                    return Ok(Some((timestamp * EDFLIB_TIME_DIMENSION as f64) as i64));
+                    return Ok(Some(seconds_to_ticks(timestamp)));
                 }
                 break;
             }
@@ -1252,14 +3230,36 @@ impl EdfReader {
     /// TAL format: "+<onset>[\x15<duration>]\x14<description>\x14"
     /// 
     /// This closely follows the edflib_get_annotations logic for parsing TAL data.
-    fn parse_tal_data(&self, data: &[u8], _record_idx: usize, is_first_annotation_signal: bool) -> Result<Vec<Annotation>> {
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    fn parse_tal_data(
+        &self,
+        data: &[u8],
+        record_idx: usize,
+        skip_timekeeping: bool,
+        warnings: &mut Vec<TalWarning>,
+    ) -> Result<Vec<Annotation>> {
         let mut annotations = Vec::new();
+        let strict = self.strict_tal;
+        
+        // 没有结束符时只解析到最后一个NUL为止
+        let data = match data.iter().rposition(|&b| b == 0) {
+            Some(end) if end + 1 == data.len() => data,
+            end => {
+                let offset = end.map_or(0, |end| end + 1);
+                let reason = "annotation signal is not NUL-terminated";
+                if strict {
+                    return Err(EdfError::MalformedTal { record: record_idx, offset, reason });
+                }
+                edf_warn!(record = record_idx, offset, "skipped malformed TAL: annotation signal is not NUL-terminated");
+                warnings.push(TalWarning { record: record_idx, offset, reason });
+                match end {
+                    Some(end) => &data[..=end],
+                    None => return Ok(annotations),
+                }
+            }
+        };
         let max = data.len();
         
-        if max == 0 || data[max - 1] != 0 {
-            return Ok(annotations);
-        }
-        
         // // 临时调试输出
         // println!("DEBUG: 开始解析TAL数据，长度: {}", max);
         // let preview_len = 50.min(max);
@@ -1290,6 +3290,30 @@ impl EdfReader {
         let mut annots_in_record = 0;
         let mut _annots_in_tal = 0;
         let mut duration = false;
+        // 当前TAL的起始偏移，报告错误时使用
+        let mut tal_start = 0;
+        
+        // 格式错误的TAL：严格模式下报错，否则跳到下一个以NUL分隔的TAL继续解析
+        macro_rules! malformed {
+            ($reason:literal $(, $($field:tt)+)?) => {{
+                if strict {
+                    return Err(EdfError::MalformedTal { record: record_idx, offset: tal_start, reason: $reason });
+                }
+                edf_warn!(record = record_idx, offset = tal_start, $($($field)+,)? concat!("skipped malformed TAL: ", $reason));
+                warnings.push(TalWarning { record: record_idx, offset: tal_start, reason: $reason });
+                k = data[k..].iter().position(|&b| b == 0).map_or(max, |end| k + end + 1);
+                tal_start = k;
+                zero = 1;
+                state = TalState::WaitingForOnset;
+                duration = false;
+                n = 0;
+                scratchpad.fill(0);
+                time_in_txt.fill(0);
+                duration_in_txt.fill(0);
+                _annots_in_tal = 0;
+                continue;
+            }};
+        }
         
         while k < max - 1 {
             let byte = data[k];
@@ -1299,7 +3323,7 @@ impl EdfReader {
                 if zero == 0 {
                     if k > 0 && data[k - 1] != 20 {
                         // 格式错误：null字节前应该是分隔符
-                        break;
+                        malformed!("NUL byte not preceded by a separator");
                     }
                     // 重置状态到新TAL开始
                     state = TalState::WaitingForOnset;
@@ -1309,12 +3333,19 @@ impl EdfReader {
                 }
                 zero += 1;
                 k += 1;
+                tal_start = k;
                 continue;
             }
             
             if zero > 1 {
-                // 格式错误：连续的null字节太多
-                break;
+                // 格式错误：连续的null字节太多，宽松模式下继续解析之后的TAL
+                let offset = k + 1 - zero;
+                let reason = "too many consecutive NUL bytes";
+                if strict {
+                    return Err(EdfError::MalformedTal { record: record_idx, offset, reason });
+                }
+                edf_warn!(record = record_idx, offset, "malformed TAL: too many consecutive NUL bytes");
+                warnings.push(TalWarning { record: record_idx, offset, reason });
             }
             zero = 0;
             
@@ -1327,7 +3358,7 @@ impl EdfReader {
                         n = 0;
                     } else if byte == 20 || byte == 21 {
                         // 如果没有onset就遇到分隔符，说明格式错误
-                        break;
+                        malformed!("separator before onset");
                     }
                     k += 1;
                 }
@@ -1340,8 +3371,7 @@ impl EdfReader {
                         
                         // 验证onset格式
                         if !Self::is_valid_onset(&onset_str) {
-                            // println!("DEBUG: 无效的onset格式: '{}'", onset_str);
-                            break;
+                            malformed!("invalid onset", onset = %onset_str);
                         }
                         
                         // 保存onset时间
@@ -1360,8 +3390,7 @@ impl EdfReader {
                         
                         // 验证onset格式
                         if !Self::is_valid_onset(&onset_str) {
-                            // println!("DEBUG: 无效的onset格式: '{}'", onset_str);
-                            break;
+                            malformed!("invalid onset", onset = %onset_str);
                         }
                         
                         // 保存onset时间
@@ -1391,8 +3420,7 @@ impl EdfReader {
                         
                         // 验证duration格式
                         if !Self::is_valid_duration(&duration_str) {
-                            // println!("DEBUG: 无效的duration格式: '{}'", duration_str);
-                            break;
+                            malformed!("invalid duration", duration = %duration_str);
                         }
                         
                         // 保存duration
@@ -1407,8 +3435,7 @@ impl EdfReader {
                         // println!("DEBUG: 完成duration字段: '{}'", duration_str);
                     } else if byte == 21 {
                         // 不允许在duration状态下再次遇到duration分隔符
-                        // println!("DEBUG: 错误 - 多个duration字段");
-                        break;
+                        malformed!("more than one duration field");
                     } else {
                         // 收集duration字符
                         if n < scratchpad.len() - 1 {
@@ -1432,12 +3459,12 @@ impl EdfReader {
                         
                         // 根据EDF+标准，时间戳注释（timestamp annotations）有空描述
                         // 且通常在每个数据记录的开头。用户注释即使描述为空也应该保留
-                        let is_timestamp_annotation = is_first_annotation_signal && 
+                        let is_timestamp_annotation = skip_timekeeping && 
                                                        annots_in_record == 0 && 
                                                        description.is_empty();
                         
-                        // println!("DEBUG: 是时间戳注释={}, 是第一个注释信号={}, 记录中注释数={}", 
-                        //         is_timestamp_annotation, is_first_annotation_signal, annots_in_record);
+                        // println!("DEBUG: 是时间戳注释={}, 跳过时间戳={}, 记录中注释数={}", 
+                        //         is_timestamp_annotation, skip_timekeeping, annots_in_record);
                         
                         if !is_timestamp_annotation {
                             let time_str = String::from_utf8_lossy(&time_in_txt)
@@ -1445,21 +3472,18 @@ impl EdfReader {
                             
                             if let Ok(onset_seconds) = time_str.parse::<f64>() {
                                 // 计算绝对时间戳
-                                let onset_time = (onset_seconds * EDFLIB_TIME_DIMENSION as f64) as i64;
+                                let onset_time = seconds_to_ticks(onset_seconds);
                                 
                                 // 从注释时间戳中减去文件的 starttime_offset（类似 edflib）
                                 let adjusted_onset = onset_time - self.header.starttime_subsecond;
                                 
+                                // 没有duration子字段时为None
                                 let duration_time = if duration {
                                     let duration_str = String::from_utf8_lossy(&duration_in_txt)
                                         .trim_end_matches('\0').to_string();
-                                    if let Ok(duration_seconds) = duration_str.parse::<f64>() {
-                                        (duration_seconds * EDFLIB_TIME_DIMENSION as f64) as i64
-                                    } else {
-                                        -1
-                                    }
+                                    duration_str.parse::<f64>().ok().map(seconds_to_ticks)
                                 } else {
-                                    -1
+                                    None
                                 };
                                 
                                 annotations.push(Annotation {
@@ -1473,7 +3497,7 @@ impl EdfReader {
                                         // if duration_time >= 0 { Some(duration_time as f64 / EDFLIB_TIME_DIMENSION as f64) } else { None },
                                         // annotations.last().unwrap().description);
                             } else {
-                                // println!("DEBUG: 无法解析onset时间: '{}'", time_str);
+                                edf_warn!(record = record_idx, offset = k, onset = %time_str, "dropped annotation: unparseable onset");
                             }
                         } else {
                             // println!("DEBUG: 跳过时间戳注释");
@@ -1492,7 +3516,7 @@ impl EdfReader {
                         duration_in_txt.fill(0);
                     } else if byte == 21 {
                         // 在描述状态下不应该遇到duration分隔符
-                        break;
+                        malformed!("duration separator inside description");
                     } else {
                         // 收集描述字符
                         if n < scratchpad.len() - 1 {
@@ -1573,6 +3597,7 @@ impl EdfReader {
         datarecords: i64,
         record_size: usize,
         header_size: usize,
+        channels: &AnnotationChannels,
     ) -> Result<(i64, i64)> {
         let mut annotation_count = 0i64;
         let mut starttime_subsecond = 0i64;
@@ -1618,10 +3643,12 @@ impl EdfReader {
                         record_idx == 0,
                         ann_idx == 0  // 只有第一个注释信号才是 true
                     )?;
-                    annotation_count += record_annotations;
+                    if channels.includes(ann_idx) {
+                        annotation_count += record_annotations;
+                    }
                     
-                    // 第一个记录可能包含subsecond信息
-                    if record_idx == 0 && subsecond != 0 {
+                    // 第一个记录的时间戳可能包含subsecond信息，只在第一个注释信号中
+                    if record_idx == 0 && ann_idx == 0 && subsecond != 0 {
                         starttime_subsecond = subsecond;
                     }
                 }
@@ -1802,7 +3829,7 @@ impl EdfReader {
         // 寻找第一个时间戳
         let mut k = 0;
         let mut n = 0;
-        let mut scratchpad = vec![0u8; 64];
+        let mut scratchpad = [0u8; 64];
         
         while k < data.len() - 1 {
             let byte = data[k];
@@ -1817,7 +3844,7 @@ impl EdfReader {
                 let time_str = time_str.trim_start_matches('+');
                 
                 if let Ok(timestamp) = time_str.parse::<f64>() {
-                    let timestamp_units = (timestamp * EDFLIB_TIME_DIMENSION as f64) as i64;
+                    let timestamp_units = seconds_to_ticks(timestamp);
                     return timestamp_units % EDFLIB_TIME_DIMENSION;
                 }
                 break;
@@ -1834,3 +3861,9 @@ impl EdfReader {
         0
     }
 }
+
+/// 将样本位置拆分为（数据记录索引, 记录内偏移）
+fn split_position(samples_per_record: i32, position: i64) -> (i64, i64) {
+    let samples_per_record = samples_per_record as i64;
+    (position / samples_per_record, position % samples_per_record)
+}
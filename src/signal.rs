@@ -0,0 +1,307 @@
+//! Per-signal access through a single handle
+//!
+//! [`EdfReader::signal_mut`](crate::EdfReader::signal_mut) returns a
+//! [`SignalHandle`] that bundles the metadata and the read position of one
+//! signal, so code that processes a signal does not have to carry the
+//! `(reader, index)` pair around.
+//!
+//! The handle borrows the reader mutably, so only one handle exists at a
+//! time. Read positions are the reader's own: reads through a handle
+//! advance [`EdfReader::tell`](crate::EdfReader::tell) for that signal and
+//! vice versa.
+//!
+//! [`EdfReader::samples`](crate::EdfReader::samples) iterates over the
+//! physical values of one signal as [`Samples`], from either end.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use edfplus::EdfReader;
+//!
+//! # edfplus::doctest_utils::create_multi_channel_test_file("signal_handle.edf")?;
+//! let mut reader = EdfReader::open("signal_handle.edf")?;
+//!
+//! for index in 0..reader.header().signals.len() {
+//!     let mut signal = reader.signal_mut(index)?;
+//!     let first_second = signal.read(signal.sample_frequency() as usize)?;
+//!     let stats = signal.stats()?;
+//!
+//!     println!("{} [{}]: {} samples, first {:.2}, mean {:.2}",
+//!         signal.label(), signal.dimension(), signal.len(), first_second[0], stats.mean);
+//! }
+//! # drop(reader);
+//! # std::fs::remove_file("signal_handle.edf").ok();
+//! # Ok::<(), edfplus::EdfError>(())
+//! ```
+
+use crate::error::{EdfError, Result};
+use crate::reader::EdfReader;
+use crate::time;
+use crate::types::SignalParam;
+
+/// One signal of an [`EdfReader`], see the [module documentation](self)
+pub struct SignalHandle<'a> {
+    reader: &'a mut EdfReader,
+    index: usize,
+}
+
+/// Summary statistics of the physical values of a signal
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SignalStats {
+    /// Number of samples
+    pub count: i64,
+    /// Smallest value, NaN for an empty signal
+    pub min: f64,
+    /// Largest value, NaN for an empty signal
+    pub max: f64,
+    /// Arithmetic mean, NaN for an empty signal
+    pub mean: f64,
+    /// Population standard deviation, NaN for an empty signal
+    pub std_dev: f64,
+}
+
+impl<'a> SignalHandle<'a> {
+    pub(crate) fn new(reader: &'a mut EdfReader, index: usize) -> Self {
+        SignalHandle { reader, index }
+    }
+
+    /// Index of the signal in [`EdfHeader::signals`](crate::EdfHeader::signals)
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Header parameters of the signal
+    pub fn param(&self) -> &SignalParam {
+        &self.reader.header().signals[self.index]
+    }
+
+    /// Signal label, e.g. `"EEG Fpz-Cz"`
+    pub fn label(&self) -> &str {
+        &self.param().label
+    }
+
+    /// Physical dimension, e.g. `"uV"`
+    pub fn dimension(&self) -> &str {
+        &self.param().physical_dimension
+    }
+
+    /// Samples per second, 0.0 if the data record duration is 0
+    pub fn sample_frequency(&self) -> f64 {
+        let record_seconds = time::ticks_to_seconds(self.reader.header().datarecord_duration);
+        if record_seconds > 0.0 {
+            self.param().samples_per_record as f64 / record_seconds
+        } else {
+            0.0
+        }
+    }
+
+    /// Total number of samples in the file
+    pub fn len(&self) -> i64 {
+        self.param().samples_in_file
+    }
+
+    /// Whether the signal has no samples
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reads physical values from the current position, see
+    /// [`EdfReader::read_physical_samples`]
+    pub fn read(&mut self, count: usize) -> Result<Vec<f64>> {
+        self.reader.read_physical_samples(self.index, count)
+    }
+
+    /// Reads physical values starting at `position` without moving the
+    /// current position
+    ///
+    /// `position` is clamped like in [`seek`](Self::seek).
+    pub fn read_at(&mut self, position: i64, count: usize) -> Result<Vec<f64>> {
+        let saved = self.tell();
+        self.seek(position);
+        let result = self.read(count);
+        self.seek(saved);
+        result
+    }
+
+    /// Moves the read position and returns the actual position, clamped to
+    /// `0..=len()`
+    pub fn seek(&mut self, position: i64) -> i64 {
+        self.reader.seek(self.index, position)
+            .expect("signal index is checked by signal_mut")
+    }
+
+    /// Current read position
+    pub fn tell(&self) -> i64 {
+        self.reader.tell(self.index)
+            .expect("signal index is checked by signal_mut")
+    }
+
+    /// Computes statistics over all samples without moving the current
+    /// position
+    ///
+    /// Reads the whole signal, one data record at a time.
+    ///
+    /// # Errors
+    ///
+    /// * `EdfError::FileReadError` - I/O error reading from file
+    pub fn stats(&mut self) -> Result<SignalStats> {
+        let saved = self.tell();
+        let chunk = self.param().samples_per_record.max(1) as usize;
+        self.seek(0);
+
+        let mut stats = SignalStats {
+            count: 0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            mean: 0.0,
+            std_dev: 0.0,
+        };
+        // Welford 算法累积方差
+        let mut m2 = 0.0;
+        let result = loop {
+            let samples = match self.read(chunk) {
+                Ok(samples) if samples.is_empty() => break Ok(()),
+                Ok(samples) => samples,
+                Err(e) => break Err(e),
+            };
+            for value in samples {
+                stats.count += 1;
+                stats.min = stats.min.min(value);
+                stats.max = stats.max.max(value);
+                let delta = value - stats.mean;
+                stats.mean += delta / stats.count as f64;
+                m2 += delta * (value - stats.mean);
+            }
+        };
+        self.seek(saved);
+        result?;
+
+        if stats.count == 0 {
+            stats.min = f64::NAN;
+            stats.max = f64::NAN;
+            stats.mean = f64::NAN;
+            stats.std_dev = f64::NAN;
+        } else {
+            stats.std_dev = (m2 / stats.count as f64).sqrt();
+        }
+        Ok(stats)
+    }
+}
+
+/// Iterator over the physical values of one signal, see
+/// [`EdfReader::samples`]
+///
+/// Values are read one data record at a time. Iterating from the back
+/// with [`next_back`](DoubleEndedIterator::next_back) or `rev()` reads the
+/// data records backwards, so the end of a long signal is reached without
+/// reading the rest. The reader's read position of the signal is restored
+/// when the iterator is dropped.
+///
+/// An error ends the iteration after it has been returned.
+pub struct Samples<'a> {
+    reader: &'a mut EdfReader,
+    signal: usize,
+    samples_per_record: i64,
+    saved_position: i64,
+    // 尚未读取的样本范围 [front, back)
+    front: i64,
+    back: i64,
+    // 已读取但尚未返回的样本
+    front_chunk: std::vec::IntoIter<f64>,
+    back_chunk: std::vec::IntoIter<f64>,
+}
+
+impl<'a> Samples<'a> {
+    pub(crate) fn new(reader: &'a mut EdfReader, signal: usize) -> Result<Self> {
+        let param = reader.header().signals.get(signal).ok_or(EdfError::InvalidSignalIndex(signal))?;
+        let (samples_per_record, back) = (param.samples_per_record.max(1) as i64, param.samples_in_file.max(0));
+        let saved_position = reader.tell(signal)?;
+        Ok(Samples {
+            reader,
+            signal,
+            samples_per_record,
+            saved_position,
+            front: 0,
+            back,
+            front_chunk: Vec::new().into_iter(),
+            back_chunk: Vec::new().into_iter(),
+        })
+    }
+
+    /// 读取 [start, end) 范围的样本
+    fn read_chunk(&mut self, start: i64, end: i64) -> Result<std::vec::IntoIter<f64>> {
+        self.reader.seek(self.signal, start)?;
+        let samples = self.reader.read_physical_samples(self.signal, (end - start) as usize)?;
+        if samples.len() as i64 != end - start {
+            return Err(EdfError::InvalidSampleIndex { index: start + samples.len() as i64, count: end });
+        }
+        Ok(samples.into_iter())
+    }
+
+    /// 出错后结束迭代
+    fn fail(&mut self, error: EdfError) -> Option<Result<f64>> {
+        self.front = self.back;
+        self.front_chunk = Vec::new().into_iter();
+        self.back_chunk = Vec::new().into_iter();
+        Some(Err(error))
+    }
+}
+
+impl Iterator for Samples<'_> {
+    type Item = Result<f64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(value) = self.front_chunk.next() {
+            return Some(Ok(value));
+        }
+        if self.front >= self.back {
+            return self.back_chunk.next().map(Ok);
+        }
+
+        // 读到当前记录末尾
+        let end = ((self.front / self.samples_per_record + 1) * self.samples_per_record).min(self.back);
+        match self.read_chunk(self.front, end) {
+            Ok(chunk) => {
+                self.front_chunk = chunk;
+                self.front = end;
+                self.front_chunk.next().map(Ok)
+            }
+            Err(e) => self.fail(e),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.front_chunk.len() + (self.back - self.front) as usize + self.back_chunk.len();
+        (len, Some(len))
+    }
+}
+
+impl DoubleEndedIterator for Samples<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if let Some(value) = self.back_chunk.next_back() {
+            return Some(Ok(value));
+        }
+        if self.front >= self.back {
+            return self.front_chunk.next_back().map(Ok);
+        }
+
+        // 从当前记录开头读起
+        let start = ((self.back - 1) / self.samples_per_record * self.samples_per_record).max(self.front);
+        match self.read_chunk(start, self.back) {
+            Ok(chunk) => {
+                self.back_chunk = chunk;
+                self.back = start;
+                self.back_chunk.next_back().map(Ok)
+            }
+            Err(e) => self.fail(e),
+        }
+    }
+}
+
+impl ExactSizeIterator for Samples<'_> {}
+
+impl Drop for Samples<'_> {
+    fn drop(&mut self) {
+        self.reader.seek(self.signal, self.saved_position).ok();
+    }
+}
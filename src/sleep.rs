@@ -0,0 +1,542 @@
+//! Sleep staging helpers
+//!
+//! Sleep recordings store the hypnogram as ordinary EDF+ annotations such as
+//! `"Sleep stage W"` or `"Sleep stage N2"`, usually one per 30-second epoch.
+//! This module converts between those annotations and a per-epoch stage vector.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use edfplus::Annotation;
+//! use edfplus::sleep::{hypnogram, SleepStage};
+//!
+//! let annotations = vec![
+//!     Annotation { onset: 0, duration: Some(600_000_000), description: "Sleep stage W".to_string() },
+//!     Annotation { onset: 600_000_000, duration: Some(300_000_000), description: "Sleep stage 2".to_string() },
+//!     // 90-120 s is not scored
+//!     Annotation { onset: 1_200_000_000, duration: Some(300_000_000), description: "Sleep stage R".to_string() },
+//! ];
+//!
+//! // Five minutes of recording, e.g. `reader.header().file_duration`
+//! let stages = hypnogram(&annotations, 30.0, 3_000_000_000)?;
+//! assert_eq!(stages, vec![
+//!     SleepStage::Wake,
+//!     SleepStage::Wake,
+//!     SleepStage::N2,
+//!     SleepStage::Unscored,
+//!     SleepStage::Rem,
+//! ]);
+//! # Ok::<(), edfplus::EdfError>(())
+//! ```
+
+use std::fmt;
+
+use crate::error::{EdfError, Result};
+use crate::types::Annotation;
+use crate::time;
+
+/// Sleep stage of a single scoring epoch
+///
+/// Covers both AASM (N1-N3) and Rechtschaffen & Kales (stages 1-4) scoring.
+/// R&K stage 3 maps onto `N3`; R&K stage 4 is kept separate as `N4` so that
+/// files scored with the older rules round-trip unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SleepStage {
+    /// Wakefulness
+    Wake,
+    /// Stage N1 (R&K stage 1)
+    N1,
+    /// Stage N2 (R&K stage 2)
+    N2,
+    /// Stage N3 (R&K stage 3)
+    N3,
+    /// R&K stage 4 (merged into N3 under AASM rules)
+    N4,
+    /// Rapid eye movement sleep
+    Rem,
+    /// Movement time
+    Movement,
+    /// No stage annotation covers this epoch, or it was explicitly marked unscored
+    Unscored,
+}
+
+impl SleepStage {
+    /// Canonical EDF+ annotation text for this stage
+    ///
+    /// These are the spellings written by [`stages_to_annotations`].
+    pub fn description(&self) -> &'static str {
+        match self {
+            SleepStage::Wake => "Sleep stage W",
+            SleepStage::N1 => "Sleep stage N1",
+            SleepStage::N2 => "Sleep stage N2",
+            SleepStage::N3 => "Sleep stage N3",
+            SleepStage::N4 => "Sleep stage 4",
+            SleepStage::Rem => "Sleep stage R",
+            SleepStage::Movement => "Movement time",
+            SleepStage::Unscored => "Sleep stage ?",
+        }
+    }
+}
+
+impl fmt::Display for SleepStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.description())
+    }
+}
+
+/// Table mapping annotation descriptions to sleep stages
+///
+/// Lookups are case-insensitive and ignore leading/trailing whitespace,
+/// repeated spaces and underscores, so `"SLEEP_STAGE_W"` and
+/// `"Sleep stage  w"` both match `"Sleep stage W"`.
+///
+/// [`StageMapping::standard`] recognizes the common AASM and R&K spellings;
+/// vendor-specific texts can be added with [`insert`](Self::insert).
+///
+/// # Examples
+///
+/// ```rust
+/// use edfplus::sleep::{SleepStage, StageMapping};
+///
+/// let mut mapping = StageMapping::standard();
+/// mapping.insert("Stadium 2", SleepStage::N2);
+///
+/// assert_eq!(mapping.lookup("sleep stage n2"), Some(SleepStage::N2));
+/// assert_eq!(mapping.lookup("STADIUM 2"), Some(SleepStage::N2));
+/// assert_eq!(mapping.lookup("Arousal"), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct StageMapping {
+    entries: Vec<(String, SleepStage)>,
+}
+
+impl StageMapping {
+    /// Creates an empty mapping table
+    pub fn new() -> Self {
+        StageMapping { entries: Vec::new() }
+    }
+
+    /// Creates a mapping table with the common AASM and R&K spellings
+    pub fn standard() -> Self {
+        let table: &[(&str, SleepStage)] = &[
+            ("Sleep stage W", SleepStage::Wake),
+            ("Sleep stage Wake", SleepStage::Wake),
+            ("Sleep stage 0", SleepStage::Wake),
+            ("Stage W", SleepStage::Wake),
+            ("Wake", SleepStage::Wake),
+            ("W", SleepStage::Wake),
+            ("Sleep stage N1", SleepStage::N1),
+            ("Sleep stage 1", SleepStage::N1),
+            ("Stage N1", SleepStage::N1),
+            ("Stage 1", SleepStage::N1),
+            ("N1", SleepStage::N1),
+            ("S1", SleepStage::N1),
+            ("Sleep stage N2", SleepStage::N2),
+            ("Sleep stage 2", SleepStage::N2),
+            ("Stage N2", SleepStage::N2),
+            ("Stage 2", SleepStage::N2),
+            ("N2", SleepStage::N2),
+            ("S2", SleepStage::N2),
+            ("Sleep stage N3", SleepStage::N3),
+            ("Sleep stage 3", SleepStage::N3),
+            ("Stage N3", SleepStage::N3),
+            ("Stage 3", SleepStage::N3),
+            ("N3", SleepStage::N3),
+            ("S3", SleepStage::N3),
+            ("Sleep stage 4", SleepStage::N4),
+            ("Sleep stage N4", SleepStage::N4),
+            ("Stage 4", SleepStage::N4),
+            ("N4", SleepStage::N4),
+            ("S4", SleepStage::N4),
+            ("Sleep stage R", SleepStage::Rem),
+            ("Sleep stage REM", SleepStage::Rem),
+            ("Stage R", SleepStage::Rem),
+            ("Stage REM", SleepStage::Rem),
+            ("REM", SleepStage::Rem),
+            ("R", SleepStage::Rem),
+            ("Movement time", SleepStage::Movement),
+            ("Sleep stage MT", SleepStage::Movement),
+            ("Sleep stage M", SleepStage::Movement),
+            ("MT", SleepStage::Movement),
+            ("Sleep stage ?", SleepStage::Unscored),
+            ("Unscored", SleepStage::Unscored),
+            ("?", SleepStage::Unscored),
+        ];
+
+        let mut mapping = StageMapping::new();
+        for &(description, stage) in table {
+            mapping.insert(description, stage);
+        }
+        mapping
+    }
+
+    /// Adds or replaces the stage for a description
+    pub fn insert(&mut self, description: &str, stage: SleepStage) -> &mut Self {
+        let key = normalize_description(description);
+        match self.entries.iter_mut().find(|(k, _)| *k == key) {
+            Some(entry) => entry.1 = stage,
+            None => self.entries.push((key, stage)),
+        }
+        self
+    }
+
+    /// Looks up the stage for an annotation description
+    ///
+    /// Returns `None` for descriptions that are not sleep stage annotations.
+    pub fn lookup(&self, description: &str) -> Option<SleepStage> {
+        let key = normalize_description(description);
+        self.entries.iter().find(|(k, _)| *k == key).map(|(_, stage)| *stage)
+    }
+}
+
+impl Default for StageMapping {
+    fn default() -> Self {
+        StageMapping::standard()
+    }
+}
+
+/// 统一大小写、下划线和空白，便于匹配不同厂商的写法
+fn normalize_description(description: &str) -> String {
+    description
+        .replace('_', " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Two stage annotations disagreeing about the same epoch
+///
+/// The later annotation (in slice order) wins; the overruled stage is
+/// reported here so the conflict is never silent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StageConflict {
+    /// Index of the epoch that was scored twice
+    pub epoch: usize,
+    /// Stage assigned by the earlier annotation
+    pub previous: SleepStage,
+    /// Stage assigned by the later annotation, which was kept
+    pub replacement: SleepStage,
+    /// Onset of the later annotation in 100-nanosecond units
+    pub onset: i64,
+}
+
+/// Result of [`score_epochs`]: one stage per epoch plus the conflicts found
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hypnogram {
+    /// Epoch length in 100-nanosecond units
+    pub epoch_duration: i64,
+    /// One stage per epoch, starting at the recording start
+    pub stages: Vec<SleepStage>,
+    /// Epochs that were scored with contradictory stages
+    pub conflicts: Vec<StageConflict>,
+    /// Stage annotations reaching past the end of the recording
+    ///
+    /// Their epochs within the recording are scored, the rest are not.
+    pub out_of_range: Vec<Annotation>,
+}
+
+/// Builds a hypnogram using the standard description table
+///
+/// Equivalent to [`score_epochs`] with [`StageMapping::standard`], returning
+/// only [`Hypnogram::stages`]. Epochs scored with contradictory stages keep
+/// the later stage, and the conflicts are dropped without notice, as are
+/// stage annotations reaching past the end of the recording; call
+/// [`score_epochs`] to get them as [`Hypnogram::conflicts`] and
+/// [`Hypnogram::out_of_range`].
+///
+/// # Errors
+///
+/// * `EdfError::InvalidFormat` - `epoch_seconds` is not a positive, finite
+///   number, or `recording_duration` is negative
+pub fn hypnogram(annotations: &[Annotation], epoch_seconds: f64, recording_duration: i64) -> Result<Vec<SleepStage>> {
+    Ok(score_epochs(annotations, epoch_seconds, recording_duration, &StageMapping::standard())?.stages)
+}
+
+/// Builds a hypnogram from sleep stage annotations
+///
+/// Epoch `k` covers `[k * epoch, (k + 1) * epoch)` from the recording start.
+/// A stage annotation with a duration scores every epoch whose midpoint lies
+/// within `[onset, onset + duration)`; an annotation without a duration scores
+/// the single epoch containing its onset. Epochs not covered by any stage
+/// annotation are `Unscored`, and annotations not found in `mapping` are ignored.
+///
+/// Annotations are applied in slice order (the reader returns them sorted by
+/// onset), and the last writer wins. Every overruled stage is recorded in
+/// [`Hypnogram::conflicts`].
+///
+/// Only the epochs starting before `recording_duration` (in 100-nanosecond
+/// units, usually [`EdfHeader::file_duration`](crate::EdfHeader::file_duration))
+/// are scored, so a stray annotation far past the end cannot grow the
+/// vector. Annotations that would score later epochs are listed in
+/// [`Hypnogram::out_of_range`]. The vector ends with the last scored epoch.
+///
+/// # Errors
+///
+/// * `EdfError::InvalidFormat` - `epoch_seconds` is not a positive, finite
+///   number, or `recording_duration` is negative
+///
+/// # Examples
+///
+/// ```rust
+/// use edfplus::Annotation;
+/// use edfplus::sleep::{score_epochs, SleepStage, StageMapping};
+///
+/// let annotations = vec![
+///     Annotation { onset: 0, duration: Some(600_000_000), description: "Sleep stage N2".to_string() },
+///     Annotation { onset: 300_000_000, duration: Some(300_000_000), description: "Sleep stage N3".to_string() },
+/// ];
+///
+/// let result = score_epochs(&annotations, 30.0, 600_000_000, &StageMapping::standard())?;
+/// assert_eq!(result.stages, vec![SleepStage::N2, SleepStage::N3]);
+/// assert_eq!(result.conflicts.len(), 1);
+/// assert_eq!(result.conflicts[0].epoch, 1);
+/// assert!(result.out_of_range.is_empty());
+/// # Ok::<(), edfplus::EdfError>(())
+/// ```
+pub fn score_epochs(
+    annotations: &[Annotation],
+    epoch_seconds: f64,
+    recording_duration: i64,
+    mapping: &StageMapping,
+) -> Result<Hypnogram> {
+    let epoch_duration = epoch_ticks(epoch_seconds)?;
+    if recording_duration < 0 {
+        return Err(EdfError::InvalidFormat(format!(
+            "Recording duration must not be negative, got {}",
+            recording_duration
+        )));
+    }
+    // 记录内的 epoch 数，最后一个不完整的 epoch 也算
+    let epoch_count = (recording_duration as i128 + epoch_duration as i128 - 1) / epoch_duration as i128;
+    let mut stages: Vec<SleepStage> = Vec::new();
+    let mut conflicts = Vec::new();
+    let mut out_of_range = Vec::new();
+
+    for annotation in annotations {
+        let Some(stage) = mapping.lookup(&annotation.description) else {
+            continue;
+        };
+
+        let (onset, epoch) = (annotation.onset.max(0) as i128, epoch_duration as i128);
+        let (first, last) = if let Some(duration) = annotation.duration.filter(|&d| d > 0) {
+            // 中点落在 [onset, onset + duration) 内的 epoch，用 i128 计算避免 2 * end 溢出
+            let end = annotation.onset as i128 + duration as i128;
+            let first = (2 * onset - epoch - 1).div_euclid(2 * epoch) + 1;
+            let last = (2 * end - epoch - 1).div_euclid(2 * epoch);
+            (first.max(0), last)
+        } else {
+            (onset / epoch, onset / epoch)
+        };
+
+        // 超出记录的 epoch 不评分，只报告注释
+        if last >= epoch_count {
+            out_of_range.push(annotation.clone());
+        }
+        let last = last.min(epoch_count - 1);
+
+        for epoch in first..=last {
+            let epoch = epoch as usize;
+            if stages.len() <= epoch {
+                stages.resize(epoch + 1, SleepStage::Unscored);
+            }
+            let previous = stages[epoch];
+            if previous != SleepStage::Unscored && previous != stage {
+                conflicts.push(StageConflict {
+                    epoch,
+                    previous,
+                    replacement: stage,
+                    onset: annotation.onset,
+                });
+            }
+            stages[epoch] = stage;
+        }
+    }
+
+    Ok(Hypnogram { epoch_duration, stages, conflicts, out_of_range })
+}
+
+/// Converts a stage vector back into annotations
+///
+/// Consecutive epochs with the same stage are merged into one annotation
+/// whose duration spans the whole run. `Unscored` epochs produce no
+/// annotation. Descriptions use [`SleepStage::description`], so the result
+/// can be passed to `EdfWriter::add_annotation` to write a scored file and
+/// reads back to the same stages through [`hypnogram`].
+///
+/// # Errors
+///
+/// * `EdfError::InvalidFormat` - `epoch_seconds` is not a positive, finite number
+///
+/// # Examples
+///
+/// ```rust
+/// use edfplus::sleep::{hypnogram, stages_to_annotations, SleepStage};
+///
+/// let stages = vec![SleepStage::Wake, SleepStage::Wake, SleepStage::Unscored, SleepStage::N1];
+/// let annotations = stages_to_annotations(&stages, 30.0)?;
+///
+/// assert_eq!(annotations.len(), 2);
+/// assert_eq!(annotations[0].description, "Sleep stage W");
+/// assert_eq!(annotations[0].duration, Some(600_000_000));
+/// assert_eq!(annotations[1].onset, 900_000_000);
+///
+/// assert_eq!(hypnogram(&annotations, 30.0, 1_200_000_000)?, stages);
+/// # Ok::<(), edfplus::EdfError>(())
+/// ```
+pub fn stages_to_annotations(stages: &[SleepStage], epoch_seconds: f64) -> Result<Vec<Annotation>> {
+    let epoch_duration = epoch_ticks(epoch_seconds)?;
+    let mut annotations = Vec::new();
+    let mut run_start = 0;
+
+    for i in 1..=stages.len() {
+        if i < stages.len() && stages[i] == stages[run_start] {
+            continue;
+        }
+        let stage = stages[run_start];
+        if stage != SleepStage::Unscored {
+            annotations.push(Annotation {
+                onset: run_start as i64 * epoch_duration,
+                duration: Some((i - run_start) as i64 * epoch_duration),
+                description: stage.description().to_string(),
+            });
+        }
+        run_start = i;
+    }
+
+    Ok(annotations)
+}
+
+fn epoch_ticks(epoch_seconds: f64) -> Result<i64> {
+    if !epoch_seconds.is_finite() || epoch_seconds <= 0.0 {
+        return Err(EdfError::InvalidFormat(format!(
+            "Epoch length must be a positive number of seconds, got {}",
+            epoch_seconds
+        )));
+    }
+    let ticks = time::seconds_to_ticks(epoch_seconds);
+    if ticks <= 0 {
+        return Err(EdfError::InvalidFormat("Epoch length is shorter than 100 ns".to_string()));
+    }
+    Ok(ticks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stage(onset_s: f64, duration_s: Option<f64>, description: &str) -> Annotation {
+        Annotation {
+            onset: time::seconds_to_ticks(onset_s),
+            duration: duration_s.map(time::seconds_to_ticks),
+            description: description.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_standard_spellings() {
+        let mapping = StageMapping::standard();
+        assert_eq!(mapping.lookup("Sleep stage W"), Some(SleepStage::Wake));
+        assert_eq!(mapping.lookup("sleep_stage_n3"), Some(SleepStage::N3));
+        assert_eq!(mapping.lookup("  Sleep  stage 4 "), Some(SleepStage::N4));
+        assert_eq!(mapping.lookup("Sleep stage R"), Some(SleepStage::Rem));
+        assert_eq!(mapping.lookup("Sleep stage ?"), Some(SleepStage::Unscored));
+        assert_eq!(mapping.lookup("Lights off"), None);
+    }
+
+    #[test]
+    fn test_gaps_and_unaligned_annotations() {
+        let annotations = vec![
+            stage(0.0, Some(30.0), "Sleep stage W"),
+            stage(60.0, None, "Sleep stage 1"),
+            // 不对齐：覆盖 105-165s，中点在 105 和 135 的 epoch 被评分
+            stage(105.0, Some(60.0), "Sleep stage 2"),
+            stage(50.0, Some(5.0), "Arousal"),
+        ];
+        let stages = hypnogram(&annotations, 30.0, 1_800_000_000).unwrap();
+        assert_eq!(stages, vec![
+            SleepStage::Wake,
+            SleepStage::Unscored,
+            SleepStage::N1,
+            SleepStage::N2,
+            SleepStage::N2,
+        ]);
+    }
+
+    #[test]
+    fn test_last_writer_wins_with_conflicts() {
+        let annotations = vec![
+            stage(0.0, Some(90.0), "Sleep stage N2"),
+            stage(30.0, Some(30.0), "Sleep stage N2"),
+            stage(30.0, Some(30.0), "Sleep stage R"),
+        ];
+        let result = score_epochs(&annotations, 30.0, 900_000_000, &StageMapping::standard()).unwrap();
+        assert_eq!(result.stages, vec![SleepStage::N2, SleepStage::Rem, SleepStage::N2]);
+        assert_eq!(result.conflicts, vec![StageConflict {
+            epoch: 1,
+            previous: SleepStage::N2,
+            replacement: SleepStage::Rem,
+            onset: 300_000_000,
+        }]);
+    }
+
+    #[test]
+    fn test_vendor_mapping_and_round_trip() {
+        let mut mapping = StageMapping::new();
+        mapping.insert("Wach", SleepStage::Wake).insert("Tiefschlaf", SleepStage::N3);
+        let annotations = vec![
+            stage(0.0, Some(40.0), "WACH"),
+            stage(40.0, Some(20.0), "Tiefschlaf"),
+        ];
+        let result = score_epochs(&annotations, 20.0, 600_000_000, &mapping).unwrap();
+        assert_eq!(result.stages, vec![SleepStage::Wake, SleepStage::Wake, SleepStage::N3]);
+
+        let written = stages_to_annotations(&result.stages, 20.0).unwrap();
+        assert_eq!(written.len(), 2);
+        assert_eq!(hypnogram(&written, 20.0, 600_000_000).unwrap(), result.stages);
+    }
+
+    #[test]
+    fn test_annotations_past_the_recording_end() {
+        let far = Annotation {
+            onset: i64::MAX - 1,
+            duration: None,
+            description: "Sleep stage W".to_string(),
+        };
+        // 2 * end 超出 i64 范围
+        let endless = Annotation {
+            onset: 0,
+            duration: Some(i64::MAX),
+            description: "Sleep stage N2".to_string(),
+        };
+        let annotations = vec![
+            endless.clone(),
+            stage(60.0, Some(30.0), "Sleep stage R"),
+            far.clone(),
+        ];
+
+        // 5 分钟的记录，30 秒一个 epoch
+        let result = score_epochs(&annotations, 30.0, 3_000_000_000, &StageMapping::standard()).unwrap();
+        let mut expected = vec![SleepStage::N2; 10];
+        expected[2] = SleepStage::Rem;
+        assert_eq!(result.stages, expected);
+        assert_eq!(result.out_of_range, vec![endless, far]);
+
+        // 不完整的最后一个 epoch 仍在记录内
+        let tail = vec![stage(300.0, None, "Sleep stage W")];
+        let result = score_epochs(&tail, 30.0, 3_000_000_001, &StageMapping::standard()).unwrap();
+        assert_eq!(result.stages.len(), 11);
+        assert!(result.out_of_range.is_empty());
+        assert!(hypnogram(&tail, 30.0, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_epoch_length() {
+        assert!(hypnogram(&[], 0.0, 0).is_err());
+        assert!(hypnogram(&[], -30.0, 0).is_err());
+        assert!(hypnogram(&[], f64::NAN, 0).is_err());
+        assert!(hypnogram(&[], 30.0, -1).is_err());
+        assert!(stages_to_annotations(&[], f64::INFINITY).is_err());
+        assert!(hypnogram(&[], 30.0, 0).unwrap().is_empty());
+    }
+}
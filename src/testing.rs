@@ -0,0 +1,98 @@
+//! Assertion helpers for tests of EDF+ processing pipelines
+//!
+//! Code that reads a file, transforms it and writes a new one usually ends
+//! up with the same assertions in every test. [`assert_round_trip`] bundles
+//! them on top of [`compare`](crate::compare::compare) and panics with a
+//! readable list of differences.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use edfplus::EdfReader;
+//! use edfplus::testing::{assert_round_trip, RoundTripChecks, SignalCheck};
+//!
+//! # edfplus::doctest_utils::create_simple_test_file("round_trip_in.edf")?;
+//! # edfplus::doctest_utils::create_simple_test_file("round_trip_out.edf")?;
+//! let mut original = EdfReader::open("round_trip_in.edf")?;
+//! let mut produced = EdfReader::open("round_trip_out.edf")?;
+//!
+//! assert_round_trip(&mut original, &mut produced, RoundTripChecks {
+//!     signals: SignalCheck::PhysicalWithin(0.01),
+//!     ..RoundTripChecks::default()
+//! });
+//! # drop(original);
+//! # drop(produced);
+//! # std::fs::remove_file("round_trip_in.edf").ok();
+//! # std::fs::remove_file("round_trip_out.edf").ok();
+//! # Ok::<(), edfplus::EdfError>(())
+//! ```
+
+use crate::compare::{compare, CompareOptions};
+use crate::reader::EdfReader;
+
+/// How signal samples are compared
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SignalCheck {
+    /// Digital values and calibration must be identical
+    Exact,
+    /// Physical values may differ by at most the given amount
+    PhysicalWithin(f64),
+}
+
+/// How header fields are compared
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderCheck {
+    /// Patient/recording identification, start time and all signal parameters
+    Full,
+    /// Only the signal parameters that affect the data (samples per record
+    /// and, for [`SignalCheck::Exact`], the calibration)
+    DataOnly,
+}
+
+/// What [`assert_round_trip`] checks
+///
+/// The default is the strictest check: exact samples, annotations and the
+/// full header.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RoundTripChecks {
+    /// Comparison of the signal samples
+    pub signals: SignalCheck,
+    /// Whether the annotation lists must match
+    pub annotations: bool,
+    /// Comparison of the header fields
+    pub header: HeaderCheck,
+}
+
+impl Default for RoundTripChecks {
+    fn default() -> Self {
+        RoundTripChecks {
+            signals: SignalCheck::Exact,
+            annotations: true,
+            header: HeaderCheck::Full,
+        }
+    }
+}
+
+/// Asserts that `produced` contains the same recording as `original`
+///
+/// # Panics
+///
+/// Panics if any check fails, listing the first differences found, or if
+/// reading either file fails.
+pub fn assert_round_trip(original: &mut EdfReader, produced: &mut EdfReader, checks: RoundTripChecks) {
+    let mut options = CompareOptions::new()
+        .ignore_metadata(checks.header == HeaderCheck::DataOnly)
+        .annotations(checks.annotations)
+        .max_mismatches(20);
+    if let SignalCheck::PhysicalWithin(tolerance) = checks.signals {
+        options = options.physical_tolerance(tolerance);
+    }
+    
+    let report = match compare(original, produced, &options) {
+        Ok(report) => report,
+        Err(e) => panic!("round trip comparison failed: {}", e),
+    };
+    if !report.is_identical() {
+        panic!("round trip mismatch (original vs produced), {}", report);
+    }
+}
@@ -0,0 +1,283 @@
+//! Conversions between EDF+ time ticks and other time representations
+//!
+//! All times in this crate (annotation onsets and durations, record
+//! durations, file durations, subsecond start offsets) are integer counts of
+//! 100-nanosecond ticks, see [`TICKS_PER_SECOND`]. The functions in this
+//! module convert between ticks and seconds, [`std::time::Duration`] and
+//! [`chrono::TimeDelta`] with well-defined rounding, and the reader and
+//! writer use them internally so that every conversion in the crate rounds
+//! the same way.
+//!
+//! # Rounding
+//!
+//! * Conversions from ticks are exact, except [`ticks_to_seconds`], which is
+//!   subject to `f64` precision (exact for `|ticks| < 2^53`).
+//! * Conversions to ticks round to the nearest tick, ties to even, and
+//!   saturate at `i64::MIN`/`i64::MAX`. `NaN` becomes 0.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use edfplus::time;
+//!
+//! // 0.3 s is not exactly representable as f64, but still gives 3_000_000 ticks
+//! assert_eq!(time::seconds_to_ticks(0.3), 3_000_000);
+//! assert_eq!(time::ticks_to_seconds(15_000_000), 1.5);
+//!
+//! let duration = time::ticks_to_duration(25_000_001).unwrap();
+//! assert_eq!(duration, std::time::Duration::from_nanos(2_500_000_100));
+//!
+//! assert_eq!(time::format_hms(36_615_000_000), "01:01:01.5");
+//! ```
+
+use std::time::Duration;
+
+use chrono::TimeDelta;
+
+/// Number of ticks per second, one tick is 100 nanoseconds
+pub const TICKS_PER_SECOND: i64 = crate::EDFLIB_TIME_DIMENSION;
+
+/// 每个tick的纳秒数
+const NANOS_PER_TICK: i64 = 1_000_000_000 / TICKS_PER_SECOND;
+
+/// Converts ticks to seconds
+///
+/// # Examples
+///
+/// ```rust
+/// use edfplus::time::ticks_to_seconds;
+///
+/// assert_eq!(ticks_to_seconds(10_000_000), 1.0);
+/// assert_eq!(ticks_to_seconds(-2_500_000), -0.25);
+/// ```
+pub fn ticks_to_seconds(ticks: i64) -> f64 {
+    ticks as f64 / TICKS_PER_SECOND as f64
+}
+
+/// Converts seconds to ticks, rounding half to even and saturating
+///
+/// # Examples
+///
+/// ```rust
+/// use edfplus::time::seconds_to_ticks;
+///
+/// assert_eq!(seconds_to_ticks(1.5), 15_000_000);
+/// assert_eq!(seconds_to_ticks(0.000_000_05), 0);    // 0.5 ticks, tie to even
+/// assert_eq!(seconds_to_ticks(0.000_000_15), 2);    // 1.5 ticks, tie to even
+/// assert_eq!(seconds_to_ticks(f64::INFINITY), i64::MAX);
+/// ```
+pub fn seconds_to_ticks(seconds: f64) -> i64 {
+    // `as` 转换对超出范围的值饱和，NaN 转为 0
+    (seconds * TICKS_PER_SECOND as f64).round_ties_even() as i64
+}
+
+/// Converts ticks to a [`Duration`], `None` for negative values
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+/// use edfplus::time::ticks_to_duration;
+///
+/// assert_eq!(ticks_to_duration(10_000_001), Some(Duration::new(1, 100)));
+/// assert_eq!(ticks_to_duration(-1), None);
+/// ```
+pub fn ticks_to_duration(ticks: i64) -> Option<Duration> {
+    if ticks < 0 {
+        return None;
+    }
+    Some(Duration::new(
+        (ticks / TICKS_PER_SECOND) as u64,
+        ((ticks % TICKS_PER_SECOND) * NANOS_PER_TICK) as u32,
+    ))
+}
+
+/// Converts a [`Duration`] to ticks, rounding half to even and saturating
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+/// use edfplus::time::duration_to_ticks;
+///
+/// assert_eq!(duration_to_ticks(Duration::from_millis(1500)), 15_000_000);
+/// assert_eq!(duration_to_ticks(Duration::from_nanos(250)), 2);   // 2.5 ticks
+/// assert_eq!(duration_to_ticks(Duration::MAX), i64::MAX);
+/// ```
+pub fn duration_to_ticks(duration: Duration) -> i64 {
+    let nanos = duration.as_nanos();
+    let tick_nanos = NANOS_PER_TICK as u128;
+    let (quotient, remainder) = (nanos / tick_nanos, nanos % tick_nanos);
+    let rounded = if remainder * 2 > tick_nanos || (remainder * 2 == tick_nanos && quotient % 2 == 1) {
+        quotient + 1
+    } else {
+        quotient
+    };
+    i64::try_from(rounded).unwrap_or(i64::MAX)
+}
+
+/// Converts ticks to a [`chrono::TimeDelta`]
+///
+/// Exact for every `i64` tick count, including negative values.
+///
+/// # Examples
+///
+/// ```rust
+/// use chrono::TimeDelta;
+/// use edfplus::time::ticks_to_chrono_duration;
+///
+/// assert_eq!(ticks_to_chrono_duration(15_000_000), TimeDelta::milliseconds(1500));
+/// assert_eq!(ticks_to_chrono_duration(-1), TimeDelta::nanoseconds(-100));
+/// ```
+pub fn ticks_to_chrono_duration(ticks: i64) -> TimeDelta {
+    TimeDelta::seconds(ticks.div_euclid(TICKS_PER_SECOND))
+        + TimeDelta::nanoseconds(ticks.rem_euclid(TICKS_PER_SECOND) * NANOS_PER_TICK)
+}
+
+/// Converts a [`chrono::TimeDelta`] to ticks, rounding half to even and saturating
+///
+/// # Examples
+///
+/// ```rust
+/// use chrono::TimeDelta;
+/// use edfplus::time::chrono_duration_to_ticks;
+///
+/// assert_eq!(chrono_duration_to_ticks(TimeDelta::seconds(2)), 20_000_000);
+/// assert_eq!(chrono_duration_to_ticks(TimeDelta::nanoseconds(-150)), -2);
+/// ```
+pub fn chrono_duration_to_ticks(delta: TimeDelta) -> i64 {
+    // 秒与亚秒纳秒同号，用i128精确计算总纳秒数
+    let total = delta.num_seconds() as i128 * 1_000_000_000 + delta.subsec_nanos() as i128;
+
+    let tick_nanos = NANOS_PER_TICK as i128;
+    let (quotient, remainder) = (total.div_euclid(tick_nanos), total.rem_euclid(tick_nanos));
+    let rounded = if remainder * 2 > tick_nanos || (remainder * 2 == tick_nanos && quotient % 2 != 0) {
+        quotient + 1
+    } else {
+        quotient
+    };
+    rounded.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+/// Formats ticks as decimal seconds, exactly and without trailing zeros
+///
+/// This is the notation used for onsets and durations in EDF+ annotations.
+///
+/// # Examples
+///
+/// ```rust
+/// use edfplus::time::format_seconds;
+///
+/// assert_eq!(format_seconds(30_000_000), "3");
+/// assert_eq!(format_seconds(15_000_000), "1.5");
+/// assert_eq!(format_seconds(1), "0.0000001");
+/// assert_eq!(format_seconds(-5_000_000), "-0.5");
+/// ```
+pub fn format_seconds(ticks: i64) -> String {
+    let sign = if ticks < 0 { "-" } else { "" };
+    let ticks = ticks.unsigned_abs();
+    let per_second = TICKS_PER_SECOND as u64;
+    let fraction = ticks % per_second;
+
+    if fraction == 0 {
+        format!("{}{}", sign, ticks / per_second)
+    } else {
+        let digits = format!("{:07}", fraction);
+        format!("{}{}.{}", sign, ticks / per_second, digits.trim_end_matches('0'))
+    }
+}
+
+/// Formats ticks as `HH:MM:SS` with the fractional seconds, if any
+///
+/// Hours are not wrapped at 24, trailing zeros of the fraction are omitted
+/// and negative values get a leading `-`.
+///
+/// # Examples
+///
+/// ```rust
+/// use edfplus::time::format_hms;
+///
+/// assert_eq!(format_hms(0), "00:00:00");
+/// assert_eq!(format_hms(905_000_000), "00:01:30.5");
+/// assert_eq!(format_hms(900_000 * 10_000_000 + 1), "250:00:00.0000001");
+/// assert_eq!(format_hms(-15_000_000), "-00:00:01.5");
+/// ```
+pub fn format_hms(ticks: i64) -> String {
+    let sign = if ticks < 0 { "-" } else { "" };
+    let ticks = ticks.unsigned_abs();
+    let per_second = TICKS_PER_SECOND as u64;
+    let seconds = ticks / per_second;
+
+    // 小数部分与 format_seconds 相同
+    let whole = format_seconds((ticks % per_second) as i64);
+    format!("{}{:02}:{:02}:{:02}{}", sign, seconds / 3600, seconds / 60 % 60, seconds % 60, whole.trim_start_matches('0'))
+}
+
+/// 以秒为单位（反）序列化tick值
+#[cfg(feature = "serde")]
+pub(crate) mod serde_seconds {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(ticks: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(super::ticks_to_seconds(*ticks))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+        f64::deserialize(deserializer).map(super::seconds_to_ticks)
+    }
+
+    /// 可为空的秒数
+    pub mod option {
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(ticks: &Option<i64>, serializer: S) -> Result<S::Ok, S::Error> {
+            match ticks {
+                Some(ticks) => serializer.serialize_some(&super::super::ticks_to_seconds(*ticks)),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<i64>, D::Error> {
+            Option::<f64>::deserialize(deserializer).map(|seconds| seconds.map(super::super::seconds_to_ticks))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seconds_round_trip() {
+        // 所有毫秒值经过秒数往返后保持不变
+        for millis in -100_000i64..100_000 {
+            let ticks = millis * 10_000;
+            assert_eq!(seconds_to_ticks(ticks_to_seconds(ticks)), ticks);
+            assert_eq!(seconds_to_ticks(millis as f64 / 1000.0), ticks);
+        }
+        assert_eq!(seconds_to_ticks(f64::NAN), 0);
+        assert_eq!(seconds_to_ticks(-1e300), i64::MIN);
+        assert_eq!(seconds_to_ticks(-0.000_000_25), -2);
+    }
+
+    #[test]
+    fn test_duration_conversions() {
+        for ticks in [0, 1, 9_999_999, 10_000_000, 123_456_789_012, i64::MAX] {
+            let duration = ticks_to_duration(ticks).unwrap();
+            assert_eq!(duration_to_ticks(duration), ticks);
+
+            let delta = ticks_to_chrono_duration(ticks);
+            assert_eq!(chrono_duration_to_ticks(delta), ticks);
+            assert_eq!(chrono_duration_to_ticks(ticks_to_chrono_duration(-ticks)), -ticks);
+        }
+
+        // 不足一个tick的部分四舍六入五成双
+        assert_eq!(duration_to_ticks(Duration::from_nanos(49)), 0);
+        assert_eq!(duration_to_ticks(Duration::from_nanos(50)), 0);
+        assert_eq!(duration_to_ticks(Duration::from_nanos(51)), 1);
+        assert_eq!(duration_to_ticks(Duration::from_nanos(150)), 2);
+        assert_eq!(chrono_duration_to_ticks(TimeDelta::nanoseconds(-50)), 0);
+        assert_eq!(chrono_duration_to_ticks(TimeDelta::nanoseconds(-51)), -1);
+        assert_eq!(chrono_duration_to_ticks(TimeDelta::nanoseconds(350)), 4);
+    }
+}
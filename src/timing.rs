@@ -0,0 +1,276 @@
+//! Drift of the timekeeping TALs against the data record grid
+//!
+//! Every data record of an EDF+ file starts with a timekeeping TAL that
+//! states when the record begins. In a continuous file this must be exactly
+//! `record_index * datarecord_duration` after the start; recorders with a
+//! skewed clock write timestamps that slowly drift away from that grid.
+//! [`EdfReader::timing_report`](crate::EdfReader::timing_report) compares
+//! every record's timestamp with its nominal position and summarizes the
+//! difference as a [`TimingReport`].
+//!
+//! For EDF+C files a report with
+//! [`strictly_continuous`](TimingReport::strictly_continuous) set is a
+//! compliance check of the timekeeping. For EDF+D files the nominal
+//! positions are computed per segment of contiguous records, see
+//! [`TimingSegment`].
+//!
+//! # Gaps
+//!
+//! The time between two segments is a [`Gap`]: no data was recorded there.
+//! [`EdfReader::gaps`](crate::EdfReader::gaps) lists them and
+//! [`EdfReader::read_window`](crate::EdfReader::read_window) refuses to read
+//! across one, so a dropout is never mistaken for contiguous data.
+//! [`EdfReader::read_window_with_gaps`](crate::EdfReader::read_window_with_gaps)
+//! returns the recorded parts of a window together with the gaps between
+//! them as a [`WindowWithGaps`]. EDF+C files have no gaps.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use edfplus::EdfReader;
+//!
+//! # edfplus::doctest_utils::create_simple_test_file("timing_report.edf")?;
+//! let mut reader = EdfReader::open("timing_report.edf")?;
+//!
+//! let report = reader.timing_report()?;
+//! assert!(report.strictly_continuous);
+//! assert_eq!(report.max_abs_drift, 0);
+//! assert_eq!(report.first_exceeding, None);
+//! # drop(reader);
+//! # std::fs::remove_file("timing_report.edf").ok();
+//! # Ok::<(), edfplus::EdfError>(())
+//! ```
+
+/// Summary of the timekeeping drift of a file
+///
+/// All times are in 100 ns units, relative to the start of the recording
+/// like [`Annotation::onset`](crate::Annotation::onset).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimingReport {
+    /// Drift that counts as exceeding, see [`first_exceeding`](Self::first_exceeding)
+    pub threshold: i64,
+    /// Number of data records with a readable timekeeping TAL
+    pub records: i64,
+    /// Data records whose timekeeping TAL is missing or unreadable
+    pub missing_timestamps: Vec<i64>,
+    /// Largest absolute difference between a timestamp and its nominal position
+    pub max_abs_drift: i64,
+    /// Mean absolute difference between the timestamps and their nominal
+    /// positions, rounded down
+    pub mean_abs_drift: i64,
+    /// First data record whose absolute drift is larger than `threshold`
+    pub first_exceeding: Option<i64>,
+    /// Whether every data record has a timestamp exactly on its nominal
+    /// position and the file is a single segment
+    pub strictly_continuous: bool,
+    /// Segments of contiguous data records, a single one for EDF+C
+    pub segments: Vec<TimingSegment>,
+    /// Gaps between consecutive segments; segments that overlap or touch
+    /// have no gap between them
+    pub gaps: Vec<Gap>,
+}
+
+/// Data records that are contiguous in time
+///
+/// The nominal position of record `first_record + i` is
+/// `onset + i * datarecord_duration`. In an EDF+D file a new segment starts
+/// at every record whose timestamp is at least one data record duration
+/// away from the end of the previous record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimingSegment {
+    /// Index of the first data record of the segment
+    pub first_record: i64,
+    /// Number of data records in the segment
+    pub records: i64,
+    /// Nominal start of the segment
+    pub onset: i64,
+}
+
+/// Time between two [`TimingSegment`]s without data
+///
+/// All times are in 100 ns units, relative to the start of the recording.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Gap {
+    /// Last data record before the gap
+    pub after_record: i64,
+    /// End of the data record `after_record`
+    pub start: i64,
+    /// Time until the next data record starts
+    pub duration: i64,
+}
+
+impl Gap {
+    /// End of the gap, the start of the next data record
+    pub fn end(&self) -> i64 {
+        self.start + self.duration
+    }
+}
+
+/// Physical values of a time window that may contain gaps, see
+/// [`EdfReader::read_window_with_gaps`](crate::EdfReader::read_window_with_gaps)
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowWithGaps {
+    /// Recorded parts of the window, in time order
+    pub parts: Vec<WindowPart>,
+    /// Gaps inside the window, clipped to the window
+    pub gaps: Vec<Gap>,
+}
+
+/// Contiguous samples of a [`WindowWithGaps`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowPart {
+    /// Time of the first sample in 100 ns units
+    pub start: i64,
+    /// Index of the first sample in the signal
+    pub first_sample: i64,
+    /// Physical values
+    pub samples: Vec<f64>,
+}
+
+/// 相邻段之间的空隙
+pub(crate) fn gaps_between(segments: &[TimingSegment], datarecord_duration: i64) -> Vec<Gap> {
+    segments.windows(2)
+        .filter_map(|pair| {
+            let end = pair[0].onset + pair[0].records * datarecord_duration;
+            (pair[1].onset > end).then_some(Gap {
+                after_record: pair[1].first_record - 1,
+                start: end,
+                duration: pair[1].onset - end,
+            })
+        })
+        .collect()
+}
+
+/// 逐个记录累积漂移统计
+pub(crate) struct TimingAccumulator {
+    continuous: bool,
+    datarecord_duration: i64,
+    report: TimingReport,
+    sum_abs_drift: i128,
+    previous: Option<i64>,
+}
+
+impl TimingAccumulator {
+    pub(crate) fn new(continuous: bool, datarecord_duration: i64, threshold: i64) -> Self {
+        TimingAccumulator {
+            continuous,
+            datarecord_duration,
+            report: TimingReport {
+                threshold,
+                records: 0,
+                missing_timestamps: Vec::new(),
+                max_abs_drift: 0,
+                mean_abs_drift: 0,
+                first_exceeding: None,
+                strictly_continuous: true,
+                // EDF+C 只有一个从0开始的段
+                segments: if continuous {
+                    vec![TimingSegment { first_record: 0, records: 0, onset: 0 }]
+                } else {
+                    Vec::new()
+                },
+                gaps: Vec::new(),
+            },
+            sum_abs_drift: 0,
+            previous: None,
+        }
+    }
+
+    pub(crate) fn push(&mut self, record: i64, timestamp: Option<i64>) {
+        let Some(timestamp) = timestamp else {
+            self.report.missing_timestamps.push(record);
+            self.report.strictly_continuous = false;
+            if let Some(segment) = self.report.segments.last_mut() {
+                segment.records += 1;
+            }
+            return;
+        };
+
+        // EDF+D 中与上一记录结尾相差至少一个记录时长时开始新的段
+        let starts_segment = match self.previous {
+            _ if self.continuous => false,
+            None => true,
+            Some(previous) => {
+                (timestamp - (previous + self.datarecord_duration)).abs() >= self.datarecord_duration.max(1)
+            }
+        };
+        if starts_segment {
+            self.report.segments.push(TimingSegment { first_record: record, records: 0, onset: timestamp });
+        }
+
+        let segment = self.report.segments.last_mut().expect("a segment was started");
+        let nominal = segment.onset + (record - segment.first_record) * self.datarecord_duration;
+        segment.records += 1;
+
+        let drift = (timestamp - nominal).abs();
+        self.report.records += 1;
+        self.sum_abs_drift += drift as i128;
+        self.report.max_abs_drift = self.report.max_abs_drift.max(drift);
+        if drift > self.report.threshold && self.report.first_exceeding.is_none() {
+            self.report.first_exceeding = Some(record);
+        }
+        if drift != 0 {
+            self.report.strictly_continuous = false;
+        }
+        self.previous = Some(timestamp);
+    }
+
+    pub(crate) fn finish(mut self) -> TimingReport {
+        if self.report.segments.len() > 1 {
+            self.report.strictly_continuous = false;
+        }
+        self.report.gaps = gaps_between(&self.report.segments, self.datarecord_duration);
+        if self.report.records > 0 {
+            self.report.mean_abs_drift = (self.sum_abs_drift / self.report.records as i128) as i64;
+        }
+        self.report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discontinuous_segments() {
+        // 1秒记录：0-2秒，10.5-12.5秒（第二段第二个记录漂移 0.2 毫秒）
+        let mut accumulator = TimingAccumulator::new(false, 10_000_000, 1000);
+        for (record, timestamp) in [0, 10_000_000, 105_000_000, 115_002_000].into_iter().enumerate() {
+            accumulator.push(record as i64, Some(timestamp));
+        }
+        let report = accumulator.finish();
+
+        assert_eq!(report.segments, vec![
+            TimingSegment { first_record: 0, records: 2, onset: 0 },
+            TimingSegment { first_record: 2, records: 2, onset: 105_000_000 },
+        ]);
+        assert_eq!(report.max_abs_drift, 2000);
+        assert_eq!(report.mean_abs_drift, 500);
+        assert_eq!(report.first_exceeding, Some(3));
+        assert!(!report.strictly_continuous);
+    }
+
+    #[test]
+    fn test_gaps() {
+        // 1秒记录：0-3秒，5-7秒（2秒空隙），1207-1208秒（20分钟空隙）
+        let mut accumulator = TimingAccumulator::new(false, 10_000_000, 1000);
+        let timestamps = [0, 1, 2, 5, 6, 1207].map(|seconds| seconds * 10_000_000);
+        for (record, timestamp) in timestamps.into_iter().enumerate() {
+            accumulator.push(record as i64, Some(timestamp));
+        }
+        let report = accumulator.finish();
+
+        assert_eq!(report.gaps, vec![
+            Gap { after_record: 2, start: 30_000_000, duration: 20_000_000 },
+            Gap { after_record: 4, start: 70_000_000, duration: 12_000_000_000 },
+        ]);
+        assert_eq!(report.gaps[1].end(), 12_070_000_000);
+
+        // EDF+C 没有空隙
+        let mut accumulator = TimingAccumulator::new(true, 10_000_000, 1000);
+        accumulator.push(0, Some(0));
+        accumulator.push(1, Some(10_000_000));
+        assert!(accumulator.finish().gaps.is_empty());
+    }
+}
@@ -1,21 +1,105 @@
-use chrono::{NaiveDate, NaiveTime};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 
-/// Supported EDF file types
+use crate::labels::ParsedLabel;
+use crate::fixed::{self, ExactCalibration, FixedPhysical, MAX_AUTO_DECIMALS};
+
+/// Format variant of an EDF-family file
 /// 
-/// Currently only EDF+ format is supported as it's the modern standard
-/// with support for annotations and extended metadata.
+/// Determined from the version field and the first bytes of the reserved
+/// field in the main header. The reader opens
+/// [`EdfPlusContinuous`](FileType::EdfPlusContinuous) and
+/// [`EdfPlusDiscontinuous`](FileType::EdfPlusDiscontinuous) files;
+/// [`EdfWriter`](crate::EdfWriter) produces EDF+C.
 /// 
-/// This type is used internally for file format validation.
-#[derive(Debug, Clone, PartialEq)]
+/// # Examples
+/// 
+/// ```rust
+/// use edfplus::{EdfReader, FileType};
+/// 
+/// # edfplus::doctest_utils::create_simple_test_file("file_type.edf")?;
+/// let reader = EdfReader::open("file_type.edf")?;
+/// let file_type = reader.header().file_type;
+/// 
+/// assert_eq!(file_type, FileType::EdfPlusContinuous);
+/// assert!(file_type.is_plus());
+/// assert!(file_type.is_continuous());
+/// assert_eq!(file_type.sample_bytes(), 2);
+/// assert_eq!(file_type.to_string(), "EDF+C");
+/// # drop(reader);
+/// # std::fs::remove_file("file_type.edf").ok();
+/// # Ok::<(), edfplus::EdfError>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FileType {
-    /// EDF+ format - European Data Format Plus
-    /// 
-    /// This is the recommended format for new recordings as it supports:
-    /// - Annotations and events
-    /// - Extended patient information  
-    /// - Equipment information
-    /// - Standardized field formats
-    EdfPlus,
+    /// Classic EDF without annotation signals
+    Edf,
+    /// EDF+ with contiguous data records (`EDF+C`)
+    EdfPlusContinuous,
+    /// EDF+ with possibly interrupted data records (`EDF+D`)
+    EdfPlusDiscontinuous,
+    /// BioSemi 24-bit BDF
+    Bdf,
+    /// BDF+ with contiguous data records (`BDF+C`)
+    BdfPlusContinuous,
+    /// BDF+ with possibly interrupted data records (`BDF+D`)
+    BdfPlusDiscontinuous,
+}
+
+impl FileType {
+    /// Detects the file type from the version and reserved header fields
+    /// 
+    /// Returns `None` if the version field matches neither EDF (`"0"`) nor
+    /// BDF (`0xFF` followed by `"BIOSEMI"`).
+    pub(crate) fn detect(version: &[u8], reserved: &[u8]) -> Option<FileType> {
+        let is_bdf = version.first() == Some(&0xFF) && version.get(1..8) == Some(&b"BIOSEMI"[..]);
+        let is_edf = String::from_utf8_lossy(version).trim().starts_with('0');
+        
+        match (is_bdf, is_edf, reserved.get(..5)) {
+            (true, _, Some(b"BDF+C")) => Some(FileType::BdfPlusContinuous),
+            (true, _, Some(b"BDF+D")) => Some(FileType::BdfPlusDiscontinuous),
+            (true, _, _) => Some(FileType::Bdf),
+            (_, true, Some(b"EDF+C")) => Some(FileType::EdfPlusContinuous),
+            (_, true, Some(b"EDF+D")) => Some(FileType::EdfPlusDiscontinuous),
+            (_, true, _) => Some(FileType::Edf),
+            _ => None,
+        }
+    }
+    
+    /// Whether this is an EDF+ or BDF+ file with annotation signals
+    pub fn is_plus(&self) -> bool {
+        !matches!(self, FileType::Edf | FileType::Bdf)
+    }
+    
+    /// Whether the data records are contiguous in time
+    /// 
+    /// Classic EDF/BDF files have no way to express gaps and are always
+    /// continuous.
+    pub fn is_continuous(&self) -> bool {
+        !matches!(self, FileType::EdfPlusDiscontinuous | FileType::BdfPlusDiscontinuous)
+    }
+    
+    /// Size of one sample in bytes: 2 for EDF, 3 for BDF
+    pub fn sample_bytes(&self) -> usize {
+        match self {
+            FileType::Edf | FileType::EdfPlusContinuous | FileType::EdfPlusDiscontinuous => 2,
+            FileType::Bdf | FileType::BdfPlusContinuous | FileType::BdfPlusDiscontinuous => 3,
+        }
+    }
+}
+
+impl std::fmt::Display for FileType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            FileType::Edf => "EDF",
+            FileType::EdfPlusContinuous => "EDF+C",
+            FileType::EdfPlusDiscontinuous => "EDF+D",
+            FileType::Bdf => "BDF",
+            FileType::BdfPlusContinuous => "BDF+C",
+            FileType::BdfPlusDiscontinuous => "BDF+D",
+        };
+        f.write_str(name)
+    }
 }
 
 /// Signal parameters and metadata
@@ -67,12 +151,15 @@ pub struct SignalParam {
     /// For example, 256 samples per record = 256 Hz sampling rate.
     pub samples_per_record: i32,
     
-    /// Physical dimension/unit (e.g., "µV", "mV", "BPM")
+    /// Physical dimension/unit (e.g., "uV", "mV", "BPM")
+    /// 
+    /// Like all header texts it must be printable ASCII, so micro is
+    /// written as "u", see [`EdfWriter::sanitize`](crate::EdfWriter::sanitize).
     /// 
     /// # Examples
     /// 
     /// Common units:
-    /// - "uV" or "µV" for EEG signals
+    /// - "uV" for EEG signals
     /// - "mV" for ECG signals  
     /// - "BPM" for heart rate
     /// - "%" for oxygen saturation
@@ -89,6 +176,13 @@ pub struct SignalParam {
     /// 
     /// Describes the sensor or electrode used to acquire the signal.
     pub transducer: String,
+    
+    /// Content of the 32-byte per-signal reserved field
+    /// 
+    /// Normally empty, but some vendors store their own data here. The
+    /// reader preserves it and the writer writes it back, so copying a
+    /// signal keeps it intact. Must be printable ASCII.
+    pub reserved: String,
 }
 
 impl SignalParam {
@@ -112,6 +206,7 @@ impl SignalParam {
     ///     physical_dimension: "uV".to_string(),
     ///     prefilter: "".to_string(),
     ///     transducer: "".to_string(),
+    ///     reserved: String::new(),
     /// };
     /// 
     /// let bit_value = signal.bit_value();
@@ -124,9 +219,24 @@ impl SignalParam {
         (self.digital_max - self.digital_min) as f64
     }
     
-    /// Calculate the offset for digital to physical conversion
+    /// Physical units per digital count
+    /// 
+    /// Together with [`offset`](Self::offset) this is the exact linear mapping
+    /// used by [`to_physical`](Self::to_physical):
+    /// 
+    /// ```text
+    /// scale  = (physical_max - physical_min) / (digital_max - digital_min)
+    /// offset = physical_max - scale * digital_max
+    /// physical = scale * digital + offset
+    /// ```
+    /// 
+    /// The mapping is anchored at both ends of the ranges, so asymmetric
+    /// digital ranges (e.g. -32768..32767, or 0..4095) need no special
+    /// handling: `digital_min` maps to `physical_min` and `digital_max` to
+    /// `physical_max`. If `physical_max < physical_min` the signal is inverted
+    /// and `scale` is negative.
     /// 
-    /// This is used internally for the conversion calculations.
+    /// This is the same value as [`bit_value`](Self::bit_value).
     /// 
     /// # Examples
     /// 
@@ -135,23 +245,90 @@ impl SignalParam {
     /// 
     /// let signal = SignalParam {
     ///     label: "Test".to_string(),
-    ///     samples_in_file: 1000,
-    ///     physical_max: 200.0,
-    ///     physical_min: -200.0,
-    ///     digital_max: 32767,
-    ///     digital_min: -32768,
+    ///     samples_in_file: 0,
+    ///     physical_max: 100.0,
+    ///     physical_min: -100.0,
+    ///     digital_max: 2047,
+    ///     digital_min: -2048,
     ///     samples_per_record: 256,
     ///     physical_dimension: "uV".to_string(),
     ///     prefilter: "".to_string(),
     ///     transducer: "".to_string(),
+    ///     reserved: String::new(),
     /// };
     /// 
-    /// let offset = signal.offset();
-    /// // The offset should position the conversion correctly  
-    /// assert!(offset.abs() < 1.0); // Should be close to zero for symmetric ranges
+    /// let digital = 1000;
+    /// let physical = signal.scale() * digital as f64 + signal.offset();
+    /// assert_eq!(physical, signal.to_physical(digital));
+    /// ```
+    pub fn scale(&self) -> f64 {
+        self.bit_value()
+    }
+    
+    /// Physical value corresponding to a digital value of zero
+    /// 
+    /// See [`scale`](Self::scale) for the full conversion formula.
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::SignalParam;
+    /// 
+    /// let signal = SignalParam {
+    ///     label: "Temp".to_string(),
+    ///     samples_in_file: 0,
+    ///     physical_max: 40.0,
+    ///     physical_min: 30.0,
+    ///     digital_max: 32767,
+    ///     digital_min: -32768,
+    ///     samples_per_record: 1,
+    ///     physical_dimension: "degC".to_string(),
+    ///     prefilter: "".to_string(),
+    ///     transducer: "".to_string(),
+    ///     reserved: String::new(),
+    /// };
+    /// 
+    /// // Digital zero sits (almost) in the middle of the physical range
+    /// assert!((signal.offset() - 35.0).abs() < 0.001);
+    /// assert_eq!(signal.offset(), signal.to_physical(0));
     /// ```
     pub fn offset(&self) -> f64 {
-        self.physical_max / self.bit_value() - self.digital_max as f64
+        self.physical_max - self.scale() * self.digital_max as f64
+    }
+    
+    /// Whether the calibration has a negative slope
+    /// 
+    /// Some exports declare `physical_max < physical_min` on purpose to flip
+    /// the polarity of a signal. The conversions handle this like any other
+    /// calibration: `digital_min` still maps to `physical_min`, so larger
+    /// digital values mean smaller physical values, and clamping to the
+    /// digital range saturates at the declared physical ends.
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::SignalParam;
+    /// 
+    /// let signal = SignalParam {
+    ///     label: "ECG I".to_string(),
+    ///     samples_in_file: 0,
+    ///     physical_max: -5.0,
+    ///     physical_min: 5.0,
+    ///     digital_max: 32767,
+    ///     digital_min: -32768,
+    ///     samples_per_record: 256,
+    ///     physical_dimension: "mV".to_string(),
+    ///     prefilter: "".to_string(),
+    ///     transducer: "".to_string(),
+    ///     reserved: String::new(),
+    /// };
+    /// 
+    /// assert!(signal.is_inverted());
+    /// assert!(signal.to_physical(1000) < 0.0);
+    /// assert!(signal.to_digital(2.5) < 0);
+    /// ```
+    pub fn is_inverted(&self) -> bool {
+        self.scale() < 0.0
     }
     
     /// Convert a digital value to its corresponding physical value
@@ -180,6 +357,7 @@ impl SignalParam {
     ///     physical_dimension: "uV".to_string(),
     ///     prefilter: "".to_string(),
     ///     transducer: "".to_string(),
+    ///     reserved: String::new(),
     /// };
     /// 
     /// // Test maximum value
@@ -199,7 +377,7 @@ impl SignalParam {
     /// assert!((half_physical - 100.0).abs() < 1.0);
     /// ```
     pub fn to_physical(&self, digital_value: i32) -> f64 {
-        self.bit_value() * (self.offset() + digital_value as f64)
+        self.scale() * digital_value as f64 + self.offset()
     }
     
     /// Convert a physical value to its corresponding digital value
@@ -228,6 +406,7 @@ impl SignalParam {
     ///     physical_dimension: "mV".to_string(),
     ///     prefilter: "".to_string(),
     ///     transducer: "".to_string(),
+    ///     reserved: String::new(),
     /// };
     /// 
     /// // Test maximum value
@@ -247,9 +426,115 @@ impl SignalParam {
     /// assert!((pos_digital - 16384).abs() <= 100);
     /// ```
     pub fn to_digital(&self, physical_value: f64) -> i32 {
-        let digital = (physical_value / self.bit_value()) - self.offset();
+        let digital = (physical_value - self.offset()) / self.scale();
         digital.round() as i32
     }
+
+    /// Converts a digital value to an exact fixed-point physical value
+    ///
+    /// Uses the smallest number of decimals that represents every digital
+    /// value of the calibration exactly, or [`MAX_AUTO_DECIMALS`](crate::fixed::MAX_AUTO_DECIMALS)
+    /// rounded decimals if the calibration step does not terminate in
+    /// decimal. The number of decimals only depends on the calibration, so
+    /// all values of a signal share it. See the [`fixed`](crate::fixed)
+    /// module for the arithmetic and rounding.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use edfplus::SignalParam;
+    ///
+    /// let signal = SignalParam {
+    ///     label: "EEG Fpz-Cz".to_string(),
+    ///     samples_in_file: 0,
+    ///     physical_max: 200.0,
+    ///     physical_min: -200.0,
+    ///     digital_max: 32767,
+    ///     digital_min: -32768,
+    ///     samples_per_record: 256,
+    ///     physical_dimension: "uV".to_string(),
+    ///     prefilter: "".to_string(),
+    ///     transducer: "".to_string(),
+    ///     reserved: String::new(),
+    /// };
+    ///
+    /// // 400 / 65535 does not terminate: nine rounded decimals
+    /// let value = signal.to_physical_exact(1000);
+    /// assert_eq!(value.decimals, 9);
+    /// assert!(!value.exact);
+    /// assert!((value.to_f64() - signal.to_physical(1000)).abs() < 1e-9);
+    ///
+    /// // The ends of the range are exact
+    /// assert!(signal.to_physical_exact(32767).exact);
+    /// assert_eq!(signal.to_physical_exact(32767).to_string(), "200.000000000");
+    /// ```
+    pub fn to_physical_exact(&self, digital_value: i32) -> FixedPhysical {
+        let calibration = ExactCalibration::new(self);
+        let decimals = calibration.as_ref()
+            .and_then(ExactCalibration::exact_decimals)
+            .filter(|&decimals| decimals <= MAX_AUTO_DECIMALS)
+            .unwrap_or(MAX_AUTO_DECIMALS);
+        fixed::to_fixed(self, calibration.as_ref(), digital_value, decimals)
+    }
+
+    /// Converts a digital value to a fixed-point physical value with the
+    /// given number of decimals
+    ///
+    /// With `decimals = 3` a signal in `uV` gives nanovolts. `decimals` is
+    /// clamped to [`MAX_DECIMALS`](crate::fixed::MAX_DECIMALS). The value is
+    /// rounded half away from zero when it has more decimals; values outside
+    /// the `i64` range saturate.
+    pub fn to_physical_fixed(&self, digital_value: i32, decimals: u32) -> FixedPhysical {
+        let calibration = ExactCalibration::new(self);
+        fixed::to_fixed(self, calibration.as_ref(), digital_value, decimals)
+    }
+
+    /// Splits the label into signal type and specification
+    /// 
+    /// See [`ParsedLabel::parse`](crate::labels::ParsedLabel::parse).
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::SignalParam;
+    /// use edfplus::labels::SignalType;
+    /// 
+    /// let signal = SignalParam {
+    ///     label: "Temp rectal".to_string(),
+    ///     samples_in_file: 0,
+    ///     physical_max: 45.0,
+    ///     physical_min: 25.0,
+    ///     digital_max: 32767,
+    ///     digital_min: -32768,
+    ///     samples_per_record: 1,
+    ///     physical_dimension: "degC".to_string(),
+    ///     prefilter: "".to_string(),
+    ///     transducer: "".to_string(),
+    ///     reserved: String::new(),
+    /// };
+    /// 
+    /// let parsed = signal.parsed_label();
+    /// assert_eq!(parsed.signal_type, Some(SignalType::Temp));
+    /// assert_eq!(parsed.specification, "rectal");
+    /// ```
+    pub fn parsed_label(&self) -> ParsedLabel {
+        ParsedLabel::parse(&self.label)
+    }
+    
+    /// Number of samples of the signal in the file
+    /// 
+    /// This is `samples_in_file` as a count: every data record contributes
+    /// `samples_per_record` samples, so the padding of a partially filled
+    /// last record is included. Zero for a signal that is still being
+    /// written.
+    pub fn len(&self) -> usize {
+        self.samples_in_file.max(0) as usize
+    }
+    
+    /// Whether the signal has no samples in the file
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 }
 
 /// Annotation or event marker in an EDF+ file
@@ -265,29 +550,38 @@ impl SignalParam {
 /// // Create an annotation for a seizure event
 /// let seizure_event = Annotation {
 ///     onset: 1500000000,  // 150 seconds after start (in 100ns units)
-///     duration: 300000000, // 30 seconds duration (in 100ns units)  
+///     duration: Some(300000000), // 30 seconds duration (in 100ns units)  
 ///     description: "Seizure detected".to_string(),
 /// };
 /// 
 /// // Convert onset to seconds
-/// let onset_seconds = seizure_event.onset as f64 / 10_000_000.0;
+/// let onset_seconds = edfplus::time::ticks_to_seconds(seizure_event.onset);
 /// assert_eq!(onset_seconds, 150.0);
 /// 
 /// // Convert duration to seconds
-/// let duration_seconds = seizure_event.duration as f64 / 10_000_000.0;
-/// assert_eq!(duration_seconds, 30.0);
+/// let duration_seconds = seizure_event.duration.map(edfplus::time::ticks_to_seconds);
+/// assert_eq!(duration_seconds, Some(30.0));
 /// ```
-#[derive(Debug, Clone)]
+/// 
+/// With the `serde` feature, annotations serialize with `onset` and
+/// `duration` in seconds, and `duration` as null when absent, e.g.
+/// `{"onset": 150.0, "duration": 30.0, "description": "Seizure detected"}`.
+/// A missing `duration` key deserializes as `None`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Annotation {
     /// Onset time in 100-nanosecond units since recording start
     /// 
-    /// To convert to seconds: `onset as f64 / 10_000_000.0`
+    /// To convert to seconds: [`time::ticks_to_seconds`](crate::time::ticks_to_seconds)
+    #[cfg_attr(feature = "serde", serde(with = "crate::time::serde_seconds"))]
     pub onset: i64,
     
-    /// Duration in 100-nanosecond units (-1 if unknown/instantaneous)
+    /// Duration in 100-nanosecond units, `None` if the annotation has no
+    /// duration (instantaneous or unknown)
     /// 
-    /// To convert to seconds: `duration as f64 / 10_000_000.0`
-    pub duration: i64,
+    /// To convert to seconds: [`time::ticks_to_seconds`](crate::time::ticks_to_seconds)
+    #[cfg_attr(feature = "serde", serde(default, with = "crate::time::serde_seconds::option"))]
+    pub duration: Option<i64>,
     
     /// UTF-8 description of the event
     /// 
@@ -299,6 +593,91 @@ pub struct Annotation {
     pub description: String,
 }
 
+impl Annotation {
+    /// Absolute wall-clock time of the annotation onset
+    /// 
+    /// Annotation onsets are stored relative to the recording start
+    /// (including the subsecond start offset), so this is simply
+    /// [`EdfHeader::start_datetime`] plus the onset.
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::{EdfReader, EdfWriter, SignalParam};
+    /// use chrono::NaiveDate;
+    /// 
+    /// # let mut writer = EdfWriter::create("annotation_datetime.edf")?;
+    /// # writer.add_signal(SignalParam {
+    /// #     label: "EEG".to_string(),
+    /// #     samples_in_file: 0,
+    /// #     physical_max: 100.0,
+    /// #     physical_min: -100.0,
+    /// #     digital_max: 32767,
+    /// #     digital_min: -32768,
+    /// #     samples_per_record: 256,
+    /// #     physical_dimension: "uV".to_string(),
+    /// #     prefilter: "".to_string(),
+    /// #     transducer: "".to_string(),
+    /// #     reserved: String::new(),
+    /// # })?;
+    /// # writer.add_annotation(2.5, None, "Event")?;
+    /// # for _ in 0..5 { writer.write_samples(&[vec![0.0; 256]])?; }
+    /// # writer.finalize()?;
+    /// let reader = EdfReader::open("annotation_datetime.edf")?;
+    /// let event = &reader.annotations()[0];
+    /// 
+    /// // The writer's default start is 01-JAN-1985 00:00:00
+    /// let expected = NaiveDate::from_ymd_opt(1985, 1, 1).unwrap()
+    ///     .and_hms_milli_opt(0, 0, 2, 500).unwrap();
+    /// assert_eq!(event.datetime(reader.header()), expected);
+    /// 
+    /// # drop(reader);
+    /// # std::fs::remove_file("annotation_datetime.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn datetime(&self, header: &EdfHeader) -> NaiveDateTime {
+        header.start_datetime() + crate::time::ticks_to_chrono_duration(self.onset)
+    }
+}
+
+/// Malformed part of an annotation signal that the reader skipped
+/// 
+/// Collected in lenient mode (see
+/// [`EdfReaderOptions::strict_tal`](crate::EdfReaderOptions::strict_tal))
+/// and returned by [`EdfReader::tal_warnings`](crate::EdfReader::tal_warnings).
+/// The fields match those of `EdfError::MalformedTal`, which strict mode
+/// returns for the same problem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TalWarning {
+    /// Zero-based index of the data record
+    pub record: usize,
+    
+    /// Byte offset within the annotation signal of the data record
+    /// 
+    /// This is where the skipped TAL starts, i.e. the byte after the
+    /// preceding NUL. For surplus NUL bytes between two TALs it is the
+    /// first surplus NUL, and for an annotation signal that is not
+    /// NUL-terminated the byte after its last NUL.
+    pub offset: usize,
+    
+    /// What is wrong with the TAL
+    pub reason: &'static str,
+}
+
+/// Where [`EdfHeader::datarecords_in_file`] comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DatarecordsSource {
+    /// The number of data records field of the header
+    #[default]
+    Declared,
+    /// Computed from the file size, because the header field is `-1`
+    /// 
+    /// Recording software writes `-1` while a recording is in progress;
+    /// files that were not closed properly keep it.
+    ComputedFromSize,
+}
+
 /// Complete EDF+ file header information
 /// 
 /// Contains all metadata about the recording, including patient information,
@@ -324,6 +703,7 @@ pub struct Annotation {
 /// #     physical_dimension: "uV".to_string(),
 /// #     prefilter: "HP:0.1Hz".to_string(),
 /// #     transducer: "AgAgCl".to_string(),
+/// #     reserved: String::new(),
 /// # };
 /// # writer.add_signal(signal).unwrap();
 /// # let samples = vec![10.0; 256];
@@ -334,7 +714,7 @@ pub struct Annotation {
 /// let header = reader.header();
 /// 
 /// println!("Recording duration: {:.2} seconds", 
-///     header.file_duration as f64 / 10_000_000.0);
+///     edfplus::time::ticks_to_seconds(header.file_duration));
 /// println!("Number of signals: {}", header.signals.len());
 /// println!("Patient: {} ({})", header.patient_name, header.patient_code);
 /// println!("Equipment: {}", header.equipment);
@@ -349,7 +729,11 @@ pub struct Annotation {
 /// # drop(reader);
 /// # fs::remove_file("test_header_example.edf").ok();
 /// ```
+#[derive(Debug, Clone)]
 pub struct EdfHeader {
+    /// Format variant of the file
+    pub file_type: FileType,
+    
     /// List of all signals in the file (excluding annotation signals)
     /// 
     /// Each signal contains its own parameters like sampling rate,
@@ -358,7 +742,7 @@ pub struct EdfHeader {
     
     /// Total duration of the recording in 100-nanosecond units
     /// 
-    /// To convert to seconds: `file_duration as f64 / 10_000_000.0`
+    /// To convert to seconds: [`time::ticks_to_seconds`](crate::time::ticks_to_seconds)
     /// 
     /// # Examples
     /// 
@@ -380,6 +764,7 @@ pub struct EdfHeader {
     /// #     physical_dimension: "uV".to_string(),
     /// #     prefilter: "HP:0.1Hz".to_string(),
     /// #     transducer: "AgAgCl".to_string(),
+    /// #     reserved: String::new(),
     /// # };
     /// # writer.add_signal(signal).unwrap();
     /// # let samples = vec![10.0; 256];
@@ -389,7 +774,7 @@ pub struct EdfHeader {
     /// let mut reader = EdfReader::open("test_duration.edf").unwrap();
     /// let header = reader.header();
     /// 
-    /// let duration_seconds = header.file_duration as f64 / 10_000_000.0;
+    /// let duration_seconds = edfplus::time::ticks_to_seconds(header.file_duration);
     /// let duration_minutes = duration_seconds / 60.0;
     /// println!("Recording length: {:.1} minutes", duration_minutes);
     /// 
@@ -414,6 +799,10 @@ pub struct EdfHeader {
     /// but can be configured differently.
     pub datarecords_in_file: i64,
     
+    /// Whether `datarecords_in_file` was read from the header or computed
+    /// from the file size
+    pub datarecords_source: DatarecordsSource,
+    
     /// Duration of each data record in 100-nanosecond units
     /// 
     /// Default is 10,000,000 (1 second). Shorter records provide
@@ -473,4 +862,214 @@ pub struct EdfHeader {
     /// 
     /// Free text field for recording details, protocols, etc.
     pub recording_additional: String,
+    
+    /// Content of the main header's reserved field after the `EDF+C`/`EDF+D` marker
+    /// 
+    /// Up to 39 characters, normally empty. Some vendors store their own
+    /// data here; use [`EdfWriter::set_reserved_main`](crate::EdfWriter::set_reserved_main)
+    /// to carry it over to a copy.
+    pub reserved_main: String,
+    
+    /// 注释信号数量，不包含在 `signals` 中
+    pub(crate) annotation_signal_count: usize,
+}
+
+impl EdfHeader {
+    /// Absolute start of the recording, including the subsecond offset
+    /// 
+    /// Combines `start_date`, `start_time` and `starttime_subsecond` into a
+    /// single timestamp. This is the reference point that sample positions
+    /// and annotation onsets are measured from.
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::EdfReader;
+    /// 
+    /// # edfplus::doctest_utils::create_simple_test_file("start_datetime.edf")?;
+    /// let reader = EdfReader::open("start_datetime.edf")?;
+    /// let start = reader.header().start_datetime();
+    /// println!("Recording started at {}", start);
+    /// 
+    /// assert_eq!(start.date(), reader.header().start_date);
+    /// # drop(reader);
+    /// # std::fs::remove_file("start_datetime.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn start_datetime(&self) -> NaiveDateTime {
+        self.start_date.and_time(self.start_time)
+            + crate::time::ticks_to_chrono_duration(self.starttime_subsecond)
+    }
+    
+    /// Duration of the recording
+    /// 
+    /// [`file_duration`](Self::file_duration) as a [`std::time::Duration`],
+    /// i.e. the number of data records times their duration, including the
+    /// padding of a partially filled last record.
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::EdfReader;
+    /// 
+    /// # edfplus::doctest_utils::create_simple_test_file("header_duration.edf")?;
+    /// let reader = EdfReader::open("header_duration.edf")?;
+    /// let header = reader.header();
+    /// 
+    /// let seconds = header.duration().as_secs_f64();
+    /// assert_eq!(seconds, edfplus::time::ticks_to_seconds(header.file_duration));
+    /// # drop(reader);
+    /// # std::fs::remove_file("header_duration.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn duration(&self) -> std::time::Duration {
+        crate::time::ticks_to_duration(self.file_duration).unwrap_or_default()
+    }
+    
+    /// Number of `EDF Annotations` signals in the file
+    /// 
+    /// The annotation signals are not part of [`signals`](Self::signals).
+    /// EDF+ files have at least one, which carries the timekeeping TALs;
+    /// classic EDF files have none. Select the ones to parse with
+    /// [`EdfReaderOptions::parse_annotation_channels`](crate::EdfReaderOptions::parse_annotation_channels).
+    pub fn annotation_signal_count(&self) -> usize {
+        self.annotation_signal_count
+    }
+}
+
+/// All samples of one data record, see [`EdfReader::read_record`](crate::EdfReader::read_record)
+/// 
+/// The data record is the natural unit of EDF: every signal contributes
+/// `samples_per_record` samples to each record, and the annotation signals
+/// carry the record's timekeeping stamp and any annotations starting in it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordView {
+    /// Zero-based index of the data record
+    pub index: i64,
+    
+    /// Start of the record from the timekeeping annotation, in 100-nanosecond
+    /// units since recording start (like [`Annotation::onset`])
+    /// 
+    /// `None` if the record has no readable timekeeping annotation.
+    pub timestamp: Option<i64>,
+    
+    /// Physical samples of each signal, indexed like `EdfHeader::signals`
+    pub signals: Vec<Vec<f64>>,
+    
+    /// Annotations stored in this record, timekeeping annotations excluded
+    pub annotations: Vec<Annotation>,
+}
+
+impl RecordView {
+    /// Physical samples of one signal in this record
+    /// 
+    /// Returns `None` if `signal` is out of range.
+    pub fn signal(&self, signal: usize) -> Option<&[f64]> {
+        self.signals.get(signal).map(Vec::as_slice)
+    }
+}
+
+/// Position of a sample within the data records, see
+/// [`EdfReader::locate_sample`](crate::EdfReader::locate_sample)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SampleLocation {
+    /// Zero-based index of the data record containing the sample
+    pub record: i64,
+    
+    /// Index of the sample within that record, `0..samples_per_record`
+    pub offset_in_record: i64,
+    
+    /// Time of the sample in 100-nanosecond units since recording start
+    /// (like [`Annotation::onset`]), rounded to the nearest unit
+    pub time_100ns: i64,
+}
+
+/// Patient sex as encoded in the EDF+ patient identification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sex {
+    /// Encoded as `F`
+    Female,
+    /// Encoded as `M`
+    Male,
+}
+
+impl Sex {
+    /// The single-letter code used in the header
+    pub fn code(self) -> &'static str {
+        match self {
+            Sex::Female => "F",
+            Sex::Male => "M",
+        }
+    }
+}
+
+/// Structured EDF+ patient identification, see [`EdfWriter::set_patient`](crate::EdfWriter::set_patient)
+/// 
+/// Every subfield is optional; `None` is written as the `X` placeholder the
+/// EDF+ specification prescribes for unknown or anonymized values, so the
+/// subfields always stay in their positions.
+/// 
+/// # Examples
+/// 
+/// ```rust
+/// use edfplus::{PatientInfo, Sex};
+/// use chrono::NaiveDate;
+/// 
+/// let patient = PatientInfo {
+///     code: Some("MCH-0234567".to_string()),
+///     sex: Some(Sex::Female),
+///     birthdate: NaiveDate::from_ymd_opt(1951, 5, 2),
+///     name: Some("Haagse Harry".to_string()),
+///     ..PatientInfo::default()
+/// };
+/// assert_eq!(patient.identification(), "MCH-0234567 F 02-MAY-1951 Haagse_Harry X");
+/// 
+/// // Fully anonymized
+/// assert_eq!(PatientInfo::default().identification(), "X X X X X");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PatientInfo {
+    /// Hospital administration code; spaces are written as `_`
+    pub code: Option<String>,
+    /// Sex of the patient
+    pub sex: Option<Sex>,
+    /// Date of birth, written as `dd-MMM-yyyy`
+    pub birthdate: Option<NaiveDate>,
+    /// Patient name; spaces are written as `_`
+    pub name: Option<String>,
+    /// Additional free text, written verbatim after the four subfields
+    pub additional: Option<String>,
+}
+
+impl PatientInfo {
+    /// Composes the local patient identification field as written to the header
+    pub fn identification(&self) -> String {
+        let [code, sex, birthdate, name, additional] = self.subfields();
+        format!("{} {} {} {} {}", code, sex, birthdate, name, additional)
+    }
+    
+    /// 按规范编码后的各个子字段
+    pub(crate) fn subfields(&self) -> [String; 5] {
+        [
+            encode_subfield(self.code.as_deref()),
+            self.sex.map_or_else(|| "X".to_string(), |sex| sex.code().to_string()),
+            self.birthdate.map_or_else(
+                || "X".to_string(),
+                |date| date.format("%d-%b-%Y").to_string().to_uppercase(),
+            ),
+            encode_subfield(self.name.as_deref()),
+            match self.additional.as_deref().map(str::trim) {
+                Some(text) if !text.is_empty() => text.to_string(),
+                _ => "X".to_string(),
+            },
+        ]
+    }
+}
+
+/// 子字段中的空格用下划线替换，缺失或空值写为"X"
+fn encode_subfield(value: Option<&str>) -> String {
+    match value.map(str::trim) {
+        Some(text) if !text.is_empty() => text.replace(' ', "_"),
+        _ => "X".to_string(),
+    }
 }
@@ -0,0 +1,224 @@
+//! Physical dimensions of signals
+//!
+//! The `physical_dimension` of a signal is free text such as `"uV"`,
+//! `"mmHg"` or `"%"`. [`normalize`] maps the common spellings onto a
+//! [`Unit`]: the [`Dimension`] it measures and the conversion into the SI
+//! unit of that dimension. [`EdfReader::read_si_samples`](crate::EdfReader::read_si_samples)
+//! uses the mapping to read physical values in SI units and refuses signals
+//! whose unit measures something else, so a pressure channel cannot be read
+//! as a voltage by mistake.
+//!
+//! # Units
+//!
+//! | Dimension | SI unit | Accepted texts |
+//! |---|---|---|
+//! | [`ElectricPotential`](Dimension::ElectricPotential) | volt | `V`, `mV`, `uV` (also `µV`), `nV` |
+//! | [`Pressure`](Dimension::Pressure) | pascal | `Pa`, `hPa`, `kPa`, `mbar`, `mmHg`, `cmH2O` |
+//! | [`Temperature`](Dimension::Temperature) | kelvin | `K`, `degC` |
+//! | [`Frequency`](Dimension::Frequency) | hertz | `Hz`, `bpm` (also `BPM`, `/min`) |
+//! | [`Time`](Dimension::Time) | second | `s`, `ms` |
+//! | [`Ratio`](Dimension::Ratio) | 1 | `%` |
+//!
+//! The texts are matched exactly after trimming, since the case of a
+//! prefix matters (`mV` and `MV`).
+//!
+//! # Dimensionless signals
+//!
+//! Signals with a blank or unknown unit have no [`Unit`]. They can only be
+//! read as [`Dimension::Ratio`], with the physical values passed through
+//! unchanged; reading them as any other dimension fails with
+//! `EdfError::IncompatibleDimension`. Note the difference to `%`, whose
+//! values are divided by 100.
+//!
+//! # Typed quantities
+//!
+//! With the `uom` feature, [`EdfReader::read_quantity`](crate::EdfReader::read_quantity)
+//! returns the values as quantities of the [`uom`](https://docs.rs/uom)
+//! crate instead of plain SI numbers, so the compiler rejects adding a
+//! voltage to a pressure. Every [`Dimension`] has one quantity type, see
+//! [`Quantity`]; dimensionless signals are read as `uom::si::f64::Ratio`
+//! with the rules above.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use edfplus::units::{self, Dimension};
+//!
+//! let unit = units::normalize("mmHg").unwrap();
+//! assert_eq!(unit.dimension, Dimension::Pressure);
+//! assert!((unit.to_si(1.0) - 133.322387415).abs() < 1e-9);
+//!
+//! assert_eq!(units::normalize("µV").unwrap().symbol, "uV");
+//! assert!((units::normalize("degC").unwrap().to_si(37.0) - 310.15).abs() < 1e-9);
+//! assert_eq!(units::normalize("counts"), None);
+//! ```
+
+use std::fmt;
+
+/// Physical quantity measured by a signal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Dimension {
+    /// Voltage, in volt
+    ElectricPotential,
+    /// Pressure, in pascal
+    Pressure,
+    /// Temperature, in kelvin
+    Temperature,
+    /// Frequency or rate, in hertz
+    Frequency,
+    /// Time, in seconds
+    Time,
+    /// Dimensionless, as a fraction of one
+    Ratio,
+}
+
+impl Dimension {
+    /// Name of the dimension, e.g. `"electric potential"`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Dimension::ElectricPotential => "electric potential",
+            Dimension::Pressure => "pressure",
+            Dimension::Temperature => "temperature",
+            Dimension::Frequency => "frequency",
+            Dimension::Time => "time",
+            Dimension::Ratio => "ratio",
+        }
+    }
+}
+
+impl fmt::Display for Dimension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A recognized unit, see [`normalize`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Unit {
+    /// Canonical spelling, e.g. `"uV"` for `"µV"`
+    pub symbol: &'static str,
+    /// Quantity the unit measures
+    pub dimension: Dimension,
+    /// Factor to the SI unit of the dimension
+    pub scale: f64,
+    /// Offset added after scaling, only non-zero for `degC`
+    pub offset: f64,
+}
+
+impl Unit {
+    /// Converts a value in this unit to the SI unit of its dimension
+    pub fn to_si(&self, value: f64) -> f64 {
+        value * self.scale + self.offset
+    }
+
+    const fn new(symbol: &'static str, dimension: Dimension, scale: f64) -> Self {
+        Unit { symbol, dimension, scale, offset: 0.0 }
+    }
+}
+
+/// 单位换算表：(文本, 单位)
+const UNITS: &[(&str, Unit)] = &[
+    ("V", Unit::new("V", Dimension::ElectricPotential, 1.0)),
+    ("mV", Unit::new("mV", Dimension::ElectricPotential, 1e-3)),
+    ("uV", Unit::new("uV", Dimension::ElectricPotential, 1e-6)),
+    ("µV", Unit::new("uV", Dimension::ElectricPotential, 1e-6)),
+    ("nV", Unit::new("nV", Dimension::ElectricPotential, 1e-9)),
+    ("Pa", Unit::new("Pa", Dimension::Pressure, 1.0)),
+    ("hPa", Unit::new("hPa", Dimension::Pressure, 100.0)),
+    ("kPa", Unit::new("kPa", Dimension::Pressure, 1000.0)),
+    ("mbar", Unit::new("mbar", Dimension::Pressure, 100.0)),
+    ("mmHg", Unit::new("mmHg", Dimension::Pressure, 133.322387415)),
+    ("cmH2O", Unit::new("cmH2O", Dimension::Pressure, 98.0665)),
+    ("K", Unit::new("K", Dimension::Temperature, 1.0)),
+    ("degC", Unit { symbol: "degC", dimension: Dimension::Temperature, scale: 1.0, offset: 273.15 }),
+    ("Hz", Unit::new("Hz", Dimension::Frequency, 1.0)),
+    ("bpm", Unit::new("bpm", Dimension::Frequency, 1.0 / 60.0)),
+    ("BPM", Unit::new("bpm", Dimension::Frequency, 1.0 / 60.0)),
+    ("/min", Unit::new("bpm", Dimension::Frequency, 1.0 / 60.0)),
+    ("s", Unit::new("s", Dimension::Time, 1.0)),
+    ("ms", Unit::new("ms", Dimension::Time, 1e-3)),
+    ("%", Unit::new("%", Dimension::Ratio, 0.01)),
+];
+
+/// Looks up the unit of a `physical_dimension` text
+///
+/// Returns `None` for blank and unknown units, see the
+/// [module documentation](self) for the accepted texts.
+pub fn normalize(physical_dimension: &str) -> Option<Unit> {
+    let text = physical_dimension.trim();
+    UNITS.iter()
+        .find(|(spelling, _)| *spelling == text)
+        .map(|(_, unit)| *unit)
+}
+
+/// 将 `physical_dimension` 的值换算为 `requested` 的SI单位；量纲不符时为 None
+pub(crate) fn si_unit(physical_dimension: &str, requested: Dimension) -> Option<Unit> {
+    match normalize(physical_dimension) {
+        Some(unit) => (unit.dimension == requested).then_some(unit),
+        // 空白或未知单位按无量纲处理，数值不变
+        None => (requested == Dimension::Ratio).then_some(Unit::new("", Dimension::Ratio, 1.0)),
+    }
+}
+
+/// A `uom` quantity type a signal can be read as, see
+/// [`EdfReader::read_quantity`](crate::EdfReader::read_quantity)
+///
+/// | Quantity | [`Dimension`] |
+/// |---|---|
+/// | `uom::si::f64::ElectricPotential` | [`ElectricPotential`](Dimension::ElectricPotential) |
+/// | `uom::si::f64::Pressure` | [`Pressure`](Dimension::Pressure) |
+/// | `uom::si::f64::ThermodynamicTemperature` | [`Temperature`](Dimension::Temperature) |
+/// | `uom::si::f64::Frequency` | [`Frequency`](Dimension::Frequency) |
+/// | `uom::si::f64::Time` | [`Time`](Dimension::Time) |
+/// | `uom::si::f64::Ratio` | [`Ratio`](Dimension::Ratio) |
+#[cfg(feature = "uom")]
+pub trait Quantity: Sized {
+    /// Dimension the quantity measures
+    const DIMENSION: Dimension;
+
+    /// Creates the quantity from a value in the SI unit of [`Self::DIMENSION`]
+    fn from_si(value: f64) -> Self;
+}
+
+#[cfg(feature = "uom")]
+macro_rules! impl_quantity {
+    ($quantity:ident, $dimension:ident, $module:ident :: $unit:ident) => {
+        impl Quantity for uom::si::f64::$quantity {
+            const DIMENSION: Dimension = Dimension::$dimension;
+
+            fn from_si(value: f64) -> Self {
+                uom::si::f64::$quantity::new::<uom::si::$module::$unit>(value)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "uom")]
+impl_quantity!(ElectricPotential, ElectricPotential, electric_potential::volt);
+#[cfg(feature = "uom")]
+impl_quantity!(Pressure, Pressure, pressure::pascal);
+#[cfg(feature = "uom")]
+impl_quantity!(ThermodynamicTemperature, Temperature, thermodynamic_temperature::kelvin);
+#[cfg(feature = "uom")]
+impl_quantity!(Frequency, Frequency, frequency::hertz);
+#[cfg(feature = "uom")]
+impl_quantity!(Time, Time, time::second);
+#[cfg(feature = "uom")]
+impl_quantity!(Ratio, Ratio, ratio::ratio);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_si_unit() {
+        assert_eq!(si_unit(" uV ", Dimension::ElectricPotential).map(|u| u.scale), Some(1e-6));
+        assert_eq!(si_unit("uV", Dimension::Pressure), None);
+        assert_eq!(si_unit("MV", Dimension::ElectricPotential), None);
+        assert_eq!(si_unit("%", Dimension::Ratio).map(|u| u.scale), Some(0.01));
+        assert_eq!(si_unit("", Dimension::Ratio).map(|u| u.scale), Some(1.0));
+        assert_eq!(si_unit("", Dimension::ElectricPotential), None);
+        assert_eq!(si_unit("µV", Dimension::ElectricPotential).map(|u| u.symbol), Some("uV"));
+    }
+}
@@ -12,7 +12,7 @@ pub fn is_integer_number(s: &str) -> bool {
     }
     
     // 简单的整数检查
-    s.chars().next().map_or(false, |first| first == '+' || first == '-' || first.is_ascii_digit()) &&
+    s.chars().next().is_some_and(|first| first == '+' || first == '-' || first.is_ascii_digit()) &&
     s.chars().skip(if s.starts_with('+') || s.starts_with('-') { 1 } else { 0 })
         .all(|c| c.is_ascii_digit() || c == ' ') &&
     s.chars().any(|c| c.is_ascii_digit())
@@ -42,10 +42,10 @@ pub fn parse_edf_time(s: &str) -> Result<i64> {
     }
     
     // 处理符号
-    let (negative, s) = if s.starts_with('-') {
-        (true, &s[1..])
-    } else if s.starts_with('+') {
-        (false, &s[1..])
+    let (negative, s) = if let Some(rest) = s.strip_prefix('-') {
+        (true, rest)
+    } else if let Some(rest) = s.strip_prefix('+') {
+        (false, rest)
     } else {
         (false, s)
     };
@@ -119,6 +119,67 @@ pub fn atof_nonlocalized(s: &str) -> f64 {
     s.parse().unwrap_or(0.0)
 }
 
+/// 去除头部数字字段两端的空格和NUL以及开头的'+'，空字段返回None
+fn header_number_text(raw: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(raw);
+    let text = text.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+    let text = text.strip_prefix('+').unwrap_or(text);
+    if text.is_empty() {
+        None
+    } else {
+        Some(text.to_string())
+    }
+}
+
+/// Parses a numeric header field, tolerating padding
+/// 
+/// Leading/trailing spaces and NULs are stripped on both sides and an
+/// optional leading `+` is accepted, so `"320     "`, `"     320"`,
+/// `"+320"` and `"320\0\0\0\0\0"` all parse as 320.
+/// 
+/// An empty or unparseable field is an error in strict mode. With `lenient`
+/// set, `default` is returned instead and a warning is emitted.
+/// 
+/// # Errors
+/// 
+/// * `EdfError::MissingField` - The field contains only padding
+/// * `EdfError::InvalidFormat` - The field is not an integer
+pub fn parse_header_int(raw: &[u8], field: &str, lenient: bool, default: i32) -> Result<i32> {
+    parse_header_number(raw, field, lenient, default)
+}
+
+/// Parses a numeric header field as a floating point value
+/// 
+/// Follows the same rules as [`parse_header_int`].
+/// 
+/// # Errors
+/// 
+/// * `EdfError::MissingField` - The field contains only padding
+/// * `EdfError::InvalidFormat` - The field is not a number
+pub fn parse_header_float(raw: &[u8], field: &str, lenient: bool, default: f64) -> Result<f64> {
+    parse_header_number(raw, field, lenient, default)
+}
+
+fn parse_header_number<T>(raw: &[u8], field: &str, lenient: bool, default: T) -> Result<T>
+where
+    T: std::str::FromStr + std::fmt::Display,
+{
+    let error = match header_number_text(raw) {
+        Some(text) => match text.parse::<T>() {
+            Ok(value) => return Ok(value),
+            Err(_) => EdfError::InvalidFormat(format!("Invalid value '{}' in header field: {}", text, field)),
+        },
+        None => EdfError::MissingField(field.to_string()),
+    };
+    
+    if lenient {
+        edf_warn!(field, error = %error, default = %default, "unusable numeric header field, using default");
+        Ok(default)
+    } else {
+        Err(error)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -141,4 +202,53 @@ mod tests {
         assert_eq!(parse_edf_time("-2.5").unwrap(), -25_000_000);
         assert_eq!(parse_edf_time("+0.0000001").unwrap(), 1);
     }
+
+    #[test]
+    fn test_parse_header_numbers() {
+        // (字段内容, 期望的整数结果)
+        let int_cases: [(&[u8], Option<i32>); 10] = [
+            (b"320     ", Some(320)),
+            (b"     320", Some(320)),
+            (b"  320   ", Some(320)),
+            (b"+320    ", Some(320)),
+            (b"-32768  ", Some(-32768)),
+            (b"320\0\0\0\0\0", Some(320)),
+            (b"\0 +7 \0  ", Some(7)),
+            (b"        ", None),
+            (b"\0\0\0\0\0\0\0\0", None),
+            (b"+       ", None),
+        ];
+        for (raw, expected) in int_cases {
+            match expected {
+                Some(value) => assert_eq!(parse_header_int(raw, "test", false, 0).unwrap(), value, "{:?}", raw),
+                None => {
+                    assert!(matches!(parse_header_int(raw, "test", false, 0), Err(EdfError::MissingField(_))), "{:?}", raw);
+                    assert_eq!(parse_header_int(raw, "test", true, 42).unwrap(), 42);
+                }
+            }
+        }
+
+        let float_cases: [(&[u8], Option<f64>); 6] = [
+            (b"-200    ", Some(-200.0)),
+            (b"  +200.5", Some(200.5)),
+            (b"1e3\0\0\0\0\0", Some(1000.0)),
+            (b" -0.001 ", Some(-0.001)),
+            (b"        ", None),
+            (b"\0       ", None),
+        ];
+        for (raw, expected) in float_cases {
+            match expected {
+                Some(value) => assert_eq!(parse_header_float(raw, "test", false, 0.0).unwrap(), value, "{:?}", raw),
+                None => {
+                    assert!(matches!(parse_header_float(raw, "test", false, 0.0), Err(EdfError::MissingField(_))), "{:?}", raw);
+                    assert_eq!(parse_header_float(raw, "test", true, 1.5).unwrap(), 1.5);
+                }
+            }
+        }
+
+        // 非数字内容：严格模式报错，宽松模式使用默认值
+        assert!(matches!(parse_header_int(b"abc     ", "test", false, 0), Err(EdfError::InvalidFormat(_))));
+        assert_eq!(parse_header_int(b"abc     ", "test", true, -1).unwrap(), -1);
+        assert!(matches!(parse_header_int(b"3 2 0   ", "test", false, 0), Err(EdfError::InvalidFormat(_))));
+    }
 }
@@ -0,0 +1,134 @@
+//! Distinct annotation descriptions and how often they occur
+//!
+//! [`vocabulary`] summarizes a list of annotations by description: how many
+//! annotations use it and their total duration. This answers "which event
+//! types does this file contain?" before the annotations are queried by
+//! description. [`EdfReader::annotation_vocabulary`](crate::EdfReader::annotation_vocabulary)
+//! computes it for the annotations of a file.
+//!
+//! Descriptions are compared exactly by default. [`VocabularyOptions`] can
+//! make the comparison ignore case and whitespace, so `"Sleep stage W"` and
+//! `" sleep  stage w"` are counted together; the spelling of the first
+//! occurrence is reported.
+//!
+//! Descriptions are counted by reference and only normalized copies are
+//! allocated, so a file with millions of annotations but a few distinct
+//! descriptions needs memory for the distinct descriptions only.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use edfplus::Annotation;
+//! use edfplus::vocabulary::{vocabulary, VocabularyOptions};
+//!
+//! let annotations = vec![
+//!     Annotation { onset: 0, duration: Some(300_000_000), description: "Sleep stage W".to_string() },
+//!     Annotation { onset: 300_000_000, duration: Some(300_000_000), description: "Sleep stage 1".to_string() },
+//!     Annotation { onset: 600_000_000, duration: Some(300_000_000), description: "sleep stage w ".to_string() },
+//!     Annotation { onset: 650_000_000, duration: None, description: "Arousal".to_string() },
+//! ];
+//!
+//! let exact = vocabulary(&annotations, &VocabularyOptions::new());
+//! assert_eq!(exact.len(), 4);
+//!
+//! let normalized = vocabulary(&annotations, &VocabularyOptions::new().ignore_case(true).ignore_whitespace(true));
+//! assert_eq!(normalized[0], ("Sleep stage W".to_string(), 2, 60.0));
+//! assert_eq!(normalized.len(), 3);
+//! ```
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use crate::time;
+use crate::types::Annotation;
+
+/// Options for [`vocabulary`]
+#[derive(Debug, Clone, Default)]
+pub struct VocabularyOptions {
+    ignore_case: bool,
+    ignore_whitespace: bool,
+}
+
+impl VocabularyOptions {
+    /// Creates options that compare descriptions exactly
+    pub fn new() -> Self {
+        VocabularyOptions::default()
+    }
+
+    /// Compares descriptions case-insensitively (default: `false`)
+    pub fn ignore_case(mut self, ignore: bool) -> Self {
+        self.ignore_case = ignore;
+        self
+    }
+
+    /// Ignores leading and trailing whitespace and treats runs of
+    /// whitespace inside a description as a single space (default: `false`)
+    pub fn ignore_whitespace(mut self, ignore: bool) -> Self {
+        self.ignore_whitespace = ignore;
+        self
+    }
+
+    /// 比较用的键，只有规范化改变了文本时才分配
+    fn key<'a>(&self, description: &'a str) -> Cow<'a, str> {
+        let mut key = Cow::Borrowed(description);
+        if self.ignore_whitespace {
+            let trimmed = description.trim();
+            let mut previous_space = false;
+            let collapsed = trimmed.chars().all(|c| {
+                let ok = !c.is_whitespace() || (c == ' ' && !previous_space);
+                previous_space = c.is_whitespace();
+                ok
+            });
+            key = if collapsed {
+                Cow::Borrowed(trimmed)
+            } else {
+                Cow::Owned(trimmed.split_whitespace().collect::<Vec<_>>().join(" "))
+            };
+        }
+        if self.ignore_case && key.chars().any(char::is_uppercase) {
+            key = Cow::Owned(key.to_lowercase());
+        }
+        key
+    }
+}
+
+/// Counts the distinct descriptions of `annotations`
+///
+/// Returns `(description, count, total duration in seconds)` for every
+/// distinct description, most frequent first and alphabetically among equal
+/// counts. Annotations without a duration count with zero seconds.
+pub fn vocabulary(annotations: &[Annotation], options: &VocabularyOptions) -> Vec<(String, usize, f64)> {
+    // 键 -> 条目序号；条目保存首次出现的写法、次数和总时长（100纳秒）
+    let mut index: HashMap<Cow<'_, str>, usize> = HashMap::new();
+    let mut entries: Vec<(&str, usize, i64)> = Vec::new();
+
+    for annotation in annotations {
+        let key = options.key(&annotation.description);
+        let entry = *index.entry(key).or_insert_with(|| {
+            entries.push((&annotation.description, 0, 0));
+            entries.len() - 1
+        });
+        let (_, count, duration) = &mut entries[entry];
+        *count += 1;
+        *duration = duration.saturating_add(annotation.duration.unwrap_or(0).max(0));
+    }
+
+    entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    entries.into_iter()
+        .map(|(description, count, duration)| (description.to_string(), count, time::ticks_to_seconds(duration)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_only_allocates_when_normalizing() {
+        let options = VocabularyOptions::new().ignore_case(true).ignore_whitespace(true);
+        assert!(matches!(options.key("spike"), Cow::Borrowed("spike")));
+        assert!(matches!(options.key(" spike wave "), Cow::Borrowed("spike wave")));
+        assert_eq!(options.key("Spike\t \nWave"), "spike wave");
+        assert_eq!(VocabularyOptions::new().key(" Spike "), " Spike ");
+    }
+}
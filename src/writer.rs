@@ -1,20 +1,31 @@
-use std::fs::File;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{File, OpenOptions};
 use std::io::{BufWriter, Write};
-use std::path::Path;
-use chrono::{NaiveDate, NaiveTime, Datelike, Timelike};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Datelike, Timelike};
 
-use crate::types::SignalParam;
+use crate::types::{FileType, PatientInfo, SignalParam};
+use crate::header;
+use crate::reader::EdfReader;
+use crate::labels::{self, LabelCheck, LabelPolicy};
+use crate::time;
+use crate::channels::{ChannelSender, ChannelWriter};
+use crate::compliance::{Profile, Rule, Violation};
+use crate::live::EdfLiveView;
 use crate::error::{EdfError, Result};
 use crate::EDFLIB_TIME_DIMENSION;
 
 /// Maximum number of annotation channels (matches edflib)
 const EDFLIB_MAX_ANNOTATION_CHANNELS: usize = 64;
 
-/// TAL (Time-stamped Annotations Lists) data size per annotation channel in bytes
+/// Default TAL (Time-stamped Annotations Lists) data size per annotation channel in bytes
 /// 
-/// Each annotation channel gets exactly 120 bytes in each data record to store
-/// TAL data. This includes time stamps, durations, descriptions, and formatting 
-/// characters. The 120-byte limit is part of the EDF+ specification.
+/// Each annotation channel gets 120 bytes in each data record to store
+/// TAL data unless [`EdfWriter::annotation_capacity`] says otherwise. This
+/// includes time stamps, durations, descriptions, and formatting characters.
+/// The 120-byte size matches edflib.
 const EDFLIB_ANNOTATION_BYTES: usize = 120;
 
 /// Maximum effective length for annotation descriptions in the TAL format
@@ -45,6 +56,29 @@ const EDFLIB_ANNOTATION_BYTES: usize = 120;
 /// ```
 const EDFLIB_WRITE_MAX_ANNOTATION_LEN: usize = 40;
 
+/// Bytes reserved for the timekeeping TAL of a data record by
+/// [`AnnotationCapacity::Auto`], and the smallest accepted
+/// [`AnnotationCapacity::Bytes`]
+const TIMEKEEPING_TAL_BYTES: usize = 32;
+
+/// Largest data record the EDF specification allows, in bytes, see
+/// [`EdfWriter::allow_oversized_records`]
+const EDF_MAX_RECORD_BYTES: usize = 61440;
+
+/// Replacement for non-printable header characters, see [`EdfWriter::sanitize`]
+const SANITIZE_REPLACEMENT: char = '_';
+
+/// Start date and time written when neither
+/// [`EdfWriter::set_start_datetime`] nor [`EdfWriter::set_clock`] is used:
+/// 01-JAN-1985 00:00:00, the earliest date the header can store
+/// 
+/// The writer never reads the system clock on its own, so the same
+/// signals, annotations and settings always give a byte-identical file.
+pub const DEFAULT_START_DATETIME: NaiveDateTime = match NaiveDate::from_ymd_opt(1985, 1, 1) {
+    Some(date) => date.and_time(NaiveTime::MIN),
+    None => panic!("valid date"),
+};
+
 
 
 /// EDF+ file writer for creating European Data Format Plus files
@@ -86,6 +120,7 @@ const EDFLIB_WRITE_MAX_ANNOTATION_LEN: usize = 40;
 ///     physical_dimension: "uV".to_string(),
 ///     prefilter: "HP:0.1Hz LP:70Hz".to_string(),
 ///     transducer: "AgAgCl cup electrodes".to_string(),
+///     reserved: String::new(),
 /// };
 /// 
 /// writer.add_signal(eeg_signal)?;
@@ -126,6 +161,7 @@ const EDFLIB_WRITE_MAX_ANNOTATION_LEN: usize = 40;
 ///         physical_dimension: "uV".to_string(),
 ///         prefilter: "HP:0.1Hz LP:70Hz".to_string(),
 ///         transducer: "AgAgCl electrodes".to_string(),
+///         reserved: String::new(),
 ///     },
 ///     SignalParam {
 ///         label: "ECG Lead II".to_string(),
@@ -136,6 +172,7 @@ const EDFLIB_WRITE_MAX_ANNOTATION_LEN: usize = 40;
 ///         physical_dimension: "mV".to_string(),
 ///         prefilter: "HP:0.1Hz LP:100Hz".to_string(),
 ///         transducer: "Chest electrodes".to_string(),
+///         reserved: String::new(),
 ///     },
 /// ];
 /// 
@@ -175,6 +212,9 @@ pub struct EdfWriter {
     signals: Vec<SignalParam>,
     start_date: NaiveDate,
     start_time: NaiveTime,
+    // 未设置开始时间时写入头部前调用的时钟
+    start_set: bool,
+    clock: Option<Box<dyn Fn() -> NaiveDateTime + Send>>,
     datarecord_duration: i64,
     samples_written: usize,
     header_written: bool,
@@ -190,6 +230,13 @@ pub struct EdfWriter {
     equipment: String,
     recording_additional: String,
     
+    // 主头部保留字段中文件类型标识之后的内容
+    reserved_main: String,
+    
+    // 经典EDF的自由文本患者/记录信息
+    patient_text: String,
+    recording_text: String,
+    
     // 注释存储
     annotations: Vec<crate::types::Annotation>,
 
@@ -198,6 +245,277 @@ pub struct EdfWriter {
     
     // 多注释通道支持 (遵循edflib设计)
     nr_annot_chns: usize,                    // 注释通道数量 (默认1)
+    
+    // 是否允许超出记录时长的注释（写入最后一个记录）
+    allow_out_of_range_annotations: bool,
+    
+    // 注释在 finalize 时统一分配到数据记录中
+    defer_annotations: bool,
+    
+    // 时间戳TAL的小数位数，None 时与edflib相同
+    timestamp_precision: Option<u8>,
+    
+    // 是否允许超过 61440 字节的数据记录，以及写入头部时的记录大小
+    allow_oversized_records: bool,
+    oversized_record_bytes: Option<usize>,
+    
+    // 写入的文件格式
+    file_type: FileType,
+    
+    // write_digital_samples 的窄化策略
+    narrowing_policy: NarrowingPolicy,
+    
+    // Scale 策略下每个信号的原始数字范围（写入头部时确定）
+    digital_sources: Vec<(i32, i32)>,
+    
+    // 被钳位或缩放改变的样本数
+    narrowed_samples: usize,
+    
+    // write_samples 的量化方式，Dithered 时带有随机数状态
+    quantization: Quantization,
+    dither: Option<Dither>,
+    
+    // 信号标签的标准文本检查
+    label_policy: LabelPolicy,
+    
+    // 头部文本中的不可打印字符：替换而不是报错
+    sanitize: bool,
+    sanitized_fields: Vec<String>,
+    
+    // Strict 时收集的违规项，以及已写入数据记录的注释索引
+    profile: Profile,
+    violations: Vec<Violation>,
+    placed_annotations: BTreeSet<usize>,
+    
+    // 每个注释通道每个记录的字节数，Auto 在写入头部时确定
+    annotation_capacity: AnnotationCapacity,
+    annotation_bytes: usize,
+    
+    // 原子写入：(临时文件, 目标路径, 是否覆盖)
+    atomic_target: Option<(PathBuf, PathBuf, bool)>,
+    
+    // 正在写入的文件路径，以及与 reader_view 共享的已刷新记录数
+    file_path: PathBuf,
+    live_records: Option<Arc<AtomicUsize>>,
+}
+
+/// How [`EdfWriter::write_digital_samples`] handles values that do not fit
+/// the stored 16-bit digital range of a signal
+/// 
+/// The stored range is the signal's `digital_min..=digital_max`, limited to
+/// `-32768..=32767`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NarrowingPolicy {
+    /// Fail with `EdfError::DigitalValueOutOfRange`, writing nothing
+    #[default]
+    Reject,
+    
+    /// Clamp to the stored range
+    Saturate,
+    
+    /// Treat `digital_min..=digital_max` as the range of the source values
+    /// and map it linearly onto the stored range
+    /// 
+    /// The header gets the stored range as digital minimum/maximum while the
+    /// physical range is kept, so the physical meaning of every value is
+    /// preserved. Values outside the source range are clamped first.
+    Scale,
+}
+
+/// How [`EdfWriter::write_samples`] rounds physical values to digital values
+/// 
+/// Quantizing a low-amplitude signal to 16 bits with plain rounding makes
+/// the error correlated with the signal, which shows up as steps and
+/// harmonics. Dither adds noise before rounding so the error becomes
+/// independent of the signal, at the cost of slightly more noise.
+/// [`EdfWriter::write_digital_samples`] is never affected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Quantization {
+    /// Round to the nearest digital value, ties to even
+    #[default]
+    Round,
+    
+    /// Add triangular (TPDF) dither of ±1 digital step before rounding
+    /// 
+    /// The dither is generated by a pseudo-random sequence started from
+    /// `seed`, so the same seed and input always give the same file.
+    Dithered { seed: u64 },
+}
+
+/// Space reserved for annotations in every data record, per annotation
+/// signal, see [`EdfWriter::annotation_capacity`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnotationCapacity {
+    /// A fixed number of bytes, rounded up to an even number
+    /// 
+    /// The default is `Bytes(120)`, the size edflib uses.
+    Bytes(usize),
+    
+    /// Sized when the header is written, from the annotations added so far
+    /// 
+    /// The annotation signals get room for the timekeeping TAL plus the
+    /// largest number of TAL bytes any data record needs for the queued
+    /// annotations, with a quarter of headroom. Queue all annotations before
+    /// the first `write_samples` call to make sure they fit.
+    Auto,
+}
+
+impl Default for AnnotationCapacity {
+    fn default() -> Self {
+        AnnotationCapacity::Bytes(EDFLIB_ANNOTATION_BYTES)
+    }
+}
+
+/// Options controlling how [`EdfWriter`] creates its file
+/// 
+/// The defaults are the safe choice: an existing file is never replaced.
+/// [`EdfWriter::create`] uses `overwrite(true)` for compatibility with
+/// `File::create`; [`EdfWriter::create_new`] uses the defaults.
+/// 
+/// # Examples
+/// 
+/// ```rust
+/// use edfplus::{EdfError, EdfWriterOptions};
+/// 
+/// # std::fs::write("existing.edf", b"precious data")?;
+/// // Refuses to clobber an existing recording
+/// let result = EdfWriterOptions::new().create("existing.edf");
+/// assert!(matches!(result, Err(EdfError::FileExists(_))));
+/// 
+/// // Writes to a temporary file that only replaces the target in finalize()
+/// let writer = EdfWriterOptions::new()
+///     .overwrite(true)
+///     .atomic(true)
+///     .create("existing.edf")?;
+/// # drop(writer);
+/// assert_eq!(std::fs::read("existing.edf")?, b"precious data");
+/// # std::fs::remove_file("existing.edf").ok();
+/// # Ok::<(), edfplus::EdfError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct EdfWriterOptions {
+    overwrite: bool,
+    atomic: bool,
+    file_type: FileType,
+}
+
+impl EdfWriterOptions {
+    /// Creates options that neither overwrite nor write atomically and
+    /// produce an EDF+C file
+    pub fn new() -> Self {
+        EdfWriterOptions {
+            overwrite: false,
+            atomic: false,
+            file_type: FileType::EdfPlusContinuous,
+        }
+    }
+    
+    /// Replaces an existing file at the target path (default: `false`)
+    /// 
+    /// When disabled, creating the writer fails with `EdfError::FileExists`
+    /// if the target already exists.
+    pub fn overwrite(mut self, overwrite: bool) -> Self {
+        self.overwrite = overwrite;
+        self
+    }
+    
+    /// Writes to a temporary file next to the target and renames it in
+    /// [`finalize`](EdfWriter::finalize) (default: `false`)
+    /// 
+    /// A writer dropped without finalizing removes the temporary file
+    /// (`.<name>.tmp` in the same directory) and leaves the target path
+    /// untouched. The same happens when `finalize` rejects the written
+    /// file, e.g. with `EdfError::AnnotationsOutOfRange`. After a crash only
+    /// the temporary file remains.
+    pub fn atomic(mut self, atomic: bool) -> Self {
+        self.atomic = atomic;
+        self
+    }
+    
+    /// Format of the written file (default: [`FileType::EdfPlusContinuous`])
+    /// 
+    /// [`FileType::Edf`] writes classic EDF, see
+    /// [`EdfWriter::create_classic`]. Other variants are rejected by
+    /// [`create`](Self::create).
+    pub fn file_type(mut self, file_type: FileType) -> Self {
+        self.file_type = file_type;
+        self
+    }
+    
+    /// Creates the writer
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::FileExists` - Target exists and overwriting is disabled
+    /// * `EdfError::FileNotFound` - Cannot create the file
+    /// * `EdfError::UnsupportedFileType` - The file type cannot be written
+    pub fn create<P: AsRef<Path>>(&self, path: P) -> Result<EdfWriter> {
+        EdfWriter::create_with_options(path.as_ref(), self)
+    }
+}
+
+impl Default for EdfWriterOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Outcome of [`EdfWriter::finalize`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WriteSummary {
+    /// Number of data records written
+    pub datarecords: usize,
+    
+    /// Recorded duration in 100-nanosecond units
+    pub duration: i64,
+    
+    /// Number of annotations added with `add_annotation`
+    pub annotations: usize,
+    
+    /// Annotations whose onset or end lies outside the recorded duration
+    /// 
+    /// Only reported when
+    /// [`allow_out_of_range_annotations`](EdfWriter::allow_out_of_range_annotations)
+    /// is enabled; otherwise `finalize` returns an error instead. Annotations
+    /// starting after the last record are stored in the final data record.
+    pub out_of_range_annotations: Vec<crate::types::Annotation>,
+    
+    /// Out-of-range annotations that did not fit into the final data record
+    /// and are therefore missing from the file; with
+    /// [`defer_annotations`](EdfWriter::defer_annotations) all annotations
+    /// that did not fit into any data record
+    pub dropped_annotations: Vec<crate::types::Annotation>,
+    
+    /// Number of samples passed to
+    /// [`write_digital_samples`](EdfWriter::write_digital_samples) whose
+    /// stored value differs from the input because of the
+    /// [`NarrowingPolicy`]
+    pub narrowed_samples: usize,
+    
+    /// Labels that do not follow the EDF+ standard texts, with the result
+    /// of [`labels::check`](crate::labels::check)
+    /// 
+    /// Only filled under [`LabelPolicy::Standard`], see
+    /// [`validate_labels`](EdfWriter::validate_labels).
+    pub nonconformant_labels: Vec<(String, LabelCheck)>,
+    
+    /// Header fields whose non-printable characters were replaced
+    /// 
+    /// Only filled when [`sanitize`](EdfWriter::sanitize) is enabled, e.g.
+    /// `"Signal 0 label"` or `"Patient name"`.
+    pub sanitized_fields: Vec<String>,
+    
+    /// Size of the data records in bytes, if it exceeds the 61440 bytes
+    /// allowed by the EDF specification
+    /// 
+    /// Only possible with
+    /// [`allow_oversized_records`](EdfWriter::allow_oversized_records).
+    pub oversized_record_bytes: Option<usize>,
+    
+    /// Quantization used by [`write_samples`](EdfWriter::write_samples),
+    /// see [`EdfWriter::quantization`]
+    pub quantization: Quantization,
 }
 
 impl EdfWriter {
@@ -206,6 +524,13 @@ impl EdfWriter {
     /// Opens a new file for writing and initializes the writer with default
     /// values. The file will be created (or truncated if it exists).
     /// 
+    /// **⚠️ EXISTING FILES ARE OVERWRITTEN**
+    /// 
+    /// Like `File::create`, this silently truncates any file already at
+    /// `path`. Use [`create_new`](Self::create_new) to fail instead, or
+    /// [`EdfWriterOptions`] for atomic writes that only replace the target
+    /// once the file is finalized.
+    /// 
     /// # Arguments
     /// 
     /// * `path` - Path where the EDF+ file should be created
@@ -253,20 +578,130 @@ impl EdfWriter {
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
     pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let file = File::create(&path)
-            .map_err(|e| EdfError::FileNotFound(format!("{}: {}", path.as_ref().display(), e)))?;
+        EdfWriterOptions::new().overwrite(true).create(path)
+    }
+    
+    /// Creates a new EDF+ file writer, failing if the file already exists
+    /// 
+    /// This is the safe counterpart of [`create`](Self::create) and the same
+    /// as `EdfWriterOptions::new().create(path)`.
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::FileExists` - A file already exists at `path`
+    /// * `EdfError::FileNotFound` - Cannot create file (permission issues, invalid path, etc.)
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::{EdfError, EdfWriter};
+    /// 
+    /// let writer = EdfWriter::create_new("create_new.edf")?;
+    /// # drop(writer);
+    /// 
+    /// // A second attempt does not truncate the first file
+    /// assert!(matches!(EdfWriter::create_new("create_new.edf"), Err(EdfError::FileExists(_))));
+    /// # std::fs::remove_file("create_new.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn create_new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        EdfWriterOptions::new().create(path)
+    }
+    
+    /// Creates a writer for a classic EDF file without annotation signal
+    /// 
+    /// For consumers that cannot handle EDF+. The file has no
+    /// `EDF Annotations` signal and a blank reserved field, so
+    /// [`add_annotation`](Self::add_annotation) fails, and the patient and
+    /// recording identification are free text set with
+    /// [`set_patient_text`](Self::set_patient_text) and
+    /// [`set_recording_text`](Self::set_recording_text) instead of EDF+
+    /// subfields. Like [`create`](Self::create), an existing file is
+    /// overwritten; use [`EdfWriterOptions::file_type`] for other options.
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::FileNotFound` - Cannot create file (permission issues, invalid path, etc.)
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::{EdfWriter, FileType, SignalParam};
+    /// 
+    /// let mut writer = EdfWriter::create_classic("classic.edf")?;
+    /// assert_eq!(writer.file_type(), FileType::Edf);
+    /// 
+    /// writer.set_patient_text("Doe, John  born 1961")?;
+    /// writer.set_recording_text("Legacy export, ward 3")?;
+    /// writer.add_signal(SignalParam {
+    ///     label: "EEG Fpz-Cz".to_string(),
+    ///     samples_in_file: 0,
+    ///     physical_max: 200.0, physical_min: -200.0,
+    ///     digital_max: 32767, digital_min: -32768,
+    ///     samples_per_record: 100,
+    ///     physical_dimension: "uV".to_string(),
+    ///     prefilter: "".to_string(),
+    ///     transducer: "".to_string(),
+    ///     reserved: String::new(),
+    /// })?;
+    /// 
+    /// // Classic EDF cannot store annotations
+    /// assert!(writer.add_annotation(0.0, None, "Start").is_err());
+    /// 
+    /// writer.write_samples(&[vec![0.0; 100]])?;
+    /// writer.finalize()?;
+    /// 
+    /// // One signal header, no annotation signal
+    /// assert_eq!(std::fs::metadata("classic.edf")?.len(), 2 * 256 + 200);
+    /// # std::fs::remove_file("classic.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn create_classic<P: AsRef<Path>>(path: P) -> Result<Self> {
+        EdfWriterOptions::new().overwrite(true).file_type(FileType::Edf).create(path)
+    }
+    
+    fn create_with_options(path: &Path, options: &EdfWriterOptions) -> Result<Self> {
+        if !matches!(options.file_type, FileType::Edf | FileType::EdfPlusContinuous) {
+            return Err(EdfError::UnsupportedFileType(format!(
+                "Writing {} files is not supported", options.file_type
+            )));
+        }
         
-        let writer = BufWriter::new(file);
+        if !options.overwrite && path.exists() {
+            return Err(EdfError::FileExists(path.display().to_string()));
+        }
+        
+        // 原子写入时先写到同目录下的临时文件
+        let (file_path, atomic_target) = if options.atomic {
+            let file_name = path.file_name()
+                .ok_or_else(|| EdfError::FileNotFound(format!("{}: not a file path", path.display())))?;
+            let temp_path = path.with_file_name(format!(".{}.tmp", file_name.to_string_lossy()));
+            (temp_path.clone(), Some((temp_path, path.to_path_buf(), options.overwrite)))
+        } else {
+            (path.to_path_buf(), None)
+        };
         
-        // 使用默认日期时间
-        let default_date = NaiveDate::from_ymd_opt(1985, 1, 1).unwrap();
-        let default_time = NaiveTime::from_hms_opt(0, 0, 0).unwrap();
+        let mut open_options = OpenOptions::new();
+        open_options.write(true);
+        if options.overwrite || options.atomic {
+            open_options.create(true).truncate(true);
+        } else {
+            open_options.create_new(true);
+        }
+        let file = open_options.open(&file_path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::AlreadyExists => EdfError::FileExists(file_path.display().to_string()),
+            _ => EdfError::FileNotFound(format!("{}: {}", file_path.display(), e)),
+        })?;
+        
+        let writer = BufWriter::new(file);
         
         Ok(EdfWriter {
             file: writer,
             signals: Vec::new(),
-            start_date: default_date,
-            start_time: default_time,
+            start_date: DEFAULT_START_DATETIME.date(),
+            start_time: DEFAULT_START_DATETIME.time(),
+            start_set: false,
+            clock: None,
             datarecord_duration: EDFLIB_TIME_DIMENSION, // 1秒
             samples_written: 0,
             header_written: false,
@@ -279,9 +714,34 @@ impl EdfWriter {
             technician: "X".to_string(),
             equipment: "X".to_string(),
             recording_additional: "X".to_string(),
+            reserved_main: String::new(),
+            patient_text: String::new(),
+            recording_text: String::new(),
             annotations: Vec::new(),
             starttime_subsecond: 0,
-            nr_annot_chns: 1,  // 默认1个注释通道
+            nr_annot_chns: if options.file_type.is_plus() { 1 } else { 0 },  // 默认1个注释通道，经典EDF没有
+            allow_out_of_range_annotations: false,
+            defer_annotations: false,
+            timestamp_precision: None,
+            allow_oversized_records: false,
+            oversized_record_bytes: None,
+            file_type: options.file_type,
+            narrowing_policy: NarrowingPolicy::default(),
+            digital_sources: Vec::new(),
+            narrowed_samples: 0,
+            quantization: Quantization::default(),
+            dither: None,
+            label_policy: LabelPolicy::default(),
+            sanitize: false,
+            sanitized_fields: Vec::new(),
+            profile: Profile::default(),
+            violations: Vec::new(),
+            placed_annotations: BTreeSet::new(),
+            annotation_capacity: AnnotationCapacity::default(),
+            annotation_bytes: EDFLIB_ANNOTATION_BYTES,
+            atomic_target,
+            file_path,
+            live_records: None,
         })
     }
     
@@ -327,6 +787,7 @@ impl EdfWriter {
     ///     physical_dimension: "uV".to_string(),
     ///     prefilter: "HP:0.1Hz LP:70Hz N:50Hz".to_string(),
     ///     transducer: "AgAgCl cup electrodes".to_string(),
+    ///     reserved: String::new(),
     /// };
     /// 
     /// writer.add_signal(eeg_signal)?;
@@ -353,6 +814,7 @@ impl EdfWriter {
     ///     physical_dimension: "uV".to_string(),
     ///     prefilter: "HP:0.1Hz LP:200Hz".to_string(),
     ///     transducer: "Gold cup electrodes".to_string(),
+    ///     reserved: String::new(),
     /// })?;
     /// 
     /// // Lower-frequency physiological signal
@@ -365,6 +827,7 @@ impl EdfWriter {
     ///     physical_dimension: "degC".to_string(),
     ///     prefilter: "None".to_string(),
     ///     transducer: "Thermistor".to_string(),
+    ///     reserved: String::new(),
     /// })?;
     /// 
     /// # // Cleanup (hidden from docs)
@@ -383,6 +846,7 @@ impl EdfWriter {
         if signal.digital_min == signal.digital_max {
             return Err(EdfError::DigitalMinEqualsMax);
         }
+        validate_header_text(&signal.reserved, header::SignalField::Reserved.width(), "Signal reserved field")?;
         
         self.signals.push(signal);
         Ok(())
@@ -403,7 +867,8 @@ impl EdfWriter {
     /// 
     /// # Errors
     /// 
-    /// * `EdfError::InvalidFormat` - Trying to modify after header written
+    /// * `EdfError::InvalidFormat` - Trying to modify after header written, or
+    ///   the writer produces classic EDF
     /// 
     /// # Format Requirements
     /// 
@@ -475,14 +940,193 @@ impl EdfWriter {
         if self.header_written {
             return Err(EdfError::InvalidFormat("Cannot modify patient info after writing header".to_string()));
         }
+        self.require_edf_plus("EDF+ patient subfields", "set_patient_text")?;
+        
+        // 空字符串会导致子字段错位，使用"X"占位
+        self.patient_code = placeholder_if_empty(code);
+        self.sex = placeholder_if_empty(sex);
+        self.birthdate = placeholder_if_empty(birthdate);
+        self.patient_name = placeholder_if_empty(name);
+        Ok(())
+    }
+    
+    /// Sets structured patient information for the EDF+ file
+    /// 
+    /// Unknown subfields (`None`) are written as the `X` placeholder and
+    /// spaces inside the code and name are written as underscores, so the
+    /// identification field always has well-formed subfields. This replaces
+    /// all patient subfields, including the additional information.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `info` - Patient identification, see [`PatientInfo`]
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidFormat` - Trying to modify after header written, the
+    ///   writer produces classic EDF, or the composed identification is longer
+    ///   than 80 bytes
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::{EdfWriter, PatientInfo, Sex};
+    /// 
+    /// let mut writer = EdfWriter::create("patient_struct.edf")?;
+    /// 
+    /// // Only the code and sex are known, the rest becomes "X"
+    /// writer.set_patient(&PatientInfo {
+    ///     code: Some("P001-2024".to_string()),
+    ///     sex: Some(Sex::Male),
+    ///     ..PatientInfo::default()
+    /// })?;
+    /// 
+    /// // Fields that do not fit are rejected instead of truncated
+    /// let too_long = PatientInfo {
+    ///     name: Some("A".repeat(80)),
+    ///     ..PatientInfo::default()
+    /// };
+    /// assert!(writer.set_patient(&too_long).is_err());
+    /// 
+    /// # std::fs::remove_file("patient_struct.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn set_patient(&mut self, info: &PatientInfo) -> Result<()> {
+        if self.header_written {
+            return Err(EdfError::InvalidFormat("Cannot modify patient info after writing header".to_string()));
+        }
+        self.require_edf_plus("EDF+ patient subfields", "set_patient_text")?;
+        
+        let identification = info.identification();
+        if identification.len() > 80 {
+            return Err(EdfError::InvalidFormat(format!(
+                "Patient identification is {} bytes, maximum is 80: '{}'",
+                identification.len(), identification
+            )));
+        }
         
-        self.patient_code = code.to_string();
-        self.sex = sex.to_string();
-        self.birthdate = birthdate.to_string();
-        self.patient_name = name.to_string();
+        let [code, sex, birthdate, name, additional] = info.subfields();
+        self.patient_code = code;
+        self.sex = sex;
+        self.birthdate = birthdate;
+        self.patient_name = name;
+        self.patient_additional = additional;
         Ok(())
     }
     
+    /// Sets the content of the main header's reserved field
+    /// 
+    /// The text is written after the `EDF+C` marker. It is empty by default;
+    /// set it to carry vendor-specific data over from a source file, see
+    /// [`EdfHeader::reserved_main`](crate::EdfHeader::reserved_main). The
+    /// per-signal reserved fields are set through [`SignalParam::reserved`].
+    /// 
+    /// # Arguments
+    /// 
+    /// * `reserved` - Printable ASCII text of at most 39 characters
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidFormat` - Trying to modify after header written,
+    ///   the text is too long or not printable ASCII
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::{EdfReader, EdfWriter, SignalParam};
+    /// 
+    /// # edfplus::doctest_utils::create_simple_test_file("reserved_source.edf")?;
+    /// let source = EdfReader::open("reserved_source.edf")?;
+    /// 
+    /// let mut writer = EdfWriter::create("reserved_copy.edf")?;
+    /// writer.set_reserved_main(&source.header().reserved_main)?;
+    /// for signal in &source.header().signals {
+    ///     // SignalParam::reserved is carried over with the signal
+    ///     writer.add_signal(signal.clone())?;
+    /// }
+    /// 
+    /// assert!(writer.set_reserved_main("non-ASCII: µV").is_err());
+    /// # drop(writer);
+    /// # drop(source);
+    /// # std::fs::remove_file("reserved_source.edf").ok();
+    /// # std::fs::remove_file("reserved_copy.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn set_reserved_main(&mut self, reserved: &str) -> Result<()> {
+        if self.header_written {
+            return Err(EdfError::InvalidFormat("Cannot modify reserved field after writing header".to_string()));
+        }
+        if !self.file_type.is_plus() {
+            return Err(EdfError::InvalidFormat("The reserved field of classic EDF files must be blank".to_string()));
+        }
+        
+        let available = header::RESERVED.len() - self.file_type.to_string().len();
+        validate_header_text(reserved, available, "Main reserved field")?;
+        self.reserved_main = reserved.to_string();
+        Ok(())
+    }
+    
+    /// Sets the free-text patient identification of a classic EDF file
+    /// 
+    /// The text is written verbatim into the 80-byte patient field. EDF+
+    /// files compose this field from subfields instead, see
+    /// [`set_patient`](Self::set_patient).
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidFormat` - Trying to modify after header written,
+    ///   the writer produces EDF+, or the text is longer than 80 bytes or
+    ///   not printable ASCII
+    pub fn set_patient_text(&mut self, text: &str) -> Result<()> {
+        if self.header_written {
+            return Err(EdfError::InvalidFormat("Cannot modify patient info after writing header".to_string()));
+        }
+        if self.file_type.is_plus() {
+            return Err(EdfError::InvalidFormat(
+                "Free-text patient identification is only available for classic EDF, use set_patient".to_string()
+            ));
+        }
+        
+        validate_header_text(text, header::PATIENT.len(), "Patient identification")?;
+        self.patient_text = text.to_string();
+        Ok(())
+    }
+    
+    /// Sets the free-text recording identification of a classic EDF file
+    /// 
+    /// The text is written verbatim into the 80-byte recording field. EDF+
+    /// files compose this field from the start date and recording subfields.
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidFormat` - Trying to modify after header written,
+    ///   the writer produces EDF+, or the text is longer than 80 bytes or
+    ///   not printable ASCII
+    pub fn set_recording_text(&mut self, text: &str) -> Result<()> {
+        if self.header_written {
+            return Err(EdfError::InvalidFormat("Cannot modify recording info after writing header".to_string()));
+        }
+        if self.file_type.is_plus() {
+            return Err(EdfError::InvalidFormat(
+                "Free-text recording identification is only available for classic EDF".to_string()
+            ));
+        }
+        
+        validate_header_text(text, header::RECORDING.len(), "Recording identification")?;
+        self.recording_text = text.to_string();
+        Ok(())
+    }
+    
+    /// 经典EDF不支持的功能返回错误
+    fn require_edf_plus(&self, feature: &str, alternative: &str) -> Result<()> {
+        if self.file_type.is_plus() {
+            return Ok(());
+        }
+        Err(EdfError::InvalidFormat(format!(
+            "Classic EDF files do not support {}, use {} instead", feature, alternative
+        )))
+    }
+    
     /// Sets the data record duration for the EDF+ file
     /// 
     /// The data record duration determines how long each data record represents
@@ -543,7 +1187,115 @@ impl EdfWriter {
         }
         
         // Convert seconds to EDFLIB_TIME_DIMENSION units (100 nanoseconds)
-        self.datarecord_duration = (duration_seconds * EDFLIB_TIME_DIMENSION as f64) as i64;
+        self.datarecord_duration = time::seconds_to_ticks(duration_seconds);
+        Ok(())
+    }
+
+    /// Sets the start date and time of the recording (default:
+    /// [`DEFAULT_START_DATETIME`])
+    /// 
+    /// Takes precedence over a clock set with [`set_clock`](Self::set_clock).
+    /// A fractional part of the seconds is kept as subsecond start time, see
+    /// [`set_subsecond_starttime`](Self::set_subsecond_starttime), truncated
+    /// to 100 nanoseconds.
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidFormat` - Header already written, the year is
+    ///   outside 1985-2084 (the header stores two digits), or a fractional
+    ///   second is given for classic EDF
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::{EdfReader, EdfWriter};
+    /// use chrono::NaiveDate;
+    /// 
+    /// let start = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap()
+    ///     .and_hms_milli_opt(22, 30, 5, 250).unwrap();
+    /// 
+    /// let mut writer = EdfWriter::create("start_datetime.edf")?;
+    /// writer.set_start_datetime(start)?;
+    /// # writer.add_signal(edfplus::SignalParam {
+    /// #     label: "EEG".to_string(), samples_in_file: 0,
+    /// #     physical_max: 100.0, physical_min: -100.0,
+    /// #     digital_max: 32767, digital_min: -32768,
+    /// #     samples_per_record: 10, physical_dimension: "uV".to_string(),
+    /// #     prefilter: "".to_string(), transducer: "".to_string(),
+    /// #     reserved: String::new(),
+    /// # })?;
+    /// # writer.write_samples(&[vec![0.0; 10]])?;
+    /// # writer.finalize()?;
+    /// 
+    /// let reader = EdfReader::open("start_datetime.edf")?;
+    /// assert_eq!(reader.header().start_datetime(), start);
+    /// # std::fs::remove_file("start_datetime.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn set_start_datetime(&mut self, datetime: NaiveDateTime) -> Result<()> {
+        if self.header_written {
+            return Err(EdfError::InvalidFormat("Cannot modify start date/time after writing header".to_string()));
+        }
+        if !(1985..=2084).contains(&datetime.year()) {
+            return Err(EdfError::InvalidFormat(format!(
+                "Start year must be between 1985 and 2084, got {}", datetime.year()
+            )));
+        }
+        
+        self.set_subsecond_starttime(datetime.nanosecond() as i64 / 100)?;
+        self.start_date = datetime.date();
+        self.start_time = datetime.time().with_nanosecond(0).unwrap_or(datetime.time());
+        self.start_set = true;
+        Ok(())
+    }
+    
+    /// Sets a clock that provides the start date and time
+    /// 
+    /// Without an explicit [`set_start_datetime`](Self::set_start_datetime)
+    /// the clock is called once, when the header is written, and its value
+    /// is used as if passed to `set_start_datetime`. Pass a function
+    /// returning the current local time to stamp recordings with "now", or a
+    /// fixed value in tests. Without a clock the start is
+    /// [`DEFAULT_START_DATETIME`].
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidFormat` - Header already written
+    /// 
+    /// The value of the clock is checked like in `set_start_datetime`, so
+    /// writing the header fails if it is outside the years 1985-2084.
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::{EdfReader, EdfWriter};
+    /// use chrono::NaiveDate;
+    /// 
+    /// let now = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap().and_hms_opt(9, 0, 0).unwrap();
+    /// 
+    /// let mut writer = EdfWriter::create("clock.edf")?;
+    /// writer.set_clock(move || now)?;
+    /// # writer.add_signal(edfplus::SignalParam {
+    /// #     label: "EEG".to_string(), samples_in_file: 0,
+    /// #     physical_max: 100.0, physical_min: -100.0,
+    /// #     digital_max: 32767, digital_min: -32768,
+    /// #     samples_per_record: 10, physical_dimension: "uV".to_string(),
+    /// #     prefilter: "".to_string(), transducer: "".to_string(),
+    /// #     reserved: String::new(),
+    /// # })?;
+    /// # writer.write_samples(&[vec![0.0; 10]])?;
+    /// # writer.finalize()?;
+    /// 
+    /// let reader = EdfReader::open("clock.edf")?;
+    /// assert_eq!(reader.header().start_datetime(), now);
+    /// # std::fs::remove_file("clock.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn set_clock(&mut self, clock: impl Fn() -> NaiveDateTime + Send + 'static) -> Result<()> {
+        if self.header_written {
+            return Err(EdfError::InvalidFormat("Cannot modify start date/time after writing header".to_string()));
+        }
+        self.clock = Some(Box::new(clock));
         Ok(())
     }
 
@@ -553,10 +1305,68 @@ impl EdfWriter {
             return Ok(());
         }
         
+        self.check_header_text()?;
+        self.check_timestamp_precision()?;
+        
+        if !self.start_set {
+            if let Some(clock) = self.clock.take() {
+                self.set_start_datetime(clock())?;
+            }
+        }
+        
+        if self.profile == Profile::Strict {
+            let mut violations = self.header_violations();
+            violations.extend(self.nonconformant_labels().into_iter().map(|(label, check)| {
+                let hint = check.suggestion
+                    .map(|suggestion| format!(", expected e.g. {:?}", suggestion))
+                    .unwrap_or_default();
+                Violation::new(Rule::Label, format!("Signal label {:?} does not follow the EDF+ standard texts{}", label, hint))
+            }));
+            self.violations.extend(violations);
+        } else if self.label_policy == LabelPolicy::Strict {
+            if let Some((label, check)) = self.nonconformant_labels().into_iter().next() {
+                let hint = check.suggestion
+                    .map(|suggestion| format!(", expected e.g. {:?}", suggestion))
+                    .unwrap_or_default();
+                return Err(EdfError::InvalidFormat(format!(
+                    "Signal label {:?} does not follow the EDF+ standard texts{}", label, hint
+                )));
+            }
+        }
+        
+        // Scale策略：记录原始数字范围，头部使用可存储的16位范围
+        if self.narrowing_policy == NarrowingPolicy::Scale {
+            self.digital_sources = self.signals.iter()
+                .map(|signal| (signal.digital_min, signal.digital_max))
+                .collect();
+            for signal in &mut self.signals {
+                let (min, max) = stored_digital_range(signal);
+                signal.digital_min = min;
+                signal.digital_max = max;
+            }
+        }
+        
         // 创建注释信号 - 支持多个注释通道
         let mut annotation_signals = Vec::new();
-        let annotation_bytes_per_record = EDFLIB_ANNOTATION_BYTES; // 每个注释通道120字节
-        let annotation_samples_per_record = annotation_bytes_per_record / 2; // 每样本2字节
+        if self.annotation_capacity == AnnotationCapacity::Auto {
+            self.annotation_bytes = self.auto_annotation_bytes();
+        }
+        let annotation_samples_per_record = self.annotation_bytes / 2; // 每样本2字节
+        
+        // 规范限制每个数据记录最多 61440 字节
+        let record_bytes = self.signals.iter().map(|s| s.samples_per_record as usize * 2).sum::<usize>()
+            + self.nr_annot_chns * self.annotation_bytes;
+        if record_bytes > EDF_MAX_RECORD_BYTES {
+            if self.profile == Profile::Strict {
+                self.violations.push(Violation::new(Rule::RecordSize, format!(
+                    "Data record is {} bytes, the EDF specification allows at most {}", record_bytes, EDF_MAX_RECORD_BYTES
+                )));
+            } else if !self.allow_oversized_records {
+                return Err(EdfError::RecordTooLarge { size: record_bytes, limit: EDF_MAX_RECORD_BYTES });
+            }
+            edf_warn!(size = record_bytes, limit = EDF_MAX_RECORD_BYTES, "writing data records larger than the EDF limit");
+            self.oversized_record_bytes = Some(record_bytes);
+        }
         
         for _ in 0..self.nr_annot_chns {
             annotation_signals.push(SignalParam {
@@ -570,6 +1380,7 @@ impl EdfWriter {
                 physical_dimension: "".to_string(),
                 prefilter: "".to_string(),
                 transducer: "".to_string(),
+                reserved: String::new(),
             });
         }
         
@@ -582,20 +1393,26 @@ impl EdfWriter {
         // 版本 (8字节)
         main_header[0..8].copy_from_slice(b"0       ");
         
-        // 患者信息字段 (80字节)
-        let patient_field = format!("{} {} {} {} {}", 
-            self.patient_code, self.sex, self.birthdate, self.patient_name, self.patient_additional);
-        let patient_bytes = patient_field.as_bytes();
-        let patient_len = patient_bytes.len().min(80);
-        main_header[8..8+patient_len].copy_from_slice(&patient_bytes[..patient_len]);
-        
-        // 记录信息字段 (80字节)
-        let recording_field = format!("Startdate {} {} {} {} {}", 
-            self.start_date.format("%d-%b-%Y"), self.admin_code, self.technician, 
-            self.equipment, self.recording_additional);
-        let recording_bytes = recording_field.as_bytes();
-        let recording_len = recording_bytes.len().min(80);
-        main_header[88..88+recording_len].copy_from_slice(&recording_bytes[..recording_len]);
+        if self.file_type.is_plus() {
+            // 患者信息字段 (80字节)
+            let patient_field = format!("{} {} {} {} {}", 
+                self.patient_code, self.sex, self.birthdate, self.patient_name, self.patient_additional);
+            let patient_bytes = patient_field.as_bytes();
+            let patient_len = patient_bytes.len().min(80);
+            main_header[8..8+patient_len].copy_from_slice(&patient_bytes[..patient_len]);
+            
+            // 记录信息字段 (80字节)
+            let recording_field = format!("Startdate {} {} {} {} {}", 
+                self.start_date.format("%d-%b-%Y"), self.admin_code, self.technician, 
+                self.equipment, self.recording_additional);
+            let recording_bytes = recording_field.as_bytes();
+            let recording_len = recording_bytes.len().min(80);
+            main_header[88..88+recording_len].copy_from_slice(&recording_bytes[..recording_len]);
+        } else {
+            // 经典EDF：自由文本原样写入，空格填充
+            main_header[header::PATIENT].copy_from_slice(&space_padded(&self.patient_text, header::PATIENT.len()));
+            main_header[header::RECORDING].copy_from_slice(&space_padded(&self.recording_text, header::RECORDING.len()));
+        }
         
         // 开始日期 (8字节) "dd.mm.yy"
         let date_str = format!("{:02}.{:02}.{:02}", 
@@ -611,16 +1428,23 @@ impl EdfWriter {
         let header_size_str = format!("{:<8}", header_size);
         main_header[184..192].copy_from_slice(header_size_str.as_bytes());
         
-        // EDF+标识 (44字节)
-        main_header[192..197].copy_from_slice(b"EDF+C");
+        // EDF+标识 (44字节)，经典EDF为空白
+        if self.file_type.is_plus() {
+            let file_type_tag = self.file_type.to_string();
+            main_header[192..192 + file_type_tag.len()].copy_from_slice(file_type_tag.as_bytes());
+            let reserved_start = 192 + file_type_tag.len();
+            main_header[reserved_start..reserved_start + self.reserved_main.len()]
+                .copy_from_slice(self.reserved_main.as_bytes());
+        } else {
+            main_header[header::RESERVED].fill(b' ');
+        }
         
         // 数据记录数 (8字节)
         let datarecords_str = format!("{:<8}", total_datarecords);
         main_header[236..244].copy_from_slice(datarecords_str.as_bytes());
         
         // 数据记录持续时间 (8字节)
-        let duration_seconds = self.datarecord_duration as f64 / EDFLIB_TIME_DIMENSION as f64;
-        let duration_str = format!("{:<8}", duration_seconds);
+        let duration_str = format!("{:<8}", time::format_seconds(self.datarecord_duration));
         main_header[244..252].copy_from_slice(duration_str.as_bytes());
         
         // 信号数 (4字节)
@@ -693,6 +1517,7 @@ impl EdfWriter {
     ///     physical_dimension: "uV".to_string(),
     ///     prefilter: "HP:0.1Hz LP:70Hz".to_string(),
     ///     transducer: "AgAgCl electrodes".to_string(),
+    ///     reserved: String::new(),
     /// })?;
     /// 
     /// writer.add_signal(SignalParam {
@@ -704,6 +1529,7 @@ impl EdfWriter {
     ///     physical_dimension: "mV".to_string(),
     ///     prefilter: "HP:0.1Hz LP:100Hz".to_string(),
     ///     transducer: "Chest electrodes".to_string(),
+    ///     reserved: String::new(),
     /// })?;
     /// 
     /// // Generate sample data (256 samples for each signal)
@@ -742,6 +1568,7 @@ impl EdfWriter {
     ///     physical_dimension: "uV".to_string(),
     ///     prefilter: "HP:0.1Hz LP:70Hz".to_string(),
     ///     transducer: "AgAgCl electrodes".to_string(),
+    ///     reserved: String::new(),
     /// })?;
     /// 
     /// // Write 10 seconds of continuous data (10 data records)
@@ -783,6 +1610,7 @@ impl EdfWriter {
     ///     physical_dimension: "uV".to_string(),
     ///     prefilter: "HP:0.1Hz LP:200Hz".to_string(),
     ///     transducer: "Gold cup electrodes".to_string(),
+    ///     reserved: String::new(),
     /// })?;
     /// 
     /// // Lower-frequency physiological signal
@@ -795,6 +1623,7 @@ impl EdfWriter {
     ///     physical_dimension: "arbitrary".to_string(),
     ///     prefilter: "LP:10Hz".to_string(),
     ///     transducer: "Strain gauge".to_string(),
+    ///     reserved: String::new(),
     /// })?;
     /// 
     /// // Write 5 seconds of data
@@ -826,20 +1655,7 @@ impl EdfWriter {
     /// # Ok::<(), edfplus::EdfError>(())
     /// ```
     pub fn write_samples(&mut self, samples: &[Vec<f64>]) -> Result<()> {
-        if samples.len() != self.signals.len() {
-            return Err(EdfError::InvalidFormat("Sample count must match signal count".to_string()));
-        }
-        
-        // 验证每个信号的样本数
-        for (i, signal_samples) in samples.iter().enumerate() {
-            let expected_samples = self.signals[i].samples_per_record as usize;
-            if signal_samples.len() != expected_samples {
-                return Err(EdfError::InvalidFormat(
-                    format!("Signal {} expected {} samples per record, got {}", 
-                           i, expected_samples, signal_samples.len())
-                ));
-            }
-        }
+        self.check_record_shape(samples.iter().map(Vec::len))?;
         
         // 如果还没写头部，先写头部
         if !self.header_written {
@@ -849,12 +1665,14 @@ impl EdfWriter {
         // 按照edflib的顺序写入数据：信号数据 + 注释信号
     
         // 写入所有信号的样本数据
-        for signal_idx in 0..self.signals.len() {
-            let signal = &self.signals[signal_idx];
-            let signal_samples = &samples[signal_idx];
-            
+        for (signal, signal_samples) in self.signals.iter().zip(samples) {
+
             for &physical_value in signal_samples {
-                let digital_value = signal.to_digital(physical_value);
+                let mut digital = (physical_value - signal.offset()) / signal.scale();
+                if let Some(dither) = &mut self.dither {
+                    digital += dither.next_tpdf();
+                }
+                let digital_value = digital.round_ties_even() as i32;
                 
                 // 应用范围限制
                 let clamped_value = digital_value
@@ -867,25 +1685,674 @@ impl EdfWriter {
             }
         }
         
+        self.finish_record()
+    }
+    
+    /// Writes one data record of digital values given as `i32`
+    /// 
+    /// Values are narrowed to each signal's stored 16-bit digital range
+    /// according to the writer's [`NarrowingPolicy`] (see
+    /// [`set_narrowing_policy`](Self::set_narrowing_policy)). The number of
+    /// changed samples is reported in [`WriteSummary::narrowed_samples`].
+    /// 
+    /// # Arguments
+    /// 
+    /// * `samples` - One vector of digital values per signal, each with
+    ///   exactly `samples_per_record` values
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidFormat` - Wrong number of signals or samples
+    /// * `EdfError::DigitalValueOutOfRange` - A value does not fit and the
+    ///   policy is [`NarrowingPolicy::Reject`]; nothing is written
+    /// * `EdfError::Io` - I/O error writing to file
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::{EdfWriter, NarrowingPolicy, SignalParam};
+    /// 
+    /// let mut writer = EdfWriter::create("digital_i32.edf")?;
+    /// writer.add_signal(SignalParam {
+    ///     label: "EEG".to_string(),
+    ///     samples_in_file: 0,
+    ///     physical_max: 200.0, physical_min: -200.0,
+    ///     digital_max: 32767, digital_min: -32768,
+    ///     samples_per_record: 4,
+    ///     physical_dimension: "uV".to_string(),
+    ///     prefilter: "".to_string(),
+    ///     transducer: "".to_string(),
+    ///     reserved: String::new(),
+    /// })?;
+    /// writer.set_narrowing_policy(NarrowingPolicy::Saturate)?;
+    /// 
+    /// writer.write_digital_samples(&[vec![0, 100, 40_000, -40_000]])?;
+    /// 
+    /// let summary = writer.finalize()?;
+    /// assert_eq!(summary.narrowed_samples, 2);
+    /// # std::fs::remove_file("digital_i32.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn write_digital_samples(&mut self, samples: &[Vec<i32>]) -> Result<()> {
+        self.check_record_shape(samples.iter().map(Vec::len))?;
+        
+        // Reject策略下先检查所有值，避免写入不完整的记录
+        if self.narrowing_policy == NarrowingPolicy::Reject {
+            for (signal_idx, (signal, signal_samples)) in self.signals.iter().zip(samples).enumerate() {
+                let (min, max) = stored_digital_range(signal);
+                if let Some(index) = signal_samples.iter().position(|&v| v < min || v > max) {
+                    return Err(EdfError::DigitalValueOutOfRange {
+                        signal: signal_idx,
+                        index,
+                        value: signal_samples[index],
+                        min,
+                        max,
+                    });
+                }
+            }
+        }
+        
+        if !self.header_written {
+            self.write_header(1)?;
+        }
+        
+        for (signal_idx, signal_samples) in samples.iter().enumerate() {
+            let (min, max) = stored_digital_range(&self.signals[signal_idx]);
+            let source = self.digital_sources.get(signal_idx).copied();
+            
+            for &value in signal_samples {
+                let stored = match (self.narrowing_policy, source) {
+                    (NarrowingPolicy::Scale, Some((source_min, source_max))) => {
+                        // 在i128中计算线性映射并四舍五入
+                        let clamped = value.max(source_min).min(source_max) as i128;
+                        let numerator = (clamped - source_min as i128) * (max - min) as i128;
+                        let span = source_max as i128 - source_min as i128;
+                        min + ((2 * numerator + span) / (2 * span)) as i32
+                    }
+                    _ => value.max(min).min(max),
+                };
+                if stored != value {
+                    self.narrowed_samples += 1;
+                }
+                self.file.write_all(&(stored as i16).to_le_bytes())?;
+            }
+        }
+        
+        self.finish_record()
+    }
+    
+    /// Sets how [`write_digital_samples`](Self::write_digital_samples) narrows values
+    /// 
+    /// The default is [`NarrowingPolicy::Reject`].
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidFormat` - Switching to or from
+    ///   [`NarrowingPolicy::Scale`] after the header has been written, since
+    ///   it changes the signals' calibration
+    pub fn set_narrowing_policy(&mut self, policy: NarrowingPolicy) -> Result<()> {
+        let involves_scale = policy == NarrowingPolicy::Scale || self.narrowing_policy == NarrowingPolicy::Scale;
+        if self.header_written && involves_scale && policy != self.narrowing_policy {
+            return Err(EdfError::InvalidFormat(
+                "Cannot change the Scale narrowing policy after writing header".to_string()
+            ));
+        }
+        
+        self.narrowing_policy = policy;
+        Ok(())
+    }
+    
+    /// Sets how [`write_samples`](Self::write_samples) rounds physical values
+    /// 
+    /// The default is [`Quantization::Round`]. The mode is reported in
+    /// [`WriteSummary::quantization`].
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidFormat` - The header has already been written
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::{EdfWriter, Quantization, SignalParam};
+    /// 
+    /// let mut writer = EdfWriter::create("dithered.edf")?;
+    /// writer.quantization(Quantization::Dithered { seed: 42 })?;
+    /// # writer.add_signal(SignalParam {
+    /// #     label: "EEG Fpz-Cz".to_string(), samples_in_file: 0,
+    /// #     physical_max: 100.0, physical_min: -100.0,
+    /// #     digital_max: 32767, digital_min: -32768,
+    /// #     samples_per_record: 10, physical_dimension: "uV".to_string(),
+    /// #     prefilter: "".to_string(), transducer: "".to_string(),
+    /// #     reserved: String::new(),
+    /// # })?;
+    /// writer.write_samples(&[vec![0.001; 10]])?;
+    /// 
+    /// let summary = writer.finalize()?;
+    /// assert_eq!(summary.quantization, Quantization::Dithered { seed: 42 });
+    /// # std::fs::remove_file("dithered.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn quantization(&mut self, quantization: Quantization) -> Result<()> {
+        if self.header_written {
+            return Err(EdfError::InvalidFormat("Cannot change quantization after writing header".to_string()));
+        }
+        
+        self.dither = match quantization {
+            Quantization::Round => None,
+            Quantization::Dithered { seed } => Some(Dither { state: seed }),
+        };
+        self.quantization = quantization;
+        Ok(())
+    }
+    
+    /// Checks signal labels against the EDF+ standard texts
+    /// 
+    /// See [`labels::check`](crate::labels::check). The default is
+    /// [`LabelPolicy::Off`]. Under [`LabelPolicy::Standard`] nonconformant
+    /// labels are reported in
+    /// [`WriteSummary::nonconformant_labels`]; under [`LabelPolicy::Strict`]
+    /// writing the header fails.
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::{EdfWriter, SignalParam};
+    /// use edfplus::labels::LabelPolicy;
+    /// 
+    /// let mut writer = EdfWriter::create("label_check.edf")?;
+    /// writer.validate_labels(LabelPolicy::Standard);
+    /// # let signal = |label: &str| SignalParam {
+    /// #     label: label.to_string(), samples_in_file: 0,
+    /// #     physical_max: 100.0, physical_min: -100.0,
+    /// #     digital_max: 32767, digital_min: -32768,
+    /// #     samples_per_record: 10, physical_dimension: "uV".to_string(),
+    /// #     prefilter: "".to_string(), transducer: "".to_string(),
+    /// #     reserved: String::new(),
+    /// # };
+    /// writer.add_signal(signal("EEG Fpz-Cz"))?;
+    /// writer.add_signal(signal("EEG FP1-CZ"))?;
+    /// writer.write_samples(&[vec![0.0; 10], vec![0.0; 10]])?;
+    /// 
+    /// let summary = writer.finalize()?;
+    /// assert_eq!(summary.nonconformant_labels.len(), 1);
+    /// let (label, check) = &summary.nonconformant_labels[0];
+    /// assert_eq!(label, "EEG FP1-CZ");
+    /// assert_eq!(check.suggestion.as_deref(), Some("EEG Fp1-Cz"));
+    /// # std::fs::remove_file("label_check.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn validate_labels(&mut self, policy: LabelPolicy) {
+        self.label_policy = policy;
+    }
+    
+    /// Sets the space reserved for annotations in every data record
+    /// 
+    /// The space is per annotation signal and fixed once the header is
+    /// written. Annotations that do not fit into their data record are not
+    /// written, so files with many annotations per record need more than the
+    /// default [`AnnotationCapacity::Bytes(120)`](AnnotationCapacity::Bytes).
+    /// [`AnnotationCapacity::Auto`] picks the size from the annotations
+    /// queued before the first record is written.
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidFormat` - Header already written, or fewer than
+    ///   32 bytes requested
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::{AnnotationCapacity, EdfReader, EdfWriter};
+    /// 
+    /// let mut writer = EdfWriter::create("auto_capacity.edf")?;
+    /// writer.annotation_capacity(AnnotationCapacity::Auto)?;
+    /// # writer.add_signal(edfplus::SignalParam {
+    /// #     label: "EEG Fpz-Cz".to_string(), samples_in_file: 0,
+    /// #     physical_max: 100.0, physical_min: -100.0,
+    /// #     digital_max: 32767, digital_min: -32768,
+    /// #     samples_per_record: 10, physical_dimension: "uV".to_string(),
+    /// #     prefilter: "".to_string(), transducer: "".to_string(),
+    /// #     reserved: String::new(),
+    /// # })?;
+    /// 
+    /// // Queue everything first: 20 spikes within the first second
+    /// for i in 0..20 {
+    ///     writer.add_annotation(i as f64 * 0.05, None, "Spike")?;
+    /// }
+    /// writer.write_samples(&[vec![0.0; 10]])?;
+    /// writer.finalize()?;
+    /// 
+    /// let reader = EdfReader::open("auto_capacity.edf")?;
+    /// assert_eq!(reader.annotations().len(), 20);
+    /// # std::fs::remove_file("auto_capacity.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn annotation_capacity(&mut self, capacity: AnnotationCapacity) -> Result<()> {
+        if self.header_written {
+            return Err(EdfError::InvalidFormat("Cannot modify annotation capacity after writing header".to_string()));
+        }
+        
+        if let AnnotationCapacity::Bytes(bytes) = capacity {
+            if bytes < TIMEKEEPING_TAL_BYTES {
+                return Err(EdfError::InvalidFormat(format!(
+                    "Annotation capacity must be at least {} bytes, got {}", TIMEKEEPING_TAL_BYTES, bytes
+                )));
+            }
+            self.annotation_bytes = bytes.next_multiple_of(2);
+        }
+        self.annotation_capacity = capacity;
+        Ok(())
+    }
+    
+    /// 根据已添加的注释计算每个注释通道所需的字节数
+    fn auto_annotation_bytes(&self) -> usize {
+        // (记录, 通道) -> TAL字节数，分配方式与 build_tal_for_channel 一致
+        let mut needed: BTreeMap<(i64, usize), usize> = BTreeMap::new();
+        for (annot_idx, annotation) in self.annotations.iter().enumerate() {
+            let record = annotation.onset / self.datarecord_duration;
+            let channel = annot_idx % self.nr_annot_chns.max(1);
+            
+            let mut bytes = 1 + time::format_seconds(annotation.onset + self.starttime_subsecond).len() + 1;
+            if let Some(duration) = annotation.duration {
+                bytes += 1 + time::format_seconds(duration).len();
+            }
+            bytes += annotation.description.len().min(EDFLIB_WRITE_MAX_ANNOTATION_LEN) + 1;
+            *needed.entry((record, channel)).or_default() += bytes;
+        }
+        
+        let largest = needed.iter()
+            .map(|(&(_, channel), &bytes)| if channel == 0 { bytes + TIMEKEEPING_TAL_BYTES } else { bytes })
+            .max()
+            .unwrap_or(0)
+            .max(TIMEKEEPING_TAL_BYTES);
+        // 预留四分之一余量以及结束符
+        (largest + largest / 4 + 2).next_multiple_of(2)
+    }
+    
+    /// Replaces non-printable characters in header fields instead of failing
+    /// 
+    /// The header may only contain printable ASCII (`0x20..=0x7e`). When the
+    /// header is written every header-bound text is checked: the signal
+    /// labels, transducers, physical dimensions, prefilters and reserved
+    /// fields, the patient and recording subfields after spaces have been
+    /// encoded as underscores, and the free-text fields of classic EDF. By
+    /// default a field with any other character, such as a newline or an
+    /// accented letter, fails with `EdfError::InvalidFormat`. With
+    /// `sanitize(true)` each such character is replaced by `_` and the field
+    /// is listed in [`WriteSummary::sanitized_fields`].
+    /// 
+    /// Annotation descriptions are not affected, they may contain UTF-8.
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::{EdfReader, EdfWriter, PatientInfo};
+    /// 
+    /// let mut writer = EdfWriter::create("sanitized.edf")?;
+    /// writer.sanitize(true);
+    /// writer.set_patient(&PatientInfo {
+    ///     name: Some("Müller".to_string()),
+    ///     ..PatientInfo::default()
+    /// })?;
+    /// # writer.add_signal(edfplus::SignalParam {
+    /// #     label: "EEG Fpz-Cz".to_string(), samples_in_file: 0,
+    /// #     physical_max: 100.0, physical_min: -100.0,
+    /// #     digital_max: 32767, digital_min: -32768,
+    /// #     samples_per_record: 10, physical_dimension: "uV".to_string(),
+    /// #     prefilter: "".to_string(), transducer: "".to_string(),
+    /// #     reserved: String::new(),
+    /// # })?;
+    /// writer.write_samples(&[vec![0.0; 10]])?;
+    /// 
+    /// let summary = writer.finalize()?;
+    /// assert_eq!(summary.sanitized_fields, vec!["Patient name".to_string()]);
+    /// 
+    /// let reader = EdfReader::open("sanitized.edf")?;
+    /// assert_eq!(reader.header().patient_name, "M_ller");
+    /// # std::fs::remove_file("sanitized.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn sanitize(&mut self, sanitize: bool) {
+        self.sanitize = sanitize;
+    }
+    
+    /// 检查所有写入头部的文本，按 sanitize 设置替换或报错
+    fn check_header_text(&mut self) -> Result<()> {
+        let mut fields: Vec<(String, &mut String)> = if self.file_type.is_plus() {
+            vec![
+                ("Patient code".to_string(), &mut self.patient_code),
+                ("Patient sex".to_string(), &mut self.sex),
+                ("Patient birthdate".to_string(), &mut self.birthdate),
+                ("Patient name".to_string(), &mut self.patient_name),
+                ("Patient additional information".to_string(), &mut self.patient_additional),
+                ("Hospital administration code".to_string(), &mut self.admin_code),
+                ("Technician".to_string(), &mut self.technician),
+                ("Equipment".to_string(), &mut self.equipment),
+                ("Recording additional information".to_string(), &mut self.recording_additional),
+                ("Main reserved field".to_string(), &mut self.reserved_main),
+            ]
+        } else {
+            vec![
+                ("Patient identification".to_string(), &mut self.patient_text),
+                ("Recording identification".to_string(), &mut self.recording_text),
+            ]
+        };
+        for (i, signal) in self.signals.iter_mut().enumerate() {
+            fields.push((format!("Signal {} label", i), &mut signal.label));
+            fields.push((format!("Signal {} transducer", i), &mut signal.transducer));
+            fields.push((format!("Signal {} physical dimension", i), &mut signal.physical_dimension));
+            fields.push((format!("Signal {} prefilter", i), &mut signal.prefilter));
+            fields.push((format!("Signal {} reserved field", i), &mut signal.reserved));
+        }
+        
+        let mut sanitized = Vec::new();
+        for (field, value) in fields {
+            if value.bytes().all(is_printable) {
+                continue;
+            }
+            if !self.sanitize {
+                if self.profile == Profile::Strict {
+                    self.violations.push(Violation::new(
+                        Rule::Charset, format!("{} must be printable ASCII: {:?}", field, value)
+                    ));
+                    continue;
+                }
+                return Err(EdfError::InvalidFormat(format!(
+                    "{} must be printable ASCII: {:?}", field, value
+                )));
+            }
+            *value = value.chars()
+                .map(|c| if c.is_ascii() && is_printable(c as u8) { c } else { SANITIZE_REPLACEMENT })
+                .collect();
+            edf_warn!(field = %field, value = %value, "replaced non-printable characters in header field");
+            sanitized.push(field);
+        }
+        self.sanitized_fields = sanitized;
+        Ok(())
+    }
+    
+    /// Selects the validations applied to the file (default:
+    /// [`Profile::Relaxed`])
+    /// 
+    /// [`Profile::Strict`] checks field lengths, printable ASCII, the
+    /// patient subfield formats, signal labels, the data record size and the
+    /// placement of annotations, regardless of the individual settings such
+    /// as [`validate_labels`](Self::validate_labels) or
+    /// [`allow_oversized_records`](Self::allow_oversized_records). Writing
+    /// continues when a check fails; [`finalize`](Self::finalize) then
+    /// reports all violations together. See the
+    /// [`compliance`](crate::compliance) module for the rules.
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidFormat` - Header already written
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::{EdfError, EdfWriter, Profile};
+    /// 
+    /// let mut writer = EdfWriter::create("strict.edf")?;
+    /// writer.compliance_profile(Profile::Strict)?;
+    /// writer.set_patient_info("P001", "male", "X", "X")?;
+    /// # writer.add_signal(edfplus::SignalParam {
+    /// #     label: "EEG Fpz-Cz".to_string(), samples_in_file: 0,
+    /// #     physical_max: 100.0, physical_min: -100.0,
+    /// #     digital_max: 32767, digital_min: -32768,
+    /// #     samples_per_record: 10, physical_dimension: "uV".to_string(),
+    /// #     prefilter: "".to_string(), transducer: "".to_string(),
+    /// #     reserved: String::new(),
+    /// # })?;
+    /// writer.write_samples(&[vec![0.0; 10]])?;
+    /// 
+    /// match writer.finalize() {
+    ///     Err(EdfError::ComplianceViolations(violations)) => {
+    ///         for violation in &violations {
+    ///             println!("{}", violation);
+    ///         }
+    ///         # assert_eq!(violations.len(), 1);
+    ///     }
+    ///     _ => unreachable!(),
+    /// }
+    /// # std::fs::remove_file("strict.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn compliance_profile(&mut self, profile: Profile) -> Result<()> {
+        if self.header_written {
+            return Err(EdfError::InvalidFormat("Cannot modify compliance profile after writing header".to_string()));
+        }
+        self.profile = profile;
+        Ok(())
+    }
+    
+    /// Strict 模式下头部字段的长度和患者子字段格式
+    fn header_violations(&self) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        let mut check_length = |field: String, value: &str, width: usize| {
+            if value.len() > width {
+                violations.push(Violation::new(Rule::Length, format!(
+                    "{} is {} bytes, maximum is {}: {:?}", field, value.len(), width, value
+                )));
+            }
+        };
+        
+        if self.file_type.is_plus() {
+            let patient = format!("{} {} {} {} {}",
+                self.patient_code, self.sex, self.birthdate, self.patient_name, self.patient_additional);
+            check_length("Patient identification".to_string(), &patient, header::PATIENT.len());
+            let recording = format!("Startdate {} {} {} {} {}",
+                self.start_date.format("%d-%b-%Y"), self.admin_code, self.technician,
+                self.equipment, self.recording_additional);
+            check_length("Recording identification".to_string(), &recording, header::RECORDING.len());
+        }
+        for (i, signal) in self.signals.iter().enumerate() {
+            check_length(format!("Signal {} label", i), &signal.label, header::SignalField::Label.width());
+            check_length(format!("Signal {} transducer", i), &signal.transducer, header::SignalField::Transducer.width());
+            check_length(format!("Signal {} physical dimension", i), &signal.physical_dimension,
+                header::SignalField::PhysicalDimension.width());
+            check_length(format!("Signal {} prefilter", i), &signal.prefilter, header::SignalField::Prefilter.width());
+            check_length(format!("Signal {} physical minimum", i), &signal.physical_min.to_string(), 8);
+            check_length(format!("Signal {} physical maximum", i), &signal.physical_max.to_string(), 8);
+        }
+        
+        if self.file_type.is_plus() {
+            if !matches!(self.sex.as_str(), "M" | "F" | "X") {
+                violations.push(Violation::new(Rule::PatientFormat, format!(
+                    "Patient sex must be M, F or X: {:?}", self.sex
+                )));
+            }
+            // 出生日期为 "X" 或大写的 dd-MMM-yyyy
+            let birthdate_valid = self.birthdate == "X"
+                || (self.birthdate == self.birthdate.to_uppercase()
+                    && NaiveDate::parse_from_str(&self.birthdate, "%d-%b-%Y").is_ok());
+            if !birthdate_valid {
+                violations.push(Violation::new(Rule::PatientFormat, format!(
+                    "Patient birthdate must be dd-MMM-yyyy or X: {:?}", self.birthdate
+                )));
+            }
+            for (field, value) in [("Patient code", &self.patient_code), ("Patient name", &self.patient_name)] {
+                if value.contains(' ') {
+                    violations.push(Violation::new(Rule::PatientFormat, format!(
+                        "{} must not contain spaces, use underscores: {:?}", field, value
+                    )));
+                }
+            }
+        }
+        violations
+    }
+    
+    /// Strict 模式下注释的位置和描述长度
+    fn annotation_violations(&self, duration: i64) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        for (i, annotation) in self.annotations.iter().enumerate() {
+            let at = format!("'{}' at {}s", annotation.description, time::format_seconds(annotation.onset));
+            let ends_late = annotation.duration.is_some_and(|d| d > 0 && annotation.onset + d > duration);
+            if annotation.onset >= duration || ends_late {
+                violations.push(Violation::new(Rule::AnnotationPlacement, format!(
+                    "Annotation {} is outside the recorded duration of {}s", at, time::format_seconds(duration)
+                )));
+            } else if !self.placed_annotations.contains(&i) {
+                violations.push(Violation::new(Rule::AnnotationPlacement, format!(
+                    "Annotation {} is not stored in its data record", at
+                )));
+            }
+            if annotation.description.len() > EDFLIB_WRITE_MAX_ANNOTATION_LEN {
+                violations.push(Violation::new(Rule::Length, format!(
+                    "Annotation {} is {} bytes, maximum is {}",
+                    at, annotation.description.len(), EDFLIB_WRITE_MAX_ANNOTATION_LEN
+                )));
+            }
+        }
+        violations
+    }
+    
+    /// 不符合标准文本的信号标签
+    fn nonconformant_labels(&self) -> Vec<(String, LabelCheck)> {
+        self.signals.iter()
+            .map(|signal| (signal.label.clone(), labels::check(&signal.label)))
+            .filter(|(_, check)| !check.conformant)
+            .collect()
+    }
+    
+    /// 验证每个信号的样本数与 samples_per_record 一致
+    fn check_record_shape(&self, lengths: impl ExactSizeIterator<Item = usize>) -> Result<()> {
+        if lengths.len() != self.signals.len() {
+            return Err(EdfError::InvalidFormat("Sample count must match signal count".to_string()));
+        }
+        
+        // 验证每个信号的样本数
+        for (i, length) in lengths.enumerate() {
+            let expected_samples = self.signals[i].samples_per_record as usize;
+            if length != expected_samples {
+                return Err(EdfError::InvalidFormat(
+                    format!("Signal {} expected {} samples per record, got {}", 
+                           i, expected_samples, length)
+                ));
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// 写入当前记录的注释信号并完成该记录
+    fn finish_record(&mut self) -> Result<()> {
         // 写入注释信号的TAL数据 - 支持多个注释通道
         for channel_idx in 0..self.nr_annot_chns {
-            let annotation_data = self.generate_annotation_tal_for_channel(self.samples_written, channel_idx)?;
+            let (annotation_data, written) = self.build_tal_for_channel(self.samples_written, channel_idx, false);
             self.file.write_all(&annotation_data)?;
+            if self.profile == Profile::Strict {
+                self.placed_annotations.extend(written);
+            }
         }
         
         self.samples_written += 1;
+        
+        // 只有完全写入文件的记录才对 reader_view 可见
+        if let Some(records) = &self.live_records {
+            self.file.flush()?;
+            records.store(self.samples_written, Ordering::Release);
+        }
         Ok(())
     }
     
+    /// Returns a read-only view of the data records written so far
+    /// 
+    /// The view reads the file through its own handle and shares the
+    /// writer's count of complete data records instead of trusting the
+    /// header on disk, see the [`live`](crate::live) module. From now on
+    /// every data record is flushed before it becomes visible, which costs
+    /// one write call per record; the view never blocks the writer. Several
+    /// views can be created, also from other threads.
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidFormat` - The header is not written yet; write
+    ///   the first data record before creating a view
+    /// * `EdfError::FileNotFound` - The file cannot be opened for reading
+    /// * `EdfError::Io` - Flushing the written records failed
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::{EdfWriter, SignalParam};
+    /// 
+    /// let mut writer = EdfWriter::create("reader_view.edf")?;
+    /// # writer.add_signal(SignalParam {
+    /// #     label: "EEG Fpz-Cz".to_string(), samples_in_file: 0,
+    /// #     physical_max: 100.0, physical_min: -100.0,
+    /// #     digital_max: 32767, digital_min: -32768,
+    /// #     samples_per_record: 10, physical_dimension: "uV".to_string(),
+    /// #     prefilter: "".to_string(), transducer: "".to_string(),
+    /// #     reserved: String::new(),
+    /// # })?;
+    /// assert!(writer.reader_view().is_err());
+    /// 
+    /// writer.write_samples(&[vec![1.0; 10]])?;
+    /// let mut view = writer.reader_view()?;
+    /// assert_eq!(view.samples_available(0)?, 10);
+    /// assert_eq!(view.read_physical_samples(0, 10)?.len(), 10);
+    /// # drop(view);
+    /// # writer.finalize()?;
+    /// # std::fs::remove_file("reader_view.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn reader_view(&mut self) -> Result<EdfLiveView> {
+        if !self.header_written {
+            return Err(EdfError::InvalidFormat(
+                "Cannot create a reader view before the header is written".to_string()
+            ));
+        }
+        
+        let records = match &self.live_records {
+            Some(records) => Arc::clone(records),
+            None => {
+                self.file.flush()?;
+                let records = Arc::new(AtomicUsize::new(self.samples_written));
+                self.live_records = Some(Arc::clone(&records));
+                records
+            }
+        };
+        
+        let data_bytes: usize = self.signals.iter().map(|s| s.samples_per_record as usize * 2).sum();
+        EdfLiveView::new(
+            &self.file_path,
+            self.signals.clone(),
+            self.datarecord_duration,
+            (self.signals.len() + self.nr_annot_chns + 1) * 256,
+            data_bytes + self.nr_annot_chns * self.annotation_bytes,
+            records,
+        )
+    }
+    
     /// Finalizes the EDF+ file and closes it
     /// 
     /// This method must be called to complete the file writing process.
     /// It flushes any remaining data to disk and properly closes the file.
     /// After calling this method, the writer is consumed and cannot be used again.
     /// 
+    /// Every annotation is checked against the recorded duration: both its
+    /// onset and its end (onset + duration) must lie within the written data
+    /// records. By default any violation is reported as an error; with
+    /// [`allow_out_of_range_annotations`](Self::allow_out_of_range_annotations)
+    /// the annotations are kept and listed in the returned [`WriteSummary`].
+    /// 
+    /// # Returns
+    /// 
+    /// A [`WriteSummary`] describing what was written.
+    /// 
     /// # Errors
     /// 
-    /// * `EdfError::FileWriteError` - I/O error during file finalization
+    /// * `EdfError::Io` - I/O error during file finalization
+    /// * `EdfError::AnnotationsOutOfRange` - Annotations outside the recorded
+    ///   duration. The file itself is still completed; annotations that start
+    ///   after the last data record are not written to it. For atomic writes
+    ///   the target is left untouched instead.
+    /// * `EdfError::ComplianceViolations` - The writer uses
+    ///   [`Profile::Strict`] and the file breaks one of its rules, see
+    ///   [`compliance_profile`](Self::compliance_profile). The file is
+    ///   completed like above, except for atomic writes, where the target is
+    ///   left untouched.
     /// 
     /// # File Integrity
     /// 
@@ -913,6 +2380,7 @@ impl EdfWriter {
     ///     physical_dimension: "V".to_string(),
     ///     prefilter: "None".to_string(),
     ///     transducer: "Test".to_string(),
+    ///     reserved: String::new(),
     /// })?;
     /// 
     /// let samples = vec![0.1, 0.2, 0.3, 0.4, 0.5, -0.1, -0.2, -0.3, -0.4, -0.5];
@@ -936,7 +2404,7 @@ impl EdfWriter {
     ///     // ... add signals and write data ...
     ///     
     ///     match writer.finalize() {
-    ///         Ok(()) => println!("File successfully completed"),
+    ///         Ok(summary) => println!("File successfully completed: {} records", summary.datarecords),
     ///         Err(e) => {
     ///             eprintln!("Error finalizing file: {}", e);
     ///             // File may be corrupted
@@ -950,28 +2418,125 @@ impl EdfWriter {
     /// 
     /// # test_finalize().unwrap();
     /// ```
-    pub fn finalize(mut self) -> Result<()> {
+    pub fn finalize(mut self) -> Result<WriteSummary> {
+        use std::io::{Seek, SeekFrom};
+        
+        let duration = self.samples_written as i64 * self.datarecord_duration;
+        
+        // 找出超出已写入时长的注释：开始于最后一个记录之后，或结束于其之后
+        let pending: Vec<usize> = (0..self.annotations.len())
+            .filter(|&i| self.annotations[i].onset >= duration)
+            .collect();
+        let out_of_range: Vec<crate::types::Annotation> = self.annotations.iter()
+            .filter(|a| a.onset >= duration || a.duration.is_some_and(|d| d > 0 && a.onset + d > duration))
+            .cloned()
+            .collect();
+        
+        // 刷新缓冲区以确保所有数据都写入了
+        self.file.flush()?;
+        
+        let mut dropped = Vec::new();
+        if self.defer_annotations {
+            if self.samples_written > 0 {
+                dropped = self.write_deferred_annotations()?;
+            } else if self.allow_out_of_range_annotations {
+                dropped = self.annotations.clone();
+            }
+        } else if self.allow_out_of_range_annotations && !pending.is_empty() {
+            if self.samples_written == 0 {
+                dropped.extend(pending.iter().map(|&i| self.annotations[i].clone()));
+            } else {
+                // 将尚未写入的注释放入最后一个数据记录，重写其TAL数据
+                let last_record = self.samples_written - 1;
+                let data_bytes: usize = self.signals.iter().map(|s| s.samples_per_record as usize * 2).sum();
+                let record_size = data_bytes + self.nr_annot_chns * self.annotation_bytes;
+                let header_size = (self.signals.len() + self.nr_annot_chns + 1) * 256;
+                let tal_offset = header_size + last_record * record_size + data_bytes;
+                
+                let mut written = Vec::new();
+                let file = self.file.get_mut();
+                file.seek(SeekFrom::Start(tal_offset as u64))?;
+                for channel_idx in 0..self.nr_annot_chns {
+                    let (tal_data, indices) = self.build_tal_for_channel(last_record, channel_idx, true);
+                    written.extend(indices);
+                    self.file.get_mut().write_all(&tal_data)?;
+                }
+                dropped.extend(pending.iter()
+                    .filter(|i| !written.contains(i))
+                    .map(|&i| self.annotations[i].clone()));
+            }
+        }
+        
         // 如果有数据写入但头部记录数不正确，需要更新头部
         if self.header_written && self.samples_written > 1 {
-            use std::io::{Seek, SeekFrom};
-            
-            // 刷新缓冲区以确保所有数据都写入了
-            self.file.flush()?;
-            
             // 获取内部文件引用并seek到数据记录数位置 (236-244字节)
-            let mut file = self.file.into_inner().map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+            let file = self.file.get_mut();
             file.seek(SeekFrom::Start(236))?;
             
             // 更新数据记录数
             let datarecords_str = format!("{:<8}", self.samples_written);
             file.write_all(datarecords_str.as_bytes())?;
-            
-            // 确保数据写入磁盘
-            file.flush()?;
-        } else {
-            self.file.flush()?;
         }
-        Ok(())
+        
+        // 确保数据写入磁盘
+        self.file.flush()?;
+        
+        let out_of_range_error = !out_of_range.is_empty() && !self.allow_out_of_range_annotations;
+        let mut violations = std::mem::take(&mut self.violations);
+        if self.profile == Profile::Strict {
+            violations.extend(self.annotation_violations(duration));
+        }
+        let summary = WriteSummary {
+            datarecords: self.samples_written,
+            duration,
+            annotations: self.annotations.len(),
+            out_of_range_annotations: out_of_range,
+            dropped_annotations: dropped,
+            narrowed_samples: self.narrowed_samples,
+            nonconformant_labels: if self.label_policy == LabelPolicy::Standard {
+                self.nonconformant_labels()
+            } else {
+                Vec::new()
+            },
+            sanitized_fields: std::mem::take(&mut self.sanitized_fields),
+            oversized_record_bytes: self.oversized_record_bytes,
+            quantization: self.quantization,
+        };
+        
+        // 原子写入：数据完整并关闭文件后再移动到目标路径
+        if self.atomic_target.is_some() {
+            // 出错时不替换目标文件，临时文件由 Drop 删除
+            if !violations.is_empty() {
+                return Err(EdfError::ComplianceViolations(violations));
+            }
+            if out_of_range_error {
+                return Err(EdfError::AnnotationsOutOfRange {
+                    annotations: summary.out_of_range_annotations,
+                    duration,
+                });
+            }
+            self.file.get_ref().sync_all()?;
+            let (temp_path, target, overwrite) = self.atomic_target.take().expect("checked above");
+            drop(self);
+            if !overwrite && target.exists() {
+                std::fs::remove_file(&temp_path).ok();
+                return Err(EdfError::FileExists(target.display().to_string()));
+            }
+            std::fs::rename(&temp_path, &target)?;
+        }
+        
+        if !violations.is_empty() {
+            return Err(EdfError::ComplianceViolations(violations));
+        }
+        
+        if out_of_range_error {
+            return Err(EdfError::AnnotationsOutOfRange {
+                annotations: summary.out_of_range_annotations,
+                duration,
+            });
+        }
+        
+        Ok(summary)
     }
     
     /// Adds an annotation/event to the EDF+ file
@@ -1020,10 +2585,13 @@ impl EdfWriter {
     /// 
     /// ## Time Range Constraints
     /// 
-    /// **Critical**: Annotations are only saved if their onset time falls within written data records:
-    /// - Annotations with `onset_seconds` >= total file duration will be **silently discarded**
+    /// **Critical**: Annotations must fall within the written data records:
+    /// - [`finalize`](Self::finalize) returns `EdfError::AnnotationsOutOfRange` if an
+    ///   annotation starts at or after the total file duration, or ends after it
     /// - Each data record covers a specific time range (typically 1 second)
     /// - An annotation at time T is only saved if there's a data record covering [T, T+duration)
+    /// - Use [`allow_out_of_range_annotations`](Self::allow_out_of_range_annotations) to
+    ///   store late annotations in the final data record instead
     /// 
     /// ```rust
     /// // Write 5 seconds of data (5 records)
@@ -1041,6 +2609,7 @@ impl EdfWriter {
     /// #     physical_dimension: "uV".to_string(),
     /// #     prefilter: "".to_string(),
     /// #     transducer: "".to_string(),
+    /// #     reserved: String::new(),
     /// # };
     /// # writer.add_signal(signal)?;
     /// 
@@ -1048,14 +2617,15 @@ impl EdfWriter {
     /// writer.add_annotation(2.5, None, "Valid event")?;
     /// writer.add_annotation(4.999, None, "Last moment")?;
     /// 
-    /// // ❌ Lost - outside file duration
-    /// writer.add_annotation(5.0, None, "Will be discarded")?;
-    /// writer.add_annotation(6.0, None, "Also discarded")?;
+    /// // ❌ Rejected - outside file duration
+    /// writer.add_annotation(5.0, None, "Out of range")?;
+    /// writer.add_annotation(6.0, None, "Also out of range")?;
     /// 
     /// for i in 0..5 {
     ///     let samples = vec![0.0; 256];
     ///     writer.write_samples(&[samples])?;
     /// }
+    /// assert!(writer.finalize().is_err());
     /// # std::fs::remove_file("annotations.edf").ok();
     /// # Ok(())
     /// # }
@@ -1081,6 +2651,7 @@ impl EdfWriter {
     /// - `duration_seconds` is negative
     /// - `description` is empty
     /// - `description` exceeds 512 characters (pre-truncation validation)
+    /// - the writer produces classic EDF, see [`create_classic`](Self::create_classic)
     /// 
     /// # Examples
     /// 
@@ -1105,6 +2676,7 @@ impl EdfWriter {
     ///     physical_dimension: "uV".to_string(),
     ///     prefilter: "HP:0.1Hz".to_string(),
     ///     transducer: "AgAgCl".to_string(),
+    ///     reserved: String::new(),
     /// };
     /// writer.add_signal(signal)?;
     /// 
@@ -1147,6 +2719,7 @@ impl EdfWriter {
     ///     physical_dimension: "uV".to_string(),
     ///     prefilter: "0.1-35Hz".to_string(),
     ///     transducer: "AgAgCl".to_string(),
+    ///     reserved: String::new(),
     /// };
     /// writer.add_signal(eeg_signal)?;
     /// 
@@ -1177,6 +2750,12 @@ impl EdfWriter {
     /// # Ok::<(), edfplus::EdfError>(())
     /// ```
     pub fn add_annotation(&mut self, onset_seconds: f64, duration_seconds: Option<f64>, description: &str) -> Result<()> {
+        if !self.file_type.is_plus() {
+            return Err(EdfError::InvalidFormat(
+                "Classic EDF files have no annotation signal, use an EDF+ writer to store annotations".to_string()
+            ));
+        }
+        
         // Validate inputs
         if onset_seconds < 0.0 {
             return Err(EdfError::InvalidFormat("Annotation onset cannot be negative".to_string()));
@@ -1197,10 +2776,8 @@ impl EdfWriter {
         }
         
         // Convert to internal time units (100 nanoseconds)
-        let onset = (onset_seconds * EDFLIB_TIME_DIMENSION as f64) as i64;
-        let duration = duration_seconds
-            .map(|d| (d * EDFLIB_TIME_DIMENSION as f64) as i64)
-            .unwrap_or(-1);
+        let onset = time::seconds_to_ticks(onset_seconds);
+        let duration = duration_seconds.map(time::seconds_to_ticks);
         
         // Create and store annotation
         let annotation = crate::types::Annotation {
@@ -1243,6 +2820,442 @@ impl EdfWriter {
     pub fn annotation_count(&self) -> usize {
         self.annotations.len()
     }
+    
+    /// Splits the writer into one sender per signal for multi-threaded acquisition
+    /// 
+    /// Shortcut for `ChannelWriter::new(self).into_senders()`; see
+    /// [`channels`](crate::channels) for details and an example. All signals
+    /// must have been added before calling this.
+    pub fn into_channels(self) -> Vec<ChannelSender> {
+        ChannelWriter::new(self).into_senders()
+    }
+    
+    /// 已添加的信号
+    pub(crate) fn signals(&self) -> &[SignalParam] {
+        &self.signals
+    }
+    
+    /// Format variant of the file being written
+    /// 
+    /// This is the value a reader will report as
+    /// [`EdfHeader::file_type`](crate::EdfHeader::file_type).
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::{EdfWriter, FileType};
+    /// 
+    /// let writer = EdfWriter::create("file_type_writer.edf")?;
+    /// assert_eq!(writer.file_type(), FileType::EdfPlusContinuous);
+    /// # drop(writer);
+    /// # std::fs::remove_file("file_type_writer.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn file_type(&self) -> FileType {
+        self.file_type
+    }
+    
+    /// Keeps annotations that lie outside the recorded duration (default: `false`)
+    /// 
+    /// By default [`finalize`](Self::finalize) fails if an annotation starts
+    /// after the last data record or ends after it. When allowed, annotations
+    /// starting after the last record are stored in the final data record,
+    /// which the EDF+ specification permits, and all offending annotations
+    /// are listed in [`WriteSummary::out_of_range_annotations`].
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::{EdfWriter, SignalParam};
+    /// 
+    /// let mut writer = EdfWriter::create("late_annotation.edf")?;
+    /// # writer.add_signal(SignalParam {
+    /// #     label: "EEG".to_string(), samples_in_file: 0,
+    /// #     physical_max: 100.0, physical_min: -100.0,
+    /// #     digital_max: 32767, digital_min: -32768,
+    /// #     samples_per_record: 10, physical_dimension: "uV".to_string(),
+    /// #     prefilter: "".to_string(), transducer: "".to_string(),
+    /// #     reserved: String::new(),
+    /// # })?;
+    /// writer.allow_out_of_range_annotations(true);
+    /// writer.add_annotation(30.0, None, "Lights on")?;
+    /// 
+    /// // Only 2 seconds of data
+    /// writer.write_samples(&[vec![0.0; 10]])?;
+    /// writer.write_samples(&[vec![0.0; 10]])?;
+    /// 
+    /// let summary = writer.finalize()?;
+    /// assert_eq!(summary.out_of_range_annotations.len(), 1);
+    /// assert!(summary.dropped_annotations.is_empty());
+    /// # std::fs::remove_file("late_annotation.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn allow_out_of_range_annotations(&mut self, allow: bool) {
+        self.allow_out_of_range_annotations = allow;
+    }
+    
+    /// Places all annotations when the file is finalized (default: `false`)
+    /// 
+    /// By default an annotation is written with the data record that
+    /// contains its onset, so it must be added before that record is
+    /// written. In deferred mode the data records get only their
+    /// timekeeping TAL while writing, and annotations can be added at any
+    /// time before [`finalize`](Self::finalize). `finalize` then stores each
+    /// annotation in the data record containing its onset; when the
+    /// annotation space of that record is full, the annotation goes to the
+    /// nearest record with space left, later records first. This is valid
+    /// EDF+, since every TAL carries its own onset. Only when no record has
+    /// room for the whole annotation is its description shortened to the
+    /// space left, as an eager write does.
+    /// 
+    /// Annotations that fit nowhere are listed in
+    /// [`WriteSummary::dropped_annotations`]; size the annotation space with
+    /// [`annotation_capacity`](Self::annotation_capacity). Out-of-range
+    /// annotations are handled as without deferral, see
+    /// [`allow_out_of_range_annotations`](Self::allow_out_of_range_annotations).
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidFormat` - Header already written
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::{EdfReader, EdfWriter};
+    /// 
+    /// let mut writer = EdfWriter::create("deferred.edf")?;
+    /// writer.defer_annotations(true)?;
+    /// # writer.add_signal(edfplus::SignalParam {
+    /// #     label: "EEG Fpz-Cz".to_string(), samples_in_file: 0,
+    /// #     physical_max: 100.0, physical_min: -100.0,
+    /// #     digital_max: 32767, digital_min: -32768,
+    /// #     samples_per_record: 10, physical_dimension: "uV".to_string(),
+    /// #     prefilter: "".to_string(), transducer: "".to_string(),
+    /// #     reserved: String::new(),
+    /// # })?;
+    /// for _ in 0..3 {
+    ///     writer.write_samples(&[vec![0.0; 10]])?;
+    /// }
+    /// 
+    /// // Events collected elsewhere, added after the samples
+    /// writer.add_annotation(0.5, None, "Eyes closed")?;
+    /// writer.add_annotation(2.25, Some(0.5), "Artifact")?;
+    /// writer.finalize()?;
+    /// 
+    /// let reader = EdfReader::open("deferred.edf")?;
+    /// assert_eq!(reader.annotations().len(), 2);
+    /// # std::fs::remove_file("deferred.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn defer_annotations(&mut self, defer: bool) -> Result<()> {
+        if self.header_written {
+            return Err(EdfError::InvalidFormat("Cannot defer annotations after writing header".to_string()));
+        }
+        self.defer_annotations = defer;
+        Ok(())
+    }
+    
+    /// Sets the number of decimals of the timekeeping TALs
+    /// 
+    /// Every data record of an EDF+ file starts with a timekeeping TAL
+    /// holding the start time of the record, e.g. `"+123\x14\x14"`. By
+    /// default the writer formats it like edflib: whole seconds when the
+    /// data record duration and the subsecond start time are whole seconds,
+    /// seven decimals otherwise (`"+0.5000000\x14\x14"`). Some tools
+    /// expect a fixed number of decimals instead; with `timestamp_precision`
+    /// every timekeeping TAL gets exactly `digits` decimals
+    /// (`"+123.0000\x14\x14"` for 4), or none for 0. The decimal point is
+    /// always `.`, independent of the locale.
+    /// 
+    /// Annotation onsets and durations are not affected, they are always
+    /// written exactly and without trailing zeros.
+    /// 
+    /// # Errors
+    /// 
+    /// * `EdfError::InvalidFormat` - Header already written, more than 7
+    ///   decimals (the resolution of EDF+ time is 100 ns), or, when the
+    ///   header is written, record start times that cannot be written
+    ///   exactly with `digits` decimals
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::EdfWriter;
+    /// 
+    /// let mut writer = EdfWriter::create("timestamp_precision.edf")?;
+    /// writer.timestamp_precision(3)?;
+    /// # writer.add_signal(edfplus::SignalParam {
+    /// #     label: "EEG Fpz-Cz".to_string(), samples_in_file: 0,
+    /// #     physical_max: 100.0, physical_min: -100.0,
+    /// #     digital_max: 32767, digital_min: -32768,
+    /// #     samples_per_record: 10, physical_dimension: "uV".to_string(),
+    /// #     prefilter: "".to_string(), transducer: "".to_string(),
+    /// #     reserved: String::new(),
+    /// # })?;
+    /// writer.write_samples(&[vec![0.0; 10]])?;
+    /// writer.finalize()?;
+    /// 
+    /// // The annotation signal follows the 10 samples of the data record
+    /// let bytes = std::fs::read("timestamp_precision.edf")?;
+    /// assert!(bytes[3 * 256 + 20..].starts_with(b"+0.000\x14\x14\0"));
+    /// # std::fs::remove_file("timestamp_precision.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn timestamp_precision(&mut self, digits: u8) -> Result<()> {
+        if self.header_written {
+            return Err(EdfError::InvalidFormat("Cannot modify timestamp precision after writing header".to_string()));
+        }
+        if digits > 7 {
+            return Err(EdfError::InvalidFormat(format!(
+                "Timestamp precision must be at most 7 decimals, got {}", digits
+            )));
+        }
+        self.timestamp_precision = Some(digits);
+        Ok(())
+    }
+    
+    /// 时间戳TAL的小数位数：设置的精度，或者与edflib相同
+    fn timestamp_digits(&self) -> u8 {
+        match self.timestamp_precision {
+            Some(digits) => digits,
+            None if self.datarecord_duration % EDFLIB_TIME_DIMENSION != 0 || self.starttime_subsecond != 0 => 7,
+            None => 0,
+        }
+    }
+    
+    /// 检查所有记录的开始时间都能用设置的小数位数精确表示
+    fn check_timestamp_precision(&self) -> Result<()> {
+        let Some(digits) = self.timestamp_precision else {
+            return Ok(());
+        };
+        let resolution = 10_i64.pow(7 - u32::from(digits));
+        if self.nr_annot_chns > 0
+            && (self.datarecord_duration % resolution != 0 || self.starttime_subsecond % resolution != 0)
+        {
+            return Err(EdfError::InvalidFormat(format!(
+                "Record start times of {}s records starting at +{}s cannot be written with {} decimals",
+                time::format_seconds(self.datarecord_duration),
+                time::format_seconds(self.starttime_subsecond),
+                digits
+            )));
+        }
+        Ok(())
+    }
+    
+    /// 延迟模式：把注释分配到数据记录中并重写所有记录的注释信号
+    /// 
+    /// 返回没有空间写入的注释
+    fn write_deferred_annotations(&mut self) -> Result<Vec<crate::types::Annotation>> {
+        use std::io::{Seek, SeekFrom};
+        
+        let records = self.samples_written;
+        let duration = records as i64 * self.datarecord_duration;
+        // 每个记录每个通道的TAL，第一个通道以时间戳开始
+        let mut slots: Vec<Vec<Vec<u8>>> = (0..records)
+            .map(|record| {
+                let mut channels = vec![Vec::new(); self.nr_annot_chns];
+                channels[0] = self.timekeeping_tal(record);
+                channels
+            })
+            .collect();
+        
+        // 按开始时间分配，时间相同时保持添加顺序
+        let mut order: Vec<usize> = (0..self.annotations.len()).collect();
+        order.sort_by_key(|&i| self.annotations[i].onset);
+        
+        let mut dropped = Vec::new();
+        for annot_idx in order {
+            let annotation = &self.annotations[annot_idx];
+            let target = if annotation.onset < duration {
+                (annotation.onset / self.datarecord_duration) as usize
+            } else if self.allow_out_of_range_annotations && records > 0 {
+                records - 1
+            } else {
+                // 超出范围的注释由 finalize 报告
+                continue;
+            };
+            
+            let full_len = self.annotation_tal(annotation, usize::MAX).map_or(0, |tal| tal.len());
+            // 从目标记录开始，依次尝试之后和之前最近的记录
+            let candidates = || (0..records).flat_map(move |distance| {
+                [target.checked_add(distance), target.checked_sub(distance).filter(|_| distance > 0)]
+            }).flatten().filter(move |&record| record < records);
+            
+            // 先找能放下完整TAL的记录，都放不下时像立即写入一样截断描述
+            let mut placed = false;
+            'passes: for truncate in [false, true] {
+                for record in candidates() {
+                    for offset in 0..self.nr_annot_chns {
+                        let channel = (annot_idx + offset) % self.nr_annot_chns;
+                        let slot = &mut slots[record][channel];
+                        // 至少保留一个结束符
+                        let space = self.annotation_bytes.saturating_sub(slot.len() + 1);
+                        match self.annotation_tal(annotation, space) {
+                            Some(tal) if truncate || tal.len() == full_len => {
+                                slot.extend_from_slice(&tal);
+                                placed = true;
+                                break 'passes;
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            if placed {
+                if self.profile == Profile::Strict {
+                    self.placed_annotations.insert(annot_idx);
+                }
+            } else {
+                dropped.push(annotation.clone());
+            }
+        }
+        
+        let data_bytes: usize = self.signals.iter().map(|s| s.samples_per_record as usize * 2).sum();
+        let record_size = data_bytes + self.nr_annot_chns * self.annotation_bytes;
+        let header_size = (self.signals.len() + self.nr_annot_chns + 1) * 256;
+        for (record, channels) in slots.into_iter().enumerate() {
+            self.file.seek(SeekFrom::Start((header_size + record * record_size + data_bytes) as u64))?;
+            for mut tal_data in channels {
+                tal_data.resize(self.annotation_bytes, 0x00);
+                self.file.write_all(&tal_data)?;
+            }
+        }
+        self.file.flush()?;
+        Ok(dropped)
+    }
+
+    /// Writes data records larger than 61440 bytes (default: `false`)
+    /// 
+    /// The EDF specification limits a data record to 61440 bytes, including
+    /// the annotation signals. By default writing the header of a file with
+    /// larger records fails with `EdfError::RecordTooLarge`, so a shorter
+    /// data record duration can be chosen. When allowed, the file is written
+    /// as requested and the record size is reported in
+    /// [`WriteSummary::oversized_record_bytes`]. This library reads such
+    /// files, other tools may not.
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use edfplus::{EdfError, EdfWriter, SignalParam};
+    /// 
+    /// // 64 channels at 1 kHz do not fit into 1-second records
+    /// let signal = SignalParam {
+    ///     label: "EEG".to_string(), samples_in_file: 0,
+    ///     physical_max: 100.0, physical_min: -100.0,
+    ///     digital_max: 32767, digital_min: -32768,
+    ///     samples_per_record: 1000, physical_dimension: "uV".to_string(),
+    ///     prefilter: "".to_string(), transducer: "".to_string(),
+    ///     reserved: String::new(),
+    /// };
+    /// let record = vec![vec![0.0; 1000]; 64];
+    /// 
+    /// let mut writer = EdfWriter::create("oversized.edf")?;
+    /// for _ in 0..64 {
+    ///     writer.add_signal(signal.clone())?;
+    /// }
+    /// assert!(matches!(writer.write_samples(&record), Err(EdfError::RecordTooLarge { .. })));
+    /// 
+    /// let mut writer = EdfWriter::create("oversized.edf")?;
+    /// writer.allow_oversized_records(true);
+    /// for _ in 0..64 {
+    ///     writer.add_signal(signal.clone())?;
+    /// }
+    /// writer.write_samples(&record)?;
+    /// let summary = writer.finalize()?;
+    /// assert_eq!(summary.oversized_record_bytes, Some(64 * 2000 + 120));
+    /// # std::fs::remove_file("oversized.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn allow_oversized_records(&mut self, allow: bool) {
+        self.allow_oversized_records = allow;
+    }
+
+    /// Copies all annotations of a reader, shifted by `offset_seconds`
+    ///
+    /// Onsets and durations are copied in the internal 100 ns units, so
+    /// they keep their full precision, and the descriptions are copied
+    /// verbatim. The timekeeping annotations of the source are not part of
+    /// [`EdfReader::annotations`] and are not copied; the writer creates
+    /// its own.
+    ///
+    /// For a cropped copy that starts `t` seconds into the source, pass
+    /// `-t`: annotations that start before the output are skipped, and
+    /// annotations starting or ending after the written data follow
+    /// [`allow_out_of_range_annotations`](Self::allow_out_of_range_annotations)
+    /// when the file is finalized.
+    ///
+    /// # Returns
+    ///
+    /// Number of annotations copied.
+    ///
+    /// # Errors
+    ///
+    /// * `EdfError::InvalidFormat` - the writer produces classic EDF, see
+    ///   [`create_classic`](Self::create_classic)
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use edfplus::{EdfReader, EdfWriter, SignalParam};
+    ///
+    /// # let mut writer = EdfWriter::create("copy_source.edf")?;
+    /// # writer.add_signal(SignalParam {
+    /// #     label: "EEG Fpz-Cz".to_string(), samples_in_file: 0,
+    /// #     physical_max: 100.0, physical_min: -100.0,
+    /// #     digital_max: 32767, digital_min: -32768,
+    /// #     samples_per_record: 10, physical_dimension: "uV".to_string(),
+    /// #     prefilter: "".to_string(), transducer: "".to_string(),
+    /// #     reserved: String::new(),
+    /// # })?;
+    /// # writer.add_annotation(0.5, None, "Before crop")?;
+    /// # writer.add_annotation(2.25, Some(0.5), "Arousal")?;
+    /// # for _ in 0..4 {
+    /// #     writer.write_samples(&[vec![0.0; 10]])?;
+    /// # }
+    /// # writer.finalize()?;
+    /// let mut source = EdfReader::open("copy_source.edf")?;
+    ///
+    /// // Copy the last two seconds
+    /// let mut writer = EdfWriter::create("copy_cropped.edf")?;
+    /// writer.add_signal(source.header().signals[0].clone())?;
+    /// let copied = writer.copy_annotations_from(&source, -2.0)?;
+    /// assert_eq!(copied, 1);
+    ///
+    /// source.seek(0, 20)?;
+    /// for _ in 0..2 {
+    ///     writer.write_samples(&[source.read_physical_samples(0, 10)?])?;
+    /// }
+    /// writer.finalize()?;
+    ///
+    /// let cropped = EdfReader::open("copy_cropped.edf")?;
+    /// assert_eq!(cropped.annotations()[0].description, "Arousal");
+    /// assert_eq!(cropped.annotations()[0].onset, 2_500_000);
+    /// # drop(source);
+    /// # drop(cropped);
+    /// # std::fs::remove_file("copy_source.edf").ok();
+    /// # std::fs::remove_file("copy_cropped.edf").ok();
+    /// # Ok::<(), edfplus::EdfError>(())
+    /// ```
+    pub fn copy_annotations_from(&mut self, reader: &EdfReader, offset_seconds: f64) -> Result<usize> {
+        if !self.file_type.is_plus() {
+            return Err(EdfError::InvalidFormat(
+                "Classic EDF files have no annotation signal, use an EDF+ writer to store annotations".to_string()
+            ));
+        }
+
+        let offset = time::seconds_to_ticks(offset_seconds);
+        let before = self.annotations.len();
+        self.annotations.extend(reader.annotations().iter()
+            // 早于输出起点的注释被跳过
+            .filter(|annotation| annotation.onset + offset >= 0)
+            .map(|annotation| crate::types::Annotation {
+                onset: annotation.onset + offset,
+                duration: annotation.duration,
+                description: annotation.description.clone(),
+            }));
+        Ok(self.annotations.len() - before)
+    }
 
     /// Generates TAL data for a specific annotation channel (遵循edflib多通道设计)
     /// 
@@ -1263,45 +3276,81 @@ impl EdfWriter {
     /// # Returns
     /// 
     /// 返回120字节的TAL数据，严格符合EDF+标准格式
+    #[cfg(test)]
     fn generate_annotation_tal_for_channel(&self, data_record_index: usize, channel_idx: usize) -> Result<Vec<u8>> {
-        let mut tal_data = Vec::with_capacity(EDFLIB_ANNOTATION_BYTES);
+        Ok(self.build_tal_for_channel(data_record_index, channel_idx, false).0)
+    }
+    
+    /// 数据记录的时间戳TAL："+<onset>\x14\x14\x00"
+    fn timekeeping_tal(&self, data_record_index: usize) -> Vec<u8> {
+        let mut tal_data = Vec::with_capacity(self.annotation_bytes);
+        tal_data.push(b'+');
+        
+        // 记录开始时间相对于头部的整秒开始时间，每个记录都包含子秒偏移（遵循edflib）
+        let record_time = data_record_index as i64 * self.datarecord_duration + self.starttime_subsecond;
+        let seconds = record_time / EDFLIB_TIME_DIMENSION;
+        let digits = usize::from(self.timestamp_digits());
+        if digits == 0 {
+            tal_data.extend_from_slice(seconds.to_string().as_bytes());
+        } else {
+            // 固定小数位数，精度已在写入头部时检查
+            let fraction = format!("{:07}", record_time % EDFLIB_TIME_DIMENSION);
+            tal_data.extend_from_slice(format!("{}.{}", seconds, &fraction[..digits]).as_bytes());
+        }
+        
+        tal_data.push(0x14); // ASCII 20 - start of annotation
+        tal_data.push(0x14); // ASCII 20 - end of annotation (empty)
+        tal_data.push(0x00); // Null terminator
+        tal_data
+    }
+    
+    /// 单个注释的TAL："+<onset>[\x15<duration>]\x14<description>\x14"
+    /// 
+    /// 描述截断到40字节，并截断到总长度不超过 `space`；连一个字节的描述都放不下时返回 None
+    fn annotation_tal(&self, annotation: &crate::types::Annotation, space: usize) -> Option<Vec<u8>> {
+        let mut tal = vec![b'+'];
+        tal.extend_from_slice(time::format_seconds(annotation.onset + self.starttime_subsecond).as_bytes());
+        if let Some(duration) = annotation.duration {
+            tal.push(0x15);
+            tal.extend_from_slice(time::format_seconds(duration).as_bytes());
+        }
+        tal.push(0x14);
+        let description = annotation.description.as_bytes();
+        let description_len = description.len().min(EDFLIB_WRITE_MAX_ANNOTATION_LEN);
+        if tal.len() + description_len.min(1) + 1 > space {
+            return None;
+        }
+        tal.extend_from_slice(&description[..description_len.min(space - tal.len() - 1)]);
+        tal.push(0x14);
+        Some(tal)
+    }
+    
+    /// 生成TAL数据，并返回实际写入的注释索引
+    /// 
+    /// `include_later` 为 true 时，开始于该记录之后的注释也会写入（用于最后一个记录）
+    fn build_tal_for_channel(&self, data_record_index: usize, channel_idx: usize, include_later: bool) -> (Vec<u8>, Vec<usize>) {
+        let mut tal_data = Vec::with_capacity(self.annotation_bytes);
         
         // 数据记录的时间范围
-        let data_record_time_start = data_record_index as f64 * (self.datarecord_duration as f64 / EDFLIB_TIME_DIMENSION as f64);
-        let data_record_time_end = (data_record_index + 1) as f64 * (self.datarecord_duration as f64 / EDFLIB_TIME_DIMENSION as f64);
+        let data_record_time_start = data_record_index as i64 * self.datarecord_duration;
+        let data_record_time_end = data_record_time_start + self.datarecord_duration;
         
         // 第一个注释通道处理时间戳记录（遵循edflib设计）
         if channel_idx == 0 {
-            // 时间戳注释，格式: "+<onset>\x14\x14\x00"
-            tal_data.push(b'+');
-            
-            // 添加子秒精度支持
-            if data_record_index == 0 && self.starttime_subsecond > 0 {
-                // 第一个记录包含子秒开始时间
-                let time_with_subsecond = data_record_time_start + (self.starttime_subsecond as f64 / EDFLIB_TIME_DIMENSION as f64);
-                let time_str = format!("{:.7}", time_with_subsecond).trim_end_matches('0').trim_end_matches('.').to_string();
-                tal_data.extend_from_slice(time_str.as_bytes());
-            } else {
-                let time_str = if data_record_time_start.fract() == 0.0 {
-                    format!("{}", data_record_time_start as i64)
-                } else {
-                    format!("{:.6}", data_record_time_start).trim_end_matches('0').trim_end_matches('.').to_string()
-                };
-                tal_data.extend_from_slice(time_str.as_bytes());
-            }
-            
-            tal_data.push(0x14); // ASCII 20 - start of annotation
-            tal_data.push(0x14); // ASCII 20 - end of annotation (empty)
-            tal_data.push(0x00); // Null terminator
+            tal_data = self.timekeeping_tal(data_record_index);
+        }
+        
+        // 延迟模式下注释在 finalize 时写入
+        if self.defer_annotations {
+            tal_data.resize(self.annotation_bytes, 0x00);
+            return (tal_data, Vec::new());
         }
         
         // 查找属于当前数据记录和注释通道的注释
         let mut record_annotations = Vec::new();
         for (annot_idx, annotation) in self.annotations.iter().enumerate() {
-            let annotation_time = annotation.onset as f64 / EDFLIB_TIME_DIMENSION as f64;
-            
             // 检查注释是否属于当前数据记录
-            if annotation_time >= data_record_time_start && annotation_time < data_record_time_end {
+            if annotation.onset >= data_record_time_start && (annotation.onset < data_record_time_end || include_later) {
                 // 按照edflib策略分配注释到通道
                 let target_channel = if self.nr_annot_chns == 1 {
                     0 // 单通道模式，所有注释都在通道0
@@ -1326,27 +3375,26 @@ impl EdfWriter {
                 };
                 
                 if target_channel == channel_idx {
-                    record_annotations.push(annotation);
+                    record_annotations.push((annot_idx, annotation));
                 }
             }
         }
         
         // 添加分配给当前通道的注释
-        for annotation in record_annotations {
-            let annotation_time = annotation.onset as f64 / EDFLIB_TIME_DIMENSION as f64;
-            
+        let mut written = Vec::new();
+        for (annot_idx, annotation) in record_annotations {
             // 计算基本注释结构所需的最小空间
-            let time_str = format!("{:.7}", annotation_time).trim_end_matches('0').trim_end_matches('.').to_string();
+            // TAL中的开始时间相对于头部的整秒开始时间，需要加上子秒偏移（与读取端对应）
+            let time_str = time::format_seconds(annotation.onset + self.starttime_subsecond);
             let mut min_needed_space = 1 + time_str.len() + 2 + 1; // +, time, \x14, \x14 (不包括描述)
             
-            if annotation.duration >= 0 {
-                let duration_str = format!("{:.7}", annotation.duration as f64 / EDFLIB_TIME_DIMENSION as f64)
-                    .trim_end_matches('0').trim_end_matches('.').to_string();
+            if let Some(duration) = annotation.duration {
+                let duration_str = time::format_seconds(duration);
                 min_needed_space += 1 + duration_str.len(); // \x15 + duration
             }
             
             // 检查是否有足够的最小空间来容纳注释结构（描述可以被截断）
-            if tal_data.len() + min_needed_space > EDFLIB_ANNOTATION_BYTES - 2 {
+            if tal_data.len() + min_needed_space > self.annotation_bytes - 2 {
                 break; // 没有足够空间放入基本结构，跳过剩余注释
             }
             
@@ -1355,10 +3403,9 @@ impl EdfWriter {
             tal_data.extend_from_slice(time_str.as_bytes());
             
             // 添加持续时间（如果指定）
-            if annotation.duration >= 0 {
+            if let Some(duration) = annotation.duration {
                 tal_data.push(0x15); // ASCII 21 - duration separator
-                let duration_str = format!("{:.7}", annotation.duration as f64 / EDFLIB_TIME_DIMENSION as f64)
-                    .trim_end_matches('0').trim_end_matches('.').to_string();
+                let duration_str = time::format_seconds(duration);
                 tal_data.extend_from_slice(duration_str.as_bytes());
             }
             
@@ -1367,17 +3414,18 @@ impl EdfWriter {
             // 截断过长的描述（遵循edflib限制）
             let description_bytes = annotation.description.as_bytes();
             let max_desc_len = EDFLIB_WRITE_MAX_ANNOTATION_LEN.min(
-                EDFLIB_ANNOTATION_BYTES - tal_data.len() - 2 // 为结束符预留空间
+                self.annotation_bytes - tal_data.len() - 2 // 为结束符预留空间
             );
             let desc_len = description_bytes.len().min(max_desc_len);
             tal_data.extend_from_slice(&description_bytes[..desc_len]);
             
             tal_data.push(0x14); // ASCII 20 - end of annotation
+            written.push(annot_idx);
         }
         
-        // 填充到确切的120字节，用零填充（遵循edflib）
-        tal_data.resize(EDFLIB_ANNOTATION_BYTES, 0x00);
-        Ok(tal_data)
+        // 填充到注释通道的字节数，用零填充（遵循edflib）
+        tal_data.resize(self.annotation_bytes, 0x00);
+        (tal_data, written)
     }
 
     // 添加subsecond开始时间支持
@@ -1386,9 +3434,13 @@ impl EdfWriter {
             return Err(EdfError::InvalidFormat("Cannot modify subsecond start time after writing header".to_string()));
         }
         
-        if subsecond < 0 || subsecond >= EDFLIB_TIME_DIMENSION {
+        if !(0..EDFLIB_TIME_DIMENSION).contains(&subsecond) {
             return Err(EdfError::InvalidFormat("Subsecond must be between 0 and 9999999".to_string()));
         }
+        // 子秒开始时间保存在注释信号的时间戳中
+        if subsecond != 0 {
+            self.require_edf_plus("subsecond start times", "an EDF+ writer")?;
+        }
         
         self.starttime_subsecond = subsecond;
         Ok(())
@@ -1470,8 +3522,11 @@ impl EdfWriter {
         }
         
         // 10. 保留字段 (32字节 × 信号数)
-        for _signal in &all_signals {
-            let field_data = [b' '; 32];
+        for signal in &all_signals {
+            let mut field_data = [b' '; 32];
+            let reserved_bytes = signal.reserved.as_bytes();
+            let len = reserved_bytes.len().min(32);
+            field_data[..len].copy_from_slice(&reserved_bytes[..len]);
             self.file.write_all(&field_data)?;
         }
         
@@ -1489,7 +3544,8 @@ impl EdfWriter {
     /// 
     /// # Errors
     /// 
-    /// * `EdfError::InvalidFormat` - Trying to modify after header written
+    /// * `EdfError::InvalidFormat` - Trying to modify after header written, or
+    ///   the writer produces classic EDF
     /// * `EdfError::InvalidArgument` - Invalid number of annotation signals
     /// 
     /// # Examples
@@ -1510,6 +3566,7 @@ impl EdfWriter {
         if self.header_written {
             return Err(EdfError::InvalidFormat("Cannot modify annotation signals after writing header".to_string()));
         }
+        self.require_edf_plus("annotation signals", "an EDF+ writer")?;
         
         if annot_signals == 0 || annot_signals > EDFLIB_MAX_ANNOTATION_CHANNELS {
             return Err(EdfError::InvalidFormat(format!(
@@ -1524,6 +3581,79 @@ impl EdfWriter {
 
 }
 
+impl Drop for EdfWriter {
+    fn drop(&mut self) {
+        // 未完成的原子写入：删除临时文件，目标文件保持不变
+        if let Some((temp_path, _, _)) = self.atomic_target.take() {
+            std::fs::remove_file(temp_path).ok();
+        }
+    }
+}
+
+/// 信号可存储的数字范围：声明的范围与16位范围的交集
+fn stored_digital_range(signal: &SignalParam) -> (i32, i32) {
+    (signal.digital_min.max(i16::MIN as i32), signal.digital_max.min(i16::MAX as i32))
+}
+
+/// 头部文本只能包含可打印ASCII字符且不超过字段宽度
+pub(crate) fn validate_header_text(value: &str, width: usize, field: &str) -> Result<()> {
+    if value.len() > width {
+        return Err(EdfError::InvalidFormat(format!(
+            "{} is {} bytes, maximum is {}: '{}'", field, value.len(), width, value
+        )));
+    }
+    if !value.bytes().all(is_printable) {
+        return Err(EdfError::InvalidFormat(format!(
+            "{} must be printable ASCII: '{}'", field, value
+        )));
+    }
+    Ok(())
+}
+
+/// 头部允许的字符：可打印ASCII
+fn is_printable(byte: u8) -> bool {
+    (0x20..=0x7e).contains(&byte)
+}
+
+/// 左对齐并用空格填充到固定宽度
+fn space_padded(value: &str, width: usize) -> Vec<u8> {
+    let mut field = vec![b' '; width];
+    let len = value.len().min(width);
+    field[..len].copy_from_slice(&value.as_bytes()[..len]);
+    field
+}
+
+/// TPDF 抖动的伪随机数发生器（SplitMix64）
+struct Dither {
+    state: u64,
+}
+
+impl Dither {
+    /// [0, 1) 均匀分布
+    fn next_uniform(&mut self) -> f64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+    
+    /// 两个均匀分布之差：(-1, 1) 上的三角分布
+    fn next_tpdf(&mut self) -> f64 {
+        self.next_uniform() - self.next_uniform()
+    }
+}
+
+/// 空的头部子字段写为"X"
+fn placeholder_if_empty(value: &str) -> String {
+    if value.trim().is_empty() {
+        "X".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1542,6 +3672,7 @@ mod tests {
             physical_dimension: "uV".to_string(),
             prefilter: "HP:0.1Hz LP:70Hz".to_string(),
             transducer: "AgAgCl electrodes".to_string(),
+            reserved: String::new(),
         }
     }
     
@@ -1595,6 +3726,38 @@ mod tests {
         cleanup_test_file("test_locked.edf");
     }
 
+    #[test]
+    fn test_set_patient_placeholders_and_limit() {
+        let mut writer = EdfWriter::create("test_set_patient.edf").unwrap();
+        
+        writer.set_patient(&PatientInfo {
+            name: Some("Jane Doe".to_string()),
+            birthdate: NaiveDate::from_ymd_opt(1990, 3, 15),
+            ..PatientInfo::default()
+        }).unwrap();
+        assert_eq!(writer.patient_code, "X");
+        assert_eq!(writer.sex, "X");
+        assert_eq!(writer.birthdate, "15-MAR-1990");
+        assert_eq!(writer.patient_name, "Jane_Doe");
+        assert_eq!(writer.patient_additional, "X");
+        
+        // 空字符串同样写为占位符
+        writer.set_patient_info("", "M", " ", "Name").unwrap();
+        assert_eq!(writer.patient_code, "X");
+        assert_eq!(writer.birthdate, "X");
+        
+        // 超过80字节时拒绝且不修改已有信息
+        let too_long = PatientInfo {
+            additional: Some("x".repeat(73)),
+            ..PatientInfo::default()
+        };
+        assert_eq!(too_long.identification().len(), 81);
+        assert!(writer.set_patient(&too_long).is_err());
+        assert_eq!(writer.patient_name, "Name");
+        
+        cleanup_test_file("test_set_patient.edf");
+    }
+
     #[test]
     fn test_multi_channel_annotation_header_creation() {
         let mut writer = EdfWriter::create("test_multi_header.edf").unwrap();
@@ -1665,7 +3828,7 @@ mod tests {
         writer.add_annotation(0.0, None, "Recording Start").unwrap();
         writer.add_annotation(2.5, Some(1.0), "Artifact").unwrap();
         writer.add_annotation(5.0, None, "Eyes Closed").unwrap();
-        writer.add_annotation(10.0, None, "Eyes Open").unwrap();
+        writer.add_annotation(9.5, None, "Eyes Open").unwrap();
         
         // Write 10 seconds of test data
         for second in 0..10 {
@@ -1,4 +1,5 @@
-use edfplus::{EdfReader, EdfWriter, SignalParam};
+use edfplus::{AnnotationCapacity, AnnotationChannels, EdfReader, EdfReaderOptions, EdfWriter, SignalParam};
+use edfplus::vocabulary::VocabularyOptions;
 use std::fs;
 use std::path::Path;
 
@@ -22,6 +23,7 @@ fn create_test_signal() -> SignalParam {
         physical_dimension: "uV".to_string(),
         prefilter: "HP:0.1Hz LP:70Hz".to_string(),
         transducer: "Test electrodes".to_string(),
+        reserved: String::new(),
     }
 }
 
@@ -67,7 +69,7 @@ fn test_basic_annotation_write_read() {
         assert_eq!(annotations.len(), 5);
         
         // 验证具体注释内容
-        let expected_annotations = vec![
+        let expected_annotations = [
             (0.0, None, "Recording Start"),
             (1.5, Some(2.0), "Sleep Stage N1"),
             (3.5, None, "Eye Movement"),
@@ -88,14 +90,13 @@ fn test_basic_annotation_write_read() {
             // 验证持续时间
             match expected_duration {
                 Some(expected_dur) => {
-                    assert!(annotation.duration >= 0);
-                    let actual_duration = annotation.duration as f64 / 10_000_000.0;
+                    let actual_duration = annotation.duration.expect("Expected a duration") as f64 / 10_000_000.0;
                     assert!((actual_duration - expected_dur).abs() < tolerance,
                            "Annotation {} duration mismatch: expected {}, got {}", 
                            i, expected_dur, actual_duration);
                 }
                 None => {
-                    assert_eq!(annotation.duration, -1, "Expected instantaneous event");
+                    assert_eq!(annotation.duration, None, "Expected instantaneous event");
                 }
             }
             
@@ -104,11 +105,7 @@ fn test_basic_annotation_write_read() {
             
             println!("Annotation {}: {:.3}s - {} (duration: {:?})", 
                     i, actual_onset, annotation.description, 
-                    if annotation.duration >= 0 { 
-                        Some(annotation.duration as f64 / 10_000_000.0) 
-                    } else { 
-                        None 
-                    });
+                    annotation.duration.map(|d| d as f64 / 10_000_000.0));
         }
     }
     
@@ -131,7 +128,7 @@ fn test_annotation_time_precision() {
         writer.add_annotation(0.0001, None, "Microsecond Event").unwrap();      // 0.1ms
         writer.add_annotation(0.1234567, None, "High Precision").unwrap();      // 123.4567ms
         writer.add_annotation(1.9999999, Some(0.0000001), "Nanosecond Duration").unwrap(); // 100ns duration
-        writer.add_annotation(3.141592653589793, None, "Pi Seconds").unwrap();  // π秒
+        writer.add_annotation(std::f64::consts::PI, None, "Pi Seconds").unwrap();  // π秒
         
         // 写入5秒的数据
         for second in 0..5 {
@@ -155,11 +152,11 @@ fn test_annotation_time_precision() {
         assert_eq!(annotations.len(), 4);
         
         // 验证高精度时间（EDF+内部使用100纳秒单位）
-        let precision_tests = vec![
+        let precision_tests = [
             (0.0001, "Microsecond Event"),
             (0.1234567, "High Precision"),
             (1.9999999, "Nanosecond Duration"),
-            (3.141592653589793, "Pi Seconds"),
+            (std::f64::consts::PI, "Pi Seconds"),
         ];
         
         for (i, (expected_time, expected_desc)) in precision_tests.iter().enumerate() {
@@ -235,7 +232,7 @@ fn test_annotation_edge_cases() {
         // 验证零持续时间
         let zero_duration = &annotations[1];
         assert_eq!(zero_duration.onset, 0);
-        assert_eq!(zero_duration.duration, 0);
+        assert_eq!(zero_duration.duration, Some(0));
         assert_eq!(zero_duration.description, "Zero duration");
         
         // 验证长描述被正确截断
@@ -257,11 +254,7 @@ fn test_annotation_edge_cases() {
         println!("Edge case tests passed:");
         for (i, annotation) in annotations.iter().enumerate() {
             let onset_s = annotation.onset as f64 / 10_000_000.0;
-            let duration_s = if annotation.duration >= 0 {
-                Some(annotation.duration as f64 / 10_000_000.0)
-            } else {
-                None
-            };
+            let duration_s = annotation.duration.map(|d| d as f64 / 10_000_000.0);
             println!("  {}: {:.3}s - {} (len: {}, duration: {:?})",
                     i, onset_s, &annotation.description[..annotation.description.len().min(50)],
                     annotation.description.len(), duration_s);
@@ -346,11 +339,7 @@ fn test_multiple_annotation_channels() {
         
         for (i, annotation) in annotations.iter().enumerate() {
             let onset_s = annotation.onset as f64 / 10_000_000.0;
-            let duration_s = if annotation.duration >= 0 {
-                Some(annotation.duration as f64 / 10_000_000.0)
-            } else {
-                None
-            };
+            let duration_s = annotation.duration.map(|d| d as f64 / 10_000_000.0);
             println!("    {}: {:.1}s - {} (duration: {:?})",
                     i, onset_s, annotation.description, duration_s);
         }
@@ -526,11 +515,7 @@ fn test_sleep_study_annotations() {
         println!("\nSleep Stages:");
         for annotation in &stage_annotations {
             let onset_min = annotation.onset as f64 / 10_000_000.0 / 60.0;
-            let duration_min = if annotation.duration > 0 {
-                annotation.duration as f64 / 10_000_000.0 / 60.0
-            } else {
-                0.0
-            };
+            let duration_min = annotation.duration.map_or(0.0, |d| d as f64 / 10_000_000.0 / 60.0);
             println!("    {:.1}-{:.1}min: {}", 
                     onset_min, onset_min + duration_min, annotation.description);
         }
@@ -568,6 +553,7 @@ fn test_edf_header_fields_comprehensive() {
             physical_dimension: "uV".to_string(),
             prefilter: "HP:0.1Hz LP:70Hz".to_string(),
             transducer: "AgAgCl cup electrodes".to_string(),
+            reserved: String::new(),
         };
         writer.add_signal(signal1).unwrap();
         
@@ -582,6 +568,7 @@ fn test_edf_header_fields_comprehensive() {
             physical_dimension: "mV".to_string(),
             prefilter: "HP:0.05Hz LP:150Hz".to_string(),
             transducer: "Disposable electrodes".to_string(),
+            reserved: String::new(),
         };
         writer.add_signal(signal2).unwrap();
         
@@ -593,9 +580,10 @@ fn test_edf_header_fields_comprehensive() {
             digital_max: 32767,
             digital_min: -32768,
             samples_per_record: 1,   // 1 Hz
-            physical_dimension: "°C".to_string(),
+            physical_dimension: "degC".to_string(),
             prefilter: "".to_string(),
             transducer: "Thermistor probe".to_string(),
+            reserved: String::new(),
         };
         writer.add_signal(signal3).unwrap();
         
@@ -679,7 +667,7 @@ fn test_edf_header_fields_comprehensive() {
         println!("  Annotations read: {} (expected: 6)", annotations.len());
         
         // 验证注释内容
-        let expected_annotations = vec![
+        let expected_annotations = [
             "Recording start",
             "Test event 1", 
             "Marker point",
@@ -742,17 +730,13 @@ fn test_edf_header_fields_comprehensive() {
         
         assert_eq!(header.signals[2].label, "Temperature");
         assert_eq!(header.signals[2].samples_per_record, 1);
-        assert_eq!(header.signals[2].physical_dimension, "°C");
+        assert_eq!(header.signals[2].physical_dimension, "degC");
         
         // 验证注释详细信息
         println!("\n📋 Annotation Details:");
         for (i, annotation) in annotations.iter().enumerate() {
             let onset_s = annotation.onset as f64 / 10_000_000.0;
-            let duration_s = if annotation.duration >= 0 {
-                Some(annotation.duration as f64 / 10_000_000.0)
-            } else {
-                None
-            };
+            let duration_s = annotation.duration.map(|d| d as f64 / 10_000_000.0);
             
             println!("  [{:2}] {:.1}s: {} (duration: {:?})", 
                     i, onset_s, annotation.description, duration_s);
@@ -808,6 +792,7 @@ fn test_header_fields_edge_cases() {
             physical_dimension: "V".to_string(),
             prefilter: "".to_string(),
             transducer: "".to_string(),
+            reserved: String::new(),
         };
         writer.add_signal(signal).unwrap();
         
@@ -937,7 +922,7 @@ fn test_header_fields_maximum_annotations() {
                    i, annotation.description);
             
             // 验证时间在合理范围内
-            assert!(actual_onset >= 0.0 && actual_onset < 30.0,
+            assert!((0.0..30.0).contains(&actual_onset),
                    "Annotation {} time should be in [0,30): {:.3}s", 
                    i, actual_onset);
         }
@@ -1139,7 +1124,7 @@ fn test_multiple_annotations_per_record() {
         println!("\n📄 Test Summary:");
         println!("  • Single record can store {} annotations in 120 bytes", record_0_annotations.len());
         println!("  • Average space per annotation: ~{:.1} bytes", 
-                if record_0_annotations.len() > 0 { 
+                if !record_0_annotations.is_empty() { 
                     estimated_tal_usage as f64 / record_0_annotations.len() as f64 
                 } else { 0.0 });
         
@@ -1250,3 +1235,691 @@ fn test_tal_buffer_stress_test() {
     
     cleanup_test_file(filename);
 }
+
+#[test]
+fn test_out_of_range_annotations_rejected_at_finalize() {
+    let filename = "test_out_of_range_rejected.edf";
+    
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.add_signal(create_test_signal()).unwrap();
+    
+    writer.add_annotation(1.0, None, "Valid").unwrap();
+    writer.add_annotation(2.5, Some(1.0), "Runs past end").unwrap();
+    writer.add_annotation(3.0, None, "After end").unwrap();
+    
+    // 只写入3秒的数据
+    for _ in 0..3 {
+        writer.write_samples(&[vec![0.0; 256]]).unwrap();
+    }
+    
+    match writer.finalize() {
+        Err(edfplus::EdfError::AnnotationsOutOfRange { annotations, duration }) => {
+            assert_eq!(duration, 3 * edfplus::EDFLIB_TIME_DIMENSION);
+            let descriptions: Vec<&str> = annotations.iter().map(|a| a.description.as_str()).collect();
+            assert_eq!(descriptions, vec!["Runs past end", "After end"]);
+        }
+        other => panic!("Expected AnnotationsOutOfRange, got {:?}", other),
+    }
+    
+    // 文件仍然完整，但超出范围的注释没有写入
+    let reader = EdfReader::open(filename).unwrap();
+    assert_eq!(reader.header().datarecords_in_file, 3);
+    let descriptions: Vec<&str> = reader.annotations().iter().map(|a| a.description.as_str()).collect();
+    assert_eq!(descriptions, vec!["Valid", "Runs past end"]);
+    
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_out_of_range_annotations_allowed() {
+    let filename = "test_out_of_range_allowed.edf";
+    
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.add_signal(create_test_signal()).unwrap();
+    writer.allow_out_of_range_annotations(true);
+    
+    writer.add_annotation(1.0, None, "Valid").unwrap();
+    writer.add_annotation(2.5, Some(1.0), "Runs past end").unwrap();
+    writer.add_annotation(4.0, None, "After end").unwrap();
+    
+    for _ in 0..3 {
+        writer.write_samples(&[vec![0.0; 256]]).unwrap();
+    }
+    
+    let summary = writer.finalize().unwrap();
+    assert_eq!(summary.datarecords, 3);
+    assert_eq!(summary.duration, 3 * edfplus::EDFLIB_TIME_DIMENSION);
+    assert_eq!(summary.annotations, 3);
+    let flagged: Vec<&str> = summary.out_of_range_annotations.iter().map(|a| a.description.as_str()).collect();
+    assert_eq!(flagged, vec!["Runs past end", "After end"]);
+    assert!(summary.dropped_annotations.is_empty());
+    
+    // 超出范围的注释被写入最后一个数据记录，时间保持不变
+    let mut reader = EdfReader::open(filename).unwrap();
+    let late = reader.annotations().iter()
+        .find(|a| a.description == "After end")
+        .expect("late annotation should be stored")
+        .clone();
+    assert_eq!(late.onset, 4 * edfplus::EDFLIB_TIME_DIMENSION);
+    
+    let last_record = reader.read_record(2).unwrap();
+    assert!(last_record.annotations.iter().any(|a| a.description == "After end"));
+    
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_annotations_in_record_match_global_list() {
+    let filename = "test_annotations_in_record.edf";
+    
+    {
+        let mut writer = EdfWriter::create(filename).unwrap();
+        writer.add_signal(create_test_signal()).unwrap();
+        writer.set_number_of_annotation_signals(2).unwrap();
+        
+        writer.add_annotation(0.0, None, "Start").unwrap();
+        writer.add_annotation(0.5, Some(0.25), "Blink").unwrap();
+        writer.add_annotation(2.25, None, "Spindle").unwrap();
+        writer.add_annotation(2.75, Some(1.0), "Arousal").unwrap();
+        writer.add_annotation(4.5, None, "End").unwrap();
+        
+        for _ in 0..5 {
+            writer.write_samples(&[vec![0.0; 256]]).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+    
+    let global = EdfReader::open(filename).unwrap().annotations().to_vec();
+    assert_eq!(global.len(), 5);
+    
+    // 不预先解析注释，逐记录读取
+    let mut reader = EdfReaderOptions::new().parse_annotations(false).open(filename).unwrap();
+    assert!(reader.annotations().is_empty());
+    
+    let mut per_record = Vec::new();
+    for record in 0..reader.header().datarecords_in_file {
+        let annotations = reader.annotations_in_record(record).unwrap();
+        let start = record * edfplus::EDFLIB_TIME_DIMENSION;
+        for annotation in &annotations {
+            assert!(annotation.onset >= start && annotation.onset < start + edfplus::EDFLIB_TIME_DIMENSION);
+        }
+        per_record.extend(annotations);
+    }
+    assert_eq!(per_record, global);
+    
+    let record_2: Vec<String> = reader.annotations_in_record(2).unwrap()
+        .into_iter().map(|a| a.description).collect();
+    assert_eq!(record_2, vec!["Spindle", "Arousal"]);
+    assert!(reader.annotations_in_record(1).unwrap().is_empty());
+    
+    // 包含时间戳TAL时，它以空描述出现在记录开始时间
+    let with_timekeeping = reader.annotations_in_record_with_timekeeping(3).unwrap();
+    assert_eq!(with_timekeeping.len(), 1);
+    assert_eq!(with_timekeeping[0].onset, 3 * edfplus::EDFLIB_TIME_DIMENSION);
+    assert!(with_timekeeping[0].description.is_empty());
+    
+    assert!(matches!(
+        reader.annotations_in_record(5),
+        Err(edfplus::EdfError::InvalidRecordIndex { index: 5, count: 5 })
+    ));
+    assert!(reader.annotations_in_record(-1).is_err());
+    
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_annotations_by_record_keep_placement() {
+    use std::io::{Seek, SeekFrom, Write};
+    
+    let filename = "test_annotations_by_record.edf";
+    
+    {
+        let mut writer = EdfWriter::create(filename).unwrap();
+        writer.add_signal(create_test_signal()).unwrap();
+        writer.add_annotation(0.5, None, "Event").unwrap();
+        for _ in 0..3 {
+            writer.write_samples(&[vec![0.0; 256]]).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+    
+    // 在第三个记录中再写入同一时间的注释，且TAL顺序与时间顺序相反
+    // 头部 3*256 字节之后，每个记录 256*2 字节样本 + 120 字节注释
+    {
+        let tal_offset = 3 * 256 + 2 * (512 + 120) + 512;
+        let mut file = fs::OpenOptions::new().write(true).open(filename).unwrap();
+        file.seek(SeekFrom::Start(tal_offset as u64)).unwrap();
+        file.write_all(b"+2\x14\x14\0+2.5\x14Later\x14\0+0.5\x14Event\x14\0").unwrap();
+    }
+    
+    let mut reader = EdfReader::open(filename).unwrap();
+    
+    // 全局列表按时间排序，无法区分两个相同的注释来自哪个记录
+    let global: Vec<(i64, String)> = reader.annotations().iter()
+        .map(|a| (a.onset, a.description.clone())).collect();
+    assert_eq!(global, vec![
+        (5_000_000, "Event".to_string()),
+        (5_000_000, "Event".to_string()),
+        (25_000_000, "Later".to_string()),
+    ]);
+    
+    let records = reader.annotations_by_record().unwrap();
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[0].len(), 1);
+    assert_eq!(records[0][0].onset, 5_000_000);
+    assert!(records[1].is_empty());
+    
+    // 记录内保持TAL顺序
+    let record_2: Vec<(i64, &str)> = records[2].iter()
+        .map(|a| (a.onset, a.description.as_str())).collect();
+    assert_eq!(record_2, vec![(25_000_000, "Later"), (5_000_000, "Event")]);
+    
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_extract_annotations_to_events_only_file() {
+    let source = "test_extract_annotations_source.edf";
+    let events = "test_extract_annotations_events.edf";
+    let start = chrono::NaiveDate::from_ymd_opt(2023, 11, 2).unwrap()
+        .and_hms_milli_opt(23, 59, 58, 125).unwrap();
+    
+    {
+        let mut writer = EdfWriter::create(source).unwrap();
+        writer.set_start_datetime(start).unwrap();
+        writer.set_datarecord_duration(0.5).unwrap();
+        writer.set_number_of_annotation_signals(3).unwrap();
+        writer.allow_out_of_range_annotations(true);
+        writer.add_signal(create_test_signal()).unwrap();
+        
+        writer.add_annotation(0.0, None, "Recording start").unwrap();
+        writer.add_annotation(0.1234567, Some(0.25), "Spike").unwrap();
+        writer.add_annotation(0.2, Some(0.0), "Marker").unwrap();
+        writer.add_annotation(1.75, Some(30.0), "Sleep stage W").unwrap();
+        writer.add_annotation(2.4999999, None, "Last record").unwrap();
+        // 晚于最后一个记录，写入最后一个记录
+        writer.add_annotation(10.0, None, "Lights on").unwrap();
+        
+        for _ in 0..5 {
+            writer.write_samples(&[vec![1.0; 256]]).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+    
+    let summary = edfplus::extract_annotations(source, events).unwrap();
+    assert!(summary.dropped_annotations.is_empty());
+    
+    let source_reader = EdfReader::open(source).unwrap();
+    let events_reader = EdfReader::open(events).unwrap();
+    assert_eq!(source_reader.annotations().len(), 6);
+    assert_eq!(events_reader.annotations(), source_reader.annotations());
+    
+    let (a, b) = (source_reader.header(), events_reader.header());
+    assert!(b.signals.is_empty());
+    assert_eq!(b.start_datetime(), start);
+    assert_eq!(b.start_datetime(), a.start_datetime());
+    assert_eq!(b.datarecord_duration, a.datarecord_duration);
+    assert_eq!(b.datarecords_in_file, a.datarecords_in_file);
+    assert!(b.patient_name.is_empty() || b.patient_name == "X");
+    
+    // 三个注释信号：头部 4×256 字节，每个记录 3×120 字节
+    assert_eq!(fs::metadata(events).unwrap().len(), 4 * 256 + 5 * 3 * 120);
+    
+    drop(source_reader);
+    drop(events_reader);
+    cleanup_test_file(source);
+    cleanup_test_file(events);
+}
+
+#[test]
+fn test_auto_annotation_capacity_sizes_records() {
+    let sparse = "test_auto_capacity_sparse.edf";
+    let dense = "test_auto_capacity_dense.edf";
+    
+    // 每个记录1个注释 vs 50个注释
+    for (filename, per_record) in [(sparse, 1), (dense, 50)] {
+        let mut writer = EdfWriter::create(filename).unwrap();
+        writer.annotation_capacity(AnnotationCapacity::Auto).unwrap();
+        writer.add_signal(create_test_signal()).unwrap();
+        for record in 0..4 {
+            for i in 0..per_record {
+                let onset = record as f64 + i as f64 / per_record as f64;
+                writer.add_annotation(onset, Some(0.01), &format!("Event {}", i)).unwrap();
+            }
+        }
+        for _ in 0..4 {
+            writer.write_samples(&[vec![0.0; 256]]).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+    
+    let mut record_sizes = Vec::new();
+    for (filename, expected) in [(sparse, 4), (dense, 200)] {
+        let reader = EdfReader::open(filename).unwrap();
+        assert_eq!(reader.annotations().len(), expected);
+        
+        let header_bytes = 3 * 256;
+        let file_size = fs::metadata(filename).unwrap().len() as usize;
+        record_sizes.push((file_size - header_bytes) / 4 - 256 * 2);
+    }
+    
+    // 稀疏注释比默认的120字节更小，密集注释远大于默认值
+    assert!(record_sizes[0] < 120, "{:?}", record_sizes);
+    assert!(record_sizes[1] > 50 * 20, "{:?}", record_sizes);
+    
+    cleanup_test_file(sparse);
+    cleanup_test_file(dense);
+}
+
+#[test]
+fn test_fixed_annotation_capacity() {
+    let filename = "test_fixed_capacity.edf";
+    
+    let mut writer = EdfWriter::create(filename).unwrap();
+    assert!(writer.annotation_capacity(AnnotationCapacity::Bytes(16)).is_err());
+    writer.annotation_capacity(AnnotationCapacity::Bytes(301)).unwrap();
+    writer.add_signal(create_test_signal()).unwrap();
+    writer.add_annotation(0.5, None, "Marker").unwrap();
+    writer.write_samples(&[vec![0.0; 256]]).unwrap();
+    assert!(writer.annotation_capacity(AnnotationCapacity::Auto).is_err());
+    writer.finalize().unwrap();
+    
+    // 向上取整为偶数字节
+    assert_eq!(fs::metadata(filename).unwrap().len(), 3 * 256 + 512 + 302);
+    let reader = EdfReader::open(filename).unwrap();
+    assert_eq!(reader.annotations().len(), 1);
+    
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_copy_annotations_into_cropped_file() {
+    let source_file = "test_copy_annotations_source.edf";
+    let cropped_file = "test_copy_annotations_cropped.edf";
+
+    {
+        let mut writer = EdfWriter::create(source_file).unwrap();
+        writer.set_subsecond_starttime(2_500_000).unwrap();
+        writer.add_signal(create_test_signal()).unwrap();
+        writer.add_annotation(1.25, None, "Before crop").unwrap();
+        writer.add_annotation(3.0, Some(0.5), "At crop start").unwrap();
+        writer.add_annotation(4.1234567, None, "Subsecond onset").unwrap();
+        writer.add_annotation(5.5, Some(0.0001), "Short event").unwrap();
+        writer.add_annotation(6.75, Some(2.0), "Crosses crop end").unwrap();
+        writer.add_annotation(8.0, None, "After crop").unwrap();
+        for record in 0..10 {
+            writer.write_samples(&[vec![record as f64; 256]]).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+
+    // 裁剪第3到第7秒
+    let mut source = EdfReader::open(source_file).unwrap();
+    let mut writer = EdfWriter::create(cropped_file).unwrap();
+    writer.add_signal(source.header().signals[0].clone()).unwrap();
+    writer.allow_out_of_range_annotations(true);
+    let copied = writer.copy_annotations_from(&source, -3.0).unwrap();
+    assert_eq!(copied, 5);
+
+    source.seek(0, 3 * 256).unwrap();
+    for _ in 0..4 {
+        let samples = source.read_physical_samples(0, 256).unwrap();
+        writer.write_samples(&[samples]).unwrap();
+    }
+    let summary = writer.finalize().unwrap();
+
+    // 超出输出时长的注释按越界策略处理
+    let late: Vec<&str> = summary.out_of_range_annotations.iter().map(|a| a.description.as_str()).collect();
+    assert_eq!(late, vec!["Crosses crop end", "After crop"]);
+
+    // 裁剪窗口内的注释与源文件一致，只平移了时间
+    let window_start = 3 * 10_000_000;
+    let window_end = 7 * 10_000_000;
+    let expected: Vec<_> = source.annotations().iter()
+        .filter(|a| a.onset >= window_start && a.onset < window_end)
+        .map(|a| (a.onset - window_start, a.duration, a.description.clone()))
+        .collect();
+    let cropped = EdfReader::open(cropped_file).unwrap();
+    let actual: Vec<_> = cropped.annotations().iter()
+        .filter(|a| a.onset < window_end - window_start)
+        .map(|a| (a.onset, a.duration, a.description.clone()))
+        .collect();
+    assert_eq!(actual.len(), 4);
+    assert_eq!(actual, expected);
+    assert_eq!(actual[1].0, 11_234_567);
+
+    // 经典EDF不能保存注释
+    let mut classic = EdfWriter::create_classic("test_copy_annotations_classic.edf").unwrap();
+    assert!(classic.copy_annotations_from(&source, 0.0).is_err());
+    drop(classic);
+
+    drop(source);
+    drop(cropped);
+    cleanup_test_file(source_file);
+    cleanup_test_file(cropped_file);
+    cleanup_test_file("test_copy_annotations_classic.edf");
+}
+
+#[test]
+fn test_annotation_vocabulary() {
+    let filename = "test_annotation_vocabulary.edf";
+    
+    // 每个30秒时段一个睡眠分期，外加大小写和空白不一致的觉醒事件
+    {
+        let mut writer = EdfWriter::create(filename).unwrap();
+        writer.add_signal(create_test_signal()).unwrap();
+        let stages = ["Sleep stage W", "Sleep stage 1", "Sleep stage 2", "Sleep stage 2"];
+        for epoch in 0..20 {
+            writer.add_annotation(epoch as f64 * 30.0, Some(30.0), stages[epoch % stages.len()]).unwrap();
+        }
+        writer.add_annotation(40.0, Some(3.0), "Arousal").unwrap();
+        writer.add_annotation(100.0, Some(5.5), "arousal ").unwrap();
+        writer.add_annotation(200.0, None, "Arousal").unwrap();
+        writer.add_annotation(310.0, None, "Eyes  closed").unwrap();
+        for _ in 0..600 {
+            writer.write_samples(&[vec![0.0; 256]]).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+    
+    let reader = EdfReader::open(filename).unwrap();
+    let exact = reader.annotation_vocabulary();
+    assert_eq!(exact, vec![
+        ("Sleep stage 2".to_string(), 10, 300.0),
+        ("Sleep stage 1".to_string(), 5, 150.0),
+        ("Sleep stage W".to_string(), 5, 150.0),
+        ("Arousal".to_string(), 2, 3.0),
+        ("Eyes  closed".to_string(), 1, 0.0),
+        ("arousal ".to_string(), 1, 5.5),
+    ]);
+    let total: usize = exact.iter().map(|(_, count, _)| count).sum();
+    assert_eq!(total, reader.annotations().len());
+    
+    // 大小写和空白规范化时使用首次出现的写法
+    let normalized = reader.annotation_vocabulary_with(
+        &VocabularyOptions::new().ignore_case(true).ignore_whitespace(true)
+    );
+    assert_eq!(normalized.len(), 5);
+    assert_eq!(normalized[3], ("Arousal".to_string(), 3, 8.5));
+    assert_eq!(normalized[4], ("Eyes  closed".to_string(), 1, 0.0));
+    
+    // 只忽略大小写时尾部空格仍然区分
+    let case_only = reader.annotation_vocabulary_with(&VocabularyOptions::new().ignore_case(true));
+    assert_eq!(case_only.len(), 6);
+    
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_select_annotation_channels() {
+    let filename = "test_select_annotation_channels.edf";
+    
+    // 三个注释通道，注释按添加顺序循环分配：通道 i 得到第 i 和 i+3 个
+    {
+        let mut writer = EdfWriter::create(filename).unwrap();
+        writer.set_number_of_annotation_signals(3).unwrap();
+        writer.set_subsecond_starttime(2_500_000).unwrap();
+        writer.add_signal(create_test_signal()).unwrap();
+        for i in 0..6 {
+            writer.add_annotation(i as f64, None, &format!("Channel {} event", i % 3)).unwrap();
+        }
+        for _ in 0..6 {
+            writer.write_samples(&[vec![0.0; 256]]).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+    
+    let open = |channels: AnnotationChannels| {
+        EdfReaderOptions::new().parse_annotation_channels(channels).open(filename)
+    };
+    
+    let reader = open(AnnotationChannels::All).unwrap();
+    assert_eq!(reader.header().annotation_signal_count(), 3);
+    assert_eq!(reader.header().signals.len(), 1);
+    assert_eq!(reader.annotations().len(), 6);
+    
+    let reader = open(AnnotationChannels::Only(vec![1])).unwrap();
+    let descriptions: Vec<&str> = reader.annotations().iter().map(|a| a.description.as_str()).collect();
+    assert_eq!(descriptions, vec!["Channel 1 event", "Channel 1 event"]);
+    assert_eq!(reader.header().annotations_in_file, 2);
+    // 第一个通道被排除时仍然读取时间戳
+    assert_eq!(reader.header().starttime_subsecond, 2_500_000);
+    
+    let reader = open(AnnotationChannels::Only(vec![0, 2])).unwrap();
+    assert_eq!(reader.annotations().len(), 4);
+    assert!(reader.annotations().iter().all(|a| a.description != "Channel 1 event"));
+    
+    let mut reader = open(AnnotationChannels::None).unwrap();
+    assert!(reader.annotations().is_empty());
+    assert_eq!(reader.header().annotations_in_file, 0);
+    assert_eq!(reader.header().starttime_subsecond, 2_500_000);
+    // 按记录读取不受选择影响
+    assert_eq!(reader.annotations_in_record(1).unwrap().len(), 1);
+    
+    assert!(open(AnnotationChannels::Only(vec![3])).is_err());
+    
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+/// 相同输入分别以立即和延迟方式写入
+fn write_with_annotations(filename: &str, defer: bool, annotation_signals: usize, events: &[(f64, Option<f64>, String)]) -> edfplus::WriteSummary {
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.defer_annotations(defer).unwrap();
+    writer.set_number_of_annotation_signals(annotation_signals).unwrap();
+    writer.add_signal(create_test_signal()).unwrap();
+    if !defer {
+        for (onset, duration, description) in events {
+            writer.add_annotation(*onset, *duration, description).unwrap();
+        }
+    }
+    for _ in 0..10 {
+        writer.write_samples(&[vec![0.0; 256]]).unwrap();
+    }
+    // 延迟模式下在写完样本后才添加注释
+    if defer {
+        for (onset, duration, description) in events {
+            writer.add_annotation(*onset, *duration, description).unwrap();
+        }
+    }
+    writer.finalize().unwrap()
+}
+
+#[test]
+fn test_deferred_annotations_match_eager() {
+    let eager = "test_annotations_eager.edf";
+    let deferred = "test_annotations_deferred.edf";
+    
+    let events: Vec<(f64, Option<f64>, String)> = (0..12)
+        .map(|i| (i as f64 * 0.75, (i % 3 == 0).then_some(1.5), format!("Event {}", i)))
+        .collect();
+    for annotation_signals in [1, 2] {
+        write_with_annotations(eager, false, annotation_signals, &events);
+        write_with_annotations(deferred, true, annotation_signals, &events);
+        
+        let a = EdfReader::open(eager).unwrap();
+        let b = EdfReader::open(deferred).unwrap();
+        assert_eq!(a.annotations().len(), 12);
+        assert_eq!(a.annotations(), b.annotations());
+        assert_eq!(a.header().annotations_in_file, b.header().annotations_in_file);
+        // 时间戳保持连续
+        assert!(EdfReader::open(deferred).unwrap().timing_report().unwrap().strictly_continuous);
+    }
+    
+    cleanup_test_file(eager);
+    cleanup_test_file(deferred);
+}
+
+#[test]
+fn test_deferred_annotations_spill_into_neighboring_records() {
+    let eager = "test_annotations_spill_eager.edf";
+    let deferred = "test_annotations_spill_deferred.edf";
+    
+    // 第5秒内的20个事件远超一个记录的120字节
+    let events: Vec<(f64, Option<f64>, String)> = (0..20)
+        .map(|i| (5.0 + i as f64 * 0.05, None, format!("Spike {}", i)))
+        .collect();
+    
+    let summary = write_with_annotations(eager, false, 1, &events);
+    assert!(summary.dropped_annotations.is_empty());
+    let eager_count = EdfReader::open(eager).unwrap().annotations().len();
+    assert!(eager_count < 20);
+    
+    let summary = write_with_annotations(deferred, true, 1, &events);
+    assert!(summary.dropped_annotations.is_empty());
+    let mut reader = EdfReader::open(deferred).unwrap();
+    let annotations = reader.annotations().to_vec();
+    assert_eq!(annotations.len(), 20);
+    for (annotation, (onset, _, description)) in annotations.iter().zip(&events) {
+        assert_eq!(annotation.onset, edfplus::time::seconds_to_ticks(*onset));
+        assert_eq!(&annotation.description, description);
+    }
+    
+    // 第5个记录装满，其余注释放在相邻记录中
+    let by_record = reader.annotations_by_record().unwrap();
+    assert!(by_record[5].len() >= eager_count - 1);
+    assert!(!by_record[6].is_empty() && !by_record[4].is_empty());
+    
+    drop(reader);
+    cleanup_test_file(eager);
+    cleanup_test_file(deferred);
+}
+
+#[test]
+fn test_deferred_annotations_truncate_like_eager_when_full() {
+    let eager = "test_annotations_full_eager.edf";
+    let deferred = "test_annotations_full_deferred.edf";
+    
+    // 只有一个记录：时间戳 5 字节 + 7 个 13 字节的TAL，剩余空间放不下完整的长描述
+    let long = "L".repeat(40);
+    let write = |filename: &str, defer: bool| {
+        let mut writer = EdfWriter::create(filename).unwrap();
+        writer.defer_annotations(defer).unwrap();
+        writer.add_signal(create_test_signal()).unwrap();
+        for i in 1..=7 {
+            writer.add_annotation(i as f64 * 0.1, None, &format!("Event {}", i)).unwrap();
+        }
+        writer.add_annotation(0.9, None, &long).unwrap();
+        writer.write_samples(&[vec![0.0; 256]]).unwrap();
+        writer.finalize().unwrap()
+    };
+    
+    assert!(write(eager, false).dropped_annotations.is_empty());
+    assert!(write(deferred, true).dropped_annotations.is_empty());
+    
+    let a = EdfReader::open(eager).unwrap();
+    let b = EdfReader::open(deferred).unwrap();
+    assert_eq!(a.annotations().len(), 8);
+    assert_eq!(a.annotations(), b.annotations());
+    // 描述截断到剩余的 17 字节
+    assert_eq!(b.annotations()[7].description, long[..17]);
+    drop((a, b));
+    
+    cleanup_test_file(eager);
+    cleanup_test_file(deferred);
+}
+
+// 写入3个数据记录并返回每个记录注释信号的原始字节
+fn write_timekeeping(filename: &str, precision: Option<u8>, record_duration: f64, subsecond: i64) -> Vec<Vec<u8>> {
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.add_signal(create_test_signal()).unwrap();
+    writer.set_datarecord_duration(record_duration).unwrap();
+    writer.set_subsecond_starttime(subsecond).unwrap();
+    if let Some(digits) = precision {
+        writer.timestamp_precision(digits).unwrap();
+    }
+    writer.add_annotation(0.0, None, "Start").unwrap();
+    for _ in 0..3 {
+        writer.write_samples(&[vec![0.0; 256]]).unwrap();
+    }
+    writer.finalize().unwrap();
+
+    // 头部 3 × 256 字节，每个记录 512 字节样本加 120 字节注释
+    let bytes = fs::read(filename).unwrap();
+    (0..3)
+        .map(|record| {
+            let start = 768 + record * (512 + 120) + 512;
+            bytes[start..start + 120].to_vec()
+        })
+        .collect()
+}
+
+// 注释信号以给定的TAL开始，其余用NUL填充
+fn assert_tals(channel: &[u8], tals: &[u8]) {
+    assert_eq!(&channel[..tals.len()], tals, "{:?}", String::from_utf8_lossy(channel));
+    assert!(channel[tals.len()..].iter().all(|&b| b == 0), "{:?}", String::from_utf8_lossy(channel));
+}
+
+#[test]
+fn test_timekeeping_tal_bytes() {
+    let filename = "test_timekeeping_tal_bytes.edf";
+
+    // 默认与edflib相同：整秒记录没有小数
+    let channels = write_timekeeping(filename, None, 1.0, 0);
+    assert_tals(&channels[0], b"+0\x14\x14\0+0\x14Start\x14\0");
+    assert_tals(&channels[1], b"+1\x14\x14\0");
+    assert_tals(&channels[2], b"+2\x14\x14\0");
+
+    // 非整秒的记录时长或开始时间：7位小数，注释不受影响
+    let channels = write_timekeeping(filename, None, 0.5, 0);
+    assert_tals(&channels[0], b"+0.0000000\x14\x14\0+0\x14Start\x14\0");
+    assert_tals(&channels[1], b"+0.5000000\x14\x14\0");
+    assert_tals(&channels[2], b"+1.0000000\x14\x14\0");
+    let channels = write_timekeeping(filename, None, 1.0, 2_500_000);
+    assert_tals(&channels[0], b"+0.2500000\x14\x14\0+0.25\x14Start\x14\0");
+    assert_tals(&channels[2], b"+2.2500000\x14\x14\0");
+
+    // 固定小数位数
+    let channels = write_timekeeping(filename, Some(4), 1.0, 0);
+    assert_tals(&channels[0], b"+0.0000\x14\x14\0+0\x14Start\x14\0");
+    assert_tals(&channels[2], b"+2.0000\x14\x14\0");
+    let channels = write_timekeeping(filename, Some(2), 0.25, 500_000);
+    assert_tals(&channels[1], b"+0.30\x14\x14\0");
+    assert_tals(&channels[2], b"+0.55\x14\x14\0");
+    let channels = write_timekeeping(filename, Some(0), 2.0, 0);
+    assert_tals(&channels[1], b"+2\x14\x14\0");
+    let channels = write_timekeeping(filename, Some(7), 1.0, 0);
+    assert_tals(&channels[1], b"+1.0000000\x14\x14\0");
+
+    // 读回的时间与格式无关
+    let reader = EdfReader::open(filename).unwrap();
+    assert_eq!(reader.annotations()[0].onset, 0);
+    assert_eq!(reader.header().datarecord_duration, 10_000_000);
+    drop(reader);
+
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_timestamp_precision_must_be_exact() {
+    let filename = "test_timestamp_precision_exact.edf";
+
+    let mut writer = EdfWriter::create(filename).unwrap();
+    assert!(writer.timestamp_precision(8).is_err());
+
+    // 0.25 秒的记录不能用1位小数表示
+    writer.add_signal(create_test_signal()).unwrap();
+    writer.set_datarecord_duration(0.25).unwrap();
+    writer.timestamp_precision(1).unwrap();
+    assert!(writer.write_samples(&[vec![0.0; 256]]).is_err());
+    drop(writer);
+
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.add_signal(create_test_signal()).unwrap();
+    writer.set_subsecond_starttime(1).unwrap();
+    writer.timestamp_precision(6).unwrap();
+    assert!(writer.write_samples(&[vec![0.0; 256]]).is_err());
+
+    // 写入头部后不能修改
+    writer.set_subsecond_starttime(0).unwrap();
+    writer.write_samples(&[vec![0.0; 256]]).unwrap();
+    assert!(writer.timestamp_precision(3).is_err());
+    writer.finalize().unwrap();
+
+    cleanup_test_file(filename);
+}
@@ -0,0 +1,107 @@
+use edfplus::calibration::{CalibrationFinding, CalibrationOptions};
+use edfplus::{EdfReader, EdfWriter, SignalParam};
+use std::fs;
+use std::path::Path;
+
+// 清理测试文件的辅助函数
+fn cleanup_test_file(filename: &str) {
+    if Path::new(filename).exists() {
+        fs::remove_file(filename).ok();
+    }
+}
+
+fn create_signal(label: &str, physical_range: f64, dimension: &str) -> SignalParam {
+    SignalParam {
+        label: label.to_string(),
+        samples_in_file: 0,
+        physical_max: physical_range,
+        physical_min: -physical_range,
+        digital_max: 32767,
+        digital_min: -32768,
+        samples_per_record: 100,
+        physical_dimension: dimension.to_string(),
+        prefilter: "".to_string(),
+        transducer: "".to_string(),
+        reserved: String::new(),
+    }
+}
+
+fn create_audit_file(filename: &str) {
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.add_signal(create_signal("Good", 100.0, "uV")).unwrap();
+    writer.add_signal(create_signal("Narrow", 1000.0, "mV")).unwrap();
+    writer.add_signal(create_signal("Clipped", 100.0, "uV")).unwrap();
+    writer.add_signal(create_signal("NoUnit", 100.0, "")).unwrap();
+
+    for record in 0..3 {
+        let wave: Vec<f64> = (0..100).map(|i| ((record * 100 + i) as f64 * 0.2).sin()).collect();
+        let good: Vec<f64> = wave.iter().map(|v| v * 90.0).collect();
+        let narrow: Vec<f64> = wave.iter().map(|v| v * 0.3).collect();
+        // 大约三分之一的样本超出物理范围而被截断
+        let clipped: Vec<f64> = wave.iter().map(|v| (v * 200.0).clamp(-100.0, 100.0)).collect();
+        writer.write_samples(&[good.clone(), narrow, clipped, good]).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+#[test]
+fn test_calibration_audit_flags_suspicious_signals() {
+    let filename = "test_calibration_audit.edf";
+    create_audit_file(filename);
+
+    let mut reader = EdfReader::open(filename).unwrap();
+    let findings = reader.calibration_audit().unwrap();
+    assert_eq!(findings.len(), 3, "{:?}", findings);
+
+    match &findings[0] {
+        CalibrationFinding::NarrowSpan { signal, label, observed_min, observed_max, physical_min, physical_max, fraction } => {
+            assert_eq!(*signal, 1);
+            assert_eq!(label, "Narrow");
+            assert!((*observed_min + 0.3).abs() < 0.05 && (*observed_max - 0.3).abs() < 0.05);
+            assert_eq!((*physical_min, *physical_max), (-1000.0, 1000.0));
+            assert!(*fraction < 0.001);
+        }
+        other => panic!("Expected NarrowSpan, got {:?}", other),
+    }
+
+    match &findings[1] {
+        CalibrationFinding::RailHugging { signal, samples_at_min, samples_at_max, total_samples, .. } => {
+            assert_eq!(*signal, 2);
+            assert_eq!(*total_samples, 300);
+            assert!(*samples_at_min > 50 && *samples_at_max > 50);
+        }
+        other => panic!("Expected RailHugging, got {:?}", other),
+    }
+
+    assert_eq!(findings[2], CalibrationFinding::EmptyDimension { signal: 3, label: "NoUnit".to_string() });
+    assert!(findings[2].to_string().contains("NoUnit"));
+
+    // 审计不影响读取位置
+    assert_eq!(reader.tell(0).unwrap(), 0);
+
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_calibration_audit_thresholds() {
+    let filename = "test_calibration_thresholds.edf";
+    create_audit_file(filename);
+
+    let mut reader = EdfReader::open(filename).unwrap();
+
+    // 放宽阈值后只剩下缺少单位的信号
+    let relaxed = CalibrationOptions::new()
+        .min_span_fraction(0.0)
+        .max_rail_fraction(1.0);
+    let findings = reader.calibration_audit_with(&relaxed).unwrap();
+    assert_eq!(findings.iter().map(|f| f.signal()).collect::<Vec<_>>(), vec![3]);
+
+    // 收紧跨度阈值后正常信号也被标记
+    let strict = CalibrationOptions::new().min_span_fraction(0.95);
+    let findings = reader.calibration_audit_with(&strict).unwrap();
+    assert!(findings.iter().any(|f| matches!(f, CalibrationFinding::NarrowSpan { signal: 0, .. })));
+
+    drop(reader);
+    cleanup_test_file(filename);
+}
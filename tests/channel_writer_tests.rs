@@ -0,0 +1,147 @@
+use edfplus::{ChannelWriter, EdfReader, EdfWriter, RaggedTail, SignalParam};
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+// 清理测试文件的辅助函数
+fn cleanup_test_file(filename: &str) {
+    if Path::new(filename).exists() {
+        fs::remove_file(filename).ok();
+    }
+}
+
+const RATES: [usize; 3] = [256, 100, 7];
+
+fn create_writer(filename: &str) -> EdfWriter {
+    let mut writer = EdfWriter::create(filename).unwrap();
+    for (i, &rate) in RATES.iter().enumerate() {
+        writer.add_signal(SignalParam {
+            label: format!("Ch{}", i),
+            samples_in_file: 0,
+            physical_max: 100.0,
+            physical_min: -100.0,
+            digital_max: 32767,
+            digital_min: -32768,
+            samples_per_record: rate as i32,
+            physical_dimension: "uV".to_string(),
+            prefilter: "".to_string(),
+            transducer: "".to_string(),
+            reserved: String::new(),
+        }).unwrap();
+    }
+    writer.add_annotation(1.5, None, "Marker").unwrap();
+    writer
+}
+
+// 每个通道的信号，带有少量多余的样本（不完整的最后一个记录）
+fn channel_data(channel: usize, seconds: usize) -> Vec<f64> {
+    let rate = RATES[channel];
+    (0..rate * seconds + channel)
+        .map(|i| 80.0 * ((i as f64 / rate as f64) * (channel + 1) as f64).sin())
+        .collect()
+}
+
+#[test]
+fn test_threaded_channels_match_single_threaded_reference() {
+    let reference = "test_channels_reference.edf";
+    let threaded = "test_channels_threaded.edf";
+    let seconds = 6;
+    
+    // 单线程参考文件
+    {
+        let mut writer = create_writer(reference);
+        for second in 0..seconds {
+            let record: Vec<Vec<f64>> = (0..RATES.len())
+                .map(|ch| channel_data(ch, seconds)[second * RATES[ch]..(second + 1) * RATES[ch]].to_vec())
+                .collect();
+            writer.write_samples(&record).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+    
+    // 多线程：每个通道使用不同的块大小和节奏，队列只容纳一个记录
+    let senders = ChannelWriter::new(create_writer(threaded))
+        .queue_records(1)
+        .into_senders();
+    let handles: Vec<_> = senders.into_iter()
+        .map(|mut sender| thread::spawn(move || {
+            let channel = sender.channel();
+            let data = channel_data(channel, seconds);
+            let chunk_size = [61, 3, 1][channel];
+            for (i, chunk) in data.chunks(chunk_size).enumerate() {
+                sender.send(chunk).unwrap();
+                if channel == 2 && i % 5 == 0 {
+                    thread::sleep(Duration::from_millis(1));
+                }
+            }
+            sender.close().unwrap()
+        }))
+        .collect();
+    
+    let summaries: Vec<_> = handles.into_iter()
+        .filter_map(|handle| handle.join().unwrap())
+        .collect();
+    assert_eq!(summaries.len(), 1);
+    assert_eq!(summaries[0].datarecords, seconds);
+    
+    assert_eq!(fs::read(reference).unwrap(), fs::read(threaded).unwrap());
+    
+    cleanup_test_file(reference);
+    cleanup_test_file(threaded);
+}
+
+#[test]
+fn test_ragged_tail_policies() {
+    let dropped = "test_channels_tail_drop.edf";
+    let padded = "test_channels_tail_pad.edf";
+    
+    for (filename, policy) in [(dropped, RaggedTail::Drop), (padded, RaggedTail::PadWithZeros)] {
+        let mut senders = ChannelWriter::new(create_writer(filename))
+            .ragged_tail(policy)
+            .into_senders();
+        // 2个完整记录，外加第一个通道的半个记录
+        for (channel, sender) in senders.iter_mut().enumerate() {
+            sender.send(&vec![50.0; RATES[channel] * 2]).unwrap();
+        }
+        senders[0].send(&[50.0; 128]).unwrap();
+        
+        let mut summary = None;
+        for sender in senders {
+            if let Some(s) = sender.close().unwrap() {
+                summary = Some(s);
+            }
+        }
+        let expected_records = if policy == RaggedTail::Drop { 2 } else { 3 };
+        assert_eq!(summary.unwrap().datarecords, expected_records);
+    }
+    
+    let mut reader = EdfReader::open(padded).unwrap();
+    reader.seek(0, 2 * 256 + 127).unwrap();
+    let tail = reader.read_physical_samples(0, 2).unwrap();
+    assert!((tail[0] - 50.0).abs() < 0.01);
+    assert!(tail[1].abs() < 0.01);
+    
+    drop(reader);
+    cleanup_test_file(dropped);
+    cleanup_test_file(padded);
+}
+
+#[test]
+fn test_send_fails_when_other_channel_closed() {
+    let filename = "test_channels_closed_peer.edf";
+    
+    let mut senders = ChannelWriter::new(create_writer(filename))
+        .queue_records(1)
+        .into_senders();
+    let last = senders.pop().unwrap();
+    assert_eq!(last.close().unwrap(), None);
+    
+    // 队列已满且其他通道已关闭时返回错误而不是死锁
+    assert!(senders[0].send(&vec![0.0; 256 * 2]).is_err());
+    
+    for sender in senders {
+        sender.close().ok();
+    }
+    cleanup_test_file(filename);
+}
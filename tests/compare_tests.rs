@@ -0,0 +1,189 @@
+use edfplus::compare::Mismatch;
+use edfplus::{compare, CompareOptions, EdfReader, EdfWriter, SignalParam};
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::Path;
+
+// 清理测试文件的辅助函数
+fn cleanup_test_file(filename: &str) {
+    if Path::new(filename).exists() {
+        fs::remove_file(filename).ok();
+    }
+}
+
+fn create_test_signal(label: &str) -> SignalParam {
+    SignalParam {
+        label: label.to_string(),
+        samples_in_file: 0,
+        physical_max: 200.0,
+        physical_min: -200.0,
+        digital_max: 32767,
+        digital_min: -32768,
+        samples_per_record: 100,
+        physical_dimension: "uV".to_string(),
+        prefilter: "HP:0.1Hz".to_string(),
+        transducer: "AgAgCl".to_string(),
+        reserved: String::new(),
+    }
+}
+
+// 写入两个信号、5秒的测试文件
+fn create_test_file(filename: &str, patient_name: &str, annotation_onset: f64) {
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.set_patient_info("P001", "F", "01-JAN-1990", patient_name).unwrap();
+    writer.add_signal(create_test_signal("EEG Fp1")).unwrap();
+    writer.add_signal(create_test_signal("EEG Fp2")).unwrap();
+    writer.add_annotation(annotation_onset, None, "Marker").unwrap();
+    for record in 0..5 {
+        let first: Vec<f64> = (0..100).map(|i| ((record * 100 + i) as f64 * 0.1).sin() * 100.0).collect();
+        let second: Vec<f64> = (0..100).map(|i| (record * 100 + i) as f64 * 0.2 - 50.0).collect();
+        writer.write_samples(&[first, second]).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+// 修改第一个信号在指定位置的数字值
+fn flip_sample(filename: &str, sample: usize) {
+    let header_size = 4 * 256;
+    let record_size = 2 * 100 * 2 + 120;
+    let offset = header_size + (sample / 100) * record_size + (sample % 100) * 2;
+    let mut file = OpenOptions::new().write(true).open(filename).unwrap();
+    file.seek(SeekFrom::Start(offset as u64)).unwrap();
+    file.write_all(&1234i16.to_le_bytes()).unwrap();
+}
+
+#[test]
+fn test_compare_identical_files() {
+    let (file_a, file_b) = ("test_compare_identical_a.edf", "test_compare_identical_b.edf");
+    create_test_file(file_a, "Test Patient", 1.5);
+    create_test_file(file_b, "Test Patient", 1.5);
+    
+    let mut a = EdfReader::open(file_a).unwrap();
+    let mut b = EdfReader::open(file_b).unwrap();
+    let report = compare(&mut a, &mut b, &CompareOptions::new()).unwrap();
+    assert!(report.is_identical(), "{:?}", report.mismatches);
+    assert_eq!(report.total_mismatches, 0);
+    
+    drop(a);
+    drop(b);
+    cleanup_test_file(file_a);
+    cleanup_test_file(file_b);
+}
+
+#[test]
+fn test_compare_flipped_sample() {
+    let (file_a, file_b) = ("test_compare_flipped_a.edf", "test_compare_flipped_b.edf");
+    create_test_file(file_a, "Test Patient", 1.5);
+    create_test_file(file_b, "Test Patient", 1.5);
+    flip_sample(file_b, 237);
+    
+    let mut a = EdfReader::open(file_a).unwrap();
+    let mut b = EdfReader::open(file_b).unwrap();
+    
+    // 比较前的读取位置应被恢复
+    a.seek(0, 42).unwrap();
+    let expected = a.read_digital_samples(0, 300).unwrap()[195];
+    a.seek(0, 42).unwrap();
+    
+    let report = compare(&mut a, &mut b, &CompareOptions::new()).unwrap();
+    assert!(!report.is_identical());
+    assert_eq!(report.mismatches, vec![Mismatch::Sample {
+        signal: 0,
+        sample: 237,
+        a: expected as f64,
+        b: 1234.0,
+    }]);
+    assert_eq!(a.tell(0).unwrap(), 42);
+    assert_eq!(b.tell(0).unwrap(), 0);
+    
+    // 只比较第二个信号时没有差异
+    let report = compare(&mut a, &mut b, &CompareOptions::new().signals(&[1])).unwrap();
+    assert!(report.is_identical());
+    
+    drop(a);
+    drop(b);
+    cleanup_test_file(file_a);
+    cleanup_test_file(file_b);
+}
+
+#[test]
+fn test_compare_shifted_annotation() {
+    let (file_a, file_b) = ("test_compare_annotation_a.edf", "test_compare_annotation_b.edf");
+    create_test_file(file_a, "Test Patient", 1.5);
+    create_test_file(file_b, "Test Patient", 2.5);
+    
+    let mut a = EdfReader::open(file_a).unwrap();
+    let mut b = EdfReader::open(file_b).unwrap();
+    let report = compare(&mut a, &mut b, &CompareOptions::new()).unwrap();
+    
+    assert_eq!(report.total_mismatches, 1);
+    match &report.mismatches[0] {
+        Mismatch::Annotation { index, a, b } => {
+            assert_eq!(*index, 0);
+            assert_eq!(a.as_ref().unwrap().onset, 15_000_000);
+            assert_eq!(b.as_ref().unwrap().onset, 25_000_000);
+        }
+        other => panic!("unexpected mismatch: {:?}", other),
+    }
+    
+    drop(a);
+    drop(b);
+    cleanup_test_file(file_a);
+    cleanup_test_file(file_b);
+}
+
+#[test]
+fn test_compare_ignore_metadata() {
+    let (file_a, file_b) = ("test_compare_metadata_a.edf", "test_compare_metadata_b.edf");
+    create_test_file(file_a, "Alice", 1.5);
+    create_test_file(file_b, "Bob", 1.5);
+    
+    let mut a = EdfReader::open(file_a).unwrap();
+    let mut b = EdfReader::open(file_b).unwrap();
+    
+    let report = compare(&mut a, &mut b, &CompareOptions::new()).unwrap();
+    assert_eq!(report.mismatches, vec![Mismatch::Metadata {
+        field: "patient_name",
+        a: "Alice".to_string(),
+        b: "Bob".to_string(),
+    }]);
+    
+    let report = compare(&mut a, &mut b, &CompareOptions::new().ignore_metadata(true)).unwrap();
+    assert!(report.is_identical());
+    
+    drop(a);
+    drop(b);
+    cleanup_test_file(file_a);
+    cleanup_test_file(file_b);
+}
+
+#[test]
+fn test_compare_physical_tolerance_and_limit() {
+    let (file_a, file_b) = ("test_compare_tolerance_a.edf", "test_compare_tolerance_b.edf");
+    create_test_file(file_a, "Test Patient", 1.5);
+    create_test_file(file_b, "Test Patient", 1.5);
+    for sample in [10, 20, 30] {
+        flip_sample(file_b, sample);
+    }
+    
+    let mut a = EdfReader::open(file_a).unwrap();
+    let mut b = EdfReader::open(file_b).unwrap();
+    
+    // 物理值差异远大于容差
+    let report = compare(&mut a, &mut b, &CompareOptions::new().physical_tolerance(0.01).max_mismatches(2)).unwrap();
+    assert_eq!(report.total_mismatches, 3);
+    assert_eq!(report.mismatches.len(), 2);
+    assert!(matches!(report.mismatches[0], Mismatch::Sample { signal: 0, sample: 10, .. }));
+    
+    // 容差足够大时视为相同
+    let report = compare(&mut a, &mut b, &CompareOptions::new().physical_tolerance(500.0)).unwrap();
+    assert!(report.is_identical());
+    
+    assert!(compare(&mut a, &mut b, &CompareOptions::new().signals(&[5])).is_err());
+    
+    drop(a);
+    drop(b);
+    cleanup_test_file(file_a);
+    cleanup_test_file(file_b);
+}
@@ -0,0 +1,217 @@
+use edfplus::{EdfError, EdfReader, EdfWriter, FixedPhysical, SignalParam};
+
+// 创建指定定标参数的信号
+fn calibrated_signal(physical_min: f64, physical_max: f64, digital_min: i32, digital_max: i32) -> SignalParam {
+    SignalParam {
+        label: "Calibration".to_string(),
+        samples_in_file: 0,
+        physical_max,
+        physical_min,
+        digital_max,
+        digital_min,
+        samples_per_record: 256,
+        physical_dimension: "uV".to_string(),
+        prefilter: "".to_string(),
+        transducer: "".to_string(),
+        reserved: String::new(),
+    }
+}
+
+#[test]
+fn test_scale_offset_match_to_physical_over_i16() {
+    let calibrations = [
+        calibrated_signal(-200.0, 200.0, -32768, 32767),   // 对称物理范围
+        calibrated_signal(30.0, 40.0, -32768, 32767),      // 偏移物理范围
+        calibrated_signal(-5000.0, -100.0, -32768, 32767), // 全负物理范围
+        calibrated_signal(100.0, -100.0, -32768, 32767),   // 反相信号
+        calibrated_signal(-3.3, 3.3, -2048, 2047),         // 12位ADC
+        calibrated_signal(0.0, 1.0, 0, 4095),              // 非对称数字范围
+        calibrated_signal(-1.0, 1.0, -1, 1),
+    ];
+    
+    for signal in &calibrations {
+        let scale = signal.scale();
+        let offset = signal.offset();
+        
+        for digital in i16::MIN..=i16::MAX {
+            let expected = signal.to_physical(digital as i32);
+            let actual = scale * (digital as f64) + offset;
+            assert_eq!(actual.to_bits(), expected.to_bits(),
+                "mismatch at digital {} for range {}..{}", digital, signal.physical_min, signal.physical_max);
+        }
+        
+        // 数字范围的两端映射到物理范围的两端
+        let span = (signal.physical_max - signal.physical_min).abs();
+        assert!((signal.to_physical(signal.digital_min) - signal.physical_min).abs() <= span * 1e-12);
+        assert!((signal.to_physical(signal.digital_max) - signal.physical_max).abs() <= span * 1e-12);
+    }
+}
+
+#[test]
+fn test_inverted_signal_has_negative_scale() {
+    let signal = calibrated_signal(100.0, -100.0, -32768, 32767);
+    
+    assert!(signal.scale() < 0.0);
+    assert!(signal.to_physical(32767) < signal.to_physical(-32768));
+    
+    // 反相信号的往返转换同样精确
+    for digital in [-32768, -1, 0, 1, 12345, 32767] {
+        assert_eq!(signal.to_digital(signal.to_physical(digital)), digital);
+    }
+}
+
+#[test]
+fn test_inverted_calibration_file_round_trip() {
+    let filename = "test_inverted_calibration.edf";
+    
+    let normal = calibrated_signal(-5.0, 5.0, -32768, 32767);
+    let inverted = calibrated_signal(5.0, -5.0, -32768, 32767);
+    assert!(!normal.is_inverted());
+    assert!(inverted.is_inverted());
+    
+    // 非对称波形，便于检查极性：尖峰向上，基线为负
+    let waveform: Vec<f64> = (0..256)
+        .map(|i| if i % 64 == 10 { 4.0 } else { -0.5 + (i % 64) as f64 * 0.01 })
+        .collect();
+    let mut out_of_range = waveform.clone();
+    out_of_range[0] = 7.5;
+    out_of_range[1] = -7.5;
+    
+    {
+        let mut writer = EdfWriter::create(filename).unwrap();
+        writer.add_signal(normal.clone()).unwrap();
+        writer.add_signal(inverted.clone()).unwrap();
+        writer.write_samples(&[waveform.clone(), waveform.clone()]).unwrap();
+        writer.write_samples(&[out_of_range.clone(), out_of_range.clone()]).unwrap();
+        writer.finalize().unwrap();
+    }
+    
+    let mut reader = EdfReader::open(filename).unwrap();
+    assert!(reader.header().signals[1].is_inverted());
+    assert_eq!(reader.header().signals[1].physical_min, 5.0);
+    assert_eq!(reader.header().signals[1].physical_max, -5.0);
+    
+    // 物理值的形状和符号与写入时一致
+    let resolution = inverted.scale().abs();
+    let normal_values = reader.read_physical_samples(0, 512).unwrap();
+    let inverted_values = reader.read_physical_samples(1, 512).unwrap();
+    for (i, (&expected, (&a, &b))) in waveform.iter().zip(normal_values.iter().zip(&inverted_values)).enumerate() {
+        assert!((a - expected).abs() <= resolution, "normal sample {}", i);
+        assert!((b - expected).abs() <= resolution, "inverted sample {}", i);
+    }
+    
+    // 数字值取反存储
+    reader.rewind(0).unwrap();
+    reader.rewind(1).unwrap();
+    let normal_digital = reader.read_digital_samples(0, 256).unwrap();
+    let inverted_digital = reader.read_digital_samples(1, 256).unwrap();
+    for (a, b) in normal_digital.iter().zip(&inverted_digital) {
+        assert!((a + b).abs() <= 1, "{} vs {}", a, b);
+    }
+    
+    // 超出范围的值饱和到声明的物理端点
+    assert!((inverted_values[256] - 5.0).abs() <= resolution);
+    assert!((inverted_values[257] + 5.0).abs() <= resolution);
+    
+    // 包络的最小值/最大值不因反相而交换
+    let envelope = reader.read_envelope(1, 0, 256, 1).unwrap();
+    assert!((envelope[0].0 - waveform.iter().cloned().fold(f64::INFINITY, f64::min)).abs() <= resolution);
+    assert!((envelope[0].1 - 4.0).abs() <= resolution);
+    
+    drop(reader);
+    std::fs::remove_file(filename).ok();
+}
+
+// 相邻 f64 之间的距离
+fn ulp(x: f64) -> f64 {
+    let x = x.abs();
+    f64::from_bits(x.to_bits() + 1) - x
+}
+
+#[test]
+fn test_exact_conversion_matches_f64_path() {
+    let calibrations = [
+        calibrated_signal(-200.0, 200.0, -32768, 32767),
+        calibrated_signal(30.0, 40.0, -32768, 32767),
+        calibrated_signal(-5000.0, -100.0, -32768, 32767),
+        calibrated_signal(100.0, -100.0, -32768, 32767),
+        calibrated_signal(-3.3, 3.3, -2048, 2047),
+        calibrated_signal(0.0, 1.0, 0, 4095),
+        calibrated_signal(-3276.8, 3276.7, -32768, 32767),
+    ];
+
+    for signal in &calibrations {
+        // 15 位小数的定点值转换为 f64 后与精确值相差不到 1 ulp；
+        // f64 路径舍入 scale、offset 和乘加三次，每次最多 1 ulp
+        let tolerance = 3.0 * ulp(signal.physical_min).max(ulp(signal.physical_max));
+        for digital in signal.digital_min.max(i16::MIN as i32)..=signal.digital_max.min(i16::MAX as i32) {
+            let exact = signal.to_physical_fixed(digital, 15);
+            let approximate = signal.to_physical(digital);
+            assert!((exact.to_f64() - approximate).abs() <= tolerance,
+                "digital {} for range {}..{}: {} vs {}", digital, signal.physical_min, signal.physical_max, exact, approximate);
+        }
+
+        // 数字范围的两端精确映射到物理范围的两端
+        assert_eq!(signal.to_physical_fixed(signal.digital_min, 15).to_f64(), signal.physical_min);
+        assert_eq!(signal.to_physical_fixed(signal.digital_max, 15).to_f64(), signal.physical_max);
+        assert!(signal.to_physical_exact(signal.digital_max).exact);
+    }
+}
+
+#[test]
+fn test_exact_conversion_is_deterministic_over_i16() {
+    // 步长正好为 0.1：全部精确，1 位小数
+    let terminating = calibrated_signal(-3276.8, 3276.7, -32768, 32767);
+    // 步长 400/65535 不是有限小数：舍入到 9 位小数
+    let repeating = calibrated_signal(-200.0, 200.0, -32768, 32767);
+
+    let mut previous = None;
+    for digital in i16::MIN as i32..=i16::MAX as i32 {
+        let value = terminating.to_physical_exact(digital);
+        assert_eq!(value, FixedPhysical { value: digital as i64, decimals: 1, exact: true });
+
+        let value = repeating.to_physical_exact(digital);
+        assert_eq!(value, repeating.to_physical_exact(digital));
+        assert_eq!(value.decimals, 9);
+        // 与独立的整数计算一致（四舍五入，远离零）
+        let numerator = (-200 * 65535 + 400 * (digital as i128 + 32768)) * 1_000_000_000;
+        let expected = (numerator + numerator.signum() * 65535 / 2) / 65535;
+        assert_eq!(value.value as i128, expected, "digital {}", digital);
+        assert_eq!(value.exact, numerator % 65535 == 0);
+        // 严格单调
+        if let Some(previous) = previous {
+            assert!(value.value > previous);
+        }
+        previous = Some(value.value);
+    }
+}
+
+#[test]
+fn test_read_physical_samples_exact() {
+    let filename = "test_read_physical_samples_exact.edf";
+    let signal = calibrated_signal(-3276.8, 3276.7, -32768, 32767);
+
+    {
+        let mut writer = EdfWriter::create(filename).unwrap();
+        writer.add_signal(signal.clone()).unwrap();
+        let samples: Vec<f64> = (0..256).map(|i| (i as f64 - 128.0) * 0.3).collect();
+        writer.write_samples(&[samples]).unwrap();
+        writer.finalize().unwrap();
+    }
+
+    let mut reader = EdfReader::open(filename).unwrap();
+    let tenths = reader.read_physical_samples_exact(0, 256, 1).unwrap();
+    let expected: Vec<i64> = (0..256).map(|i| (i - 128) * 3).collect();
+    assert_eq!(tenths, expected);
+    assert_eq!(reader.tell(0).unwrap(), 256);
+
+    // 纳伏
+    reader.rewind(0).unwrap();
+    let nanovolts = reader.read_physical_samples_exact(0, 2, 3).unwrap();
+    assert_eq!(nanovolts, vec![-38_400, -38_100]);
+
+    assert!(matches!(reader.read_physical_samples_exact(1, 1, 3), Err(EdfError::InvalidSignalIndex(1))));
+
+    drop(reader);
+    std::fs::remove_file(filename).ok();
+}
@@ -0,0 +1,132 @@
+use edfplus::digest::Sha256;
+use edfplus::{EdfReader, EdfWriter, SignalParam};
+use std::fs;
+use std::path::Path;
+
+// 清理测试文件的辅助函数
+fn cleanup_test_file(filename: &str) {
+    if Path::new(filename).exists() {
+        fs::remove_file(filename).ok();
+    }
+}
+
+fn create_signal(label: &str, physical_range: f64, samples_per_record: i32) -> SignalParam {
+    SignalParam {
+        label: label.to_string(),
+        samples_in_file: 0,
+        physical_max: physical_range,
+        physical_min: -physical_range,
+        digital_max: 32767,
+        digital_min: -32768,
+        samples_per_record,
+        physical_dimension: "uV".to_string(),
+        prefilter: "HP:0.1Hz".to_string(),
+        transducer: "AgAgCl".to_string(),
+        reserved: String::new(),
+    }
+}
+
+fn create_original(filename: &str) {
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.set_patient_info("P001", "F", "02-MAR-1975", "Original_Name").unwrap();
+    writer.add_signal(create_signal("EEG Fp1", 200.0, 100)).unwrap();
+    writer.add_signal(create_signal("Resp", 50.0, 10)).unwrap();
+    writer.add_annotation(0.5, None, "Start").unwrap();
+
+    for record in 0..5 {
+        let eeg: Vec<f64> = (0..100).map(|i| ((record * 100 + i) as f64 * 0.1).sin() * 150.0).collect();
+        let resp: Vec<f64> = (0..10).map(|i| (record * 10 + i) as f64 - 25.0).collect();
+        writer.write_samples(&[eeg, resp]).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+/// 以数字值逐记录复制，头部信息全部替换
+fn copy_with_new_header(source: &str, target: &str, modify: impl Fn(usize, &mut Vec<i32>)) {
+    let mut reader = EdfReader::open(source).unwrap();
+    let signals = reader.header().signals.clone();
+    let records = reader.header().datarecords_in_file as usize;
+
+    let mut writer = EdfWriter::create(target).unwrap();
+    writer.set_patient_info("X", "M", "X", "Renamed").unwrap();
+    for (i, signal) in signals.iter().enumerate() {
+        // 不同的标签与定标不影响摘要
+        let mut edited = create_signal(&format!("Channel {}", i), 1000.0, signal.samples_per_record);
+        edited.prefilter = String::new();
+        writer.add_signal(edited).unwrap();
+    }
+    writer.add_annotation(1.0, Some(2.0), "Edited event").unwrap();
+
+    for record in 0..records {
+        let mut data: Vec<Vec<i32>> = signals.iter().enumerate()
+            .map(|(i, signal)| reader.read_digital_samples(i, signal.samples_per_record as usize).unwrap())
+            .collect();
+        for samples in &mut data {
+            modify(record, samples);
+        }
+        writer.write_digital_samples(&data).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+#[test]
+fn test_bit_exact_copy_has_same_digest() {
+    let original = "test_digest_original.edf";
+    let copy = "test_digest_copy.edf";
+    create_original(original);
+    copy_with_new_header(original, copy, |_, _| {});
+
+    let mut a = EdfReader::open(original).unwrap();
+    let mut b = EdfReader::open(copy).unwrap();
+
+    // 头部确实不同
+    assert_ne!(a.raw_main_header()[..], b.raw_main_header()[..]);
+    assert_ne!(a.raw_signal_header(), b.raw_signal_header());
+
+    assert_eq!(a.data_digest().unwrap(), b.data_digest().unwrap());
+    for signal in 0..2 {
+        assert_eq!(a.signal_digest(signal).unwrap(), b.signal_digest(signal).unwrap());
+    }
+    assert_ne!(a.signal_digest(0).unwrap(), a.signal_digest(1).unwrap());
+
+    // 注释不同，包含注释时摘要不同
+    assert_ne!(
+        a.data_digest_with(Sha256::new(), true).unwrap(),
+        b.data_digest_with(Sha256::new(), true).unwrap()
+    );
+
+    // 计算摘要不影响样本读取位置
+    assert_eq!(a.tell(0).unwrap(), 0);
+    assert!(a.signal_digest(2).is_err());
+
+    drop(a);
+    drop(b);
+    cleanup_test_file(original);
+    cleanup_test_file(copy);
+}
+
+#[test]
+fn test_changed_sample_changes_digest() {
+    let original = "test_digest_changed_original.edf";
+    let changed = "test_digest_changed.edf";
+    create_original(original);
+
+    // 只修改最后一个记录中Resp信号的一个样本
+    copy_with_new_header(original, changed, |record, samples| {
+        if record == 4 && samples.len() == 10 {
+            samples[9] ^= 1;
+        }
+    });
+
+    let mut a = EdfReader::open(original).unwrap();
+    let mut b = EdfReader::open(changed).unwrap();
+
+    assert_ne!(a.data_digest().unwrap(), b.data_digest().unwrap());
+    assert_eq!(a.signal_digest(0).unwrap(), b.signal_digest(0).unwrap());
+    assert_ne!(a.signal_digest(1).unwrap(), b.signal_digest(1).unwrap());
+
+    drop(a);
+    drop(b);
+    cleanup_test_file(original);
+    cleanup_test_file(changed);
+}
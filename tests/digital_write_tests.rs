@@ -0,0 +1,207 @@
+use edfplus::{EdfError, EdfReader, EdfWriter, NarrowingPolicy, SignalParam};
+use std::fs;
+use std::ops::ControlFlow;
+use std::path::Path;
+
+// 清理测试文件的辅助函数
+fn cleanup_test_file(filename: &str) {
+    if Path::new(filename).exists() {
+        fs::remove_file(filename).ok();
+    }
+}
+
+fn create_test_signal(digital_min: i32, digital_max: i32) -> SignalParam {
+    SignalParam {
+        label: "EEG Fp1".to_string(),
+        samples_in_file: 0,
+        physical_max: 200.0,
+        physical_min: -200.0,
+        digital_max,
+        digital_min,
+        samples_per_record: 5,
+        physical_dimension: "uV".to_string(),
+        prefilter: "".to_string(),
+        transducer: "".to_string(),
+        reserved: String::new(),
+    }
+}
+
+const BOUNDARY_VALUES: [i32; 5] = [i32::MIN, i32::MAX, -32768, 32767, 0];
+
+#[test]
+fn test_reject_policy() {
+    let filename = "test_digital_reject.edf";
+    
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.add_signal(create_test_signal(-32768, 32767)).unwrap();
+    writer.add_signal(create_test_signal(-2048, 2047)).unwrap();
+    
+    // 默认策略为Reject，错误报告信号和索引
+    match writer.write_digital_samples(&[vec![0; 5], BOUNDARY_VALUES.to_vec()]) {
+        Err(EdfError::DigitalValueOutOfRange { signal, index, value, min, max }) => {
+            assert_eq!((signal, index, value, min, max), (1, 0, i32::MIN, -2048, 2047));
+        }
+        other => panic!("Expected DigitalValueOutOfRange, got {:?}", other),
+    }
+    match writer.write_digital_samples(&[vec![0, 0, 0, i32::MAX, 0], vec![0; 5]]) {
+        Err(EdfError::DigitalValueOutOfRange { signal, index, value, .. }) => {
+            assert_eq!((signal, index, value), (0, 3, i32::MAX));
+        }
+        other => panic!("Expected DigitalValueOutOfRange, got {:?}", other),
+    }
+    
+    // 范围边界上的值可以写入
+    writer.write_digital_samples(&[vec![-32768, 32767, 0, 1, -1], vec![-2048, 2047, 0, 1, -1]]).unwrap();
+    let summary = writer.finalize().unwrap();
+    assert_eq!(summary.datarecords, 1);
+    assert_eq!(summary.narrowed_samples, 0);
+    
+    let mut reader = EdfReader::open(filename).unwrap();
+    assert_eq!(reader.read_digital_samples(0, 5).unwrap(), vec![-32768, 32767, 0, 1, -1]);
+    assert_eq!(reader.read_digital_samples(1, 5).unwrap(), vec![-2048, 2047, 0, 1, -1]);
+    
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_saturate_policy() {
+    let filename = "test_digital_saturate.edf";
+    
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.add_signal(create_test_signal(-32768, 32767)).unwrap();
+    writer.add_signal(create_test_signal(-2048, 2047)).unwrap();
+    writer.set_narrowing_policy(NarrowingPolicy::Saturate).unwrap();
+    
+    writer.write_digital_samples(&[BOUNDARY_VALUES.to_vec(), BOUNDARY_VALUES.to_vec()]).unwrap();
+    let summary = writer.finalize().unwrap();
+    
+    // 信号0：i32::MIN/MAX被钳位；信号1：另外两个16位边界值也被钳位
+    assert_eq!(summary.narrowed_samples, 2 + 4);
+    
+    let mut reader = EdfReader::open(filename).unwrap();
+    assert_eq!(reader.read_digital_samples(0, 5).unwrap(), vec![-32768, 32767, -32768, 32767, 0]);
+    assert_eq!(reader.read_digital_samples(1, 5).unwrap(), vec![-2048, 2047, -2048, 2047, 0]);
+    
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_scale_policy() {
+    let filename = "test_digital_scale.edf";
+    
+    let mut writer = EdfWriter::create(filename).unwrap();
+    // 源数据使用完整的i32范围
+    writer.add_signal(create_test_signal(i32::MIN, i32::MAX)).unwrap();
+    // 已经适合16位的信号保持不变
+    writer.add_signal(create_test_signal(-2048, 2047)).unwrap();
+    writer.set_narrowing_policy(NarrowingPolicy::Scale).unwrap();
+    
+    writer.write_digital_samples(&[
+        BOUNDARY_VALUES.to_vec(),
+        vec![-2048, 2047, 0, i32::MAX, i32::MIN],
+    ]).unwrap();
+    
+    // 写入头部后不能再切换Scale策略
+    assert!(writer.set_narrowing_policy(NarrowingPolicy::Saturate).is_err());
+    assert!(writer.set_narrowing_policy(NarrowingPolicy::Scale).is_ok());
+    
+    let summary = writer.finalize().unwrap();
+    // 信号0除了0以外都被缩放；信号1只有超出范围的两个值被钳位
+    assert_eq!(summary.narrowed_samples, 4 + 2);
+    
+    let mut reader = EdfReader::open(filename).unwrap();
+    
+    // 头部的数字范围调整为16位，物理范围保持不变
+    let signal = reader.header().signals[0].clone();
+    assert_eq!((signal.digital_min, signal.digital_max), (-32768, 32767));
+    assert_eq!((signal.physical_min, signal.physical_max), (-200.0, 200.0));
+    
+    assert_eq!(reader.read_digital_samples(0, 5).unwrap(), vec![-32768, 32767, -1, 0, 0]);
+    assert_eq!(reader.read_digital_samples(1, 5).unwrap(), vec![-2048, 2047, 0, 2047, -2048]);
+    
+    // 物理值与源数据的定标一致
+    reader.rewind(0).unwrap();
+    let physical = reader.read_physical_samples(0, 5).unwrap();
+    assert!((physical[0] + 200.0).abs() < 1e-9);
+    assert!((physical[1] - 200.0).abs() < 1e-9);
+    assert!(physical[4].abs() < 0.01);
+    
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_scale_policy_rejected_after_header() {
+    let filename = "test_digital_scale_late.edf";
+    
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.add_signal(create_test_signal(-32768, 32767)).unwrap();
+    writer.write_samples(&[vec![0.0; 5]]).unwrap();
+    
+    assert!(matches!(
+        writer.set_narrowing_policy(NarrowingPolicy::Scale),
+        Err(EdfError::InvalidFormat(_))
+    ));
+    writer.set_narrowing_policy(NarrowingPolicy::Saturate).unwrap();
+    writer.finalize().unwrap();
+    
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_for_each_digital_chunk() {
+    let filename = "test_digital_chunks.edf";
+    
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.add_signal(create_test_signal(-32768, 32767)).unwrap();
+    writer.add_signal(create_test_signal(-2048, 2047)).unwrap();
+    for record in 0..3 {
+        let values: Vec<i32> = (0..5).map(|i| record * 5 + i).collect();
+        writer.write_digital_samples(&[values, vec![-1; 5]]).unwrap();
+    }
+    writer.finalize().unwrap();
+    
+    let mut reader = EdfReader::open(filename).unwrap();
+    
+    // 每个记录内的连续片段各回调一次
+    let mut chunks = Vec::new();
+    let visited = reader.for_each_digital_chunk(0, 3, 9, |chunk| {
+        chunks.push(chunk.to_vec());
+        ControlFlow::Continue(())
+    }).unwrap();
+    assert_eq!(visited, 9);
+    assert_eq!(chunks, vec![vec![3, 4], vec![5, 6, 7, 8, 9], vec![10, 11]]);
+    assert_eq!(reader.tell(0).unwrap(), 12);
+    
+    // 提前结束时位置停在已访问的样本之后
+    let mut calls = 0;
+    let visited = reader.for_each_digital_chunk(0, 1, usize::MAX, |_| {
+        calls += 1;
+        if calls == 2 { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+    }).unwrap();
+    assert_eq!(visited, 9);
+    assert_eq!(reader.tell(0).unwrap(), 10);
+    
+    // 与逐样本读取的结果一致
+    let mut all = Vec::new();
+    let visited = reader.for_each_digital_chunk(1, 0, usize::MAX, |chunk| {
+        all.extend(chunk.iter().map(|&v| v as i32));
+        ControlFlow::Continue(())
+    }).unwrap();
+    assert_eq!(visited, 15);
+    reader.rewind(1).unwrap();
+    assert_eq!(all, reader.read_digital_samples(1, 15).unwrap());
+    
+    // 起始位置越界时不回调
+    let visited = reader.for_each_digital_chunk(0, 100, 5, |_| panic!("no samples left")).unwrap();
+    assert_eq!(visited, 0);
+    assert!(matches!(
+        reader.for_each_digital_chunk(2, 0, 5, |_| ControlFlow::Continue(())),
+        Err(EdfError::InvalidSignalIndex(2))
+    ));
+    
+    drop(reader);
+    cleanup_test_file(filename);
+}
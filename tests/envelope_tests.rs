@@ -0,0 +1,107 @@
+use edfplus::{EdfError, EdfReader, EdfWriter, SignalParam};
+use std::fs;
+use std::path::Path;
+
+// 清理测试文件的辅助函数
+fn cleanup_test_file(filename: &str) {
+    if Path::new(filename).exists() {
+        fs::remove_file(filename).ok();
+    }
+}
+
+fn create_signal(label: &str, physical_max: f64, physical_min: f64, samples_per_record: i32) -> SignalParam {
+    SignalParam {
+        label: label.to_string(),
+        samples_in_file: 0,
+        physical_max,
+        physical_min,
+        digital_max: 32767,
+        digital_min: -32768,
+        samples_per_record,
+        physical_dimension: "uV".to_string(),
+        prefilter: "".to_string(),
+        transducer: "".to_string(),
+        reserved: String::new(),
+    }
+}
+
+/// 直接计算的参考包络
+fn reference_envelope(samples: &[f64], buckets: usize) -> Vec<(f64, f64)> {
+    let buckets = buckets.min(samples.len());
+    (0..buckets)
+        .map(|b| {
+            let bucket = &samples[samples.len() * b / buckets..samples.len() * (b + 1) / buckets];
+            let min = bucket.iter().cloned().fold(f64::INFINITY, f64::min);
+            let max = bucket.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+            (min, max)
+        })
+        .collect()
+}
+
+#[test]
+fn test_envelope_matches_reference() {
+    let filename = "test_envelope_reference.edf";
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.add_signal(create_signal("EEG", 100.0, -100.0, 37)).unwrap();
+    // 反相信号：数字最小值对应物理最大值
+    writer.add_signal(create_signal("Inverted", -50.0, 50.0, 10)).unwrap();
+    for record in 0..6 {
+        let eeg: Vec<f64> = (0..37).map(|i| ((record * 37 + i) as f64 * 0.3).sin() * 90.0).collect();
+        let inverted: Vec<f64> = (0..10).map(|i| ((record * 10 + i) as f64 * 0.7).cos() * 40.0).collect();
+        writer.write_samples(&[eeg, inverted]).unwrap();
+    }
+    writer.finalize().unwrap();
+
+    let mut reader = EdfReader::open(filename).unwrap();
+    for signal in 0..2 {
+        let total = reader.header().signals[signal].samples_in_file;
+        let samples = reader.read_physical_samples(signal, total as usize).unwrap();
+        let position = reader.tell(signal).unwrap();
+
+        for (start, end) in [(0, total), (5, total - 3), (36, 38), (12, 13)] {
+            for buckets in [1, 3, 7, 16, 100] {
+                let envelope = reader.read_envelope(signal, start, end, buckets).unwrap();
+                let expected = reference_envelope(&samples[start as usize..end as usize], buckets);
+                assert_eq!(envelope, expected, "signal {} range {}..{} buckets {}", signal, start, end, buckets);
+                assert!(envelope.iter().all(|(min, max)| min <= max));
+            }
+        }
+
+        // 不改变读取位置
+        assert_eq!(reader.tell(signal).unwrap(), position);
+    }
+
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_envelope_edge_cases() {
+    let filename = "test_envelope_edges.edf";
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.add_signal(create_signal("EEG", 100.0, -100.0, 20)).unwrap();
+    for _ in 0..2 {
+        writer.write_samples(&[vec![25.0; 20]]).unwrap();
+    }
+    writer.finalize().unwrap();
+
+    let mut reader = EdfReader::open(filename).unwrap();
+
+    // 样本少于桶数时每个样本一个桶
+    let envelope = reader.read_envelope(0, 18, 23, 50).unwrap();
+    assert_eq!(envelope.len(), 5);
+    assert!(envelope.iter().all(|(min, max)| min == max));
+
+    // 空范围与零个桶
+    assert!(reader.read_envelope(0, 10, 10, 8).unwrap().is_empty());
+    assert!(reader.read_envelope(0, 0, 40, 0).unwrap().is_empty());
+
+    // 无效参数
+    assert!(matches!(reader.read_envelope(0, 0, 41, 8), Err(EdfError::InvalidSampleIndex { index: 41, count: 40 })));
+    assert!(matches!(reader.read_envelope(0, 10, 5, 8), Err(EdfError::InvalidSampleIndex { index: 5, .. })));
+    assert!(matches!(reader.read_envelope(0, -1, 5, 8), Err(EdfError::InvalidSampleIndex { index: -1, .. })));
+    assert!(matches!(reader.read_envelope(1, 0, 5, 8), Err(EdfError::InvalidSignalIndex(1))));
+
+    drop(reader);
+    cleanup_test_file(filename);
+}
@@ -0,0 +1,116 @@
+use edfplus::info::{IntegrityFinding, SignalSummary};
+use edfplus::{file_info, EdfWriter, FileType, SignalParam};
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+// 清理测试文件的辅助函数
+fn cleanup_test_file(filename: &str) {
+    if Path::new(filename).exists() {
+        fs::remove_file(filename).ok();
+    }
+}
+
+fn create_test_signal(label: &str, samples_per_record: i32) -> SignalParam {
+    SignalParam {
+        label: label.to_string(),
+        samples_in_file: 0,
+        physical_max: 200.0,
+        physical_min: -200.0,
+        digital_max: 32767,
+        digital_min: -32768,
+        samples_per_record,
+        physical_dimension: "uV".to_string(),
+        prefilter: "HP:0.1Hz".to_string(),
+        transducer: "AgAgCl".to_string(),
+        reserved: String::new(),
+    }
+}
+
+// 创建一个5秒、两个信号、带subsecond开始时间的文件
+fn create_test_file(filename: &str) {
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.set_subsecond_starttime(5_000_000).unwrap();
+    writer.add_signal(create_test_signal("EEG Fp1", 256)).unwrap();
+    writer.add_signal(create_test_signal("EOG", 64)).unwrap();
+    writer.add_annotation(1.0, None, "Event").unwrap();
+    for _ in 0..5 {
+        writer.write_samples(&[vec![0.0; 256], vec![0.0; 64]]).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+#[test]
+fn test_file_info_summary() {
+    let filename = "test_file_info_summary.edf";
+    create_test_file(filename);
+    
+    let info = file_info(filename).unwrap();
+    assert_eq!(info.path, Path::new(filename));
+    assert_eq!(info.file_size, fs::metadata(filename).unwrap().len());
+    assert_eq!(info.format, FileType::EdfPlusContinuous);
+    assert_eq!(info.duration, 5 * 10_000_000);
+    assert_eq!(info.datarecord_duration, 10_000_000);
+    assert_eq!(info.datarecords, 5);
+    assert_eq!(info.signals, vec![
+        SignalSummary { label: "EEG Fp1".to_string(), sample_rate: 256.0, physical_dimension: "uV".to_string() },
+        SignalSummary { label: "EOG".to_string(), sample_rate: 64.0, physical_dimension: "uV".to_string() },
+    ]);
+    assert_eq!(info.annotation_count, None);
+    assert!(info.is_consistent());
+    
+    // 开始时间包含subsecond部分
+    assert_eq!(info.start.and_utc().timestamp_subsec_millis(), 500);
+    assert_eq!((info.end - info.start).num_seconds(), 5);
+    
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_file_info_truncated() {
+    let filename = "test_file_info_truncated.edf";
+    create_test_file(filename);
+    
+    let full_size = fs::metadata(filename).unwrap().len();
+    let record_size = (256 + 64) * 2 + 120;
+    let file = OpenOptions::new().write(true).open(filename).unwrap();
+    file.set_len(full_size - record_size - 10).unwrap();
+    drop(file);
+    
+    let info = file_info(filename).unwrap();
+    assert!(!info.is_consistent());
+    assert_eq!(info.findings, vec![IntegrityFinding::Truncated {
+        expected_bytes: full_size,
+        actual_bytes: full_size - record_size - 10,
+        complete_records: 3,
+    }]);
+    // 头部声明的信息保持不变
+    assert_eq!(info.datarecords, 5);
+    
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_file_info_trailing_bytes() {
+    let filename = "test_file_info_trailing.edf";
+    create_test_file(filename);
+    
+    let full_size = fs::metadata(filename).unwrap().len();
+    let mut file = OpenOptions::new().append(true).open(filename).unwrap();
+    file.write_all(&[0u8; 7]).unwrap();
+    drop(file);
+    
+    let info = file_info(filename).unwrap();
+    assert_eq!(info.findings, vec![IntegrityFinding::TrailingBytes {
+        expected_bytes: full_size,
+        actual_bytes: full_size + 7,
+    }]);
+    
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_file_info_missing_file() {
+    assert!(file_info("does_not_exist_file_info.edf").is_err());
+}
@@ -0,0 +1,129 @@
+use edfplus::frames::SlowerSignals;
+use edfplus::{EdfError, EdfReader, EdfWriter, SignalParam};
+use std::fs;
+use std::path::Path;
+
+// 清理测试文件的辅助函数
+fn cleanup_test_file(filename: &str) {
+    if Path::new(filename).exists() {
+        fs::remove_file(filename).ok();
+    }
+}
+
+fn create_signal(label: &str, samples_per_record: i32) -> SignalParam {
+    SignalParam {
+        label: label.to_string(),
+        samples_in_file: 0,
+        physical_max: 1000.0,
+        physical_min: -1000.0,
+        digital_max: 32767,
+        digital_min: -32768,
+        samples_per_record,
+        physical_dimension: "uV".to_string(),
+        prefilter: "".to_string(),
+        transducer: "".to_string(),
+        reserved: String::new(),
+    }
+}
+
+/// 三个信号：8、4、3个样本每记录，样本值等于其在文件中的序号
+fn create_mixed_rate_file(filename: &str, records: usize) {
+    let rates = [8usize, 4, 3];
+    let mut writer = EdfWriter::create(filename).unwrap();
+    for (i, &rate) in rates.iter().enumerate() {
+        writer.add_signal(create_signal(&format!("S{}", i), rate as i32)).unwrap();
+    }
+    for record in 0..records {
+        let data: Vec<Vec<f64>> = rates.iter()
+            .map(|&rate| (0..rate).map(|i| (record * rate + i) as f64).collect())
+            .collect();
+        writer.write_samples(&data).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+fn round(values: &[Option<f64>]) -> Vec<Option<i64>> {
+    values.iter().map(|v| v.map(|v| v.round() as i64)).collect()
+}
+
+#[test]
+fn test_frames_sample_and_hold() {
+    let filename = "test_frames_hold.edf";
+    create_mixed_rate_file(filename, 3);
+
+    let mut reader = EdfReader::open(filename).unwrap();
+    let frames: Vec<_> = reader.iter_frames(&[2, 0, 1]).unwrap()
+        .collect::<Result<_, _>>().unwrap();
+    assert_eq!(frames.len(), 24);
+
+    for (k, frame) in frames.iter().enumerate() {
+        let (record, offset) = (k / 8, k % 8);
+        assert_eq!(frame.time_100ns, k as i64 * 1_250_000);
+
+        // 每个信号取帧时间之前（含）的最后一个样本
+        let expected = vec![
+            Some((record * 3 + offset * 3 / 8) as i64),
+            Some(k as i64),
+            Some((record * 4 + offset / 2) as i64),
+        ];
+        assert_eq!(round(&frame.values), expected, "frame {}", k);
+    }
+
+    // 不影响按样本读取的位置
+    assert_eq!(reader.tell(0).unwrap(), 0);
+
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_frames_missing_reports_each_sample_once() {
+    let filename = "test_frames_missing.edf";
+    create_mixed_rate_file(filename, 2);
+
+    let mut reader = EdfReader::open(filename).unwrap();
+    let frames: Vec<_> = reader.iter_frames_with(&[0, 1, 2], SlowerSignals::Missing).unwrap()
+        .collect::<Result<_, _>>().unwrap();
+    assert_eq!(frames.len(), 16);
+
+    for (signal, rate) in [(0, 8), (1, 4), (2, 3)] {
+        let reported: Vec<(usize, i64)> = frames.iter().enumerate()
+            .filter_map(|(k, frame)| frame.values[signal].map(|v| (k, v.round() as i64)))
+            .collect();
+
+        // 每个样本恰好出现一次且按顺序
+        let values: Vec<i64> = reported.iter().map(|&(_, v)| v).collect();
+        assert_eq!(values, (0..2 * rate).collect::<Vec<i64>>(), "signal {}", signal);
+
+        // 出现在样本时间之后（含）的第一帧
+        for &(k, v) in &reported {
+            let first_frame = (v * 8 + rate - 1) / rate;
+            assert_eq!(k as i64, first_frame, "signal {} sample {}", signal, v);
+        }
+    }
+
+    // 整数倍关系的信号正好落在帧上
+    assert_eq!(round(&frames[2].values), vec![Some(2), Some(1), None]);
+    assert_eq!(round(&frames[3].values), vec![Some(3), None, Some(1)]);
+
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_frames_invalid_and_empty_selection() {
+    let filename = "test_frames_invalid.edf";
+    create_mixed_rate_file(filename, 1);
+
+    let mut reader = EdfReader::open(filename).unwrap();
+    assert!(matches!(reader.iter_frames(&[0, 3]), Err(EdfError::InvalidSignalIndex(3))));
+    assert_eq!(reader.iter_frames(&[]).unwrap().count(), 0);
+
+    // 单个信号时每帧即一个样本
+    let frames: Vec<_> = reader.iter_frames(&[2]).unwrap().map(Result::unwrap).collect();
+    assert_eq!(frames.len(), 3);
+    assert_eq!(frames[1].time_100ns, 3_333_333);
+
+    drop(reader);
+    cleanup_test_file(filename);
+}
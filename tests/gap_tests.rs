@@ -0,0 +1,145 @@
+use edfplus::{EdfError, EdfReader, EdfWriter, FileType, Gap, SignalParam};
+use edfplus::doctest_utils::create_discontinuous_test_file;
+use std::fs;
+use std::path::Path;
+
+// 清理测试文件的辅助函数
+fn cleanup_test_file(filename: &str) {
+    if Path::new(filename).exists() {
+        fs::remove_file(filename).ok();
+    }
+}
+
+// 10 个 1 秒记录，样本值等于样本序号
+fn create_counter_file(filename: &str) {
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.add_signal(SignalParam {
+        label: "Counter".to_string(),
+        samples_in_file: 0,
+        physical_max: 32767.0,
+        physical_min: -32768.0,
+        digital_max: 32767,
+        digital_min: -32768,
+        samples_per_record: 100,
+        physical_dimension: "".to_string(),
+        prefilter: "".to_string(),
+        transducer: "".to_string(),
+        reserved: String::new(),
+    }).unwrap();
+    for record in 0..10 {
+        writer.write_samples(&[(0..100).map(|i| (record * 100 + i) as f64).collect()]).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+#[test]
+fn test_continuous_file_has_no_gaps() {
+    let filename = "test_gaps_continuous.edf";
+    create_counter_file(filename);
+    let mut reader = EdfReader::open(filename).unwrap();
+    
+    assert!(reader.gaps().unwrap().is_empty());
+    assert!(!reader.is_gap_at(4.5).unwrap());
+    
+    // 跨越记录边界的窗口
+    let window = reader.read_window(0, 2.5, 1.0).unwrap();
+    assert_eq!(window, (250..350).map(|v| v as f64).collect::<Vec<_>>());
+    
+    let with_gaps = reader.read_window_with_gaps(0, 2.5, 1.0).unwrap();
+    assert!(with_gaps.gaps.is_empty());
+    assert_eq!(with_gaps.parts.len(), 1);
+    assert_eq!((with_gaps.parts[0].start, with_gaps.parts[0].first_sample), (25_000_000, 250));
+    assert_eq!(with_gaps.parts[0].samples, window);
+    
+    // 在记录末尾截断，读取位置不变
+    assert_eq!(reader.read_window(0, 9.5, 5.0).unwrap().len(), 50);
+    assert!(reader.read_window(0, 12.0, 1.0).unwrap().is_empty());
+    assert_eq!(reader.tell(0).unwrap(), 0);
+    
+    assert!(matches!(reader.read_window(0, -1.0, 1.0), Err(EdfError::InvalidFormat(_))));
+    assert!(matches!(reader.read_window(3, 0.0, 1.0), Err(EdfError::InvalidSignalIndex(3))));
+    drop(reader);
+    
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_discontinuous_file_lists_gaps() {
+    let filename = "test_gaps_discontinuous.edf";
+    create_discontinuous_test_file(filename).unwrap();
+    let mut reader = EdfReader::open(filename).unwrap();
+    assert_eq!(reader.header().file_type, FileType::EdfPlusDiscontinuous);
+    
+    assert_eq!(reader.gaps().unwrap(), vec![
+        Gap { after_record: 3, start: 40_000_000, duration: 20_000_000 },
+        Gap { after_record: 6, start: 90_000_000, duration: 11_990_000_000 },
+    ]);
+    
+    // 间隙是左闭右开区间
+    for (seconds, in_gap) in [
+        (3.99, false), (4.0, true), (5.5, true), (6.0, false),
+        (8.99, false), (9.0, true), (600.0, true), (1207.99, true), (1208.0, false), (1211.0, false),
+    ] {
+        assert_eq!(reader.is_gap_at(seconds).unwrap(), in_gap, "at {}s", seconds);
+    }
+    drop(reader);
+    
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_read_window_refuses_to_cross_gaps() {
+    let filename = "test_gaps_read_window.edf";
+    create_discontinuous_test_file(filename).unwrap();
+    let mut reader = EdfReader::open(filename).unwrap();
+    
+    // 段内窗口按时间读取，而不是按样本序号
+    assert_eq!(reader.read_window(0, 6.5, 1.0).unwrap(), (450..550).map(|v| v as f64).collect::<Vec<_>>());
+    assert_eq!(reader.read_window(0, 1209.0, 1.0).unwrap(), (800..900).map(|v| v as f64).collect::<Vec<_>>());
+    
+    // 错误中报告的是窗口内的那部分间隙
+    match reader.read_window(0, 3.5, 3.0) {
+        Err(EdfError::WindowCrossesGap { start, duration }) => assert_eq!((start, duration), (40_000_000, 20_000_000)),
+        other => panic!("expected WindowCrossesGap, got {:?}", other),
+    }
+    match reader.read_window(0, 100.0, 10.0) {
+        Err(EdfError::WindowCrossesGap { start, duration }) => assert_eq!((start, duration), (1_000_000_000, 100_000_000)),
+        other => panic!("expected WindowCrossesGap, got {:?}", other),
+    }
+    assert_eq!(reader.tell(0).unwrap(), 0);
+    drop(reader);
+    
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_read_window_with_gaps_marks_missing_spans() {
+    let filename = "test_gaps_window_with_gaps.edf";
+    create_discontinuous_test_file(filename).unwrap();
+    let mut reader = EdfReader::open(filename).unwrap();
+    
+    let window = reader.read_window_with_gaps(0, 3.5, 3.0).unwrap();
+    assert_eq!(window.gaps, vec![Gap { after_record: 3, start: 40_000_000, duration: 20_000_000 }]);
+    assert_eq!(window.parts.len(), 2);
+    assert_eq!((window.parts[0].start, window.parts[0].first_sample), (35_000_000, 350));
+    assert_eq!(window.parts[0].samples, (350..400).map(|v| v as f64).collect::<Vec<_>>());
+    assert_eq!((window.parts[1].start, window.parts[1].first_sample), (60_000_000, 400));
+    assert_eq!(window.parts[1].samples, (400..450).map(|v| v as f64).collect::<Vec<_>>());
+    
+    // 跨越两个间隙的窗口，两端的间隙被窗口截断
+    let window = reader.read_window_with_gaps(0, 3.5, 1205.0).unwrap();
+    assert_eq!(window.gaps, vec![
+        Gap { after_record: 3, start: 40_000_000, duration: 20_000_000 },
+        Gap { after_record: 6, start: 90_000_000, duration: 11_990_000_000 },
+    ]);
+    let spans: Vec<_> = window.parts.iter().map(|part| (part.start, part.first_sample, part.samples.len())).collect();
+    assert_eq!(spans, vec![(35_000_000, 350, 50), (60_000_000, 400, 300), (12_080_000_000, 700, 50)]);
+    
+    // 完全落在间隙中的窗口没有数据
+    let window = reader.read_window_with_gaps(0, 10.0, 5.0).unwrap();
+    assert!(window.parts.is_empty());
+    assert_eq!(window.gaps, vec![Gap { after_record: 6, start: 100_000_000, duration: 50_000_000 }]);
+    drop(reader);
+    
+    cleanup_test_file(filename);
+}
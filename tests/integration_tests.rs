@@ -22,6 +22,7 @@ fn create_test_eeg_signal() -> SignalParam {
         physical_dimension: "uV".to_string(),
         prefilter: "HP:0.1Hz LP:70Hz".to_string(),
         transducer: "AgAgCl electrodes".to_string(),
+        reserved: String::new(),
     }
 }
 
@@ -37,6 +38,7 @@ fn create_test_ecg_signal() -> SignalParam {
         physical_dimension: "mV".to_string(),
         prefilter: "HP:0.1Hz LP:100Hz".to_string(),
         transducer: "Chest electrodes".to_string(),
+        reserved: String::new(),
     }
 }
 
@@ -221,6 +223,7 @@ fn test_different_sampling_rates() {
             physical_dimension: "degC".to_string(),
             prefilter: "None".to_string(),
             transducer: "Thermistor".to_string(),
+            reserved: String::new(),
         };
         writer.add_signal(low_freq_signal).unwrap();
         
@@ -373,7 +376,7 @@ fn test_digital_vs_physical_values() {
         writer.add_signal(signal).unwrap();
         
         // 写入已知的物理值
-        let known_values = vec![
+        let known_values = [
             0.0,      // 零点
             100.0,    // 正最大值
             -100.0,   // 负最大值
@@ -412,7 +415,7 @@ fn test_digital_vs_physical_values() {
         
         
         // 验证已知值的转换
-        let known_values = vec![0.0, 100.0, -100.0, 50.0, -50.0];
+        let known_values = [0.0, 100.0, -100.0, 50.0, -50.0];
         
         for (i, &expected_physical) in known_values.iter().enumerate() {
             let actual_physical = physical_samples[i];
@@ -437,7 +440,7 @@ fn test_digital_vs_physical_values() {
         } else {
             println!("Skipping digital/physical comparison due to length mismatch");
             // 至少验证物理数据是合理的
-            let known_values = vec![0.0, 100.0, -100.0, 50.0, -50.0];
+            let known_values = [0.0, 100.0, -100.0, 50.0, -50.0];
             for (i, &expected_physical) in known_values.iter().enumerate() {
                 let actual_physical = physical_samples[i];
                 let tolerance = 0.01;
@@ -450,3 +453,401 @@ fn test_digital_vs_physical_values() {
     
     cleanup_test_file(filename);
 }
+
+#[test]
+fn test_sample_datetime_mapping() {
+    let filename = "test_sample_datetime.edf";
+    
+    {
+        let mut writer = EdfWriter::create(filename).unwrap();
+        writer.set_subsecond_starttime(2_500_000).unwrap(); // 0.25秒
+        
+        // 奇数采样率，无法整除记录时长
+        let mut odd_signal = create_test_eeg_signal();
+        odd_signal.samples_per_record = 333;
+        writer.add_signal(odd_signal).unwrap();
+        
+        let mut slow_signal = create_test_ecg_signal();
+        slow_signal.samples_per_record = 7;
+        writer.add_signal(slow_signal).unwrap();
+        
+        for _ in 0..3 {
+            writer.write_samples(&[vec![0.0; 333], vec![0.0; 7]]).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+    
+    let reader = EdfReader::open(filename).unwrap();
+    let header = reader.header();
+    let start = header.start_datetime();
+    assert_eq!(header.starttime_subsecond, 2_500_000);
+    assert_eq!(start.and_utc().timestamp_subsec_millis(), 250);
+    
+    // 每个样本位置都应能精确往返
+    for signal in 0..2 {
+        let total = header.signals[signal].samples_in_file;
+        for position in 0..=total {
+            let datetime = reader.sample_datetime(signal, position).unwrap();
+            assert_eq!(reader.datetime_to_sample(signal, datetime).unwrap(), position);
+            
+            // 两个样本之间的时间落在前一个样本上
+            let just_before_next = reader.sample_datetime(signal, position + 1).unwrap()
+                - chrono::TimeDelta::nanoseconds(1);
+            if position < total {
+                assert_eq!(reader.datetime_to_sample(signal, just_before_next).unwrap(), position);
+            }
+        }
+    }
+    
+    // 333 Hz: 第一个记录末尾的样本
+    let t = reader.sample_datetime(0, 333).unwrap();
+    assert_eq!(t - start, chrono::TimeDelta::seconds(1));
+    let t = reader.sample_datetime(0, 1).unwrap();
+    assert_eq!((t - start).num_nanoseconds().unwrap(), 3_003_003); // 1/333秒，四舍五入到纳秒
+    
+    // 范围之外与 seek 一致地夹紧
+    let before = start - chrono::TimeDelta::seconds(10);
+    let after = start + chrono::TimeDelta::days(1);
+    assert_eq!(reader.datetime_to_sample(0, before).unwrap(), 0);
+    assert_eq!(reader.datetime_to_sample(0, after).unwrap(), 999);
+    assert_eq!(reader.sample_datetime(1, -5).unwrap(), start);
+    assert_eq!(reader.sample_datetime(1, 1000).unwrap(), start + chrono::TimeDelta::seconds(3));
+    
+    assert!(reader.sample_datetime(2, 0).is_err());
+    assert!(reader.datetime_to_sample(2, start).is_err());
+    
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_raw_header_bytes() {
+    let filename = "test_raw_header.edf";
+    
+    {
+        let mut writer = EdfWriter::create(filename).unwrap();
+        writer.set_patient_info("RAW01", "F", "02-MAY-1951", "Raw Header").unwrap();
+        writer.add_signal(create_test_eeg_signal()).unwrap();
+        writer.add_signal(create_test_ecg_signal()).unwrap();
+        for _ in 0..2 {
+            writer.write_samples(&[vec![0.0; 256], vec![0.0; 256]]).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+    
+    let file_bytes = fs::read(filename).unwrap();
+    let reader = EdfReader::open(filename).unwrap();
+    
+    // 与磁盘上的字节完全一致（包括finalize时回写的记录数）
+    assert_eq!(&reader.raw_main_header()[..], &file_bytes[..256]);
+    assert_eq!(reader.raw_signal_header().len(), 3 * 256);
+    assert_eq!(reader.raw_signal_header(), &file_bytes[256..256 + 3 * 256]);
+    
+    let main = reader.raw_main_header();
+    assert_eq!(String::from_utf8_lossy(&main[edfplus::header::DATARECORDS]).trim(), "2");
+    
+    use edfplus::header::SignalField;
+    let raw = reader.raw_signal_header();
+    assert_eq!(&raw[SignalField::Label.range(3, 1)], b"ECG Lead II     ");
+    assert_eq!(&raw[SignalField::Label.range(3, 2)], b"EDF Annotations ");
+    assert_eq!(String::from_utf8_lossy(&raw[SignalField::PhysicalMax.range(3, 1)]).trim(), "5");
+    assert_eq!(String::from_utf8_lossy(&raw[SignalField::SamplesPerRecord.range(3, 2)]).trim(), "60");
+    
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_read_record() {
+    let filename = "test_read_record.edf";
+    
+    {
+        let mut writer = EdfWriter::create(filename).unwrap();
+        let mut eeg = create_test_eeg_signal();
+        eeg.samples_per_record = 100;
+        let mut ecg = create_test_ecg_signal();
+        ecg.samples_per_record = 50;
+        writer.add_signal(eeg).unwrap();
+        writer.add_signal(ecg).unwrap();
+        writer.add_annotation(1.25, Some(0.5), "In record 1").unwrap();
+        for record in 0..3 {
+            let eeg_samples: Vec<f64> = (0..100).map(|i| (record * 100 + i) as f64).collect();
+            let ecg_samples = vec![record as f64; 50];
+            writer.write_samples(&[eeg_samples, ecg_samples]).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+    
+    let mut reader = EdfReader::open(filename).unwrap();
+    reader.seek(0, 17).unwrap();
+    
+    let record = reader.read_record(1).unwrap();
+    assert_eq!(record.index, 1);
+    assert_eq!(record.timestamp, Some(10_000_000));
+    assert_eq!(record.signals.len(), 2);
+    assert_eq!(record.signal(0).unwrap().len(), 100);
+    assert_eq!(record.signal(1).unwrap().len(), 50);
+    assert!(record.signal(2).is_none());
+    assert!((record.signals[0][0] - 100.0).abs() < 0.01);
+    assert!((record.signals[0][99] - 199.0).abs() < 0.01);
+    assert!(record.signals[1].iter().all(|&v| (v - 1.0).abs() < 0.001));
+    assert_eq!(record.annotations.len(), 1);
+    assert_eq!(record.annotations[0].description, "In record 1");
+    assert_eq!(record.annotations[0].onset, 12_500_000);
+    
+    // 与按样本读取的结果一致，且不改变读取位置
+    assert_eq!(reader.tell(0).unwrap(), 17);
+    reader.seek(0, 100).unwrap();
+    assert_eq!(reader.read_physical_samples(0, 100).unwrap(), record.signals[0]);
+    
+    let first = reader.read_record(0).unwrap();
+    assert_eq!(first.timestamp, Some(0));
+    assert!(first.annotations.is_empty());
+    
+    // 越界索引报告有效范围
+    match reader.read_record(3) {
+        Err(edfplus::EdfError::InvalidRecordIndex { index, count }) => {
+            assert_eq!((index, count), (3, 3));
+        }
+        other => panic!("expected InvalidRecordIndex, got {:?}", other),
+    }
+    assert!(reader.read_record(-1).is_err());
+    
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_structured_patient_info_round_trip() {
+    use edfplus::{PatientInfo, Sex};
+    let filename = "test_patient_info_struct.edf";
+    
+    {
+        let mut writer = EdfWriter::create(filename).unwrap();
+        writer.set_patient(&PatientInfo {
+            code: Some("MCH-0234567".to_string()),
+            sex: Some(Sex::Female),
+            birthdate: chrono::NaiveDate::from_ymd_opt(1951, 5, 2),
+            name: Some("Haagse Harry".to_string()),
+            additional: None,
+        }).unwrap();
+        writer.add_signal(create_test_eeg_signal()).unwrap();
+        writer.write_samples(&[vec![0.0; 256]]).unwrap();
+        writer.finalize().unwrap();
+    }
+    
+    let reader = EdfReader::open(filename).unwrap();
+    let raw = String::from_utf8_lossy(&reader.raw_main_header()[edfplus::header::PATIENT]).to_string();
+    assert!(raw.starts_with("MCH-0234567 F 02-MAY-1951 Haagse_Harry X"));
+    
+    // 名字中的空格不再使后续子字段错位
+    let header = reader.header();
+    assert_eq!(header.patient_code, "MCH-0234567");
+    assert_eq!(header.sex, "F");
+    assert_eq!(header.birthdate, "02-MAY-1951");
+    assert_eq!(header.patient_name, "Haagse_Harry");
+    assert_eq!(header.patient_additional.trim_end_matches('\0'), "X");
+    
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_file_type_detection() {
+    use edfplus::{EdfError, FileType};
+    use std::io::{Seek, SeekFrom, Write};
+    
+    let filename = "test_file_type_detection.edf";
+    
+    {
+        let mut writer = EdfWriter::create(filename).unwrap();
+        writer.add_signal(create_test_eeg_signal()).unwrap();
+        assert_eq!(writer.file_type(), FileType::EdfPlusContinuous);
+        writer.write_samples(&[vec![0.0; 256]]).unwrap();
+        writer.finalize().unwrap();
+    }
+    
+    let reader = EdfReader::open(filename).unwrap();
+    assert_eq!(reader.header().file_type, FileType::EdfPlusContinuous);
+    drop(reader);
+    
+    // EDF+D 同样可以打开
+    {
+        let mut file = fs::OpenOptions::new().write(true).open(filename).unwrap();
+        file.seek(SeekFrom::Start(192)).unwrap();
+        file.write_all(b"EDF+D").unwrap();
+    }
+    let reader = EdfReader::open(filename).unwrap();
+    assert_eq!(reader.header().file_type, FileType::EdfPlusDiscontinuous);
+    drop(reader);
+    
+    // 不支持的变体仍会被识别出来并拒绝
+    {
+        let mut file = fs::OpenOptions::new().write(true).open(filename).unwrap();
+        file.seek(SeekFrom::Start(192)).unwrap();
+        file.write_all(b"     ").unwrap();
+    }
+    match EdfReader::open(filename) {
+        Err(EdfError::UnsupportedFileType(message)) => assert!(message.ends_with("EDF")),
+        other => panic!("Expected UnsupportedFileType, got {:?}", other.map(|_| ())),
+    }
+    
+    let variants = [
+        (FileType::Edf, false, true, 2),
+        (FileType::EdfPlusContinuous, true, true, 2),
+        (FileType::EdfPlusDiscontinuous, true, false, 2),
+        (FileType::Bdf, false, true, 3),
+        (FileType::BdfPlusContinuous, true, true, 3),
+        (FileType::BdfPlusDiscontinuous, true, false, 3),
+    ];
+    for (file_type, is_plus, is_continuous, sample_bytes) in variants {
+        assert_eq!(file_type.is_plus(), is_plus, "{}", file_type);
+        assert_eq!(file_type.is_continuous(), is_continuous, "{}", file_type);
+        assert_eq!(file_type.sample_bytes(), sample_bytes, "{}", file_type);
+    }
+    
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_locate_sample_with_odd_rates() {
+    use edfplus::{EdfError, SampleLocation};
+    
+    let filename = "test_locate_sample_odd_rates.edf";
+    
+    {
+        let mut writer = EdfWriter::create(filename).unwrap();
+        writer.set_datarecord_duration(0.5).unwrap();
+        for (label, samples_per_record) in [("Slow", 7), ("Fast", 13)] {
+            let mut signal = create_test_eeg_signal();
+            signal.label = label.to_string();
+            signal.samples_per_record = samples_per_record;
+            writer.add_signal(signal).unwrap();
+        }
+        for _ in 0..4 {
+            writer.write_samples(&[vec![0.0; 7], vec![0.0; 13]]).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+    
+    let reader = EdfReader::open(filename).unwrap();
+    let record_duration = reader.header().datarecord_duration;
+    assert_eq!(record_duration, 5_000_000);
+    
+    // 同一个样本索引在不同采样率的信号中位于不同的记录
+    assert_eq!(reader.locate_sample(0, 15).unwrap(), SampleLocation { record: 2, offset_in_record: 1, time_100ns: 10_714_286 });
+    assert_eq!(reader.locate_sample(1, 15).unwrap(), SampleLocation { record: 1, offset_in_record: 2, time_100ns: 5_769_231 });
+    
+    for (signal, samples_per_record) in [(0usize, 7i64), (1, 13)] {
+        let start = reader.header().start_datetime();
+        for position in 0..samples_per_record * 4 {
+            let location = reader.locate_sample(signal, position).unwrap();
+            assert_eq!(location.record, position / samples_per_record);
+            assert_eq!(location.offset_in_record, position % samples_per_record);
+            assert_eq!(
+                reader.first_sample_of_record(signal, location.record).unwrap() + location.offset_in_record,
+                position
+            );
+            
+            // 与 sample_datetime 的结果一致（误差小于100纳秒）
+            let elapsed_ns = (reader.sample_datetime(signal, position).unwrap() - start).num_nanoseconds().unwrap();
+            assert!((elapsed_ns - location.time_100ns * 100).abs() < 100);
+        }
+        
+        assert!(matches!(
+            reader.locate_sample(signal, samples_per_record * 4),
+            Err(EdfError::InvalidSampleIndex { .. })
+        ));
+        assert!(matches!(
+            reader.first_sample_of_record(signal, 4),
+            Err(EdfError::InvalidRecordIndex { index: 4, count: 4 })
+        ));
+    }
+    assert!(matches!(reader.locate_sample(2, 0), Err(EdfError::InvalidSignalIndex(2))));
+    
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_reserved_fields_round_trip() {
+    use edfplus::header::{self, SignalField};
+    use std::io::{Seek, SeekFrom, Write};
+    
+    let source = "test_reserved_source.edf";
+    let copy = "test_reserved_copy.edf";
+    let vendor_main = "VENDOR:acme fw=2.1.7 id=0042 cal=ok  ;";
+    let vendor_signal = ["  gain=2 ref=Cz", "gain=1 ref=A1 notch=50Hz"];
+    
+    {
+        let mut writer = EdfWriter::create(source).unwrap();
+        writer.add_signal(create_test_eeg_signal()).unwrap();
+        writer.add_signal(create_test_ecg_signal()).unwrap();
+        for _ in 0..2 {
+            writer.write_samples(&[vec![10.0; 256], vec![1.0; 256]]).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+    
+    // 模拟厂商在保留字段中写入的数据
+    {
+        let mut file = fs::OpenOptions::new().write(true).open(source).unwrap();
+        file.seek(SeekFrom::Start(header::RESERVED.start as u64 + 5)).unwrap();
+        file.write_all(vendor_main.as_bytes()).unwrap();
+        for (signal, text) in vendor_signal.iter().enumerate() {
+            let range = SignalField::Reserved.range(3, signal);
+            file.seek(SeekFrom::Start((header::MAIN_HEADER_SIZE + range.start) as u64)).unwrap();
+            file.write_all(text.as_bytes()).unwrap();
+        }
+    }
+    
+    let mut reader = EdfReader::open(source).unwrap();
+    assert_eq!(reader.header().reserved_main, vendor_main.trim_end());
+    assert_eq!(reader.header().signals[0].reserved, vendor_signal[0]);
+    assert_eq!(reader.header().signals[1].reserved, vendor_signal[1]);
+    
+    // 复制：头部与信号参数原样传递
+    {
+        let mut writer = EdfWriter::create(copy).unwrap();
+        writer.set_reserved_main(&reader.header().reserved_main).unwrap();
+        for signal in reader.header().signals.clone() {
+            writer.add_signal(signal).unwrap();
+        }
+        for _ in 0..reader.header().datarecords_in_file {
+            let record = vec![
+                reader.read_digital_samples(0, 256).unwrap(),
+                reader.read_digital_samples(1, 256).unwrap(),
+            ];
+            writer.write_digital_samples(&record).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+    
+    let copied = EdfReader::open(copy).unwrap();
+    assert_eq!(copied.header().reserved_main, reader.header().reserved_main);
+    for (a, b) in copied.header().signals.iter().zip(&reader.header().signals) {
+        assert_eq!(a.reserved, b.reserved);
+    }
+    assert_eq!(copied.raw_main_header()[header::RESERVED], reader.raw_main_header()[header::RESERVED]);
+    for signal in 0..3 {
+        let range = SignalField::Reserved.range(3, signal);
+        assert_eq!(copied.raw_signal_header()[range.clone()], reader.raw_signal_header()[range]);
+    }
+    
+    // 长度与字符校验
+    let mut writer = EdfWriter::create(copy).unwrap();
+    assert!(writer.set_reserved_main(&"x".repeat(39)).is_ok());
+    assert!(writer.set_reserved_main(&"x".repeat(40)).is_err());
+    assert!(writer.set_reserved_main("tab\there").is_err());
+    let mut signal = create_test_eeg_signal();
+    signal.reserved = "y".repeat(33);
+    assert!(writer.add_signal(signal.clone()).is_err());
+    signal.reserved = "Ω".to_string();
+    assert!(writer.add_signal(signal).is_err());
+    drop(writer);
+    
+    drop(reader);
+    drop(copied);
+    cleanup_test_file(source);
+    cleanup_test_file(copy);
+}
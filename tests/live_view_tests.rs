@@ -0,0 +1,123 @@
+use edfplus::{EdfError, EdfReader, EdfWriter, SignalParam};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+// 清理测试文件的辅助函数
+fn cleanup_test_file(filename: &str) {
+    if Path::new(filename).exists() {
+        fs::remove_file(filename).ok();
+    }
+}
+
+// 物理值与数字值相同，样本 i 的值为 i % 30000
+fn counter_signal(samples_per_record: i32) -> SignalParam {
+    SignalParam {
+        label: "Counter".to_string(),
+        samples_in_file: 0,
+        physical_max: 32767.0,
+        physical_min: -32768.0,
+        digital_max: 32767,
+        digital_min: -32768,
+        samples_per_record,
+        physical_dimension: "".to_string(),
+        prefilter: "".to_string(),
+        transducer: "".to_string(),
+        reserved: String::new(),
+    }
+}
+
+fn counter_record(record: usize, samples_per_record: usize) -> Vec<i32> {
+    (0..samples_per_record)
+        .map(|i| ((record * samples_per_record + i) % 30000) as i32)
+        .collect()
+}
+
+#[test]
+fn test_live_view_reads_trailing_windows_while_writing() {
+    let filename = "test_live_view_trailing.edf";
+    cleanup_test_file(filename);
+    
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.add_signal(counter_signal(200)).unwrap();
+    writer.add_signal(counter_signal(50)).unwrap();
+    writer.add_annotation(0.0, None, "Start").unwrap();
+    writer.write_digital_samples(&[counter_record(0, 200), counter_record(0, 50)]).unwrap();
+    
+    let mut view = writer.reader_view().unwrap();
+    let done = Arc::new(AtomicBool::new(false));
+    let observer = {
+        let done = Arc::clone(&done);
+        thread::spawn(move || {
+            let mut windows = 0;
+            let mut last_records = 0;
+            while !done.load(Ordering::Acquire) || windows == 0 {
+                let records = view.records();
+                assert!(records >= last_records, "record count went backwards");
+                last_records = records;
+                
+                // 最后 2 秒（不足时为全部）
+                let end = edfplus::time::ticks_to_seconds(view.duration());
+                let start = (end - 2.0).max(0.0);
+                for (signal, samples_per_record) in [(0, 200), (1, 50)] {
+                    let window = view.read_window(signal, start, end - start).unwrap();
+                    let first = (start * samples_per_record as f64).round() as usize;
+                    assert_eq!(window.len(), ((end - start) * samples_per_record as f64).round() as usize);
+                    for (i, value) in window.iter().enumerate() {
+                        assert_eq!(*value, ((first + i) % 30000) as f64, "torn data in signal {}", signal);
+                    }
+                }
+                
+                // 超出已写入区域的读取被拒绝
+                match view.read_window(0, end + 3600.0, 1.0) {
+                    Err(EdfError::InvalidSampleIndex { .. }) => {}
+                    other => panic!("Expected InvalidSampleIndex, got {:?}", other),
+                }
+                windows += 1;
+            }
+            (windows, view)
+        })
+    };
+    
+    for record in 1..300 {
+        writer.write_digital_samples(&[counter_record(record, 200), counter_record(record, 50)]).unwrap();
+    }
+    done.store(true, Ordering::Release);
+    let (windows, mut view) = observer.join().unwrap();
+    assert!(windows > 0);
+    assert_eq!(view.records(), 300);
+    
+    // 顺序读取与普通阅读器一致
+    view.seek(1, 14_950).unwrap();
+    let tail = view.read_physical_samples(1, 50).unwrap();
+    assert!(view.read_physical_samples(1, 1).is_err());
+    
+    writer.finalize().unwrap();
+    let mut reader = EdfReader::open(filename).unwrap();
+    reader.seek(1, 14_950).unwrap();
+    assert_eq!(reader.read_physical_samples(1, 50).unwrap(), tail);
+    drop(reader);
+    
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_live_view_requires_header() {
+    let filename = "test_live_view_header.edf";
+    cleanup_test_file(filename);
+    
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.add_signal(counter_signal(10)).unwrap();
+    assert!(matches!(writer.reader_view(), Err(EdfError::InvalidFormat(_))));
+    
+    writer.write_digital_samples(&[counter_record(0, 10)]).unwrap();
+    let mut view = writer.reader_view().unwrap();
+    assert_eq!(view.samples_available(0).unwrap(), 10);
+    assert_eq!(view.read_digital_samples(0, 10).unwrap(), counter_record(0, 10));
+    assert!(matches!(view.samples_available(1), Err(EdfError::InvalidSignalIndex(1))));
+    
+    writer.finalize().unwrap();
+    cleanup_test_file(filename);
+}
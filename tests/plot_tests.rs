@@ -0,0 +1,78 @@
+#![cfg(feature = "plotters")]
+
+use edfplus::plot::PlotOptions;
+use edfplus::{EdfError, EdfReader, EdfWriter, SignalParam};
+use std::fs;
+use std::path::Path;
+
+// 清理测试文件的辅助函数
+fn cleanup_test_file(filename: &str) {
+    if Path::new(filename).exists() {
+        fs::remove_file(filename).ok();
+    }
+}
+
+fn create_test_file(filename: &str) {
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.add_signal(SignalParam {
+        label: "EEG Fpz-Cz".to_string(),
+        samples_in_file: 0,
+        physical_max: 100.0,
+        physical_min: -100.0,
+        digital_max: 32767,
+        digital_min: -32768,
+        samples_per_record: 256,
+        physical_dimension: "uV".to_string(),
+        prefilter: "".to_string(),
+        transducer: "".to_string(),
+        reserved: String::new(),
+    }).unwrap();
+    writer.add_annotation(2.5, None, "Spike").unwrap();
+    for record in 0..10 {
+        let samples: Vec<f64> = (0..256)
+            .map(|i| 50.0 * ((record * 256 + i) as f64 * 0.05).sin())
+            .collect();
+        writer.write_samples(&[samples]).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+/// PNG 文件头中的宽度和高度
+fn png_size(bytes: &[u8]) -> (u32, u32) {
+    assert_eq!(&bytes[..8], b"\x89PNG\r\n\x1a\n");
+    let width = u32::from_be_bytes(bytes[16..20].try_into().unwrap());
+    let height = u32::from_be_bytes(bytes[20..24].try_into().unwrap());
+    (width, height)
+}
+
+#[test]
+fn test_plot_signal_is_deterministic() {
+    let filename = "test_plot_signal.edf";
+    let (first, second, plain) = ("test_plot_a.png", "test_plot_b.png", "test_plot_plain.png");
+    create_test_file(filename);
+
+    let mut reader = EdfReader::open(filename).unwrap();
+    let options = PlotOptions::new().size(640, 240).range(1.0..4.0);
+    reader.plot_signal(0, first, &options).unwrap();
+    reader.plot_signal(0, second, &options).unwrap();
+    reader.plot_signal(0, plain, &options.clone().show_annotations(false)).unwrap();
+
+    let image = fs::read(first).unwrap();
+    assert_eq!(png_size(&image), (640, 240));
+    assert_eq!(image, fs::read(second).unwrap());
+
+    // 注释标记改变图像
+    assert_ne!(image, fs::read(plain).unwrap());
+
+    // 默认选项绘制整个信号，不影响读取位置
+    reader.plot_signal(0, first, &PlotOptions::default()).unwrap();
+    assert_eq!(png_size(&fs::read(first).unwrap()), (1200, 400));
+    assert_eq!(reader.tell(0).unwrap(), 0);
+
+    assert!(matches!(reader.plot_signal(1, first, &options), Err(EdfError::InvalidSignalIndex(1))));
+
+    drop(reader);
+    for file in [filename, first, second, plain] {
+        cleanup_test_file(file);
+    }
+}
@@ -0,0 +1,107 @@
+use edfplus::{EdfError, EdfReader, EdfWriter, SignalParam};
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+// 清理测试文件的辅助函数
+fn cleanup_test_file(filename: &str) {
+    if Path::new(filename).exists() {
+        fs::remove_file(filename).ok();
+    }
+}
+
+fn create_signal(label: &str, samples_per_record: i32) -> SignalParam {
+    SignalParam {
+        label: label.to_string(),
+        samples_in_file: 0,
+        physical_max: 1000.0,
+        physical_min: -1000.0,
+        digital_max: 32767,
+        digital_min: -32768,
+        samples_per_record,
+        physical_dimension: "uV".to_string(),
+        prefilter: "".to_string(),
+        transducer: "".to_string(),
+        reserved: String::new(),
+    }
+}
+
+/// 每个记录的样本值等于记录序号，便于检查顺序
+fn create_numbered_file(filename: &str, records: usize) {
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.add_signal(create_signal("EEG", 50)).unwrap();
+    writer.add_signal(create_signal("Resp", 5)).unwrap();
+    writer.add_annotation(3.5, None, "Marker").unwrap();
+    for record in 0..records {
+        writer.write_samples(&[vec![record as f64; 50], vec![-(record as f64); 5]]).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+#[test]
+fn test_prefetch_with_slow_consumer() {
+    let filename = "test_prefetch_slow.edf";
+    create_numbered_file(filename, 20);
+
+    let mut expected = EdfReader::open(filename).unwrap();
+    let mut records = EdfReader::open(filename).unwrap().into_prefetching().lookahead(3);
+    assert_eq!(records.header().datarecords_in_file, 20);
+
+    let mut indices = Vec::new();
+    while let Some(record) = records.next_record() {
+        let record = record.unwrap();
+        // 模拟耗时的处理，工作线程在此期间预读
+        thread::sleep(Duration::from_millis(5));
+
+        assert_eq!(record, expected.read_record(record.index).unwrap());
+        assert!(record.signals[0].iter().all(|&v| (v - record.index as f64).abs() < 0.1));
+        indices.push(record.index);
+    }
+    assert_eq!(indices, (0..20).collect::<Vec<i64>>());
+    assert_eq!(records.position(), 20);
+
+    // 读完后保持结束状态
+    assert!(records.next_record().is_none());
+
+    drop(records);
+    drop(expected);
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_prefetch_seek_restarts_pipeline() {
+    let filename = "test_prefetch_seek.edf";
+    create_numbered_file(filename, 10);
+
+    let mut records = EdfReader::open(filename).unwrap().into_prefetching().lookahead(4);
+    for expected in 0..3 {
+        assert_eq!(records.next_record().unwrap().unwrap().index, expected);
+    }
+
+    // 向前和向后跳转，丢弃已预读的记录
+    records.seek(7).unwrap();
+    assert_eq!(records.position(), 7);
+    let tail: Vec<i64> = records.by_ref().map(|r| r.unwrap().index).collect();
+    assert_eq!(tail, vec![7, 8, 9]);
+
+    records.seek(3).unwrap();
+    let record = records.next_record().unwrap().unwrap();
+    assert_eq!(record.index, 3);
+    assert_eq!(record.annotations[0].description, "Marker");
+
+    records.seek(10).unwrap();
+    assert!(records.next_record().is_none());
+
+    assert!(matches!(records.seek(11), Err(EdfError::InvalidRecordIndex { index: 11, count: 10 })));
+    assert!(records.seek(-1).is_err());
+
+    // 取回阅读器后仍可正常使用
+    records.seek(5).unwrap();
+    assert_eq!(records.next_record().unwrap().unwrap().index, 5);
+    let mut reader = records.into_inner();
+    assert_eq!(reader.read_record(9).unwrap().index, 9);
+
+    drop(reader);
+    cleanup_test_file(filename);
+}
@@ -0,0 +1,377 @@
+use edfplus::{DatarecordsSource, EdfError, EdfReader, EdfReaderOptions, EdfWriter, SignalParam, TalWarning};
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::Path;
+
+// 清理测试文件的辅助函数
+fn cleanup_test_file(filename: &str) {
+    if Path::new(filename).exists() {
+        fs::remove_file(filename).ok();
+    }
+}
+
+fn create_test_signal() -> SignalParam {
+    SignalParam {
+        label: "EEG Fp1".to_string(),
+        samples_in_file: 0,
+        physical_max: 200.0,
+        physical_min: -200.0,
+        digital_max: 32767,
+        digital_min: -32768,
+        samples_per_record: 100,
+        physical_dimension: "uV".to_string(),
+        prefilter: "HP:0.1Hz".to_string(),
+        transducer: "AgAgCl".to_string(),
+        reserved: String::new(),
+    }
+}
+
+// 写入一个10秒、带注释的测试文件
+fn create_test_file(filename: &str) {
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.add_signal(create_test_signal()).unwrap();
+    writer.add_annotation(1.0, None, "First").unwrap();
+    writer.add_annotation(8.5, Some(0.5), "Late").unwrap();
+    for second in 0..10 {
+        writer.write_samples(&[vec![second as f64; 100]]).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+// 直接修改文件中的头部字节
+fn patch_bytes(filename: &str, offset: u64, bytes: &[u8]) {
+    let mut file = OpenOptions::new().write(true).open(filename).unwrap();
+    file.seek(SeekFrom::Start(offset)).unwrap();
+    file.write_all(bytes).unwrap();
+}
+
+#[test]
+fn test_default_options_match_open() {
+    let filename = "test_options_default.edf";
+    create_test_file(filename);
+    
+    let mut plain = EdfReader::open(filename).unwrap();
+    let mut with_options = EdfReaderOptions::default().open(filename).unwrap();
+    
+    assert_eq!(plain.header().datarecords_in_file, with_options.header().datarecords_in_file);
+    assert_eq!(plain.header().annotations_in_file, with_options.header().annotations_in_file);
+    assert_eq!(plain.header().datarecords_source, DatarecordsSource::Declared);
+    assert_eq!(plain.annotations().len(), 2);
+    assert_eq!(with_options.annotations().len(), 2);
+    assert_eq!(
+        plain.read_digital_samples(0, 1000).unwrap(),
+        with_options.read_digital_samples(0, 1000).unwrap()
+    );
+    
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_lenient_header_size_and_date() {
+    let filename = "test_options_lenient.edf";
+    create_test_file(filename);
+    
+    // 头部字节数与信号数量不一致
+    patch_bytes(filename, 184, b"999     ");
+    assert!(matches!(EdfReader::open(filename), Err(EdfError::InvalidHeader)));
+    let reader = EdfReaderOptions::new().lenient(true).open(filename).unwrap();
+    assert_eq!(reader.header().signals.len(), 1);
+    drop(reader);
+    
+    // 无法解析的开始日期
+    patch_bytes(filename, 168, b"xx.yy.zz");
+    assert!(EdfReaderOptions::new().lenient(false).open(filename).is_err());
+    let reader = EdfReaderOptions::new().lenient(true).open(filename).unwrap();
+    assert_eq!(reader.header().start_date, chrono::NaiveDate::from_ymd_opt(1985, 1, 1).unwrap());
+    assert_eq!(reader.annotations().len(), 2);
+    drop(reader);
+    
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_skip_annotation_parsing() {
+    let filename = "test_options_skip_annotations.edf";
+    create_test_file(filename);
+    
+    let mut reader = EdfReaderOptions::new().parse_annotations(false).open(filename).unwrap();
+    assert!(reader.annotations().is_empty());
+    assert_eq!(reader.header().annotations_in_file, 0);
+    assert_eq!(reader.read_physical_samples(0, 1000).unwrap().len(), 1000);
+    drop(reader);
+    
+    let reader = EdfReaderOptions::new().parse_annotations(true).open(filename).unwrap();
+    assert_eq!(reader.annotations().len(), 2);
+    drop(reader);
+    
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_skip_annotation_parsing_keeps_subsecond() {
+    let filename = "test_options_skip_subsecond.edf";
+    {
+        let mut writer = EdfWriter::create(filename).unwrap();
+        writer.set_subsecond_starttime(1_250_000).unwrap();
+        writer.add_signal(create_test_signal()).unwrap();
+        writer.write_samples(&[vec![0.0; 100]]).unwrap();
+        writer.finalize().unwrap();
+    }
+    
+    // 开始时间的subsecond部分来自第一个记录，仍然会被读取
+    let reader = EdfReaderOptions::new().parse_annotations(false).open(filename).unwrap();
+    assert_eq!(reader.header().starttime_subsecond, 1_250_000);
+    drop(reader);
+    
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_allow_truncated() {
+    let filename = "test_options_truncated.edf";
+    create_test_file(filename);
+    
+    // 截掉最后两个半记录
+    let record_size = 100 * 2 + 120;
+    let full_size = fs::metadata(filename).unwrap().len();
+    let file = OpenOptions::new().write(true).open(filename).unwrap();
+    file.set_len(full_size - record_size * 2 - record_size / 2).unwrap();
+    drop(file);
+    
+    // 默认行为：使用头部声明的记录数，读取越界部分失败
+    let mut reader = EdfReader::open(filename).unwrap();
+    assert_eq!(reader.header().datarecords_in_file, 10);
+    assert!(reader.read_digital_samples(0, 1000).is_err());
+    drop(reader);
+    
+    // 允许截断：只保留完整的7个记录
+    let mut reader = EdfReaderOptions::new().allow_truncated(true).open(filename).unwrap();
+    assert_eq!(reader.header().datarecords_in_file, 7);
+    assert_eq!(reader.header().file_duration, 70_000_000);
+    assert_eq!(reader.header().signals[0].samples_in_file, 700);
+    let samples = reader.read_physical_samples(0, 1000).unwrap();
+    assert_eq!(samples.len(), 700);
+    assert!((samples[699] - 6.0).abs() < 0.01);
+    // 被截掉的记录中的注释不可见，但之前的注释完整保留
+    assert_eq!(reader.annotations().len(), 1);
+    assert_eq!(reader.annotations()[0].description, "First");
+    drop(reader);
+    
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_unknown_datarecords_computed_from_size() {
+    let filename = "test_options_unknown_datarecords.edf";
+    create_test_file(filename);
+    
+    // 记录未正常结束时，数据记录数字段保持为 -1
+    patch_bytes(filename, 236, b"-1      ");
+    
+    let mut reader = EdfReader::open(filename).unwrap();
+    let header = reader.header();
+    assert_eq!(header.datarecords_in_file, 10);
+    assert_eq!(header.datarecords_source, DatarecordsSource::ComputedFromSize);
+    assert_eq!(header.file_duration, 100_000_000);
+    assert_eq!(header.signals[0].samples_in_file, 1000);
+    
+    let samples = reader.read_physical_samples(0, 1000).unwrap();
+    assert_eq!(samples.len(), 1000);
+    assert!((samples[999] - 9.0).abs() < 0.01);
+    assert_eq!(reader.annotations().len(), 2);
+    drop(reader);
+    
+    // 末尾不完整的记录不计入
+    let full_size = fs::metadata(filename).unwrap().len();
+    let file = OpenOptions::new().write(true).open(filename).unwrap();
+    file.set_len(full_size - 100).unwrap();
+    drop(file);
+    let reader = EdfReader::open(filename).unwrap();
+    assert_eq!(reader.header().datarecords_in_file, 9);
+    drop(reader);
+    
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_padded_numeric_header_fields() {
+    let filename = "test_options_padded_fields.edf";
+    create_test_file(filename);
+    
+    // 右对齐、带'+'号和NUL填充的数字字段都应被正确解析
+    patch_bytes(filename, 236, b"      10");
+    patch_bytes(filename, 244, b"+1\0\0\0\0\0\0");
+    let signal_header = 256 + 2 * 216; // 2个信号（含注释信号）的样本数列
+    patch_bytes(filename, signal_header, b"   +100 ");
+    
+    let reader = EdfReader::open(filename).unwrap();
+    assert_eq!(reader.header().datarecords_in_file, 10);
+    assert_eq!(reader.header().datarecord_duration, edfplus::EDFLIB_TIME_DIMENSION);
+    assert_eq!(reader.header().signals[0].samples_per_record, 100);
+    drop(reader);
+    
+    // 全空格字段在严格模式下报错，宽松模式下使用默认值
+    let digital_min = 256 + 2 * 120;
+    patch_bytes(filename, digital_min, b"        ");
+    match EdfReader::open(filename) {
+        Err(EdfError::MissingField(field)) => assert_eq!(field, "digital minimum"),
+        other => panic!("Expected MissingField, got {:?}", other.map(|_| ())),
+    }
+    let reader = EdfReaderOptions::new().lenient(true).open(filename).unwrap();
+    assert_eq!(reader.header().signals[0].digital_min, -32768);
+    
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_resource_limits() {
+    let filename = "test_options_limits.edf";
+    create_test_file(filename);
+    
+    // 默认限制接受正常文件
+    let reader = EdfReader::open(filename).unwrap();
+    assert_eq!(reader.annotations().len(), 2);
+    drop(reader);
+    
+    let limit_exceeded = |options: EdfReaderOptions| match options.open(filename) {
+        Err(EdfError::LimitExceeded { which, limit, requested }) => (which, limit, requested),
+        other => panic!("expected LimitExceeded, got {:?}", other.map(|_| ())),
+    };
+    
+    // 1个普通信号 + 1个注释信号，每个数据记录 200 + 120 字节
+    assert_eq!(limit_exceeded(EdfReaderOptions::new().max_signals(1)), ("max_signals", 1, 2));
+    assert_eq!(limit_exceeded(EdfReaderOptions::new().max_header_bytes(512)), ("max_header_bytes", 512, 768));
+    assert_eq!(limit_exceeded(EdfReaderOptions::new().max_record_bytes(256)), ("max_record_bytes", 256, 320));
+    assert_eq!(limit_exceeded(EdfReaderOptions::new().max_annotation_bytes(1000)), ("max_annotation_bytes", 1000, 1200));
+    assert_eq!(limit_exceeded(EdfReaderOptions::new().max_annotations(1)), ("max_annotations", 1, 2));
+    
+    // 不解析注释时只读取第一个数据记录
+    let reader = EdfReaderOptions::new().max_annotation_bytes(1000).parse_annotations(false).open(filename).unwrap();
+    assert!(reader.annotations().is_empty());
+    drop(reader);
+    
+    let error = EdfReaderOptions::new().max_signals(1).open(filename).err().unwrap();
+    assert_eq!(error.code(), edfplus::error::EDFPLUS_OTHER_ERROR);
+    assert!(error.to_string().contains("max_signals"), "{}", error);
+    
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_crafted_headers_fail_fast() {
+    let filename = "test_options_crafted.edf";
+    
+    // 声明 9999 个信号
+    create_test_file(filename);
+    patch_bytes(filename, 252, b"9999");
+    assert!(matches!(EdfReader::open(filename), Err(EdfError::InvalidSignalCount(9999))));
+    patch_bytes(filename, 252, b"4000");
+    assert!(matches!(
+        EdfReaderOptions::new().max_signals(64).open(filename),
+        Err(EdfError::LimitExceeded { which: "max_signals", limit: 64, requested: 4000 })
+    ));
+    
+    // 巨大的数据记录：第一个信号每记录 99999999 个样本
+    create_test_file(filename);
+    patch_bytes(filename, 256 + 2 * 216, b"99999999");
+    assert!(matches!(
+        EdfReader::open(filename),
+        Err(EdfError::LimitExceeded { which: "max_record_bytes", requested: 200_000_118, .. })
+    ));
+    
+    // 注释信号很大
+    create_test_file(filename);
+    patch_bytes(filename, 256 + 2 * 216 + 8, b"99999999");
+    assert!(matches!(
+        EdfReaderOptions::new().max_record_bytes(usize::MAX).open(filename),
+        Err(EdfError::LimitExceeded { which: "max_annotation_bytes", .. })
+    ));
+    
+    // 没有结束符的TAL：第一个数据记录的注释信号被填满，只丢弃该记录
+    create_test_file(filename);
+    patch_bytes(filename, 768 + 200, &[b'+'; 120]);
+    let reader = EdfReader::open(filename).unwrap();
+    assert_eq!(reader.annotations().len(), 2);
+    assert_eq!(reader.header().starttime_subsecond, 0);
+    drop(reader);
+    
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_malformed_tal_lenient_and_strict() {
+    let filename = "test_malformed_tal.edf";
+    // 第3个数据记录的注释信号，中间的TAL的onset非法
+    let tal_offset = 768 + 2 * 320 + 200;
+    
+    create_test_file(filename);
+    patch_bytes(filename, tal_offset, b"+2\x14\x14\0+2.1\x14Before\x14\0+2.x\x14Broken\x14\0+2.5\x14After\x14\0");
+    
+    // 宽松模式：跳过损坏的TAL，之后的注释不丢失
+    let reader = EdfReader::open(filename).unwrap();
+    let descriptions: Vec<&str> = reader.annotations().iter().map(|a| a.description.as_str()).collect();
+    assert_eq!(descriptions, vec!["First", "Before", "After", "Late"]);
+    assert_eq!(reader.annotations()[2].onset, 25_000_000);
+    // 偏移是损坏的TAL的起始位置
+    assert_eq!(reader.tal_warnings(), [TalWarning { record: 2, offset: 18, reason: "invalid onset" }]);
+    drop(reader);
+    
+    // 严格模式：报告记录、字节偏移和原因
+    let result = EdfReaderOptions::new().strict_tal(true).open(filename);
+    assert!(matches!(
+        result,
+        Err(EdfError::MalformedTal { record: 2, offset: 18, reason: "invalid onset" })
+    ));
+    
+    // 多余的NUL字节和描述中的duration分隔符
+    create_test_file(filename);
+    patch_bytes(filename, tal_offset, b"+2\x14\x14\0\0+2.1\x14Gap\x14\0+2.2\x14A\x15B\x14\0+2.5\x14After\x14\0");
+    let reader = EdfReader::open(filename).unwrap();
+    let descriptions: Vec<&str> = reader.annotations().iter().map(|a| a.description.as_str()).collect();
+    assert_eq!(descriptions, vec!["First", "Gap", "After", "Late"]);
+    // 多余的NUL字节从第二个NUL算起
+    assert_eq!(reader.tal_warnings(), [
+        TalWarning { record: 2, offset: 5, reason: "too many consecutive NUL bytes" },
+        TalWarning { record: 2, offset: 16, reason: "duration separator inside description" },
+    ]);
+    drop(reader);
+    assert!(matches!(
+        EdfReaderOptions::new().strict_tal(true).open(filename),
+        Err(EdfError::MalformedTal { record: 2, offset: 5, reason: "too many consecutive NUL bytes" })
+    ));
+    
+    // 没有结束符：宽松模式保留最后一个NUL之前的TAL
+    create_test_file(filename);
+    patch_bytes(filename, tal_offset, b"+2\x14\x14\0+2.5\x14Kept\x14\0");
+    patch_bytes(filename, tal_offset + 17, &[b'x'; 103]);
+    let reader = EdfReader::open(filename).unwrap();
+    assert_eq!(reader.annotations().len(), 3);
+    assert_eq!(reader.annotations()[1].description, "Kept");
+    assert_eq!(reader.tal_warnings(), [TalWarning { record: 2, offset: 17, reason: "annotation signal is not NUL-terminated" }]);
+    drop(reader);
+    let error = EdfReaderOptions::new().strict_tal(true).open(filename).err().unwrap();
+    assert!(matches!(error, EdfError::MalformedTal { record: 2, offset: 17, reason: "annotation signal is not NUL-terminated" }));
+    assert_eq!(error.code(), -3);
+    
+    // 读取单个记录时使用相同的设置
+    create_test_file(filename);
+    let mut reader = EdfReaderOptions::new().strict_tal(true).open(filename).unwrap();
+    patch_bytes(filename, tal_offset, b"+2\x14\x14\0+2.x\x14Broken\x14\0");
+    assert!(matches!(reader.read_record(2), Err(EdfError::MalformedTal { record: 2, .. })));
+    assert!(reader.tal_warnings().is_empty());
+    drop(reader);
+    
+    // 宽松模式下之后读取时发现的问题也会记录，同一个问题只记录一次
+    create_test_file(filename);
+    let mut reader = EdfReader::open(filename).unwrap();
+    assert!(reader.tal_warnings().is_empty());
+    patch_bytes(filename, tal_offset, b"+2\x14\x14\0+2.x\x14Broken\x14\0");
+    reader.read_record(2).unwrap();
+    reader.read_record(2).unwrap();
+    assert_eq!(reader.tal_warnings(), [TalWarning { record: 2, offset: 5, reason: "invalid onset" }]);
+    drop(reader);
+    
+    cleanup_test_file(filename);
+}
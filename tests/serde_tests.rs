@@ -0,0 +1,35 @@
+#![cfg(feature = "serde")]
+
+use edfplus::Annotation;
+use serde_json::json;
+
+#[test]
+fn test_annotation_json_round_trip() {
+    let annotations = vec![
+        Annotation { onset: 12_500_000, duration: None, description: "Lights off".to_string() },
+        Annotation { onset: 1_000, duration: Some(300_000_000), description: "Sleep stage W".to_string() },
+        Annotation { onset: 36_000_000_000, duration: Some(2_500_000), description: "Arousal".to_string() },
+    ];
+    
+    // 开始时间和持续时间以秒表示，没有持续时间时为 null
+    let value = serde_json::to_value(&annotations).unwrap();
+    assert_eq!(value, json!([
+        { "onset": 1.25, "duration": null, "description": "Lights off" },
+        { "onset": 0.0001, "duration": 30.0, "description": "Sleep stage W" },
+        { "onset": 3600.0, "duration": 0.25, "description": "Arousal" },
+    ]));
+    
+    let text = serde_json::to_string(&annotations).unwrap();
+    let parsed: Vec<Annotation> = serde_json::from_str(&text).unwrap();
+    assert_eq!(parsed, annotations);
+}
+
+#[test]
+fn test_annotation_without_duration_key() {
+    let parsed: Annotation = serde_json::from_str(r#"{"onset": 1.0, "description": "x"}"#).unwrap();
+    assert_eq!(parsed, Annotation { onset: 10_000_000, duration: None, description: "x".to_string() });
+    
+    // onset 和 description 仍然是必需的
+    assert!(serde_json::from_str::<Annotation>(r#"{"description": "x"}"#).is_err());
+    assert!(serde_json::from_str::<Annotation>(r#"{"onset": 1.0}"#).is_err());
+}
@@ -0,0 +1,233 @@
+use edfplus::{ChannelWriter, EdfError, EdfReader, EdfReaderOptions, EdfWriter, RaggedTail, SignalParam, TypeHeuristic};
+use edfplus::labels::SignalType;
+use std::fs;
+use std::path::Path;
+
+// 清理测试文件的辅助函数
+fn cleanup_test_file(filename: &str) {
+    if Path::new(filename).exists() {
+        fs::remove_file(filename).ok();
+    }
+}
+
+fn create_signal(label: &str, dimension: &str, samples_per_record: i32) -> SignalParam {
+    SignalParam {
+        label: label.to_string(),
+        samples_in_file: 0,
+        physical_max: 1000.0,
+        physical_min: -1000.0,
+        digital_max: 32767,
+        digital_min: -32768,
+        samples_per_record,
+        physical_dimension: dimension.to_string(),
+        prefilter: "".to_string(),
+        transducer: "".to_string(),
+        reserved: String::new(),
+    }
+}
+
+/// 两秒记录：EEG 每记录 10 个样本（值为序号），Resp 每记录 2 个样本
+fn create_test_file(filename: &str) {
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.set_datarecord_duration(2.0).unwrap();
+    writer.add_signal(create_signal("EEG Fpz-Cz", "uV", 10)).unwrap();
+    writer.add_signal(create_signal("Resp nasal", "mV", 2)).unwrap();
+    for record in 0..3 {
+        let eeg: Vec<f64> = (0..10).map(|i| (record * 10 + i) as f64).collect();
+        writer.write_samples(&[eeg, vec![-5.0, 5.0]]).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+#[test]
+fn test_signal_handle_metadata_and_reads() {
+    let filename = "test_signal_handle_reads.edf";
+    create_test_file(filename);
+
+    let mut reader = EdfReader::open(filename).unwrap();
+    let mut eeg = reader.signal_mut(0).unwrap();
+    assert_eq!(eeg.index(), 0);
+    assert_eq!(eeg.label(), "EEG Fpz-Cz");
+    assert_eq!(eeg.dimension(), "uV");
+    assert_eq!(eeg.sample_frequency(), 5.0);
+    assert_eq!(eeg.len(), 30);
+    assert!(!eeg.is_empty());
+
+    let values: Vec<i64> = eeg.read(4).unwrap().iter().map(|v| v.round() as i64).collect();
+    assert_eq!(values, vec![0, 1, 2, 3]);
+    assert_eq!(eeg.tell(), 4);
+
+    // 跨记录的定位读取不改变当前位置
+    let values: Vec<i64> = eeg.read_at(8, 4).unwrap().iter().map(|v| v.round() as i64).collect();
+    assert_eq!(values, vec![8, 9, 10, 11]);
+    assert_eq!(eeg.tell(), 4);
+
+    assert_eq!(eeg.seek(100), 30);
+    assert!(eeg.read(5).unwrap().is_empty());
+    assert_eq!(eeg.seek(-3), 0);
+
+    // 句柄与阅读器共享位置
+    eeg.seek(12);
+    assert_eq!(reader.tell(0).unwrap(), 12);
+    assert_eq!(reader.tell(1).unwrap(), 0);
+
+    let resp = reader.signal_mut(1).unwrap();
+    assert_eq!(resp.label(), "Resp nasal");
+    assert_eq!(resp.sample_frequency(), 1.0);
+    assert_eq!(resp.len(), 6);
+
+    assert!(matches!(reader.signal_mut(2), Err(EdfError::InvalidSignalIndex(2))));
+
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_signal_handle_stats() {
+    let filename = "test_signal_handle_stats.edf";
+    create_test_file(filename);
+
+    let mut reader = EdfReader::open(filename).unwrap();
+    let mut eeg = reader.signal_mut(0).unwrap();
+    eeg.seek(7);
+
+    let stats = eeg.stats().unwrap();
+    assert_eq!(stats.count, 30);
+    assert!((stats.min - 0.0).abs() < 0.1);
+    assert!((stats.max - 29.0).abs() < 0.1);
+    assert!((stats.mean - 14.5).abs() < 0.1);
+    // 0..30 的总体标准差
+    assert!((stats.std_dev - (899.0f64 / 12.0).sqrt()).abs() < 0.1);
+    assert_eq!(eeg.tell(), 7);
+
+    let stats = reader.signal_mut(1).unwrap().stats().unwrap();
+    assert_eq!(stats.count, 6);
+    assert!((stats.mean - 0.0).abs() < 0.1);
+    assert!((stats.std_dev - 5.0).abs() < 0.1);
+
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_sample_iterator_length_with_padded_last_record() {
+    let filename = "test_signal_handle_padded.edf";
+
+    // 两个半记录，最后一个记录补零
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.add_signal(create_signal("EEG Fpz-Cz", "uV", 10)).unwrap();
+    let mut senders = ChannelWriter::new(writer)
+        .ragged_tail(RaggedTail::PadWithZeros)
+        .into_senders();
+    let data: Vec<f64> = (1..=25).map(|i| i as f64).collect();
+    senders[0].send(&data).unwrap();
+    senders.pop().unwrap().close().unwrap();
+
+    let mut reader = EdfReader::open(filename).unwrap();
+    let header = reader.header();
+    assert_eq!(header.signals[0].len(), 30);
+    assert_eq!(header.duration(), std::time::Duration::from_secs(3));
+
+    let mut samples = reader.samples(0).unwrap();
+    assert_eq!(samples.len(), 30);
+    samples.next().unwrap().unwrap();
+    samples.next_back().unwrap().unwrap();
+    assert_eq!(samples.len(), 28);
+    drop(samples);
+
+    let values: Vec<f64> = reader.samples(0).unwrap().collect::<Result<_, _>>().unwrap();
+    assert_eq!(values.len(), 30);
+    assert!(values[..25].iter().zip(&data).all(|(a, b)| (a - b).abs() < 0.1));
+    assert!(values[25..].iter().all(|v| v.abs() < 0.1));
+
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_sample_iterator_reverse_matches_forward() {
+    let filename = "test_signal_handle_reverse.edf";
+    create_test_file(filename);
+
+    let mut reader = EdfReader::open(filename).unwrap();
+    reader.seek(0, 7).unwrap();
+    let forward: Vec<f64> = reader.samples(0).unwrap().collect::<Result<_, _>>().unwrap();
+    let mut backward: Vec<f64> = reader.samples(0).unwrap().rev().collect::<Result<_, _>>().unwrap();
+    backward.reverse();
+    assert_eq!(forward, backward);
+    assert_eq!(forward.len(), 30);
+
+    // 两端交替读取，在中间相遇
+    let mut samples = reader.samples(0).unwrap();
+    let mut mixed = vec![0.0; 30];
+    for i in 0..15 {
+        mixed[i] = samples.next().unwrap().unwrap();
+        mixed[29 - i] = samples.next_back().unwrap().unwrap();
+    }
+    assert!(samples.next().is_none());
+    assert!(samples.next_back().is_none());
+    drop(samples);
+    assert_eq!(mixed, forward);
+
+    // 读取位置恢复
+    assert_eq!(reader.tell(0).unwrap(), 7);
+
+    let tail: Vec<f64> = reader.samples(1).unwrap().rev().take(3).collect::<Result<_, _>>().unwrap();
+    assert_eq!(tail.len(), 3);
+    assert!((tail[0] - 5.0).abs() < 0.1 && (tail[1] + 5.0).abs() < 0.1);
+
+    assert!(matches!(reader.samples(2), Err(EdfError::InvalidSignalIndex(2))));
+
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_select_channels_by_type_with_guessed_labels() {
+    let filename = "test_signals_of_type.edf";
+    let labels = ["EEG Fpz-Cz", "C3-M2", "EKG", "Chin EMG", "EOG(L)", "Pleth"];
+
+    let mut writer = EdfWriter::create(filename).unwrap();
+    for label in labels {
+        writer.add_signal(create_signal(label, "uV", 10)).unwrap();
+    }
+    for _ in 0..2 {
+        writer.write_samples(&vec![vec![1.0; 10]; labels.len()]).unwrap();
+    }
+    writer.finalize().unwrap();
+
+    let mut reader = EdfReader::open(filename).unwrap();
+    assert_eq!(reader.eeg_channels(), vec![0, 1]);
+    assert_eq!(reader.ecg_channels(), vec![2]);
+    assert_eq!(reader.emg_channels(), vec![3]);
+    assert_eq!(reader.eog_channels(), vec![4]);
+    assert!(reader.signals_of_type(SignalType::Resp).is_empty());
+
+    // 猜测的类型被标记
+    assert!(!reader.signal_type(0).unwrap().guessed);
+    assert!(reader.signal_type(1).unwrap().guessed);
+    assert_eq!(reader.signal_type(5), None);
+    assert_eq!(reader.signal_type(6), None);
+    assert_eq!(reader.select_by_type(SignalType::Eeg), vec![0]);
+
+    let window = reader.read_window_by_type(SignalType::Eeg, 0.5, 1.0).unwrap();
+    assert_eq!(window.iter().map(|(signal, _)| *signal).collect::<Vec<_>>(), vec![0, 1]);
+    assert!(window.iter().all(|(_, samples)| samples.len() == 10));
+    drop(reader);
+
+    // 自定义关键词，或者只按标签前缀
+    let reader = EdfReaderOptions::new()
+        .type_heuristic(TypeHeuristic::default().keyword("Pleth", SignalType::SaO2))
+        .open(filename)
+        .unwrap();
+    assert_eq!(reader.signals_of_type(SignalType::SaO2), vec![5]);
+    let reader = EdfReaderOptions::new()
+        .type_heuristic(TypeHeuristic::none())
+        .open(filename)
+        .unwrap();
+    assert_eq!(reader.eeg_channels(), vec![0]);
+    assert!(reader.ecg_channels().is_empty());
+    drop(reader);
+
+    cleanup_test_file(filename);
+}
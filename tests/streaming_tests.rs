@@ -24,6 +24,7 @@ fn create_streaming_signal() -> SignalParam {
         physical_dimension: "uV".to_string(),
         prefilter: "HP:0.1Hz LP:40Hz".to_string(),
         transducer: "Streaming electrodes".to_string(),
+        reserved: String::new(),
     }
 }
 
@@ -171,15 +172,15 @@ fn test_random_access_streaming() {
             let actual_second = (current_pos - 256) / 256; // 减去刚读取的256样本
             
             // 计算主要频率成分（简单FFT替代）
-            let mut freq_powers = vec![0.0; 20]; // 检测0-20Hz
-            for freq_idx in 0..20 {
+            let mut freq_powers = [0.0; 20]; // 检测0-20Hz
+            for (freq_idx, freq_power) in freq_powers.iter_mut().enumerate() {
                 let freq = freq_idx as f64;
                 let mut power = 0.0;
                 for (i, &sample) in samples.iter().enumerate() {
                     let t = i as f64 / 256.0;
                     power += sample * (2.0 * std::f64::consts::PI * freq * t).cos();
                 }
-                freq_powers[freq_idx] = power.abs();
+                *freq_power = power.abs();
             }
             
             // 找到最强的频率
@@ -0,0 +1,124 @@
+use edfplus::testing::{assert_round_trip, HeaderCheck, RoundTripChecks, SignalCheck};
+use edfplus::{EdfReader, EdfWriter, SignalParam};
+use std::fs;
+use std::path::Path;
+
+// 清理测试文件的辅助函数
+fn cleanup_test_file(filename: &str) {
+    if Path::new(filename).exists() {
+        fs::remove_file(filename).ok();
+    }
+}
+
+fn create_test_signal(physical_max: f64) -> SignalParam {
+    SignalParam {
+        label: "EEG Cz".to_string(),
+        samples_in_file: 0,
+        physical_max,
+        physical_min: -physical_max,
+        digital_max: 32767,
+        digital_min: -32768,
+        samples_per_record: 128,
+        physical_dimension: "uV".to_string(),
+        prefilter: "HP:0.1Hz".to_string(),
+        transducer: "AgAgCl".to_string(),
+        reserved: String::new(),
+    }
+}
+
+fn create_test_file(filename: &str, patient_name: &str, physical_max: f64) {
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.set_patient_info("P042", "M", "02-MAY-1951", patient_name).unwrap();
+    writer.add_signal(create_test_signal(physical_max)).unwrap();
+    writer.add_annotation(0.5, Some(1.0), "Eyes closed").unwrap();
+    for record in 0..3 {
+        let samples: Vec<f64> = (0..128)
+            .map(|i| ((record * 128 + i) as f64 * 0.05).sin() * 80.0)
+            .collect();
+        writer.write_samples(&[samples]).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+// 读取文件并用相同参数重新写出
+fn copy_file(source: &str, target: &str) {
+    let mut reader = EdfReader::open(source).unwrap();
+    let mut writer = EdfWriter::create(target).unwrap();
+    let header = reader.header();
+    writer.set_patient_info(&header.patient_code, &header.sex, &header.birthdate, &header.patient_name).unwrap();
+    writer.add_signal(header.signals[0].clone()).unwrap();
+    let records = header.datarecords_in_file;
+    for annotation in reader.annotations() {
+        let duration = annotation.duration.map(|d| d as f64 / 10_000_000.0);
+        writer.add_annotation(annotation.onset as f64 / 10_000_000.0, duration, &annotation.description).unwrap();
+    }
+    for _ in 0..records {
+        let samples = reader.read_physical_samples(0, 128).unwrap();
+        writer.write_samples(&[samples]).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+#[test]
+fn test_round_trip_copy_is_exact() {
+    let (source, target) = ("test_round_trip_copy_in.edf", "test_round_trip_copy_out.edf");
+    create_test_file(source, "Test_Patient", 100.0);
+    copy_file(source, target);
+    
+    let mut original = EdfReader::open(source).unwrap();
+    let mut produced = EdfReader::open(target).unwrap();
+    assert_round_trip(&mut original, &mut produced, RoundTripChecks::default());
+    
+    drop(original);
+    drop(produced);
+    cleanup_test_file(source);
+    cleanup_test_file(target);
+}
+
+#[test]
+fn test_round_trip_recalibrated_within_tolerance() {
+    let (source, target) = ("test_round_trip_recal_in.edf", "test_round_trip_recal_out.edf");
+    create_test_file(source, "Test_Patient", 100.0);
+    create_test_file(target, "Someone_Else", 200.0);
+    
+    let mut original = EdfReader::open(source).unwrap();
+    let mut produced = EdfReader::open(target).unwrap();
+    assert_round_trip(&mut original, &mut produced, RoundTripChecks {
+        signals: SignalCheck::PhysicalWithin(0.01),
+        annotations: true,
+        header: HeaderCheck::DataOnly,
+    });
+    
+    drop(original);
+    drop(produced);
+    cleanup_test_file(source);
+    cleanup_test_file(target);
+}
+
+#[test]
+#[should_panic(expected = "header patient_name: \"Test_Patient\" vs \"Someone_Else\"")]
+fn test_round_trip_reports_header_diff() {
+    let (source, target) = ("test_round_trip_header_in.edf", "test_round_trip_header_out.edf");
+    create_test_file(source, "Test_Patient", 100.0);
+    create_test_file(target, "Someone_Else", 100.0);
+    
+    let mut original = EdfReader::open(source).unwrap();
+    let mut produced = EdfReader::open(target).unwrap();
+    cleanup_test_file(source);
+    cleanup_test_file(target);
+    assert_round_trip(&mut original, &mut produced, RoundTripChecks::default());
+}
+
+#[test]
+#[should_panic(expected = "signal 0 physical_max: \"100\" vs \"200\"")]
+fn test_round_trip_exact_rejects_recalibration() {
+    let (source, target) = ("test_round_trip_exact_in.edf", "test_round_trip_exact_out.edf");
+    create_test_file(source, "Test_Patient", 100.0);
+    create_test_file(target, "Test_Patient", 200.0);
+    
+    let mut original = EdfReader::open(source).unwrap();
+    let mut produced = EdfReader::open(target).unwrap();
+    cleanup_test_file(source);
+    cleanup_test_file(target);
+    assert_round_trip(&mut original, &mut produced, RoundTripChecks::default());
+}
@@ -0,0 +1,130 @@
+use edfplus::{EdfReader, EdfWriter, Gap, SignalParam, TimingSegment};
+use edfplus::doctest_utils::create_discontinuous_test_file;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::Path;
+
+// 清理测试文件的辅助函数
+fn cleanup_test_file(filename: &str) {
+    if Path::new(filename).exists() {
+        fs::remove_file(filename).ok();
+    }
+}
+
+// 10个1秒的数据记录，每个记录 200 字节信号数据 + 120 字节注释
+fn create_test_file(filename: &str) {
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.add_signal(SignalParam {
+        label: "EEG Fpz-Cz".to_string(),
+        samples_in_file: 0,
+        physical_max: 100.0,
+        physical_min: -100.0,
+        digital_max: 32767,
+        digital_min: -32768,
+        samples_per_record: 100,
+        physical_dimension: "uV".to_string(),
+        prefilter: "".to_string(),
+        transducer: "".to_string(),
+        reserved: String::new(),
+    }).unwrap();
+    writer.add_annotation(4.5, None, "Event").unwrap();
+    for _ in 0..10 {
+        writer.write_samples(&[vec![0.0; 100]]).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+// 覆盖一个数据记录的注释信号
+fn patch_annotation_signal(filename: &str, record: u64, tal: &[u8]) {
+    let mut bytes = tal.to_vec();
+    bytes.resize(120, 0);
+    let mut file = OpenOptions::new().write(true).open(filename).unwrap();
+    file.seek(SeekFrom::Start(3 * 256 + record * 320 + 200)).unwrap();
+    file.write_all(&bytes).unwrap();
+}
+
+#[test]
+fn test_timing_report_of_continuous_file() {
+    let filename = "test_timing_continuous.edf";
+    create_test_file(filename);
+
+    let mut reader = EdfReader::open(filename).unwrap();
+    let report = reader.timing_report().unwrap();
+    assert!(report.strictly_continuous);
+    assert_eq!(report.records, 10);
+    assert!(report.missing_timestamps.is_empty());
+    assert_eq!((report.max_abs_drift, report.mean_abs_drift), (0, 0));
+    assert_eq!(report.first_exceeding, None);
+    assert_eq!(report.threshold, 1000);
+    assert_eq!(report.segments, vec![TimingSegment { first_record: 0, records: 10, onset: 0 }]);
+
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_timing_report_measures_clock_skew() {
+    let filename = "test_timing_skew.edf";
+    create_test_file(filename);
+
+    // 每个记录的时间戳多漂移 0.1 毫秒
+    for record in 1..10u64 {
+        let tal = format!("+{}.{:04}\x14\x14\0", record, record);
+        patch_annotation_signal(filename, record, tal.as_bytes());
+    }
+    // 第7个记录没有可读的时间戳
+    patch_annotation_signal(filename, 7, b"");
+
+    let mut reader = EdfReader::open(filename).unwrap();
+    let report = reader.timing_report().unwrap();
+    assert!(!report.strictly_continuous);
+    assert_eq!(report.records, 9);
+    assert_eq!(report.missing_timestamps, vec![7]);
+    assert_eq!(report.max_abs_drift, 9 * 1000);
+    assert_eq!(report.mean_abs_drift, (1 + 2 + 3 + 4 + 5 + 6 + 8 + 9) * 1000 / 9);
+    // 漂移正好等于阈值时不算超出
+    assert_eq!(report.first_exceeding, Some(2));
+    assert_eq!(report.segments.len(), 1);
+    assert_eq!(report.segments[0].records, 10);
+
+    let report = reader.timing_report_with(5500).unwrap();
+    assert_eq!(report.first_exceeding, Some(6));
+    assert_eq!(reader.timing_report_with(10_000).unwrap().first_exceeding, None);
+
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_timing_report_per_segment_of_discontinuous_file() {
+    let filename = "test_timing_discontinuous.edf";
+    create_discontinuous_test_file(filename).unwrap();
+
+    // 第5个记录相对所在段（从 6 秒开始）晚 0.2 毫秒
+    patch_annotation_signal(filename, 5, b"+7.0002\x14\x14\0");
+
+    let mut reader = EdfReader::open(filename).unwrap();
+    let report = reader.timing_report().unwrap();
+    assert!(!report.strictly_continuous);
+    assert_eq!(report.records, 10);
+    assert!(report.missing_timestamps.is_empty());
+    assert_eq!(report.segments, vec![
+        TimingSegment { first_record: 0, records: 4, onset: 0 },
+        TimingSegment { first_record: 4, records: 3, onset: 60_000_000 },
+        TimingSegment { first_record: 7, records: 3, onset: 12_080_000_000 },
+    ]);
+    assert_eq!(report.gaps, vec![
+        Gap { after_record: 3, start: 40_000_000, duration: 20_000_000 },
+        Gap { after_record: 6, start: 90_000_000, duration: 11_990_000_000 },
+    ]);
+
+    // 漂移按段内的名义位置计算，间隙本身不算漂移
+    assert_eq!(report.max_abs_drift, 2000);
+    assert_eq!(report.mean_abs_drift, 200);
+    assert_eq!(report.first_exceeding, Some(5));
+    assert_eq!(reader.timing_report_with(2000).unwrap().first_exceeding, None);
+
+    drop(reader);
+    cleanup_test_file(filename);
+}
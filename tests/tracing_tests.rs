@@ -0,0 +1,159 @@
+#![cfg(feature = "tracing")]
+
+use edfplus::{EdfReader, EdfReaderOptions, EdfWriter, SignalParam};
+use std::fmt::Debug;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::Registry;
+
+// 清理测试文件的辅助函数
+fn cleanup_test_file(filename: &str) {
+    if Path::new(filename).exists() {
+        fs::remove_file(filename).ok();
+    }
+}
+
+// 捕获到的事件：级别 + 字段
+#[derive(Debug, Clone)]
+struct CapturedEvent {
+    level: Level,
+    fields: Vec<(String, String)>,
+}
+
+impl CapturedEvent {
+    fn field(&self, name: &str) -> Option<&str> {
+        self.fields.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+}
+
+struct FieldVisitor<'a>(&'a mut Vec<(String, String)>);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0.push((field.name().to_string(), format!("{:?}", value)));
+    }
+}
+
+// 记录所有edfplus事件的Layer
+#[derive(Clone, Default)]
+struct CaptureLayer {
+    events: Arc<Mutex<Vec<CapturedEvent>>>,
+}
+
+impl<S: Subscriber> Layer<S> for CaptureLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if event.metadata().target() != "edfplus" {
+            return;
+        }
+        let mut fields = Vec::new();
+        event.record(&mut FieldVisitor(&mut fields));
+        self.events.lock().unwrap().push(CapturedEvent {
+            level: *event.metadata().level(),
+            fields,
+        });
+    }
+}
+
+fn capture<T>(f: impl FnOnce() -> T) -> (T, Vec<CapturedEvent>) {
+    let layer = CaptureLayer::default();
+    let events = layer.events.clone();
+    let subscriber = Registry::default().with(layer);
+    let result = tracing::subscriber::with_default(subscriber, f);
+    let events = events.lock().unwrap().clone();
+    (result, events)
+}
+
+fn create_test_file(filename: &str) {
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.add_signal(SignalParam {
+        label: "EEG Fp1".to_string(),
+        samples_in_file: 0,
+        physical_max: 200.0,
+        physical_min: -200.0,
+        digital_max: 32767,
+        digital_min: -32768,
+        samples_per_record: 100,
+        physical_dimension: "uV".to_string(),
+        prefilter: "HP:0.1Hz".to_string(),
+        transducer: "AgAgCl".to_string(),
+        reserved: String::new(),
+    }).unwrap();
+    writer.add_annotation(0.5, None, "Intact").unwrap();
+    writer.add_annotation(1.0, None, "Corrupted").unwrap();
+    for _ in 0..3 {
+        writer.write_samples(&[vec![0.0; 100]]).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+#[test]
+fn test_malformed_tal_emits_warning() {
+    let filename = "test_tracing_malformed_tal.edf";
+    create_test_file(filename);
+    
+    // 第2个记录的注释通道: "+1\x14\x14\0+1\x14Corrupted\x14\0"
+    // 把第二个TAL的onset改为非法字符
+    let header_size = 3 * 256;
+    let record_size = 100 * 2 + 120;
+    let tal_start = header_size + record_size + 200;
+    let mut file = OpenOptions::new().write(true).open(filename).unwrap();
+    file.seek(SeekFrom::Start((tal_start + 6) as u64)).unwrap();
+    file.write_all(b"x").unwrap();
+    drop(file);
+    
+    let (reader, events) = capture(|| EdfReader::open(filename).unwrap());
+    
+    // 行为不变：损坏的注释被跳过，其余注释保留
+    assert_eq!(reader.annotations().len(), 1);
+    assert_eq!(reader.annotations()[0].description, "Intact");
+    
+    let warning = events.iter()
+        .find(|e| e.level == Level::WARN && e.field("message").is_some_and(|m| m.contains("invalid onset")))
+        .expect("expected a warning for the abandoned TAL");
+    assert_eq!(warning.field("record"), Some("1"));
+    // 偏移是被放弃的TAL的起始位置
+    assert_eq!(warning.field("offset"), Some("5"));
+    assert_eq!(warning.field("onset"), Some("x"));
+    
+    let summary = events.iter()
+        .find(|e| e.level == Level::INFO)
+        .expect("expected an open summary");
+    assert_eq!(summary.field("signals"), Some("1"));
+    assert_eq!(summary.field("records"), Some("3"));
+    assert_eq!(summary.field("annotations"), Some("1"));
+    assert!(summary.field("elapsed_us").is_some());
+    
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_lenient_recovery_emits_debug() {
+    let filename = "test_tracing_lenient.edf";
+    create_test_file(filename);
+    
+    let mut file = OpenOptions::new().write(true).open(filename).unwrap();
+    file.seek(SeekFrom::Start(184)).unwrap();
+    file.write_all(b"999     ").unwrap();
+    drop(file);
+    
+    let (reader, events) = capture(|| EdfReaderOptions::new().lenient(true).open(filename).unwrap());
+    
+    let recovered = events.iter()
+        .find(|e| e.level == Level::DEBUG)
+        .expect("expected a debug event for the recovered header field");
+    assert_eq!(recovered.field("declared"), Some("999"));
+    assert_eq!(recovered.field("expected"), Some("768"));
+    
+    // 正常文件不产生警告
+    assert!(events.iter().all(|e| e.level != Level::WARN));
+    
+    drop(reader);
+    cleanup_test_file(filename);
+}
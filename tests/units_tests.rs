@@ -0,0 +1,166 @@
+use edfplus::units::Dimension;
+use edfplus::{EdfError, EdfReader, EdfWriter, SignalParam};
+use std::fs;
+use std::path::Path;
+
+// 清理测试文件的辅助函数
+fn cleanup_test_file(filename: &str) {
+    if Path::new(filename).exists() {
+        fs::remove_file(filename).ok();
+    }
+}
+
+fn signal(label: &str, dimension: &str, physical_min: f64, physical_max: f64) -> SignalParam {
+    SignalParam {
+        label: label.to_string(),
+        samples_in_file: 0,
+        physical_max,
+        physical_min,
+        digital_max: 32767,
+        digital_min: -32768,
+        samples_per_record: 10,
+        physical_dimension: dimension.to_string(),
+        prefilter: "".to_string(),
+        transducer: "".to_string(),
+        reserved: String::new(),
+    }
+}
+
+// uV, mV, mmHg, % 以及无单位的信号，各写入一个恒定值
+fn create_unit_test_file(filename: &str) {
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.add_signal(signal("EEG Fpz-Cz", "uV", -500.0, 500.0)).unwrap();
+    writer.add_signal(signal("ECG I", "mV", -5.0, 5.0)).unwrap();
+    writer.add_signal(signal("BP arterial", "mmHg", 0.0, 300.0)).unwrap();
+    writer.add_signal(signal("SaO2 finger", "%", 0.0, 100.0)).unwrap();
+    writer.add_signal(signal("Event marker", "", -1000.0, 1000.0)).unwrap();
+    writer.write_samples(&[
+        vec![100.0; 10],
+        vec![1.5; 10],
+        vec![120.0; 10],
+        vec![97.0; 10],
+        vec![3.0; 10],
+    ]).unwrap();
+    writer.finalize().unwrap();
+}
+
+fn assert_close(values: &[f64], expected: f64, tolerance: f64) {
+    assert_eq!(values.len(), 10);
+    for value in values {
+        assert!((value - expected).abs() <= tolerance, "{} != {}", value, expected);
+    }
+}
+
+#[test]
+fn test_read_si_samples_per_unit() {
+    let filename = "test_units_si_samples.edf";
+    create_unit_test_file(filename);
+    let mut reader = EdfReader::open(filename).unwrap();
+    
+    assert_close(&reader.read_si_samples(0, 10, Dimension::ElectricPotential).unwrap(), 100e-6, 1e-9);
+    assert_close(&reader.read_si_samples(1, 10, Dimension::ElectricPotential).unwrap(), 1.5e-3, 1e-7);
+    assert_close(&reader.read_si_samples(2, 10, Dimension::Pressure).unwrap(), 120.0 * 133.322387415, 1.0);
+    assert_close(&reader.read_si_samples(3, 10, Dimension::Ratio).unwrap(), 0.97, 1e-4);
+    
+    // 无单位信号只能作为无量纲值读取，数值不变
+    assert_close(&reader.read_si_samples(4, 10, Dimension::Ratio).unwrap(), 3.0, 0.02);
+    
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_read_si_samples_rejects_other_dimensions() {
+    let filename = "test_units_incompatible.edf";
+    create_unit_test_file(filename);
+    let mut reader = EdfReader::open(filename).unwrap();
+    
+    for (signal, requested) in [
+        (0, Dimension::Pressure),
+        (2, Dimension::ElectricPotential),
+        (3, Dimension::ElectricPotential),
+        (4, Dimension::Frequency),
+    ] {
+        match reader.read_si_samples(signal, 10, requested) {
+            Err(EdfError::IncompatibleDimension { signal: s, requested: r, .. }) => {
+                assert_eq!((s, r), (signal, requested));
+            }
+            other => panic!("Expected IncompatibleDimension, got {:?}", other),
+        }
+        // 出错时读取位置不变
+        assert_eq!(reader.tell(signal).unwrap(), 0);
+    }
+    
+    match reader.read_si_samples(2, 10, Dimension::ElectricPotential) {
+        Err(error) => assert!(error.to_string().contains("'mmHg'"), "{}", error),
+        Ok(_) => unreachable!(),
+    }
+    
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+#[cfg(feature = "uom")]
+#[test]
+fn test_read_quantity_per_unit() {
+    use uom::si::electric_potential::{microvolt, millivolt};
+    use uom::si::f64::{ElectricPotential, Pressure, Ratio};
+    use uom::si::pressure::millimeter_of_mercury;
+    use uom::si::ratio::{percent, ratio};
+    
+    let filename = "test_units_quantity.edf";
+    create_unit_test_file(filename);
+    let mut reader = EdfReader::open(filename).unwrap();
+    
+    let eeg: Vec<f64> = reader.read_quantity::<ElectricPotential>(0, 10).unwrap()
+        .iter().map(|v| v.get::<microvolt>()).collect();
+    assert_close(&eeg, 100.0, 0.02);
+    let ecg: Vec<f64> = reader.read_quantity::<ElectricPotential>(1, 10).unwrap()
+        .iter().map(|v| v.get::<millivolt>()).collect();
+    assert_close(&ecg, 1.5, 1e-3);
+    let pressure: Vec<f64> = reader.read_quantity::<Pressure>(2, 10).unwrap()
+        .iter().map(|v| v.get::<millimeter_of_mercury>()).collect();
+    assert_close(&pressure, 120.0, 0.01);
+    let saturation: Vec<f64> = reader.read_quantity::<Ratio>(3, 10).unwrap()
+        .iter().map(|v| v.get::<percent>()).collect();
+    assert_close(&saturation, 97.0, 0.01);
+    
+    // 无单位信号作为无量纲值读取，数值不变
+    let marker: Vec<f64> = reader.read_quantity::<Ratio>(4, 10).unwrap()
+        .iter().map(|v| v.get::<ratio>()).collect();
+    assert_close(&marker, 3.0, 0.02);
+    
+    drop(reader);
+    cleanup_test_file(filename);
+}
+
+#[cfg(feature = "uom")]
+#[test]
+fn test_read_quantity_rejects_other_dimensions() {
+    use uom::si::f64::{ElectricPotential, Pressure, ThermodynamicTemperature};
+    
+    let filename = "test_units_quantity_incompatible.edf";
+    create_unit_test_file(filename);
+    let mut reader = EdfReader::open(filename).unwrap();
+    
+    assert!(matches!(
+        reader.read_quantity::<Pressure>(0, 10),
+        Err(EdfError::IncompatibleDimension { signal: 0, requested: Dimension::Pressure, .. })
+    ));
+    assert!(matches!(
+        reader.read_quantity::<ElectricPotential>(2, 10),
+        Err(EdfError::IncompatibleDimension { signal: 2, requested: Dimension::ElectricPotential, .. })
+    ));
+    assert!(matches!(
+        reader.read_quantity::<ElectricPotential>(3, 10),
+        Err(EdfError::IncompatibleDimension { signal: 3, .. })
+    ));
+    assert!(matches!(
+        reader.read_quantity::<ThermodynamicTemperature>(4, 10),
+        Err(EdfError::IncompatibleDimension { signal: 4, requested: Dimension::Temperature, .. })
+    ));
+    assert_eq!(reader.tell(0).unwrap(), 0);
+    
+    drop(reader);
+    cleanup_test_file(filename);
+}
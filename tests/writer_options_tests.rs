@@ -0,0 +1,512 @@
+use edfplus::{EdfError, EdfReader, EdfWriter, EdfWriterOptions, FileType, LabelPolicy, PatientInfo, Profile, Quantization, SignalParam, DEFAULT_START_DATETIME};
+use edfplus::compliance::Rule;
+use edfplus::header;
+use std::fs;
+use std::path::Path;
+
+// 清理测试文件的辅助函数
+fn cleanup_test_file(filename: &str) {
+    if Path::new(filename).exists() {
+        fs::remove_file(filename).ok();
+    }
+}
+
+fn create_test_signal() -> SignalParam {
+    SignalParam {
+        label: "EEG Fp1".to_string(),
+        samples_in_file: 0,
+        physical_max: 200.0,
+        physical_min: -200.0,
+        digital_max: 32767,
+        digital_min: -32768,
+        samples_per_record: 100,
+        physical_dimension: "uV".to_string(),
+        prefilter: "".to_string(),
+        transducer: "".to_string(),
+        reserved: String::new(),
+    }
+}
+
+fn write_records(mut writer: EdfWriter, records: usize) {
+    writer.add_signal(create_test_signal()).unwrap();
+    for _ in 0..records {
+        writer.write_samples(&[vec![0.0; 100]]).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+#[test]
+fn test_create_new_refuses_existing_file() {
+    let filename = "test_writer_create_new.edf";
+    cleanup_test_file(filename);
+    
+    // 文件不存在时正常创建
+    write_records(EdfWriter::create_new(filename).unwrap(), 2);
+    let original = fs::read(filename).unwrap();
+    
+    // 文件已存在时报错且不修改原文件
+    match EdfWriter::create_new(filename) {
+        Err(EdfError::FileExists(path)) => assert!(path.ends_with(filename)),
+        other => panic!("Expected FileExists, got {:?}", other.map(|_| ())),
+    }
+    assert!(matches!(EdfWriterOptions::new().create(filename), Err(EdfError::FileExists(_))));
+    assert_eq!(fs::read(filename).unwrap(), original);
+    
+    // create 与 overwrite(true) 会覆盖
+    write_records(EdfWriter::create(filename).unwrap(), 3);
+    assert_eq!(EdfReader::open(filename).unwrap().header().datarecords_in_file, 3);
+    write_records(EdfWriterOptions::new().overwrite(true).create(filename).unwrap(), 1);
+    assert_eq!(EdfReader::open(filename).unwrap().header().datarecords_in_file, 1);
+    
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_atomic_write_renames_on_finalize() {
+    let filename = "test_writer_atomic.edf";
+    let temp = ".test_writer_atomic.edf.tmp";
+    cleanup_test_file(filename);
+    
+    let mut writer = EdfWriterOptions::new().atomic(true).create(filename).unwrap();
+    writer.add_signal(create_test_signal()).unwrap();
+    writer.write_samples(&[vec![0.0; 100]]).unwrap();
+    
+    // 完成之前目标路径不存在，只有临时文件
+    assert!(!Path::new(filename).exists());
+    assert!(Path::new(temp).exists());
+    
+    writer.finalize().unwrap();
+    assert!(Path::new(filename).exists());
+    assert!(!Path::new(temp).exists());
+    assert_eq!(EdfReader::open(filename).unwrap().header().datarecords_in_file, 1);
+    
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_atomic_overwrite_keeps_old_file_until_finalize() {
+    let filename = "test_writer_atomic_overwrite.edf";
+    let temp = ".test_writer_atomic_overwrite.edf.tmp";
+    
+    write_records(EdfWriter::create(filename).unwrap(), 2);
+    let original = fs::read(filename).unwrap();
+    
+    // 未完成的原子写入不影响原文件
+    {
+        let mut writer = EdfWriterOptions::new().overwrite(true).atomic(true).create(filename).unwrap();
+        writer.add_signal(create_test_signal()).unwrap();
+        writer.write_samples(&[vec![0.0; 100]]).unwrap();
+    }
+    assert_eq!(fs::read(filename).unwrap(), original);
+    assert!(!Path::new(temp).exists());
+    
+    // 不允许覆盖时，原子写入同样拒绝已存在的文件
+    assert!(matches!(
+        EdfWriterOptions::new().atomic(true).create(filename),
+        Err(EdfError::FileExists(_))
+    ));
+    
+    write_records(EdfWriterOptions::new().overwrite(true).atomic(true).create(filename).unwrap(), 4);
+    assert_eq!(EdfReader::open(filename).unwrap().header().datarecords_in_file, 4);
+    assert!(!Path::new(temp).exists());
+    
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_atomic_write_not_published_on_annotation_error() {
+    let filename = "test_writer_atomic_out_of_range.edf";
+    let temp = ".test_writer_atomic_out_of_range.edf.tmp";
+    cleanup_test_file(filename);
+    
+    let write = |overwrite: bool| {
+        let mut writer = EdfWriterOptions::new().overwrite(overwrite).atomic(true).create(filename).unwrap();
+        writer.add_signal(create_test_signal()).unwrap();
+        writer.add_annotation(5.0, None, "After the end").unwrap();
+        writer.write_samples(&[vec![0.0; 100]]).unwrap();
+        writer.finalize()
+    };
+    
+    // 注释超出记录时长：返回错误，目标路径不存在，临时文件已删除
+    assert!(matches!(write(false), Err(EdfError::AnnotationsOutOfRange { .. })));
+    assert!(!Path::new(filename).exists());
+    assert!(!Path::new(temp).exists());
+    
+    // 覆盖已有文件时原文件保持不变
+    write_records(EdfWriter::create(filename).unwrap(), 2);
+    let original = fs::read(filename).unwrap();
+    assert!(matches!(write(true), Err(EdfError::AnnotationsOutOfRange { .. })));
+    assert_eq!(fs::read(filename).unwrap(), original);
+    assert!(!Path::new(temp).exists());
+    
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_classic_edf_output() {
+    let filename = "test_writer_classic.edf";
+    
+    let mut writer = EdfWriter::create_classic(filename).unwrap();
+    assert_eq!(writer.file_type(), FileType::Edf);
+    writer.set_patient_text("Doe, John (born 1961)").unwrap();
+    writer.set_recording_text("Ward 3, bed 12").unwrap();
+    
+    // EDF+专用的功能被拒绝
+    assert!(writer.add_annotation(0.0, None, "Start").is_err());
+    assert!(writer.set_patient_info("P001", "M", "X", "X").is_err());
+    assert!(writer.set_reserved_main("vendor").is_err());
+    assert!(writer.set_number_of_annotation_signals(2).is_err());
+    assert!(writer.set_subsecond_starttime(5_000_000).is_err());
+    assert!(writer.set_patient_text(&"x".repeat(81)).is_err());
+    
+    writer.add_signal(create_test_signal()).unwrap();
+    for _ in 0..3 {
+        writer.write_samples(&[vec![1.0; 100]]).unwrap();
+    }
+    let summary = writer.finalize().unwrap();
+    assert_eq!(summary.datarecords, 3);
+    
+    let bytes = fs::read(filename).unwrap();
+    assert_eq!(bytes.len(), 2 * 256 + 3 * 200);
+    assert_eq!(&bytes[header::VERSION], b"0       ");
+    assert_eq!(&bytes[header::PATIENT], format!("{:<80}", "Doe, John (born 1961)").as_bytes());
+    assert_eq!(&bytes[header::RECORDING], format!("{:<80}", "Ward 3, bed 12").as_bytes());
+    assert_eq!(&bytes[header::RESERVED], [b' '; 44].as_slice());
+    assert_eq!(&bytes[header::HEADER_BYTES], b"512     ");
+    assert_eq!(&bytes[header::DATARECORDS], b"3       ");
+    assert_eq!(&bytes[header::SIGNAL_COUNT], b"1   ");
+    assert_eq!(&bytes[256..272], b"EEG Fp1         ");
+    
+    // 当前的读取器只支持EDF+
+    match EdfReader::open(filename) {
+        Err(EdfError::UnsupportedFileType(message)) => assert!(message.contains("EDF")),
+        other => panic!("Expected UnsupportedFileType, got {:?}", other.map(|_| ())),
+    }
+    
+    // EDF+写入器不接受自由文本
+    let mut writer = EdfWriter::create(filename).unwrap();
+    assert!(writer.set_patient_text("free text").is_err());
+    assert!(writer.set_recording_text("free text").is_err());
+    drop(writer);
+    
+    assert!(matches!(
+        EdfWriterOptions::new().overwrite(true).file_type(FileType::Bdf).create(filename),
+        Err(EdfError::UnsupportedFileType(_))
+    ));
+    
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_label_policies() {
+    let filename = "test_writer_label_policy.edf";
+    let labelled = |label: &str| SignalParam { label: label.to_string(), ..create_test_signal() };
+    
+    // 默认不检查
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.add_signal(labelled("eeg fpz-cz")).unwrap();
+    writer.write_samples(&[vec![0.0; 100]]).unwrap();
+    assert!(writer.finalize().unwrap().nonconformant_labels.is_empty());
+    
+    // Standard：在写入摘要中列出
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.validate_labels(LabelPolicy::Standard);
+    for label in ["EEG Fp1", "eeg fpz-cz", "Pleth", "ECG V2"] {
+        writer.add_signal(labelled(label)).unwrap();
+    }
+    writer.write_samples(&vec![vec![0.0; 100]; 4]).unwrap();
+    let summary = writer.finalize().unwrap();
+    let reported: Vec<(&str, Option<&str>)> = summary.nonconformant_labels.iter()
+        .map(|(label, check)| (label.as_str(), check.suggestion.as_deref()))
+        .collect();
+    assert_eq!(reported, vec![("eeg fpz-cz", Some("EEG Fpz-Cz")), ("Pleth", None)]);
+    
+    // Strict：写入头部前报错
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.validate_labels(LabelPolicy::Strict);
+    writer.add_signal(labelled("EEG Fp1")).unwrap();
+    writer.add_signal(labelled("EEG FPZ-CZ")).unwrap();
+    match writer.write_samples(&[vec![0.0; 100], vec![0.0; 100]]) {
+        Err(EdfError::InvalidFormat(msg)) => assert!(msg.contains("EEG Fpz-Cz"), "{}", msg),
+        other => panic!("Expected InvalidFormat, got {:?}", other),
+    }
+    drop(writer);
+    
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_non_printable_header_text() {
+    let filename = "test_writer_sanitize.edf";
+    let signal = SignalParam { label: "EEG\nFp1".to_string(), ..create_test_signal() };
+    let patient = PatientInfo { name: Some("José García".to_string()), ..PatientInfo::default() };
+    
+    // 默认拒绝：标签中的换行符
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.add_signal(signal.clone()).unwrap();
+    match writer.write_samples(&[vec![0.0; 100]]) {
+        Err(EdfError::InvalidFormat(msg)) => assert!(msg.contains("Signal 0 label"), "{}", msg),
+        other => panic!("Expected InvalidFormat, got {:?}", other),
+    }
+    drop(writer);
+    
+    // 默认拒绝：下划线编码之后仍含重音字符的患者姓名
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.set_patient(&patient).unwrap();
+    writer.add_signal(create_test_signal()).unwrap();
+    match writer.write_samples(&[vec![0.0; 100]]) {
+        Err(EdfError::InvalidFormat(msg)) => assert!(msg.contains("Patient name"), "{}", msg),
+        other => panic!("Expected InvalidFormat, got {:?}", other),
+    }
+    drop(writer);
+    
+    // sanitize：逐字符替换为下划线并在摘要中列出
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.sanitize(true);
+    writer.set_patient(&patient).unwrap();
+    writer.add_signal(signal).unwrap();
+    writer.add_annotation(0.5, None, "Événement").unwrap();
+    writer.write_samples(&[vec![0.0; 100]]).unwrap();
+    let summary = writer.finalize().unwrap();
+    assert_eq!(summary.sanitized_fields, vec!["Patient name".to_string(), "Signal 0 label".to_string()]);
+    
+    let reader = EdfReader::open(filename).unwrap();
+    assert_eq!(reader.header().signals[0].label, "EEG_Fp1");
+    assert_eq!(reader.header().patient_name, "Jos__Garc_a");
+    // 注释描述允许UTF-8，不受影响
+    assert_eq!(reader.annotations()[0].description, "Événement");
+    drop(reader);
+    
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_oversized_records() {
+    let filename = "test_writer_oversized.edf";
+    // 128 通道 × 400 样本 × 2 字节 = 100 KB 每记录
+    let signals: Vec<SignalParam> = (0..128)
+        .map(|i| SignalParam { label: format!("EEG {}", i), samples_per_record: 400, ..create_test_signal() })
+        .collect();
+    let record = |offset: f64| -> Vec<Vec<f64>> {
+        (0..128).map(|i| (0..400).map(|j| offset + i as f64 - (j % 50) as f64).collect()).collect()
+    };
+    
+    // 默认拒绝
+    let mut writer = EdfWriter::create(filename).unwrap();
+    for signal in &signals {
+        writer.add_signal(signal.clone()).unwrap();
+    }
+    match writer.write_samples(&record(0.0)) {
+        Err(EdfError::RecordTooLarge { size, limit }) => assert_eq!((size, limit), (102_400 + 120, 61_440)),
+        other => panic!("Expected RecordTooLarge, got {:?}", other),
+    }
+    drop(writer);
+    
+    // 明确允许后按要求写入并在摘要中注明
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.allow_oversized_records(true);
+    for signal in &signals {
+        writer.add_signal(signal.clone()).unwrap();
+    }
+    writer.add_annotation(1.5, None, "Marker").unwrap();
+    writer.write_samples(&record(0.0)).unwrap();
+    writer.write_samples(&record(20.0)).unwrap();
+    let summary = writer.finalize().unwrap();
+    assert_eq!(summary.oversized_record_bytes, Some(102_400 + 120));
+    
+    let mut reader = EdfReader::open(filename).unwrap();
+    assert_eq!(reader.header().datarecords_in_file, 2);
+    assert_eq!(reader.header().datarecord_duration, 10_000_000);
+    assert_eq!(reader.annotations()[0].description, "Marker");
+    let expected = [record(0.0), record(20.0)];
+    for signal in [0, 77, 127] {
+        let samples = reader.read_physical_samples(signal, 800).unwrap();
+        let wanted: Vec<f64> = expected.iter().flat_map(|r| r[signal].clone()).collect();
+        for (read, written) in samples.iter().zip(&wanted) {
+            assert!((read - written).abs() < 0.01, "signal {}: {} != {}", signal, read, written);
+        }
+    }
+    drop(reader);
+    
+    cleanup_test_file(filename);
+}
+
+// 数字值与物理值相同的信号上的慢速斜坡：0 到 10 个数字步长
+fn write_ramp(filename: &str, quantization: Quantization) -> Vec<f64> {
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.quantization(quantization).unwrap();
+    writer.add_signal(SignalParam {
+        physical_max: 32767.0,
+        physical_min: -32768.0,
+        ..create_test_signal()
+    }).unwrap();
+    let ramp: Vec<f64> = (0..20_000).map(|i| i as f64 * 10.0 / 20_000.0).collect();
+    for record in ramp.chunks(100) {
+        writer.write_samples(&[record.to_vec()]).unwrap();
+    }
+    assert_eq!(writer.finalize().unwrap().quantization, quantization);
+    ramp
+}
+
+#[test]
+fn test_dithered_quantization() {
+    let filename = "test_writer_dither.edf";
+    
+    // 相同种子得到相同文件，不同种子或不抖动则不同
+    write_ramp(filename, Quantization::Dithered { seed: 7 });
+    let first = fs::read(filename).unwrap();
+    write_ramp(filename, Quantization::Dithered { seed: 7 });
+    assert_eq!(fs::read(filename).unwrap(), first);
+    write_ramp(filename, Quantization::Dithered { seed: 8 });
+    assert_ne!(fs::read(filename).unwrap(), first);
+    write_ramp(filename, Quantization::Round);
+    assert_ne!(fs::read(filename).unwrap(), first);
+    
+    // 抖动的平均误差接近0，截断偏低约半个步长
+    let ramp = write_ramp(filename, Quantization::Dithered { seed: 7 });
+    let mut reader = EdfReader::open(filename).unwrap();
+    let stored = reader.read_digital_samples(0, ramp.len()).unwrap();
+    drop(reader);
+    let n = ramp.len() as f64;
+    let dither_bias = stored.iter().zip(&ramp).map(|(&d, x)| d as f64 - x).sum::<f64>() / n;
+    let truncation_bias = ramp.iter().map(|x| x.trunc() - x).sum::<f64>() / n;
+    assert!(dither_bias.abs() < 0.05, "dither bias {}", dither_bias);
+    assert!(dither_bias.abs() < truncation_bias.abs() / 10.0, "{} vs {}", dither_bias, truncation_bias);
+    // 抖动至多改变一个步长
+    assert!(stored.iter().zip(&ramp).all(|(&d, x)| (d as f64 - x).abs() < 1.5));
+    
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_digital_writes_are_never_dithered() {
+    let filename = "test_writer_dither_digital.edf";
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.quantization(Quantization::Dithered { seed: 1 }).unwrap();
+    writer.add_signal(create_test_signal()).unwrap();
+    let values: Vec<i32> = (0..100).map(|i| i * 300 - 15_000).collect();
+    writer.write_digital_samples(std::slice::from_ref(&values)).unwrap();
+    assert!(writer.quantization(Quantization::Round).is_err());
+    writer.finalize().unwrap();
+    
+    let mut reader = EdfReader::open(filename).unwrap();
+    assert_eq!(reader.read_digital_samples(0, 100).unwrap(), values);
+    drop(reader);
+    
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_identical_inputs_give_identical_files() {
+    let first = "test_writer_reproducible_1.edf";
+    let second = "test_writer_reproducible_2.edf";
+    
+    for filename in [first, second] {
+        let mut writer = EdfWriter::create(filename).unwrap();
+        writer.add_annotation(0.5, None, "Start").unwrap();
+        write_records(writer, 3);
+        // 不同时刻写入的文件也必须相同
+        std::thread::sleep(std::time::Duration::from_millis(1100));
+    }
+    assert_eq!(fs::read(first).unwrap(), fs::read(second).unwrap());
+    
+    let reader = EdfReader::open(first).unwrap();
+    assert_eq!(reader.header().start_datetime(), DEFAULT_START_DATETIME);
+    
+    drop(reader);
+    cleanup_test_file(first);
+    cleanup_test_file(second);
+}
+
+#[test]
+fn test_injected_clock() {
+    let filename = "test_writer_clock.edf";
+    let now = chrono::NaiveDate::from_ymd_opt(2024, 3, 15).unwrap().and_hms_milli_opt(9, 30, 0, 250).unwrap();
+    let explicit = chrono::NaiveDate::from_ymd_opt(2020, 1, 2).unwrap().and_hms_opt(3, 4, 5).unwrap();
+    
+    // 时钟只在写入头部时调用一次
+    let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let mut writer = EdfWriter::create(filename).unwrap();
+    let counter = calls.clone();
+    writer.set_clock(move || {
+        counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        now
+    }).unwrap();
+    write_records(writer, 2);
+    assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+    assert_eq!(EdfReader::open(filename).unwrap().header().start_datetime(), now);
+    
+    // 显式开始时间优先于时钟
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.set_start_datetime(explicit).unwrap();
+    writer.set_clock(move || now).unwrap();
+    write_records(writer, 2);
+    assert_eq!(EdfReader::open(filename).unwrap().header().start_datetime(), explicit);
+    
+    // 时钟的值与 set_start_datetime 一样检查
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.set_clock(|| chrono::NaiveDate::from_ymd_opt(1970, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap()).unwrap();
+    writer.add_signal(create_test_signal()).unwrap();
+    assert!(matches!(writer.write_samples(&[vec![0.0; 100]]), Err(EdfError::InvalidFormat(_))));
+    drop(writer);
+    
+    cleanup_test_file(filename);
+}
+
+#[test]
+fn test_strict_profile_reports_all_violations() {
+    let filename = "test_writer_strict_profile.edf";
+    cleanup_test_file(filename);
+    
+    // 三处违规：非标准标签、不可打印字符、超出记录时长的注释
+    let write = |profile: Profile| {
+        let mut writer = EdfWriterOptions::new().atomic(true).create(filename).unwrap();
+        writer.compliance_profile(profile).unwrap();
+        let mut signal = create_test_signal();
+        signal.label = "EEG FP1-CZ".to_string();
+        signal.transducer = "AgCl\telectrode".to_string();
+        writer.add_signal(signal).unwrap();
+        writer.add_annotation(5.0, None, "Lights on").unwrap();
+        for _ in 0..2 {
+            writer.write_samples(&[vec![0.0; 100]])?;
+        }
+        writer.finalize()
+    };
+    
+    // 宽松模式在第一处违规处停止
+    assert!(matches!(write(Profile::Relaxed), Err(EdfError::InvalidFormat(_))));
+    
+    let Err(EdfError::ComplianceViolations(violations)) = write(Profile::Strict) else {
+        panic!("expected compliance violations");
+    };
+    let rules: Vec<Rule> = violations.iter().map(|v| v.rule).collect();
+    assert_eq!(rules, vec![Rule::Charset, Rule::Label, Rule::AnnotationPlacement]);
+    assert!(violations[0].message.contains("Signal 0 transducer"));
+    assert!(violations[1].message.contains("EEG Fp1-Cz"));
+    assert!(violations[2].message.contains("Lights on"));
+    
+    // 原子写入时不产生目标文件
+    assert!(!Path::new(filename).exists());
+    
+    // 符合规则的文件正常完成
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.compliance_profile(Profile::Strict).unwrap();
+    writer.set_patient(&PatientInfo { name: Some("Jane Doe".to_string()), ..PatientInfo::default() }).unwrap();
+    writer.add_annotation(0.5, Some(1.0), "Lights off").unwrap();
+    write_records(writer, 2);
+    
+    // 患者子字段格式和描述长度
+    let mut writer = EdfWriter::create(filename).unwrap();
+    writer.compliance_profile(Profile::Strict).unwrap();
+    writer.set_patient_info("P 001", "U", "1990-01-01", "X").unwrap();
+    writer.add_signal(create_test_signal()).unwrap();
+    writer.add_annotation(0.5, None, &"A".repeat(41)).unwrap();
+    writer.write_samples(&[vec![0.0; 100]]).unwrap();
+    let Err(EdfError::ComplianceViolations(violations)) = writer.finalize() else {
+        panic!("expected compliance violations");
+    };
+    let rules: Vec<Rule> = violations.iter().map(|v| v.rule).collect();
+    assert_eq!(rules, vec![Rule::PatientFormat, Rule::PatientFormat, Rule::PatientFormat, Rule::Length]);
+    
+    cleanup_test_file(filename);
+}